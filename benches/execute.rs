@@ -0,0 +1,101 @@
+//! Throughput of `Core::execute` on representative workloads, reported in retired instructions per second
+use criterion::{ criterion_group, criterion_main, Criterion, Throughput };
+use rysk_core::{ ArrayRam, Core, HostReturn, Register32 };
+
+/// Counts to 2^20 in a tight loop before making an environment call
+const ALU_LOOP: [u32; 5] = [
+    0x0000_0293, // addi t0, zero, 0
+    0x0010_0337, // lui t1, 0x100
+    0x0012_8293, // addi t0, t0, 1
+    0xFE62_9EE3, // bne t0, t1, -4
+    0x0000_0073  // ecall
+];
+
+/// Copy the 128 words at 0x400 to 0x600 64 times, then call the environment
+const MEMORY_COPY: [u32; 12] = [
+    0x0400_0413, // addi s0, zero, 64
+    0x4000_0513, // addi a0, zero, 0x400
+    0x6000_0593, // addi a1, zero, 0x600
+    0x6000_0613, // addi a2, zero, 0x600
+    0x0005_2283, // lw t0, 0(a0)
+    0x0055_A023, // sw t0, 0(a1)
+    0x0045_0513, // addi a0, a0, 4
+    0x0045_8593, // addi a1, a1, 4
+    0xFEC5_18E3, // bne a0, a2, -16
+    0xFFF4_0413, // addi s0, s0, -1
+    0xFC04_1EE3, // bne s0, zero, -36
+    0x0000_0073  // ecall
+];
+
+/// Bubble sort the 64 words at 0x400 into ascending order, then call the environment
+const BUBBLE_SORT: [u32; 17] = [
+    0x4000_0413, // addi s0, zero, 0x400
+    0x03F0_0493, // addi s1, zero, 63
+    0x0000_0293, // addi t0, zero, 0
+    0x0000_0E13, // addi t3, zero, 0
+    0x0292_8663, // beq t0, s1, 44
+    0x0022_9313, // slli t1, t0, 2
+    0x0083_0333, // add t1, t1, s0
+    0x0003_2F03, // lw t5, 0(t1)
+    0x0043_2F83, // lw t6, 4(t1)
+    0x01EF_D863, // bge t6, t5, 16
+    0x01F3_2023, // sw t6, 0(t1)
+    0x01E3_2223, // sw t5, 4(t1)
+    0x0010_0E13, // addi t3, zero, 1
+    0x0012_8293, // addi t0, t0, 1
+    0xFD9F_F06F, // jal zero, -40
+    0xFC0E_16E3, // bne t3, zero, -52
+    0x0000_0073  // ecall
+];
+
+/// Memory holding `program` at address 0, with the 64 words at 0x400 in descending order, the worst case for the sort
+fn memory(program: &[u32]) -> ArrayRam<0x800> {
+    let mut memory = ArrayRam::new(0);
+    for (index, instruction) in program.iter().enumerate() {
+        memory.load(index * 4, &instruction.to_le_bytes());
+    }
+    for index in 0..64 {
+        memory.load(0x400 + index * 4, &(64 - index as u32).to_le_bytes());
+    }
+    memory
+}
+
+#[cfg(feature = "ext-csr")]
+fn core() -> Core<Register32> {
+    Core::new(0, 0).with_trap_strategy(HostReturn)
+}
+#[cfg(not(feature = "ext-csr"))]
+fn core() -> Core<Register32> {
+    Core::new(0).with_trap_strategy(HostReturn)
+}
+
+/// Execute until the environment call, returning the number of instructions executed
+fn run(mut core: Core<Register32>, memory: &mut ArrayRam<0x800>) -> u64 {
+    let mut executed = 1;
+    while core.execute(memory).is_none() {
+        executed += 1
+    }
+    executed
+}
+
+fn workloads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("workloads");
+    group.sample_size(10);
+    for &(name, program) in &[("alu_loop", &ALU_LOOP[..]), ("memory_copy", &MEMORY_COPY[..]), ("bubble_sort", &BUBBLE_SORT[..])] {
+        group.throughput(Throughput::Elements(run(core(), &mut memory(program))));
+        group.bench_function(name, |b| b.iter(|| run(core(), &mut memory(program))));
+    }
+    group.finish()
+}
+
+fn icache(c: &mut Criterion) {
+    let mut group = c.benchmark_group("icache");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(run(core(), &mut memory(&ALU_LOOP))));
+    group.bench_function("uncached", |b| b.iter(|| run(core(), &mut memory(&ALU_LOOP))));
+    group.bench_function("cached", |b| b.iter(|| run(core().with_icache(64), &mut memory(&ALU_LOOP))));
+    group.finish()
+}
+
+criterion_group!(benches, workloads, icache);
+criterion_main!(benches);
@@ -0,0 +1,307 @@
+//! Expansion of the C extension's 16-bit instructions to their 32-bit equivalents.
+//!
+//! Every compressed instruction has a base ISA equivalent, so rather than duplicating each handler the core expands
+//! compressed instructions and executes them as usual, only stepping by 2 bytes instead of 4.
+
+use crate::register::{ Register, RegisterWidth };
+use crate::variant::{ CompressedVariant, CB, CI, CIW, CJ, CL, CR, CS, CSS };
+
+const LOAD: u32 = 0b0000011;
+const OP_IMM: u32 = 0b0010011;
+const OP_IMM_32: u32 = 0b0011011;
+const STORE: u32 = 0b0100011;
+#[cfg(feature = "ext-f")]
+const LOAD_FP: u32 = 0b0000111;
+#[cfg(feature = "ext-f")]
+const STORE_FP: u32 = 0b0100111;
+const OP: u32 = 0b0110011;
+const LUI: u32 = 0b0110111;
+const OP_32: u32 = 0b0111011;
+const BRANCH: u32 = 0b1100011;
+const JALR: u32 = 0b1100111;
+const JAL: u32 = 0b1101111;
+const EBREAK: u32 = 0x0010_0073;
+
+/// The stack pointer, x2, used implicitly by the stack-relative instructions
+const SP: usize = 2;
+/// The return address, x1, linked implicitly by C.JAL and C.JALR
+const RA: usize = 1;
+
+/// Returns true if the lowest 16-bit parcel of an instruction encodes a compressed instruction.
+/// All 32-bit instructions have their 2 least significant bits set.
+pub fn is_compressed(parcel: [u8; 2]) -> bool {
+    parcel[0] & 0b11 != 0b11
+}
+
+/// Expand a compressed instruction to the 32-bit instruction it is equivalent to for the register width `R`.
+///
+/// Returns `None` for illegal and reserved encodings, including the all-zero parcel and instructions of
+/// unsupported extensions such as the compressed double-precision floating-point loads and stores.
+/// HINT encodings expand to their base instruction, which has no architectural effect.
+/// ```rust
+/// use rysk_core::{ compressed, Register32 };
+/// // c.addi a0, 1 => addi a0, a0, 1
+/// assert_eq!(compressed::expand::<Register32>([0x05, 0x05]), Some(0x00150513u32.to_le_bytes()));
+/// ```
+pub fn expand<R: Register>(parcel: [u8; 2]) -> Option<[u8; 4]> {
+    let rv32 = R::WIDTH == RegisterWidth::Bits32;
+    let funct3 = parcel[1] >> 5;
+    let bit12 = parcel[1] & 0x10 != 0;
+    let instruction = match (parcel[0] & 0b11, funct3) {
+        // C.ADDI4SPN
+        (0b00, 0b000) => {
+            let CIW { destination, immediate } = CompressedVariant::decode(parcel);
+            if immediate == 0 { return None }
+            i(immediate as i32, SP, 0b000, destination, OP_IMM)
+        },
+        // C.LW
+        (0b00, 0b010) => {
+            let CL { destination, source, word_offset, .. } = CompressedVariant::decode(parcel);
+            i(word_offset as i32, source, 0b010, destination, LOAD)
+        },
+        // C.LD
+        (0b00, 0b011) if !rv32 => {
+            let CL { destination, source, double_offset, .. } = CompressedVariant::decode(parcel);
+            i(double_offset as i32, source, 0b011, destination, LOAD)
+        },
+        // C.FLW
+        #[cfg(feature = "ext-f")]
+        (0b00, 0b011) if rv32 => {
+            let CL { destination, source, word_offset, .. } = CompressedVariant::decode(parcel);
+            i(word_offset as i32, source, 0b010, destination, LOAD_FP)
+        },
+        // C.FLD
+        #[cfg(feature = "ext-d")]
+        (0b00, 0b001) => {
+            let CL { destination, source, double_offset, .. } = CompressedVariant::decode(parcel);
+            i(double_offset as i32, source, 0b011, destination, LOAD_FP)
+        },
+        // C.SW
+        (0b00, 0b110) => {
+            let CS { source1, source2, word_offset, .. } = CompressedVariant::decode(parcel);
+            s(word_offset as i32, source2, source1, 0b010, STORE)
+        },
+        // C.SD
+        (0b00, 0b111) if !rv32 => {
+            let CS { source1, source2, double_offset, .. } = CompressedVariant::decode(parcel);
+            s(double_offset as i32, source2, source1, 0b011, STORE)
+        },
+        // C.FSW
+        #[cfg(feature = "ext-f")]
+        (0b00, 0b111) if rv32 => {
+            let CS { source1, source2, word_offset, .. } = CompressedVariant::decode(parcel);
+            s(word_offset as i32, source2, source1, 0b010, STORE_FP)
+        },
+        // C.FSD
+        #[cfg(feature = "ext-d")]
+        (0b00, 0b101) => {
+            let CS { source1, source2, double_offset, .. } = CompressedVariant::decode(parcel);
+            s(double_offset as i32, source2, source1, 0b011, STORE_FP)
+        },
+
+        // C.ADDI, C.NOP
+        (0b01, 0b000) => {
+            let CI { destination, immediate } = CompressedVariant::decode(parcel);
+            i(immediate, destination, 0b000, destination, OP_IMM)
+        },
+        // C.JAL
+        // The encoding is shared with C.ADDIW, which replaces C.JAL on RV64
+        (0b01, 0b001) if rv32 => {
+            let CJ { offset } = CompressedVariant::decode(parcel);
+            j(offset, RA)
+        },
+        // C.ADDIW
+        (0b01, 0b001) => {
+            let CI { destination, immediate } = CompressedVariant::decode(parcel);
+            if destination == 0 { return None }
+            i(immediate, destination, 0b000, destination, OP_IMM_32)
+        },
+        // C.LI
+        (0b01, 0b010) => {
+            let CI { destination, immediate } = CompressedVariant::decode(parcel);
+            i(immediate, 0, 0b000, destination, OP_IMM)
+        },
+        // C.ADDI16SP
+        (0b01, 0b011) if CI::decode(parcel).destination == SP => {
+            let raw = u16::from_le_bytes(parcel) as u32;
+            let immediate = ((raw >> 12 & 1) << 9
+                | (raw >> 6 & 1) << 4
+                | (raw >> 5 & 1) << 6
+                | (raw >> 3 & 0b11) << 7
+                | (raw >> 2 & 1) << 5) as i32;
+            let immediate = (immediate << 22) >> 22;
+            if immediate == 0 { return None }
+            i(immediate, SP, 0b000, SP, OP_IMM)
+        },
+        // C.LUI
+        (0b01, 0b011) => {
+            let CI { destination, immediate } = CompressedVariant::decode(parcel);
+            if immediate == 0 { return None }
+            (immediate << 12) as u32 | (destination as u32) << 7 | LUI
+        },
+        (0b01, 0b100) => {
+            let CB { source, immediate, .. } = CompressedVariant::decode(parcel);
+            let shamt = immediate & 0x3F;
+            match (parcel[1] >> 2) & 0b11 {
+                // C.SRLI, C.SRAI
+                // Shift amounts of 32 and above are reserved on RV32
+                0b00 | 0b01 if rv32 && bit12 => return None,
+                0b00 => i(shamt, source, 0b101, source, OP_IMM),
+                0b01 => i(shamt | 0x400, source, 0b101, source, OP_IMM),
+                // C.ANDI
+                0b10 => i(immediate, source, 0b111, source, OP_IMM),
+                _ => {
+                    let CS { source1, source2, .. } = CompressedVariant::decode(parcel);
+                    match (bit12, (parcel[0] >> 5) & 0b11) {
+                        // C.SUB
+                        (false, 0b00) => r(0b0100000, source2, source1, 0b000, source1, OP),
+                        // C.XOR
+                        (false, 0b01) => r(0, source2, source1, 0b100, source1, OP),
+                        // C.OR
+                        (false, 0b10) => r(0, source2, source1, 0b110, source1, OP),
+                        // C.AND
+                        (false, _) => r(0, source2, source1, 0b111, source1, OP),
+                        // C.SUBW
+                        (true, 0b00) if !rv32 => r(0b0100000, source2, source1, 0b000, source1, OP_32),
+                        // C.ADDW
+                        (true, 0b01) if !rv32 => r(0, source2, source1, 0b000, source1, OP_32),
+                        _ => return None
+                    }
+                }
+            }
+        },
+        // C.J
+        (0b01, 0b101) => {
+            let CJ { offset } = CompressedVariant::decode(parcel);
+            j(offset, 0)
+        },
+        // C.BEQZ
+        (0b01, 0b110) => {
+            let CB { source, offset, .. } = CompressedVariant::decode(parcel);
+            b(offset, 0, source, 0b000)
+        },
+        // C.BNEZ
+        (0b01, 0b111) => {
+            let CB { source, offset, .. } = CompressedVariant::decode(parcel);
+            b(offset, 0, source, 0b001)
+        },
+
+        // C.SLLI
+        (0b10, 0b000) => {
+            let CI { destination, immediate } = CompressedVariant::decode(parcel);
+            if rv32 && bit12 { return None }
+            i(immediate & 0x3F, destination, 0b001, destination, OP_IMM)
+        },
+        // C.LWSP
+        (0b10, 0b010) => {
+            let CI { destination, .. } = CompressedVariant::decode(parcel);
+            if destination == 0 { return None }
+            let raw = u16::from_le_bytes(parcel) as u32;
+            let offset = (raw >> 12 & 1) << 5 | (raw >> 4 & 0b111) << 2 | (raw >> 2 & 0b11) << 6;
+            i(offset as i32, SP, 0b010, destination, LOAD)
+        },
+        // C.LDSP
+        (0b10, 0b011) if !rv32 => {
+            let CI { destination, .. } = CompressedVariant::decode(parcel);
+            if destination == 0 { return None }
+            let raw = u16::from_le_bytes(parcel) as u32;
+            let offset = (raw >> 12 & 1) << 5 | (raw >> 5 & 0b11) << 3 | (raw >> 2 & 0b111) << 6;
+            i(offset as i32, SP, 0b011, destination, LOAD)
+        },
+        (0b10, 0b100) => {
+            let CR { destination, source } = CompressedVariant::decode(parcel);
+            match (bit12, destination, source) {
+                // C.JR with x0 is reserved
+                (false, 0, 0) => return None,
+                // C.JR
+                (false, _, 0) => i(0, destination, 0b000, 0, JALR),
+                // C.MV
+                (false, _, _) => r(0, source, 0, 0b000, destination, OP),
+                // C.EBREAK
+                (true, 0, 0) => EBREAK,
+                // C.JALR
+                (true, _, 0) => i(0, destination, 0b000, RA, JALR),
+                // C.ADD
+                (true, _, _) => r(0, source, destination, 0b000, destination, OP)
+            }
+        },
+        // C.SWSP
+        (0b10, 0b110) => {
+            let CSS { source, word_offset, .. } = CompressedVariant::decode(parcel);
+            s(word_offset as i32, source, SP, 0b010, STORE)
+        },
+        // C.SDSP
+        (0b10, 0b111) if !rv32 => {
+            let CSS { source, double_offset, .. } = CompressedVariant::decode(parcel);
+            s(double_offset as i32, source, SP, 0b011, STORE)
+        },
+        // C.FLWSP
+        #[cfg(feature = "ext-f")]
+        (0b10, 0b011) if rv32 => {
+            let CI { destination, .. } = CompressedVariant::decode(parcel);
+            let raw = u16::from_le_bytes(parcel) as u32;
+            let offset = (raw >> 12 & 1) << 5 | (raw >> 4 & 0b111) << 2 | (raw >> 2 & 0b11) << 6;
+            i(offset as i32, SP, 0b010, destination, LOAD_FP)
+        },
+        // C.FSWSP
+        #[cfg(feature = "ext-f")]
+        (0b10, 0b111) if rv32 => {
+            let CSS { source, word_offset, .. } = CompressedVariant::decode(parcel);
+            s(word_offset as i32, source, SP, 0b010, STORE_FP)
+        },
+        // C.FLDSP
+        #[cfg(feature = "ext-d")]
+        (0b10, 0b001) => {
+            let CI { destination, .. } = CompressedVariant::decode(parcel);
+            let raw = u16::from_le_bytes(parcel) as u32;
+            let offset = (raw >> 12 & 1) << 5 | (raw >> 5 & 0b11) << 3 | (raw >> 2 & 0b111) << 6;
+            i(offset as i32, SP, 0b011, destination, LOAD_FP)
+        },
+        // C.FSDSP
+        #[cfg(feature = "ext-d")]
+        (0b10, 0b101) => {
+            let CSS { source, double_offset, .. } = CompressedVariant::decode(parcel);
+            s(double_offset as i32, source, SP, 0b011, STORE_FP)
+        },
+
+        // Reserved encodings, and the double-precision floating-point loads and stores without the D extension
+        _ => return None
+    };
+    Some(instruction.to_le_bytes())
+}
+
+/// Encode an R type instruction
+fn r(funct7: u32, source2: usize, source1: usize, funct3: u32, destination: usize, opcode: u32) -> u32 {
+    funct7 << 25 | (source2 as u32) << 20 | (source1 as u32) << 15 | funct3 << 12 | (destination as u32) << 7 | opcode
+}
+/// Encode an I type instruction
+fn i(immediate: i32, source: usize, funct3: u32, destination: usize, opcode: u32) -> u32 {
+    (immediate as u32) << 20 | (source as u32) << 15 | funct3 << 12 | (destination as u32) << 7 | opcode
+}
+/// Encode a store, an S type instruction
+fn s(immediate: i32, source2: usize, source1: usize, funct3: u32, opcode: u32) -> u32 {
+    let immediate = immediate as u32;
+    (immediate >> 5) << 25 | (source2 as u32) << 20 | (source1 as u32) << 15 | funct3 << 12 | (immediate & 0x1F) << 7 | opcode
+}
+/// Encode a branch, a B type instruction
+fn b(offset: i32, source2: usize, source1: usize, funct3: u32) -> u32 {
+    let offset = offset as u32;
+    (offset >> 12 & 1) << 31
+        | (offset >> 5 & 0x3F) << 25
+        | (source2 as u32) << 20
+        | (source1 as u32) << 15
+        | funct3 << 12
+        | (offset >> 1 & 0xF) << 8
+        | (offset >> 11 & 1) << 7
+        | BRANCH
+}
+/// Encode a JAL, a J type instruction
+fn j(offset: i32, destination: usize) -> u32 {
+    let offset = offset as u32;
+    (offset >> 20 & 1) << 31
+        | (offset >> 1 & 0x3FF) << 21
+        | (offset >> 11 & 1) << 20
+        | (offset >> 12 & 0xFF) << 12
+        | (destination as u32) << 7
+        | JAL
+}
@@ -1,10 +1,134 @@
-use crate::register::{Register,Register32,Register64};
+use crate::register::{Register,Register32,Register64,RegisterWidth};
+
+/// Bit position of the supervisor software interrupt in mip and mie
+pub const SSIP: u8 = 1;
+/// Bit position of the machine software interrupt in mip and mie
+pub const MSIP: u8 = 3;
+/// Bit position of the supervisor timer interrupt in mip and mie
+pub const STIP: u8 = 5;
+/// Bit position of the machine timer interrupt in mip and mie
+pub const MTIP: u8 = 7;
+/// Bit position of the supervisor external interrupt in mip and mie
+pub const SEIP: u8 = 9;
+/// Bit position of the machine external interrupt in mip and mie
+pub const MEIP: u8 = 11;
+
+/// Bit position of the global machine interrupt enable in mstatus
+pub const MSTATUS_MIE: u8 = 3;
+/// Bit position of the machine interrupt enable prior to the current trap in mstatus
+pub const MSTATUS_MPIE: u8 = 7;
+/// Bit position of the 2-bit privilege mode prior to the current trap in mstatus
+pub const MSTATUS_MPP: u8 = 11;
+/// Bit position of timeout wait in mstatus, which makes WFI illegal below machine mode
+pub const MSTATUS_TW: u8 = 21;
+/// Bit position of the 2-bit XLEN of user mode in mstatus. Only present on RV64 and RV128
+pub const MSTATUS_UXL: u8 = 32;
+/// Bit position of the machine-mode big-endian control in mstatus on RV64 and RV128
+pub const MSTATUS_MBE: u8 = 37;
+/// Bit position of the machine-mode big-endian control in mstatush on RV32
+pub const MSTATUSH_MBE: u8 = 5;
+
+/// Bit position of the 2-bit CBO.INVAL enable in menvcfg, which may instead perform a flush below machine mode
+pub const MENVCFG_CBIE: u8 = 4;
+/// Bit position of the CBO.CLEAN and CBO.FLUSH enable in menvcfg
+pub const MENVCFG_CBCFE: u8 = 6;
+/// Bit position of the CBO.ZERO enable in menvcfg
+pub const MENVCFG_CBZE: u8 = 7;
+
+/// The privilege modes a hart may execute in. Supervisor mode is not supported
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Privilege {
+    User,
+    Machine
+}
+impl Privilege {
+    /// The encoding of the mode in MPP and in bits 8 and 9 of a CSR address
+    pub fn bits(self) -> u8 {
+        match self {
+            Self::User => 0b00,
+            Self::Machine => 0b11
+        }
+    }
+}
+/// A machine-level interrupt source a platform may raise
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interrupt {
+    /// The machine software interrupt, raised by another hart through the CLINT
+    Software,
+    /// The machine timer interrupt, raised once mtime reaches mtimecmp
+    Timer,
+    /// The machine external interrupt, raised by the platform interrupt controller
+    External
+}
+impl Interrupt {
+    /// The bit position of the interrupt in mip and mie, which is also its exception code in mcause
+    pub fn bit(self) -> u8 {
+        match self {
+            Self::Software => MSIP,
+            Self::Timer => MTIP,
+            Self::External => MEIP
+        }
+    }
+}
+
+/// The events the programmable counters mhpmcounter3 to mhpmcounter31 may count, selected by writing the event's code
+/// to the counter's mhpmevent CSR. A counter selecting any other code, such as 0, does not count
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HpmEvent {
+    /// Loads from memory, including LR and each element of a vector load
+    Load = 1,
+    /// Stores to memory, including SC, AMOs and each element of a vector store
+    Store = 2,
+    /// Conditional branches, whether or not they are taken
+    Branch = 3,
+    /// Conditional branches which are taken
+    TakenBranch = 4,
+    /// Exceptions, whether handled by the program or returned to the execution environment
+    Exception = 5
+}
+impl HpmEvent {
+    /// The event selected by a code written to mhpmevent, or `None` if it is not supported
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            1 => Self::Load,
+            2 => Self::Store,
+            3 => Self::Branch,
+            4 => Self::TakenBranch,
+            5 => Self::Exception,
+            _ => return None
+        })
+    }
+}
+
+/// The bits of mstatus which software may write within the lowest half.
+/// MPP may only hold user or machine mode, while TW and the fields above it are written separately
+pub const MSTATUS_WRITABLE: u16 = 1 << MSTATUS_MIE | 1 << MSTATUS_MPIE | 0b11 << MSTATUS_MPP;
+
+/// The bits of mie which software may write.
+/// Supervisor interrupt bits are hardwired to zero as there is no supervisor mode
+pub const MIE_WRITABLE: u16 = 1 << MSIP | 1 << MTIP | 1 << MEIP;
+/// The bits of mip which software may write.
+/// Machine interrupts are only made pending by the platform and supervisor interrupt bits are hardwired to zero
+pub const MIP_WRITABLE: u16 = 0;
+
+/// The bits of menvcfg which software may write, all within the lowest byte.
+/// The cache-block enables are hardwired to zero unless the Zicbom and Zicboz extensions are enabled
+pub const MENVCFG_WRITABLE: u8 = if cfg!(feature = "ext-zicbom") { 0b11 << MENVCFG_CBIE | 1 << MENVCFG_CBCFE } else { 0 }
+    | if cfg!(feature = "ext-zicboz") { 1 << MENVCFG_CBZE } else { 0 };
 
 /// The Control Status Registers (CSR) a single HART must provide storage for to comply with the privileged ISA
 /// Other CSR's may not need storage and as such are not a part of this struct
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Csr<R: Register> {
+    /// The privilege mode the hart is executing in
+    pub privilege: Privilege,
     /// The ID of this hart
     pub mhartid: R,
+    /// The global interrupt enable, the state stacked by the most recent trap and the XLEN of user mode
+    pub mstatus: R,
+    /// The upper half of mstatus on RV32, holding MBE. On RV64 these fields are held in mstatus itself
+    pub mstatush: Register32,
     /// The address of a potentially vectorised interupt handler
     pub mtvec: R,
     /// Delegation of exceptions to lower modes
@@ -15,10 +139,20 @@ pub struct Csr<R: Register> {
     pub mie: R,
     /// States if an interrupt is pending
     pub mip: R,
-    /// Counts the number of cycles the hart has executed. As there is no speculative execution or other operations minstret is the same as this value
+    /// Counts the number of cycles the hart has executed, including any memory access latency reported by the MMU
     pub mcycle: Register64,
+    /// Counts the number of instructions the hart has retired. Instructions which trap do not retire
+    pub minstret: Register64,
+    /// The platform's real-time counter, as last reported by `update_timer` or `set_time`, which the time CSR reads
+    pub mtime: Register64,
+    /// The programmable counters mhpmcounter3 to mhpmcounter31
+    pub mhpmcounter: [Register64; 29],
+    /// The code of the `HpmEvent` each programmable counter counts, or 0 if it does not count
+    pub mhpmevent: [u8; 29],
     /// Determine if counters are accessible in lower privilege modes
     pub mcounteren: Register32,
+    /// Configures the environment of lower privilege modes, split into menvcfg and menvcfgh on RV32
+    pub menvcfg: Register64,
     /// Scratch register dedicated to machine-mode usage
     pub mscratch: R,
     /// The virtual address of an interrupted or excepted instruction in machine-mode
@@ -31,19 +165,203 @@ pub struct Csr<R: Register> {
 
 impl<R: Register> Csr<R> {
     pub fn new(hart: R::Unsigned, trap_address: R::Unsigned) -> Self {
+        // User mode starts with the full XLEN; SXL is zero as there is no supervisor mode
+        let mstatus = match R::WIDTH {
+            RegisterWidth::Bits32 => R::zero_extended_half((0b11u16 << MSTATUS_MPP).to_le_bytes()),
+            RegisterWidth::Bits64 => R::zero_extended_double((0b11u64 << MSTATUS_MPP | 0b10 << MSTATUS_UXL).to_le_bytes()),
+            RegisterWidth::Bits128 => R::zero_extended_double((0b11u64 << MSTATUS_MPP | 0b11 << MSTATUS_UXL).to_le_bytes())
+        };
         Self {
+            privilege: Privilege::Machine,
             mhartid: R::from_unsigned(hart),
+            mstatus,
+            mstatush: Default::default(),
             mtvec: R::from_unsigned(trap_address),
             medeleg: Default::default(),
             mideleg: Default::default(),
             mie: Default::default(),
             mip: Default::default(),
             mcycle: Default::default(),
+            minstret: Default::default(),
+            mtime: Default::default(),
+            mhpmcounter: Default::default(),
+            mhpmevent: Default::default(),
             mcounteren: Default::default(),
+            menvcfg: Default::default(),
             mscratch: Default::default(),
             mepc: Default::default(),
             mcause: Default::default(),
             mtval: Default::default()
         }
     }
+
+    /// Write mstatus as software would, ignoring read-only bits.
+    /// Unsupported privilege modes in MPP and unsupported widths in UXL leave the field unchanged. UXL is read-only on RV128
+    pub fn write_mstatus(&mut self, value: R) {
+        let previous = self.mstatus;
+        self.mstatus = Self::write_masked(self.mstatus, value, MSTATUS_WRITABLE);
+        if !matches!(self.previous_privilege_bits(), 0b00 | 0b11) {
+            let mpp = R::zero_extended_half((0b11u16 << MSTATUS_MPP).to_le_bytes());
+            self.mstatus = self.mstatus.and(mpp.not()).or(previous.and(mpp))
+        }
+        let tw = R::zero_extended_word((1u32 << MSTATUS_TW).to_le_bytes());
+        self.mstatus = self.mstatus.and(tw.not()).or(value.and(tw));
+        if R::WIDTH != RegisterWidth::Bits32 {
+            let uxl = value.double()[MSTATUS_UXL as usize / 8] & 0b11;
+            if R::WIDTH == RegisterWidth::Bits64 && (uxl == 0b01 || uxl == 0b10) {
+                self.set_uxl(uxl)
+            }
+            self.set_mbe(value.double()[MSTATUS_MBE as usize / 8] & 1 << (MSTATUS_MBE % 8) != 0)
+        }
+    }
+    /// Write mstatush as software would, ignoring read-only bits. Only present on RV32.
+    /// SBE is hardwired to zero as there is no supervisor mode
+    pub fn write_mstatush(&mut self, value: Register32) {
+        self.mstatush = Register32([value.0[0] & 1 << MSTATUSH_MBE, 0, 0, 0])
+    }
+    /// Write the lowest byte of menvcfg as software would, as the fields above it are hardwired to zero.
+    /// The reserved CBIE value of 0b10 leaves the field unchanged
+    pub fn write_menvcfg(&mut self, value: u8) {
+        let mut value = value & MENVCFG_WRITABLE;
+        if value >> MENVCFG_CBIE & 0b11 == 0b10 {
+            value = value & !(0b11 << MENVCFG_CBIE) | self.menvcfg.0[0] & 0b11 << MENVCFG_CBIE
+        }
+        self.menvcfg = Register64([value, 0, 0, 0, 0, 0, 0, 0])
+    }
+    /// Whether machine-mode memory accesses are configured as big-endian by MBE.
+    /// The core only performs little-endian accesses, so the bit is stored for software but does not change their byte order
+    pub fn machine_big_endian(&self) -> bool {
+        match R::WIDTH {
+            RegisterWidth::Bits32 => self.mstatush.0[0] & 1 << MSTATUSH_MBE != 0,
+            RegisterWidth::Bits64 | RegisterWidth::Bits128 => self.mstatus.double()[MSTATUS_MBE as usize / 8] & 1 << (MSTATUS_MBE % 8) != 0
+        }
+    }
+    /// Set MBE in mstatus. Only valid on RV64 and RV128
+    fn set_mbe(&mut self, big_endian: bool) {
+        let mut mstatus = self.mstatus.double();
+        let bit = 1 << (MSTATUS_MBE % 8);
+        mstatus[MSTATUS_MBE as usize / 8] = if big_endian { mstatus[MSTATUS_MBE as usize / 8] | bit } else { mstatus[MSTATUS_MBE as usize / 8] & !bit };
+        self.mstatus = R::zero_extended_double(mstatus)
+    }
+    /// The effective XLEN while executing in user mode
+    pub fn user_width(&self) -> RegisterWidth {
+        match R::WIDTH {
+            RegisterWidth::Bits32 => RegisterWidth::Bits32,
+            RegisterWidth::Bits64 if self.mstatus.double()[MSTATUS_UXL as usize / 8] & 0b11 == 0b01 => RegisterWidth::Bits32,
+            RegisterWidth::Bits64 => RegisterWidth::Bits64,
+            RegisterWidth::Bits128 => RegisterWidth::Bits128
+        }
+    }
+    /// Set the XLEN of user mode, given its encoding in mstatus. Only valid on RV64
+    fn set_uxl(&mut self, uxl: u8) {
+        let mut mstatus = self.mstatus.double();
+        mstatus[MSTATUS_UXL as usize / 8] = mstatus[MSTATUS_UXL as usize / 8] & !0b11 | uxl;
+        self.mstatus = R::zero_extended_double(mstatus)
+    }
+    fn previous_privilege_bits(&self) -> u8 {
+        (u16::from_le_bytes(self.mstatus.half()) >> MSTATUS_MPP) as u8 & 0b11
+    }
+    /// Returns true if WFI is illegal below machine mode, as set by mstatus.TW
+    pub fn timeout_wait(&self) -> bool {
+        self.mstatus.word()[MSTATUS_TW as usize / 8] & 1 << (MSTATUS_TW % 8) != 0
+    }
+    /// Returns true if interrupts are globally enabled
+    pub fn interrupts_enabled(&self) -> bool {
+        self.mstatus.byte() & 1 << MSTATUS_MIE != 0
+    }
+    /// Stack the interrupt enable into MPIE and the privilege mode into MPP, disabling interrupts and entering machine mode for a trap handler
+    pub(crate) fn enter_trap(&mut self) {
+        let enabled = self.interrupts_enabled();
+        self.set_status(false, enabled);
+        self.set_previous_privilege(self.privilege);
+        self.privilege = Privilege::Machine
+    }
+    /// Restore the interrupt enable from MPIE and the privilege mode from MPP on return from a trap handler.
+    /// MPIE is set and MPP is left holding user mode, as MRET does
+    pub(crate) fn return_from_trap(&mut self) {
+        let enabled = self.mstatus.byte() & 1 << MSTATUS_MPIE != 0;
+        self.set_status(enabled, true);
+        self.privilege = if self.previous_privilege_bits() == Privilege::Machine.bits() { Privilege::Machine } else { Privilege::User };
+        self.set_previous_privilege(Privilege::User)
+    }
+    fn set_previous_privilege(&mut self, privilege: Privilege) {
+        let mpp = R::zero_extended_half((0b11u16 << MSTATUS_MPP).to_le_bytes());
+        let bits = R::zero_extended_half(((privilege.bits() as u16) << MSTATUS_MPP).to_le_bytes());
+        self.mstatus = self.mstatus.and(mpp.not()).or(bits)
+    }
+    fn set_status(&mut self, enabled: bool, previous: bool) {
+        let bits = (enabled as u8) << MSTATUS_MIE | (previous as u8) << MSTATUS_MPIE;
+        self.mstatus = self.mstatus.and(R::zero_extended_byte(1 << MSTATUS_MIE | 1 << MSTATUS_MPIE).not()).or(R::zero_extended_byte(bits))
+    }
+
+    /// Write mie as software would, ignoring read-only bits
+    pub fn write_mie(&mut self, value: R) {
+        self.mie = Self::write_masked(self.mie, value, MIE_WRITABLE)
+    }
+    /// Write mip as software would, ignoring read-only bits
+    pub fn write_mip(&mut self, value: R) {
+        self.mip = Self::write_masked(self.mip, value, MIP_WRITABLE)
+    }
+    fn write_masked(current: R, value: R, writable: u16) -> R {
+        let writable = R::zero_extended_half(writable.to_le_bytes());
+        current.and(writable.not()).or(value.and(writable))
+    }
+
+    /// Set whether the machine software interrupt is pending
+    pub fn set_msip(&mut self, pending: bool) {
+        self.set_pending(MSIP, pending)
+    }
+    /// Set whether the machine timer interrupt is pending
+    pub fn set_mtip(&mut self, pending: bool) {
+        self.set_pending(MTIP, pending)
+    }
+    /// Set whether the machine external interrupt is pending
+    pub fn set_meip(&mut self, pending: bool) {
+        self.set_pending(MEIP, pending)
+    }
+    /// Set whether the machine timer interrupt is pending by comparing a platform's mtime against mtimecmp.
+    /// mtime is also recorded for the time CSR
+    pub fn update_timer(&mut self, mtime: u64, mtimecmp: u64) {
+        self.set_time(mtime);
+        self.set_mtip(mtime >= mtimecmp)
+    }
+    /// Set the value of the platform's real-time counter read by the time CSR
+    pub fn set_time(&mut self, mtime: u64) {
+        self.mtime = Register64(mtime.to_le_bytes())
+    }
+    /// Write the event selector of a programmable counter, from 3 to 31, as software would.
+    /// Codes of unsupported events are replaced by 0, stopping the counter
+    pub fn write_mhpmevent(&mut self, counter: usize, value: R) {
+        let code = value.byte();
+        let supported = value.eq(R::zero_extended_byte(code)) && HpmEvent::from_code(code).is_some();
+        self.mhpmevent[counter - 3] = if supported { code } else { 0 }
+    }
+    /// Count an occurrence of an event in every programmable counter which selects it
+    #[inline(always)]
+    pub fn count_event(&mut self, event: HpmEvent) {
+        if self.mhpmevent == [0; 29] {
+            return
+        }
+        for (counter, _) in self.mhpmcounter.iter_mut().zip(&self.mhpmevent).filter(|(_, &code)| code == event as u8) {
+            *counter = counter.add_unsigned(Register64::zero_extended_byte(1))
+        }
+    }
+    /// The number of cycles the hart has executed, which a platform may use as the base for mtime
+    pub fn cycles(&self) -> u64 {
+        u64::from_le_bytes(self.mcycle.0)
+    }
+    /// The exception code of the highest priority interrupt which is pending and enabled, if interrupts are globally enabled.
+    /// Machine interrupts are always globally enabled in user mode.
+    /// External interrupts take priority over software interrupts, which take priority over timer interrupts
+    pub fn pending_interrupt(&self) -> Option<u8> {
+        if self.privilege == Privilege::Machine && !self.interrupts_enabled() {
+            return None
+        }
+        let pending = u16::from_le_bytes(self.mip.and(self.mie).half());
+        [MEIP, MSIP, MTIP].iter().copied().find(|bit| pending & 1 << bit != 0)
+    }
+    fn set_pending(&mut self, bit: u8, pending: bool) {
+        let bit = R::zero_extended_half((1u16 << bit).to_le_bytes());
+        self.mip = if pending { self.mip.or(bit) } else { self.mip.and(bit.not()) }
+    }
 }
\ No newline at end of file
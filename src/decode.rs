@@ -0,0 +1,1787 @@
+//! Decoding of instructions into a structured form for inspection and disassembly.
+//!
+//! `decode` accepts exactly the instructions `Core::execute` does for the same register width and features,
+//! so a host can see what the core is about to run before executing it.
+//! ```rust
+//! use rysk_core::{ decode::{ decode, Instruction }, Register32 };
+//! // addi sp, sp, -16
+//! let instruction = decode::<Register32>(0xFF01_0113u32.to_le_bytes()).unwrap();
+//! assert_eq!(instruction, Instruction::Addi { rd: 2, rs1: 2, imm: (-16).into() });
+//! assert_eq!(instruction.to_string(), "addi sp, sp, -16");
+//! ```
+
+use crate::register::{ Register, RegisterWidth };
+use crate::system::Trap;
+use crate::variant::{ self, Variant };
+use core::fmt;
+#[cfg(feature = "alloc")]
+use crate::{ register::Integer, system::Mmu };
+#[cfg(feature = "alloc")]
+use core::ops::Range;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "ext-v")]
+use crate::vector::{ VectorAddressing, VectorKind, VectorMemory, VectorOp, VectorOperand, Vtype };
+
+/// The names of the registers under the standard calling convention, by index
+pub const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+    "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6"
+];
+
+/// The names of the floating-point registers under the standard calling convention, by index
+#[cfg(feature = "ext-f")]
+pub const FP_ABI_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7",
+    "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+    "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7",
+    "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11"
+];
+
+/// The operation of an AMO instruction
+#[cfg(feature = "ext-a")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AmoOp {
+    Swap,
+    Add,
+    Xor,
+    And,
+    Or,
+    Min,
+    Max,
+    Minu,
+    Maxu
+}
+
+/// A decoded instruction.
+/// Registers are indices into the register file and immediates are sign extended as they are used during execution.
+/// The immediates of LUI and AUIPC are already shifted into the upper bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Instruction<R: Register> {
+    Add { rd: usize, rs1: usize, rs2: usize },
+    Sub { rd: usize, rs1: usize, rs2: usize },
+    Slt { rd: usize, rs1: usize, rs2: usize },
+    Sltu { rd: usize, rs1: usize, rs2: usize },
+    Xor { rd: usize, rs1: usize, rs2: usize },
+    Or { rd: usize, rs1: usize, rs2: usize },
+    And { rd: usize, rs1: usize, rs2: usize },
+    Sll { rd: usize, rs1: usize, rs2: usize },
+    Srl { rd: usize, rs1: usize, rs2: usize },
+    Sra { rd: usize, rs1: usize, rs2: usize },
+    Addw { rd: usize, rs1: usize, rs2: usize },
+    Subw { rd: usize, rs1: usize, rs2: usize },
+    Sllw { rd: usize, rs1: usize, rs2: usize },
+    Srlw { rd: usize, rs1: usize, rs2: usize },
+    Sraw { rd: usize, rs1: usize, rs2: usize },
+
+    Addi { rd: usize, rs1: usize, imm: R },
+    Slti { rd: usize, rs1: usize, imm: R },
+    Sltiu { rd: usize, rs1: usize, imm: R },
+    Xori { rd: usize, rs1: usize, imm: R },
+    Ori { rd: usize, rs1: usize, imm: R },
+    Andi { rd: usize, rs1: usize, imm: R },
+    Addiw { rd: usize, rs1: usize, imm: R },
+    Slli { rd: usize, rs1: usize, shamt: u32 },
+    Srli { rd: usize, rs1: usize, shamt: u32 },
+    Srai { rd: usize, rs1: usize, shamt: u32 },
+    Slliw { rd: usize, rs1: usize, shamt: u32 },
+    Srliw { rd: usize, rs1: usize, shamt: u32 },
+    Sraiw { rd: usize, rs1: usize, shamt: u32 },
+
+    Lui { rd: usize, imm: R },
+    Auipc { rd: usize, imm: R },
+
+    Lb { rd: usize, rs1: usize, imm: R },
+    Lh { rd: usize, rs1: usize, imm: R },
+    Lw { rd: usize, rs1: usize, imm: R },
+    Ld { rd: usize, rs1: usize, imm: R },
+    Lbu { rd: usize, rs1: usize, imm: R },
+    Lhu { rd: usize, rs1: usize, imm: R },
+    Lwu { rd: usize, rs1: usize, imm: R },
+    Ldu { rd: usize, rs1: usize, imm: R },
+    Lq { rd: usize, rs1: usize, imm: R },
+    Sb { rs1: usize, rs2: usize, imm: R },
+    Sh { rs1: usize, rs2: usize, imm: R },
+    Sw { rs1: usize, rs2: usize, imm: R },
+    Sd { rs1: usize, rs2: usize, imm: R },
+    Sq { rs1: usize, rs2: usize, imm: R },
+
+    Jal { rd: usize, imm: R },
+    Jalr { rd: usize, rs1: usize, imm: R },
+    Beq { rs1: usize, rs2: usize, imm: R },
+    Bne { rs1: usize, rs2: usize, imm: R },
+    Blt { rs1: usize, rs2: usize, imm: R },
+    Bge { rs1: usize, rs2: usize, imm: R },
+    Bltu { rs1: usize, rs2: usize, imm: R },
+    Bgeu { rs1: usize, rs2: usize, imm: R },
+
+    /// FENCE, with the fence mode and the predecessor and successor sets as the bits IORW
+    Fence { fm: u8, pred: u8, succ: u8 },
+    FenceI,
+    /// CBO.CLEAN, CBO.FLUSH, CBO.INVAL and CBO.ZERO on the cache block holding the address in rs1
+    #[cfg(feature = "ext-zicbom")]
+    CboClean { rs1: usize },
+    #[cfg(feature = "ext-zicbom")]
+    CboFlush { rs1: usize },
+    #[cfg(feature = "ext-zicbom")]
+    CboInval { rs1: usize },
+    #[cfg(feature = "ext-zicboz")]
+    CboZero { rs1: usize },
+    Ecall,
+    Ebreak,
+    #[cfg(feature = "ext-csr")]
+    Mret,
+    #[cfg(feature = "ext-csr")]
+    Wfi,
+
+    #[cfg(feature = "ext-csr")]
+    Csrrw { rd: usize, rs1: usize, csr: usize },
+    #[cfg(feature = "ext-csr")]
+    Csrrs { rd: usize, rs1: usize, csr: usize },
+    #[cfg(feature = "ext-csr")]
+    Csrrc { rd: usize, rs1: usize, csr: usize },
+    #[cfg(feature = "ext-csr")]
+    Csrrwi { rd: usize, uimm: u8, csr: usize },
+    #[cfg(feature = "ext-csr")]
+    Csrrsi { rd: usize, uimm: u8, csr: usize },
+    #[cfg(feature = "ext-csr")]
+    Csrrci { rd: usize, uimm: u8, csr: usize },
+
+    #[cfg(feature = "ext-zmmul")]
+    Mul { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zmmul")]
+    Mulh { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zmmul")]
+    Mulhsu { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zmmul")]
+    Mulhu { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Div { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Divu { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Rem { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Remu { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zmmul")]
+    Mulw { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Divw { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Divuw { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Remw { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-m")]
+    Remuw { rd: usize, rs1: usize, rs2: usize },
+
+    /// LR.W, or LR.D if `double`
+    #[cfg(feature = "ext-a")]
+    Lr { rd: usize, rs1: usize, double: bool, aq: bool, rl: bool },
+    /// SC.W, or SC.D if `double`
+    #[cfg(feature = "ext-a")]
+    Sc { rd: usize, rs1: usize, rs2: usize, double: bool, aq: bool, rl: bool },
+    /// The word AMO `op`, or the double if `double`
+    #[cfg(feature = "ext-a")]
+    Amo { op: AmoOp, rd: usize, rs1: usize, rs2: usize, double: bool, aq: bool, rl: bool },
+
+    #[cfg(feature = "ext-zbkb")]
+    Pack { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbkb")]
+    Packw { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbkb")]
+    Packh { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbkb")]
+    Brev8 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zbkb")]
+    Zip { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zbkb")]
+    Unzip { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zbkx")]
+    Xperm4 { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbkx")]
+    Xperm8 { rd: usize, rs1: usize, rs2: usize },
+
+    #[cfg(feature = "ext-zbc")]
+    Clmul { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbc")]
+    Clmulh { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbc")]
+    Clmulr { rd: usize, rs1: usize, rs2: usize },
+
+    /// The SHA-256 functions, and on RV64 the SHA-512 functions
+    #[cfg(feature = "ext-zknh")]
+    Sha256sum0 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha256sum1 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha256sig0 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha256sig1 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sum0 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sum1 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sig0 { rd: usize, rs1: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sig1 { rd: usize, rs1: usize },
+    /// One word of a SHA-512 function on RV32
+    #[cfg(feature = "ext-zknh")]
+    Sha512sum0r { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sum1r { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sig0l { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sig1l { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sig0h { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zknh")]
+    Sha512sig1h { rd: usize, rs1: usize, rs2: usize },
+
+    #[cfg(feature = "ext-zbs")]
+    Bset { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbs")]
+    Bclr { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbs")]
+    Binv { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zbs")]
+    Bext { rd: usize, rs1: usize, rs2: usize },
+    /// BSETI, with the bit index held in shamt as for BCLRI, BINVI and BEXTI
+    #[cfg(feature = "ext-zbs")]
+    Bseti { rd: usize, rs1: usize, shamt: u32 },
+    #[cfg(feature = "ext-zbs")]
+    Bclri { rd: usize, rs1: usize, shamt: u32 },
+    #[cfg(feature = "ext-zbs")]
+    Binvi { rd: usize, rs1: usize, shamt: u32 },
+    #[cfg(feature = "ext-zbs")]
+    Bexti { rd: usize, rs1: usize, shamt: u32 },
+
+    #[cfg(feature = "ext-zawrs")]
+    WrsNto,
+    #[cfg(feature = "ext-zawrs")]
+    WrsSto,
+
+    // Floating-point registers are indices into the floating-point register file and `rm` is the encoded rounding mode
+    #[cfg(feature = "ext-f")]
+    Flw { rd: usize, rs1: usize, imm: R },
+    #[cfg(feature = "ext-f")]
+    Fsw { rs1: usize, rs2: usize, imm: R },
+    #[cfg(feature = "ext-f")]
+    FmaddS { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FmsubS { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FnmsubS { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FnmaddS { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FaddS { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FsubS { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FmulS { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FdivS { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FsqrtS { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FsgnjS { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-f")]
+    FsgnjnS { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-f")]
+    FsgnjxS { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-f")]
+    FminS { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-f")]
+    FmaxS { rd: usize, rs1: usize, rs2: usize },
+    /// FCVT.W.S, with an integer destination
+    #[cfg(feature = "ext-f")]
+    FcvtWS { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FcvtWuS { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FcvtLS { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FcvtLuS { rd: usize, rs1: usize, rm: u8 },
+    /// FCVT.S.W, with an integer source
+    #[cfg(feature = "ext-f")]
+    FcvtSW { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FcvtSWu { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FcvtSL { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-f")]
+    FcvtSLu { rd: usize, rs1: usize, rm: u8 },
+    /// FMV.X.W, with an integer destination
+    #[cfg(feature = "ext-f")]
+    FmvXW { rd: usize, rs1: usize },
+    /// FMV.W.X, with an integer source
+    #[cfg(feature = "ext-f")]
+    FmvWX { rd: usize, rs1: usize },
+    /// FCLASS.S, with an integer destination
+    #[cfg(feature = "ext-f")]
+    FclassS { rd: usize, rs1: usize },
+    /// FEQ.S, with an integer destination as for FLT.S and FLE.S
+    #[cfg(feature = "ext-f")]
+    FeqS { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-f")]
+    FltS { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-f")]
+    FleS { rd: usize, rs1: usize, rs2: usize },
+
+    #[cfg(feature = "ext-d")]
+    Fld { rd: usize, rs1: usize, imm: R },
+    #[cfg(feature = "ext-d")]
+    Fsd { rs1: usize, rs2: usize, imm: R },
+    #[cfg(feature = "ext-d")]
+    FmaddD { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FmsubD { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FnmsubD { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FnmaddD { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FaddD { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FsubD { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FmulD { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FdivD { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FsqrtD { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FsgnjD { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-d")]
+    FsgnjnD { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-d")]
+    FsgnjxD { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-d")]
+    FminD { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-d")]
+    FmaxD { rd: usize, rs1: usize, rs2: usize },
+    /// FCVT.S.D, narrowing a double to a single
+    #[cfg(feature = "ext-d")]
+    FcvtSD { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtDS { rd: usize, rs1: usize, rm: u8 },
+    /// FCVT.W.D, with an integer destination
+    #[cfg(feature = "ext-d")]
+    FcvtWD { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtWuD { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtLD { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtLuD { rd: usize, rs1: usize, rm: u8 },
+    /// FCVT.D.W, with an integer source
+    #[cfg(feature = "ext-d")]
+    FcvtDW { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtDWu { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtDL { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-d")]
+    FcvtDLu { rd: usize, rs1: usize, rm: u8 },
+    /// FMV.X.D, with an integer destination
+    #[cfg(feature = "ext-d")]
+    FmvXD { rd: usize, rs1: usize },
+    /// FMV.D.X, with an integer source
+    #[cfg(feature = "ext-d")]
+    FmvDX { rd: usize, rs1: usize },
+    /// FCLASS.D, with an integer destination
+    #[cfg(feature = "ext-d")]
+    FclassD { rd: usize, rs1: usize },
+    /// FEQ.D, with an integer destination as for FLT.D and FLE.D
+    #[cfg(feature = "ext-d")]
+    FeqD { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-d")]
+    FltD { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-d")]
+    FleD { rd: usize, rs1: usize, rs2: usize },
+
+    #[cfg(feature = "ext-zfh")]
+    Flh { rd: usize, rs1: usize, imm: R },
+    #[cfg(feature = "ext-zfh")]
+    Fsh { rs1: usize, rs2: usize, imm: R },
+    #[cfg(feature = "ext-zfh")]
+    FmaddH { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FmsubH { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FnmsubH { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FnmaddH { rd: usize, rs1: usize, rs2: usize, rs3: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FaddH { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FsubH { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FmulH { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FdivH { rd: usize, rs1: usize, rs2: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FsqrtH { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FsgnjH { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zfh")]
+    FsgnjnH { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zfh")]
+    FsgnjxH { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zfh")]
+    FminH { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zfh")]
+    FmaxH { rd: usize, rs1: usize, rs2: usize },
+    /// FCVT.S.H, widening a half to a single
+    #[cfg(feature = "ext-zfh")]
+    FcvtSH { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtHS { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+    FcvtDH { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+    FcvtHD { rd: usize, rs1: usize, rm: u8 },
+    /// FCVT.W.H, with an integer destination
+    #[cfg(feature = "ext-zfh")]
+    FcvtWH { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtWuH { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtLH { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtLuH { rd: usize, rs1: usize, rm: u8 },
+    /// FCVT.H.W, with an integer source
+    #[cfg(feature = "ext-zfh")]
+    FcvtHW { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtHWu { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtHL { rd: usize, rs1: usize, rm: u8 },
+    #[cfg(feature = "ext-zfh")]
+    FcvtHLu { rd: usize, rs1: usize, rm: u8 },
+    /// FMV.X.H, with an integer destination
+    #[cfg(feature = "ext-zfh")]
+    FmvXH { rd: usize, rs1: usize },
+    /// FMV.H.X, with an integer source
+    #[cfg(feature = "ext-zfh")]
+    FmvHX { rd: usize, rs1: usize },
+    /// FCLASS.H, with an integer destination
+    #[cfg(feature = "ext-zfh")]
+    FclassH { rd: usize, rs1: usize },
+    /// FEQ.H, with an integer destination as for FLT.H and FLE.H
+    #[cfg(feature = "ext-zfh")]
+    FeqH { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zfh")]
+    FltH { rd: usize, rs1: usize, rs2: usize },
+    #[cfg(feature = "ext-zfh")]
+    FleH { rd: usize, rs1: usize, rs2: usize },
+    /// VSETVLI, with the raw bits of vtype which may be reserved
+    #[cfg(feature = "ext-v")]
+    Vsetvli { rd: usize, rs1: usize, vtype: u32 },
+    /// VSETIVLI, with the AVL as an immediate
+    #[cfg(feature = "ext-v")]
+    Vsetivli { rd: usize, avl: u32, vtype: u32 },
+    #[cfg(feature = "ext-v")]
+    Vsetvl { rd: usize, rs1: usize, rs2: usize },
+    /// An integer vector arithmetic instruction, which is masked by v0 if `vm` is clear
+    #[cfg(feature = "ext-v")]
+    Vector { op: VectorOp, vd: usize, vs2: usize, operand: VectorOperand<usize>, vm: bool },
+    /// VMV.X.S, with an integer destination
+    #[cfg(feature = "ext-v")]
+    VmvXS { rd: usize, vs2: usize },
+    /// VMV.S.X, with an integer source
+    #[cfg(feature = "ext-v")]
+    VmvSX { vd: usize, rs1: usize },
+    /// VCPOP.M, with an integer destination
+    #[cfg(feature = "ext-v")]
+    Vcpop { rd: usize, vs2: usize, vm: bool },
+    /// VFIRST.M, with an integer destination
+    #[cfg(feature = "ext-v")]
+    Vfirst { rd: usize, vs2: usize, vm: bool },
+    #[cfg(feature = "ext-v")]
+    Vid { vd: usize, vm: bool },
+    /// A vector load, where `rs2` is the register holding the stride of a strided load or the vector register holding
+    /// the offsets of an indexed load, and is otherwise unused
+    #[cfg(feature = "ext-v")]
+    VectorLoad { access: VectorMemory, vd: usize, rs1: usize, rs2: usize, vm: bool },
+    /// A vector store, with `rs2` as for `VectorLoad`
+    #[cfg(feature = "ext-v")]
+    VectorStore { access: VectorMemory, vs3: usize, rs1: usize, rs2: usize, vm: bool }
+}
+
+/// The role of an instruction in control flow, as inferred from the standard calling convention's use of `ra`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ControlFlowKind {
+    /// A JAL or JALR linking `ra`
+    Call,
+    /// A JALR to an address other than `ra` without linking, such as a call made in place of returning
+    TailCall,
+    /// A JALR to `ra` without linking
+    Return,
+    /// A conditional branch
+    Branch,
+    /// Any other JAL or JALR, such as an unconditional jump within a function
+    Jump,
+    /// Execution always continues with the following instruction, unless the instruction traps
+    None
+}
+
+impl<R: Register> Instruction<R> {
+    /// Classify the instruction for building a call graph.
+    /// A JALR without a link through a register other than `ra` is assumed to be a tail call, though it may also be
+    /// an indirect jump within a function, such as through a jump table
+    /// ```rust
+    /// use rysk_core::{ decode::{ decode, ControlFlowKind }, Register32 };
+    /// // jalr zero, 0(ra)
+    /// assert_eq!(decode::<Register32>(0x0000_8067u32.to_le_bytes()).unwrap().control_flow_kind(), ControlFlowKind::Return);
+    /// ```
+    pub fn control_flow_kind(&self) -> ControlFlowKind {
+        use Instruction::*;
+        const RA: usize = 1;
+        match *self {
+            Jal { rd: RA, .. } | Jalr { rd: RA, .. } => ControlFlowKind::Call,
+            Jalr { rd: 0, rs1: RA, .. } => ControlFlowKind::Return,
+            Jalr { rd: 0, .. } => ControlFlowKind::TailCall,
+            Jal { .. } | Jalr { .. } => ControlFlowKind::Jump,
+            Beq { .. } | Bne { .. } | Blt { .. } | Bge { .. } | Bltu { .. } | Bgeu { .. } => ControlFlowKind::Branch,
+            _ => ControlFlowKind::None
+        }
+    }
+
+    /// The address a JAL or conditional branch at `address` transfers control to, which is known without executing it.
+    /// `None` for JALR, whose target depends on a register, and for every other instruction
+    pub fn target(&self, address: R) -> Option<R> {
+        use Instruction::*;
+        match *self {
+            Jal { imm, .. } | Beq { imm, .. } | Bne { imm, .. } | Blt { imm, .. } | Bge { imm, .. } | Bltu { imm, .. } | Bgeu { imm, .. } => Some(address.add_signed(imm)),
+            _ => None
+        }
+    }
+}
+
+/// A problem found in a program image by `validate`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Diagnostic<R: Register> {
+    /// The instruction at the address could not be fetched
+    FetchFault(R),
+    /// The instruction at the address is illegal
+    IllegalInstruction(R),
+    /// The jump or branch at `address` has a static target outside of the validated range
+    TargetOutOfRange { address: R, target: R }
+}
+
+/// Walk the instructions within `range`, reporting any which are illegal or jump or branch outside of the range.
+/// Instructions are decoded in sequence, so data embedded in the range is reported if it does not decode.
+/// With the C extension instructions may be compressed, otherwise every instruction is 4 bytes.
+/// ```rust
+/// use rysk_core::{ ArrayRam, Register32, decode::{ validate, Diagnostic } };
+/// let mut memory = ArrayRam::<8>::new(0);
+/// // jal zero, 8; then an illegal all-ones word
+/// memory.load(0, &0x0080_006Fu32.to_le_bytes());
+/// memory.load(4, &u32::MAX.to_le_bytes());
+/// assert_eq!(validate::<Register32>(&memory, 0..8), [
+///     Diagnostic::TargetOutOfRange { address: 0u32.into(), target: 8u32.into() },
+///     Diagnostic::IllegalInstruction(4u32.into())
+/// ]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn validate<R: Register>(mmu: &dyn Mmu<R>, range: Range<R::Unsigned>) -> Vec<Diagnostic<R>> {
+    let in_range = |address: R| address.unsigned().gte(range.start) && address.unsigned().lt(range.end);
+    let mut diagnostics = Vec::new();
+    let mut address = R::from_unsigned(range.start);
+    while in_range(address) {
+        let parcel = match mmu.fetch_parcel(address) {
+            Ok(parcel) => parcel,
+            Err(_) => {
+                diagnostics.push(Diagnostic::FetchFault(address));
+                break
+            }
+        };
+        #[cfg(feature = "ext-c")]
+        let (instruction, length) = if crate::compressed::is_compressed(parcel) {
+            (crate::compressed::expand::<R>(parcel).ok_or(()), 2)
+        } else {
+            (mmu.fetch(address).map_err(|_| ()), 4)
+        };
+        #[cfg(not(feature = "ext-c"))]
+        let (instruction, length) = {
+            let _ = parcel;
+            (mmu.fetch(address).map_err(|_| ()), 4)
+        };
+        match instruction.map(decode::<R>) {
+            Err(()) if length == 4 => diagnostics.push(Diagnostic::FetchFault(address)),
+            Err(()) | Ok(Err(_)) => diagnostics.push(Diagnostic::IllegalInstruction(address)),
+            Ok(Ok(decoded)) => if let Some(target) = decoded.target(address) {
+                if !in_range(target) {
+                    diagnostics.push(Diagnostic::TargetOutOfRange { address, target })
+                }
+            }
+        }
+        address = R::from_unsigned(address.append(length))
+    }
+    diagnostics
+}
+
+/// Decode a 32-bit instruction for the register width `R`.
+/// Compressed instructions may be decoded once expanded by `compressed::expand`.
+///
+/// Returns `Trap::IllegalInstruction` for any encoding `Core::execute` would trap on as illegal, except for accesses
+/// to CSRs which do not exist, the dynamic rounding mode and vector instructions illegal under the current vtype, as
+/// those depend on the state of the core.
+#[allow(clippy::cognitive_complexity)]
+pub fn decode<R: Register>(instruction: [u8; 4]) -> Result<Instruction<R>, Trap> {
+    use Instruction::*;
+    let rv32 = R::WIDTH == RegisterWidth::Bits32;
+    let rv128 = R::WIDTH == RegisterWidth::Bits128;
+    let opcode = instruction[0] & 0x7F;
+    let funct3 = (instruction[1] & 0x70) >> 4;
+    let funct7 = (instruction[3] & 0xFE) >> 1;
+
+    macro_rules! r {
+        ($name:ident) => {{
+            let variant::R { destination, source1, source2 } = Variant::decode(instruction);
+            $name { rd: destination, rs1: source1, rs2: source2 }
+        }};
+    }
+    macro_rules! i {
+        ($name:ident) => {{
+            let variant::I { destination, source, immediate } = Variant::decode(instruction);
+            $name { rd: destination, rs1: source, imm: immediate }
+        }};
+    }
+    macro_rules! s {
+        ($name:ident) => {{
+            let variant::S { source1, source2, immediate } = Variant::decode(instruction);
+            $name { rs1: source1, rs2: source2, imm: immediate }
+        }};
+    }
+    macro_rules! b {
+        ($name:ident) => {{
+            let variant::B { source1, source2, immediate } = Variant::decode(instruction);
+            $name { rs1: source1, rs2: source2, imm: immediate }
+        }};
+    }
+    // Shift immediates reaching beyond the register are reserved, as are immediates whose bits above the shift amount
+    // differ from `$funct`, which holds the bits selecting the instruction such as bit 10 of SRAI
+    macro_rules! shift {
+        ($name:ident, $bits:expr, $funct:expr) => {{
+            let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
+            let mask = if $bits == 128 { 0x7F } else { 0x3F };
+            let immediate = u32::from_le_bytes(immediate.word()) & 0xFFF;
+            let shamt = immediate & mask;
+            if shamt >= $bits || immediate & !mask != $funct {
+                return Err(Trap::IllegalInstruction)
+            }
+            $name { rd: destination, rs1: source, shamt }
+        }};
+    }
+    #[cfg(feature = "ext-csr")]
+    macro_rules! csr {
+        ($name:ident) => {{
+            let variant::C { destination, source, csr } = Variant::decode(instruction);
+            $name { rd: destination, rs1: source, csr }
+        }};
+        ($name:ident, immediate) => {{
+            let variant::C { destination, source, csr } = Variant::decode(instruction);
+            $name { rd: destination, uimm: source as u8, csr }
+        }};
+    }
+    let width = match R::WIDTH {
+        RegisterWidth::Bits32 => 32,
+        RegisterWidth::Bits64 => 64,
+        RegisterWidth::Bits128 => 128
+    };
+
+    Ok(match (opcode, funct3, funct7) {
+        (0b0110011, 0b000, 0b0000000) => r!(Add),
+        (0b0111011, 0b000, 0b0000000) if !rv32 => r!(Addw),
+        (0b0110011, 0b000, 0b0100000) => r!(Sub),
+        (0b0111011, 0b000, 0b0100000) if !rv32 => r!(Subw),
+        (0b0110011, 0b010, 0b0000000) => r!(Slt),
+        (0b0110011, 0b011, 0b0000000) => r!(Sltu),
+        (0b0010011, 0b000, _) => i!(Addi),
+        (0b0011011, 0b000, _) if !rv32 => i!(Addiw),
+        (0b0010011, 0b010, _) => i!(Slti),
+        (0b0010011, 0b011, _) => i!(Sltiu),
+        (0b0110011, 0b100, 0b0000000) => r!(Xor),
+        (0b0110011, 0b110, 0b0000000) => r!(Or),
+        (0b0110011, 0b111, 0b0000000) => r!(And),
+        (0b0010011, 0b100, _) => i!(Xori),
+        (0b0010011, 0b110, _) => i!(Ori),
+        (0b0010011, 0b111, _) => i!(Andi),
+        (0b0110011, 0b001, 0b0000000) => r!(Sll),
+        (0b0111011, 0b001, 0b0000000) if !rv32 => r!(Sllw),
+        (0b0110011, 0b101, 0b0000000) => r!(Srl),
+        (0b0111011, 0b101, 0b0000000) if !rv32 => r!(Srlw),
+        (0b0110011, 0b101, 0b0100000) => r!(Sra),
+        (0b0111011, 0b101, 0b0100000) if !rv32 => r!(Sraw),
+
+        #[cfg(feature = "ext-zbkb")]
+        (0b0110011, 0b100, 0b0000100) => r!(Pack),
+        #[cfg(feature = "ext-zbkb")]
+        (0b0111011, 0b100, 0b0000100) if !rv32 => r!(Packw),
+        #[cfg(feature = "ext-zbkb")]
+        (0b0110011, 0b111, 0b0000100) => r!(Packh),
+        #[cfg(feature = "ext-zbkb")]
+        (0b0010011, 0b101, 0b0110100) if variant::R::decode(instruction).source2 == 0b00111 => {
+            let variant::R { destination, source1, .. } = Variant::decode(instruction);
+            Brev8 { rd: destination, rs1: source1 }
+        },
+        #[cfg(feature = "ext-zbkb")]
+        (0b0010011, 0b001, 0b0000100) if variant::R::decode(instruction).source2 == 0b01111 && rv32 => {
+            let variant::R { destination, source1, .. } = Variant::decode(instruction);
+            Zip { rd: destination, rs1: source1 }
+        },
+        #[cfg(feature = "ext-zbkb")]
+        (0b0010011, 0b101, 0b0000100) if variant::R::decode(instruction).source2 == 0b01111 && rv32 => {
+            let variant::R { destination, source1, .. } = Variant::decode(instruction);
+            Unzip { rd: destination, rs1: source1 }
+        },
+        #[cfg(feature = "ext-zbkx")]
+        (0b0110011, 0b010, 0b0010100) => r!(Xperm4),
+        #[cfg(feature = "ext-zbkx")]
+        (0b0110011, 0b100, 0b0010100) => r!(Xperm8),
+
+        #[cfg(feature = "ext-zknh")]
+        (0b0010011, 0b001, 0b0001000) if variant::R::decode(instruction).source2 < if rv32 { 4 } else { 8 } => {
+            let variant::R { destination: rd, source1: rs1, source2 } = Variant::decode(instruction);
+            match source2 {
+                0 => Sha256sum0 { rd, rs1 },
+                1 => Sha256sum1 { rd, rs1 },
+                2 => Sha256sig0 { rd, rs1 },
+                3 => Sha256sig1 { rd, rs1 },
+                4 => Sha512sum0 { rd, rs1 },
+                5 => Sha512sum1 { rd, rs1 },
+                6 => Sha512sig0 { rd, rs1 },
+                _ => Sha512sig1 { rd, rs1 },
+            }
+        },
+        #[cfg(feature = "ext-zknh")]
+        (0b0110011, 0b000, 0b0101000) if rv32 => r!(Sha512sum0r),
+        #[cfg(feature = "ext-zknh")]
+        (0b0110011, 0b000, 0b0101001) if rv32 => r!(Sha512sum1r),
+        #[cfg(feature = "ext-zknh")]
+        (0b0110011, 0b000, 0b0101010) if rv32 => r!(Sha512sig0l),
+        #[cfg(feature = "ext-zknh")]
+        (0b0110011, 0b000, 0b0101011) if rv32 => r!(Sha512sig1l),
+        #[cfg(feature = "ext-zknh")]
+        (0b0110011, 0b000, 0b0101110) if rv32 => r!(Sha512sig0h),
+        #[cfg(feature = "ext-zknh")]
+        (0b0110011, 0b000, 0b0101111) if rv32 => r!(Sha512sig1h),
+        #[cfg(feature = "ext-zbc")]
+        (0b0110011, 0b001, 0b0000101) => r!(Clmul),
+        #[cfg(feature = "ext-zbc")]
+        (0b0110011, 0b011, 0b0000101) => r!(Clmulh),
+        #[cfg(feature = "ext-zbc")]
+        (0b0110011, 0b010, 0b0000101) => r!(Clmulr),
+
+        #[cfg(feature = "ext-zbs")]
+        (0b0110011, 0b001, 0b0010100) => r!(Bset),
+        #[cfg(feature = "ext-zbs")]
+        (0b0110011, 0b001, 0b0100100) => r!(Bclr),
+        #[cfg(feature = "ext-zbs")]
+        (0b0110011, 0b001, 0b0110100) => r!(Binv),
+        #[cfg(feature = "ext-zbs")]
+        (0b0110011, 0b101, 0b0100100) => r!(Bext),
+        #[cfg(feature = "ext-zbs")]
+        (0b0010011, 0b001, _) if funct7 & !1 == 0b0010100 => shift!(Bseti, width, 0x280),
+        #[cfg(feature = "ext-zbs")]
+        (0b0010011, 0b001, _) if funct7 & !1 == 0b0100100 => shift!(Bclri, width, 0x480),
+        #[cfg(feature = "ext-zbs")]
+        (0b0010011, 0b001, _) if funct7 & !1 == 0b0110100 => shift!(Binvi, width, 0x680),
+        #[cfg(feature = "ext-zbs")]
+        (0b0010011, 0b101, _) if funct7 & !1 == 0b0100100 => shift!(Bexti, width, 0x480),
+
+        (0b0010011, 0b001, _) => shift!(Slli, width, 0),
+        (0b0011011, 0b001, _) if !rv32 => shift!(Slliw, 32, 0),
+        (0b0010011, 0b101, _) if instruction[3] & 0x40 == 0 => shift!(Srli, width, 0),
+        (0b0011011, 0b101, _) if instruction[3] & 0x40 == 0 && !rv32 => shift!(Srliw, 32, 0),
+        (0b0010011, 0b101, _) => shift!(Srai, width, 0x400),
+        (0b0011011, 0b101, _) if !rv32 => shift!(Sraiw, 32, 0x400),
+
+        (0b0110111, _, _) => {
+            let variant::U { destination, immediate } = Variant::decode(instruction);
+            Lui { rd: destination, imm: immediate }
+        },
+        (0b0010111, _, _) => {
+            let variant::U { destination, immediate } = Variant::decode(instruction);
+            Auipc { rd: destination, imm: immediate }
+        },
+
+        (0b0000011, 0b000, _) => i!(Lb),
+        (0b0000011, 0b100, _) => i!(Lbu),
+        (0b0000011, 0b001, _) => i!(Lh),
+        (0b0000011, 0b101, _) => i!(Lhu),
+        (0b0000011, 0b010, _) => i!(Lw),
+        (0b0000011, 0b110, _) if !rv32 => i!(Lwu),
+        (0b0000011, 0b011, _) if !rv32 => i!(Ld),
+        (0b0000011, 0b111, _) if rv128 => i!(Ldu),
+        (0b0001111, 0b010, _) if rv128 => i!(Lq),
+        (0b0100011, 0b000, _) => s!(Sb),
+        (0b0100011, 0b001, _) => s!(Sh),
+        (0b0100011, 0b010, _) => s!(Sw),
+        (0b0100011, 0b011, _) if !rv32 => s!(Sd),
+        (0b0100011, 0b100, _) if rv128 => s!(Sq),
+
+        (0b1101111, _, _) => {
+            let variant::J { destination, immediate } = Variant::decode(instruction);
+            Jal { rd: destination, imm: immediate }
+        },
+        (0b1100111, 0b000, _) => i!(Jalr),
+        (0b1100011, 0b000, _) => b!(Beq),
+        (0b1100011, 0b001, _) => b!(Bne),
+        (0b1100011, 0b100, _) => b!(Blt),
+        (0b1100011, 0b110, _) => b!(Bltu),
+        (0b1100011, 0b101, _) => b!(Bge),
+        (0b1100011, 0b111, _) => b!(Bgeu),
+
+        #[cfg(feature = "ext-zawrs")]
+        (0b1110011, 0b000, _) if instruction == [0x73, 0x00, 0xD0, 0x00] => WrsNto,
+        #[cfg(feature = "ext-zawrs")]
+        (0b1110011, 0b000, _) if instruction == [0x73, 0x00, 0xD0, 0x01] => WrsSto,
+        (0b0001111, 0b000, _) => {
+            let bits = u32::from_le_bytes(instruction);
+            Fence { fm: (bits >> 28) as u8, pred: (bits >> 24) as u8 & 0xF, succ: (bits >> 20) as u8 & 0xF }
+        },
+        (0b0001111, 0b001, _) => FenceI,
+        #[cfg(any(feature = "ext-zicbom", feature = "ext-zicboz"))]
+        (0b0001111, 0b010, _) => {
+            let variant::R { destination, source1, .. } = Variant::decode(instruction);
+            match u32::from_le_bytes(instruction) >> 20 {
+                #[cfg(feature = "ext-zicbom")]
+                0 if destination == 0 => CboClean { rs1: source1 },
+                #[cfg(feature = "ext-zicbom")]
+                1 if destination == 0 => CboFlush { rs1: source1 },
+                #[cfg(feature = "ext-zicbom")]
+                2 if destination == 0 => CboInval { rs1: source1 },
+                #[cfg(feature = "ext-zicboz")]
+                4 if destination == 0 => CboZero { rs1: source1 },
+                _ => return Err(Trap::IllegalInstruction)
+            }
+        },
+        (0b1110011, 0b000, _) if instruction == [0x73, 0x00, 0x00, 0x00] => Ecall,
+        (0b1110011, 0b000, _) if instruction == [0x73, 0x00, 0x10, 0x00] => Ebreak,
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b000, _) if instruction == [0x73, 0x00, 0x20, 0x30] => Mret,
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b000, _) if instruction == [0x73, 0x00, 0x50, 0x10] => Wfi,
+
+        #[cfg(feature = "ext-zmmul")]
+        (0b0110011, 0b000, 0b0000001) => r!(Mul),
+        #[cfg(feature = "ext-zmmul")]
+        (0b0110011, 0b001, 0b0000001) => r!(Mulh),
+        #[cfg(feature = "ext-zmmul")]
+        (0b0110011, 0b010, 0b0000001) => r!(Mulhsu),
+        #[cfg(feature = "ext-zmmul")]
+        (0b0110011, 0b011, 0b0000001) => r!(Mulhu),
+        #[cfg(feature = "ext-zmmul")]
+        (0b0111011, 0b000, 0b0000001) if !rv32 => r!(Mulw),
+        #[cfg(feature = "ext-m")]
+        (0b0110011, 0b100, 0b0000001) => r!(Div),
+        #[cfg(feature = "ext-m")]
+        (0b0110011, 0b101, 0b0000001) => r!(Divu),
+        #[cfg(feature = "ext-m")]
+        (0b0111011, 0b100, 0b0000001) if !rv32 => r!(Divw),
+        #[cfg(feature = "ext-m")]
+        (0b0111011, 0b101, 0b0000001) if !rv32 => r!(Divuw),
+        #[cfg(feature = "ext-m")]
+        (0b0110011, 0b110, 0b0000001) => r!(Rem),
+        #[cfg(feature = "ext-m")]
+        (0b0110011, 0b111, 0b0000001) => r!(Remu),
+        #[cfg(feature = "ext-m")]
+        (0b0111011, 0b110, 0b0000001) if !rv32 => r!(Remw),
+        #[cfg(feature = "ext-m")]
+        (0b0111011, 0b111, 0b0000001) if !rv32 => r!(Remuw),
+
+        #[cfg(feature = "ext-a")]
+        (0b0101111, 0b010..=0b011, _) if funct3 == 0b010 || !rv32 => {
+            let variant::R { destination: rd, source1: rs1, source2: rs2 } = Variant::decode(instruction);
+            let double = funct3 == 0b011;
+            let (aq, rl) = (funct7 & 0b10 != 0, funct7 & 0b01 != 0);
+            let op = match funct7 >> 2 {
+                0b00010 if rs2 == 0 => return Ok(Lr { rd, rs1, double, aq, rl }),
+                0b00011 => return Ok(Sc { rd, rs1, rs2, double, aq, rl }),
+                0b00001 => AmoOp::Swap,
+                0b00000 => AmoOp::Add,
+                0b00100 => AmoOp::Xor,
+                0b01100 => AmoOp::And,
+                0b01000 => AmoOp::Or,
+                0b10000 => AmoOp::Min,
+                0b10100 => AmoOp::Max,
+                0b11000 => AmoOp::Minu,
+                0b11100 => AmoOp::Maxu,
+                _ => return Err(Trap::IllegalInstruction)
+            };
+            Amo { op, rd, rs1, rs2, double, aq, rl }
+        },
+
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b001, _) => csr!(Csrrw),
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b010, _) => csr!(Csrrs),
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b011, _) => csr!(Csrrc),
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b101, _) => csr!(Csrrwi, immediate),
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b110, _) => csr!(Csrrsi, immediate),
+        #[cfg(feature = "ext-csr")]
+        (0b1110011, 0b111, _) => csr!(Csrrci, immediate),
+
+        #[cfg(feature = "ext-f")]
+        (0b0000111, 0b010, _) => i!(Flw),
+        #[cfg(feature = "ext-f")]
+        (0b0100111, 0b010, _) => s!(Fsw),
+        #[cfg(feature = "ext-f")]
+        (0b1000011, _, _) | (0b1000111, _, _) | (0b1001011, _, _) | (0b1001111, _, _) if funct7 & 0b11 == 0 => {
+            let variant::R4 { destination: rd, source1: rs1, source2: rs2, source3: rs3 } = Variant::decode(instruction);
+            let rm = rounding_mode(funct3)?;
+            match opcode {
+                0b1000011 => FmaddS { rd, rs1, rs2, rs3, rm },
+                0b1000111 => FmsubS { rd, rs1, rs2, rs3, rm },
+                0b1001011 => FnmsubS { rd, rs1, rs2, rs3, rm },
+                _ => FnmaddS { rd, rs1, rs2, rs3, rm }
+            }
+        },
+        #[cfg(feature = "ext-d")]
+        (0b0000111, 0b011, _) => i!(Fld),
+        #[cfg(feature = "ext-d")]
+        (0b0100111, 0b011, _) => s!(Fsd),
+        #[cfg(feature = "ext-d")]
+        (0b1000011, _, _) | (0b1000111, _, _) | (0b1001011, _, _) | (0b1001111, _, _) if funct7 & 0b11 == 1 => {
+            let variant::R4 { destination: rd, source1: rs1, source2: rs2, source3: rs3 } = Variant::decode(instruction);
+            let rm = rounding_mode(funct3)?;
+            match opcode {
+                0b1000011 => FmaddD { rd, rs1, rs2, rs3, rm },
+                0b1000111 => FmsubD { rd, rs1, rs2, rs3, rm },
+                0b1001011 => FnmsubD { rd, rs1, rs2, rs3, rm },
+                _ => FnmaddD { rd, rs1, rs2, rs3, rm }
+            }
+        },
+        #[cfg(feature = "ext-zfh")]
+        (0b0000111, 0b001, _) => i!(Flh),
+        #[cfg(feature = "ext-zfh")]
+        (0b0100111, 0b001, _) => s!(Fsh),
+        #[cfg(feature = "ext-zfh")]
+        (0b1000011, _, _) | (0b1000111, _, _) | (0b1001011, _, _) | (0b1001111, _, _) if funct7 & 0b11 == 0b10 => {
+            let variant::R4 { destination: rd, source1: rs1, source2: rs2, source3: rs3 } = Variant::decode(instruction);
+            let rm = rounding_mode(funct3)?;
+            match opcode {
+                0b1000011 => FmaddH { rd, rs1, rs2, rs3, rm },
+                0b1000111 => FmsubH { rd, rs1, rs2, rs3, rm },
+                0b1001011 => FnmsubH { rd, rs1, rs2, rs3, rm },
+                _ => FnmaddH { rd, rs1, rs2, rs3, rm }
+            }
+        },
+        #[cfg(feature = "ext-f")]
+        (0b1010011, _, _) => {
+            let variant::R { destination: rd, source1: rs1, source2: rs2 } = Variant::decode(instruction);
+            // Conversions select the integer type with rs2, of which the double-word types are only present in RV64
+            let integer = if rv32 { 0..2 } else { 0..4 };
+            match (funct7, funct3) {
+                (0b0000000, _) => FaddS { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                (0b0000100, _) => FsubS { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                (0b0001000, _) => FmulS { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                (0b0001100, _) => FdivS { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                (0b0101100, _) if rs2 == 0 => FsqrtS { rd, rs1, rm: rounding_mode(funct3)? },
+                (0b0010000, 0b000) => FsgnjS { rd, rs1, rs2 },
+                (0b0010000, 0b001) => FsgnjnS { rd, rs1, rs2 },
+                (0b0010000, 0b010) => FsgnjxS { rd, rs1, rs2 },
+                (0b0010100, 0b000) => FminS { rd, rs1, rs2 },
+                (0b0010100, 0b001) => FmaxS { rd, rs1, rs2 },
+                (0b1100000, _) if integer.contains(&rs2) => {
+                    let rm = rounding_mode(funct3)?;
+                    [FcvtWS { rd, rs1, rm }, FcvtWuS { rd, rs1, rm }, FcvtLS { rd, rs1, rm }, FcvtLuS { rd, rs1, rm }][rs2]
+                },
+                (0b1101000, _) if integer.contains(&rs2) => {
+                    let rm = rounding_mode(funct3)?;
+                    [FcvtSW { rd, rs1, rm }, FcvtSWu { rd, rs1, rm }, FcvtSL { rd, rs1, rm }, FcvtSLu { rd, rs1, rm }][rs2]
+                },
+                (0b1110000, 0b000) if rs2 == 0 => FmvXW { rd, rs1 },
+                (0b1110000, 0b001) if rs2 == 0 => FclassS { rd, rs1 },
+                (0b1111000, 0b000) if rs2 == 0 => FmvWX { rd, rs1 },
+                (0b1010000, 0b010) => FeqS { rd, rs1, rs2 },
+                (0b1010000, 0b001) => FltS { rd, rs1, rs2 },
+                (0b1010000, 0b000) => FleS { rd, rs1, rs2 },
+
+                #[cfg(feature = "ext-d")]
+                (0b0000001, _) => FaddD { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b0000101, _) => FsubD { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b0001001, _) => FmulD { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b0001101, _) => FdivD { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b0101101, _) if rs2 == 0 => FsqrtD { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b0010001, 0b000) => FsgnjD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b0010001, 0b001) => FsgnjnD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b0010001, 0b010) => FsgnjxD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b0010101, 0b000) => FminD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b0010101, 0b001) => FmaxD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b0100000, _) if rs2 == 1 => FcvtSD { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b0100001, _) if rs2 == 0 => FcvtDS { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-d")]
+                (0b1100001, _) if integer.contains(&rs2) => {
+                    let rm = rounding_mode(funct3)?;
+                    [FcvtWD { rd, rs1, rm }, FcvtWuD { rd, rs1, rm }, FcvtLD { rd, rs1, rm }, FcvtLuD { rd, rs1, rm }][rs2]
+                },
+                #[cfg(feature = "ext-d")]
+                (0b1101001, _) if integer.contains(&rs2) => {
+                    let rm = rounding_mode(funct3)?;
+                    [FcvtDW { rd, rs1, rm }, FcvtDWu { rd, rs1, rm }, FcvtDL { rd, rs1, rm }, FcvtDLu { rd, rs1, rm }][rs2]
+                },
+                // The moves between a double and an integer register are only present in RV64
+                #[cfg(feature = "ext-d")]
+                (0b1110001, 0b000) if rs2 == 0 && !rv32 => FmvXD { rd, rs1 },
+                #[cfg(feature = "ext-d")]
+                (0b1110001, 0b001) if rs2 == 0 => FclassD { rd, rs1 },
+                #[cfg(feature = "ext-d")]
+                (0b1111001, 0b000) if rs2 == 0 && !rv32 => FmvDX { rd, rs1 },
+                #[cfg(feature = "ext-d")]
+                (0b1010001, 0b010) => FeqD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b1010001, 0b001) => FltD { rd, rs1, rs2 },
+                #[cfg(feature = "ext-d")]
+                (0b1010001, 0b000) => FleD { rd, rs1, rs2 },
+
+                #[cfg(feature = "ext-zfh")]
+                (0b0000010, _) => FaddH { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b0000110, _) => FsubH { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b0001010, _) => FmulH { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b0001110, _) => FdivH { rd, rs1, rs2, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b0101110, _) if rs2 == 0 => FsqrtH { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b0010010, 0b000) => FsgnjH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b0010010, 0b001) => FsgnjnH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b0010010, 0b010) => FsgnjxH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b0010110, 0b000) => FminH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b0010110, 0b001) => FmaxH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b0100000, _) if rs2 == 2 => FcvtSH { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b0100010, _) if rs2 == 0 => FcvtHS { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+                (0b0100001, _) if rs2 == 2 => FcvtDH { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+                (0b0100010, _) if rs2 == 1 => FcvtHD { rd, rs1, rm: rounding_mode(funct3)? },
+                #[cfg(feature = "ext-zfh")]
+                (0b1100010, _) if integer.contains(&rs2) => {
+                    let rm = rounding_mode(funct3)?;
+                    [FcvtWH { rd, rs1, rm }, FcvtWuH { rd, rs1, rm }, FcvtLH { rd, rs1, rm }, FcvtLuH { rd, rs1, rm }][rs2]
+                },
+                #[cfg(feature = "ext-zfh")]
+                (0b1101010, _) if integer.contains(&rs2) => {
+                    let rm = rounding_mode(funct3)?;
+                    [FcvtHW { rd, rs1, rm }, FcvtHWu { rd, rs1, rm }, FcvtHL { rd, rs1, rm }, FcvtHLu { rd, rs1, rm }][rs2]
+                },
+                #[cfg(feature = "ext-zfh")]
+                (0b1110010, 0b000) if rs2 == 0 => FmvXH { rd, rs1 },
+                #[cfg(feature = "ext-zfh")]
+                (0b1110010, 0b001) if rs2 == 0 => FclassH { rd, rs1 },
+                #[cfg(feature = "ext-zfh")]
+                (0b1111010, 0b000) if rs2 == 0 => FmvHX { rd, rs1 },
+                #[cfg(feature = "ext-zfh")]
+                (0b1010010, 0b010) => FeqH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b1010010, 0b001) => FltH { rd, rs1, rs2 },
+                #[cfg(feature = "ext-zfh")]
+                (0b1010010, 0b000) => FleH { rd, rs1, rs2 },
+                _ => return Err(Trap::IllegalInstruction)
+            }
+        },
+
+        #[cfg(feature = "ext-v")]
+        (0b0000111, 0b000, _) | (0b0000111, 0b101..=0b111, _) | (0b0100111, 0b000, _) | (0b0100111, 0b101..=0b111, _) => {
+            let variant::R { destination, source1, source2 } = Variant::decode(instruction);
+            let vm = funct7 & 1 != 0;
+            match VectorMemory::decode(u32::from_le_bytes(instruction)) {
+                Some(access) if opcode == 0b0000111 => VectorLoad { access, vd: destination, rs1: source1, rs2: source2, vm },
+                Some(access) => VectorStore { access, vs3: destination, rs1: source1, rs2: source2, vm },
+                None => return Err(Trap::IllegalInstruction)
+            }
+        },
+        #[cfg(feature = "ext-v")]
+        (0b1010111, 0b111, _) => {
+            let variant::R { destination, source1, source2 } = Variant::decode(instruction);
+            let bits = u32::from_le_bytes(instruction);
+            match bits >> 30 {
+                0b00 | 0b01 => Vsetvli { rd: destination, rs1: source1, vtype: (bits >> 20) & 0x7FF },
+                0b11 => Vsetivli { rd: destination, avl: source1 as u32, vtype: (bits >> 20) & 0x3FF },
+                _ if funct7 == 0b1000000 => Vsetvl { rd: destination, rs1: source1, rs2: source2 },
+                _ => return Err(Trap::IllegalInstruction)
+            }
+        },
+        #[cfg(feature = "ext-v")]
+        (0b1010111, 0b010, 0b0100000) | (0b1010111, 0b010, 0b0100001) => {
+            let variant::R { destination, source1, source2 } = Variant::decode(instruction);
+            let vm = funct7 & 1 != 0;
+            match source1 {
+                0b00000 if vm => VmvXS { rd: destination, vs2: source2 },
+                0b10000 => Vcpop { rd: destination, vs2: source2, vm },
+                0b10001 => Vfirst { rd: destination, vs2: source2, vm },
+                _ => return Err(Trap::IllegalInstruction)
+            }
+        },
+        #[cfg(feature = "ext-v")]
+        (0b1010111, 0b110, 0b0100001) if variant::R::decode(instruction).source2 == 0 => {
+            let variant::R { destination, source1, .. } = Variant::decode(instruction);
+            VmvSX { vd: destination, rs1: source1 }
+        },
+        #[cfg(feature = "ext-v")]
+        (0b1010111, 0b010, 0b0101000) | (0b1010111, 0b010, 0b0101001) if matches!(variant::R::decode(instruction), variant::R { source1: 0b10001, source2: 0, .. }) => {
+            let variant::R { destination, .. } = Variant::decode(instruction);
+            // A masked instruction may not overwrite the mask
+            if funct7 & 1 == 0 && destination == 0 {
+                return Err(Trap::IllegalInstruction)
+            }
+            Vid { vd: destination, vm: funct7 & 1 != 0 }
+        },
+        #[cfg(feature = "ext-v")]
+        (0b1010111, _, _) => {
+            let variant::R { destination, source1, source2 } = Variant::decode(instruction);
+            let vm = funct7 & 1 != 0;
+            match VectorOp::decode(funct3, funct7 >> 1) {
+                Some(op) if op.is_legal(destination, source2, !vm) => Vector { op, vd: destination, vs2: source2, operand: VectorOperand::decode(funct3, source1), vm },
+                _ => return Err(Trap::IllegalInstruction)
+            }
+        },
+
+        _ => return Err(Trap::IllegalInstruction)
+    })
+}
+
+/// The rm field of a floating-point instruction, which is illegal if it encodes a reserved rounding mode.
+/// The dynamic rounding mode is accepted as whether frm holds a valid rounding mode depends on the state of the core
+#[cfg(feature = "ext-f")]
+fn rounding_mode(rm: u8) -> Result<u8, Trap> {
+    match rm {
+        0b101 | 0b110 => Err(Trap::IllegalInstruction),
+        rm => Ok(rm)
+    }
+}
+
+/// The name of a machine-level or floating-point CSR, or `None` if it has no standard name
+pub fn csr_name(index: usize) -> Option<&'static str> {
+    Some(match index {
+        0x001 => "fflags",
+        0x002 => "frm",
+        0x003 => "fcsr",
+        0x008 => "vstart",
+        0x009 => "vxsat",
+        0x00A => "vxrm",
+        0x00F => "vcsr",
+        0x300 => "mstatus",
+        0x301 => "misa",
+        0x302 => "medeleg",
+        0x303 => "mideleg",
+        0x304 => "mie",
+        0x305 => "mtvec",
+        0x306 => "mcounteren",
+        0x30A => "menvcfg",
+        0x310 => "mstatush",
+        0x31A => "menvcfgh",
+        0x340 => "mscratch",
+        0x341 => "mepc",
+        0x342 => "mcause",
+        0x343 => "mtval",
+        0x344 => "mip",
+        0xB00 => "mcycle",
+        0xB02 => "minstret",
+        0xB80 => "mcycleh",
+        0xB82 => "minstreth",
+        0xC00 => "cycle",
+        0xC02 => "instret",
+        0xC80 => "cycleh",
+        0xC82 => "instreth",
+        0xC20 => "vl",
+        0xC21 => "vtype",
+        0xC22 => "vlenb",
+        0xF11 => "mvendorid",
+        0xF12 => "marchid",
+        0xF13 => "mimpid",
+        0xF14 => "mhartid",
+        _ => return None
+    })
+}
+
+/// The immediate as used by the instruction. All immediates fit in the lower 32 bits
+fn signed<R: Register>(immediate: R) -> i32 {
+    i32::from_le_bytes(immediate.word())
+}
+
+impl<R: Register> fmt::Display for Instruction<R> {
+    /// Format as standard assembly with ABI register names, such as `addi sp, sp, -16`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Instruction::*;
+        let x = |index: usize| ABI_NAMES[index];
+        let r = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, rs1: usize, rs2: usize|
+            write!(f, "{} {}, {}, {}", name, x(rd), x(rs1), x(rs2));
+        let i = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, rs1: usize, imm: R|
+            write!(f, "{} {}, {}, {}", name, x(rd), x(rs1), signed(imm));
+        let shift = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, rs1: usize, shamt: u32|
+            write!(f, "{} {}, {}, {}", name, x(rd), x(rs1), shamt);
+        // Loads and stores, with the address as an offset from the base register
+        let memory = |f: &mut fmt::Formatter<'_>, name: &str, register: usize, base: usize, imm: R|
+            write!(f, "{} {}, {}({})", name, x(register), signed(imm), x(base));
+        let b = |f: &mut fmt::Formatter<'_>, name: &str, rs1: usize, rs2: usize, imm: R|
+            write!(f, "{} {}, {}, {}", name, x(rs1), x(rs2), signed(imm));
+        #[cfg(feature = "ext-csr")]
+        let csr = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, csr: usize, source: &dyn fmt::Display| match csr_name(csr) {
+            Some(csr) => write!(f, "{} {}, {}, {}", name, x(rd), csr, source),
+            None => write!(f, "{} {}, {:#x}, {}", name, x(rd), csr, source)
+        };
+        #[cfg(feature = "ext-a")]
+        let ordering = |aq: bool, rl: bool| match (aq, rl) {
+            (false, false) => "",
+            (true, false) => ".aq",
+            (false, true) => ".rl",
+            (true, true) => ".aqrl"
+        };
+        #[cfg(feature = "ext-a")]
+        let size = |double: bool| if double { "d" } else { "w" };
+        #[cfg(feature = "ext-f")]
+        let fr = |index: usize| FP_ABI_NAMES[index];
+        // The rounding mode is only written when it is static
+        #[cfg(feature = "ext-f")]
+        let rm = |rm: u8| match rm {
+            0b000 => ", rne",
+            0b001 => ", rtz",
+            0b010 => ", rdn",
+            0b011 => ", rup",
+            0b100 => ", rmm",
+            _ => ""
+        };
+        #[cfg(feature = "ext-f")]
+        let fma = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, rs1: usize, rs2: usize, rs3: usize, mode: u8|
+            write!(f, "{} {}, {}, {}, {}{}", name, fr(rd), fr(rs1), fr(rs2), fr(rs3), rm(mode));
+        #[cfg(feature = "ext-f")]
+        let fp = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, rs1: usize, rs2: usize|
+            write!(f, "{} {}, {}, {}", name, fr(rd), fr(rs1), fr(rs2));
+        #[cfg(feature = "ext-f")]
+        let compare = |f: &mut fmt::Formatter<'_>, name: &str, rd: usize, rs1: usize, rs2: usize|
+            write!(f, "{} {}, {}, {}", name, x(rd), fr(rs1), fr(rs2));
+
+        match *self {
+            Add { rd, rs1, rs2 } => r(f, "add", rd, rs1, rs2),
+            Sub { rd, rs1, rs2 } => r(f, "sub", rd, rs1, rs2),
+            Slt { rd, rs1, rs2 } => r(f, "slt", rd, rs1, rs2),
+            Sltu { rd, rs1, rs2 } => r(f, "sltu", rd, rs1, rs2),
+            Xor { rd, rs1, rs2 } => r(f, "xor", rd, rs1, rs2),
+            Or { rd, rs1, rs2 } => r(f, "or", rd, rs1, rs2),
+            And { rd, rs1, rs2 } => r(f, "and", rd, rs1, rs2),
+            Sll { rd, rs1, rs2 } => r(f, "sll", rd, rs1, rs2),
+            Srl { rd, rs1, rs2 } => r(f, "srl", rd, rs1, rs2),
+            Sra { rd, rs1, rs2 } => r(f, "sra", rd, rs1, rs2),
+            Addw { rd, rs1, rs2 } => r(f, "addw", rd, rs1, rs2),
+            Subw { rd, rs1, rs2 } => r(f, "subw", rd, rs1, rs2),
+            Sllw { rd, rs1, rs2 } => r(f, "sllw", rd, rs1, rs2),
+            Srlw { rd, rs1, rs2 } => r(f, "srlw", rd, rs1, rs2),
+            Sraw { rd, rs1, rs2 } => r(f, "sraw", rd, rs1, rs2),
+
+            Addi { rd, rs1, imm } => i(f, "addi", rd, rs1, imm),
+            Slti { rd, rs1, imm } => i(f, "slti", rd, rs1, imm),
+            Sltiu { rd, rs1, imm } => i(f, "sltiu", rd, rs1, imm),
+            Xori { rd, rs1, imm } => i(f, "xori", rd, rs1, imm),
+            Ori { rd, rs1, imm } => i(f, "ori", rd, rs1, imm),
+            Andi { rd, rs1, imm } => i(f, "andi", rd, rs1, imm),
+            Addiw { rd, rs1, imm } => i(f, "addiw", rd, rs1, imm),
+            Slli { rd, rs1, shamt } => shift(f, "slli", rd, rs1, shamt),
+            Srli { rd, rs1, shamt } => shift(f, "srli", rd, rs1, shamt),
+            Srai { rd, rs1, shamt } => shift(f, "srai", rd, rs1, shamt),
+            Slliw { rd, rs1, shamt } => shift(f, "slliw", rd, rs1, shamt),
+            Srliw { rd, rs1, shamt } => shift(f, "srliw", rd, rs1, shamt),
+            Sraiw { rd, rs1, shamt } => shift(f, "sraiw", rd, rs1, shamt),
+
+            // The upper immediate is written as the 20 bits encoded
+            Lui { rd, imm } => write!(f, "lui {}, {:#x}", x(rd), signed(imm) as u32 >> 12),
+            Auipc { rd, imm } => write!(f, "auipc {}, {:#x}", x(rd), signed(imm) as u32 >> 12),
+
+            Lb { rd, rs1, imm } => memory(f, "lb", rd, rs1, imm),
+            Lh { rd, rs1, imm } => memory(f, "lh", rd, rs1, imm),
+            Lw { rd, rs1, imm } => memory(f, "lw", rd, rs1, imm),
+            Ld { rd, rs1, imm } => memory(f, "ld", rd, rs1, imm),
+            Lbu { rd, rs1, imm } => memory(f, "lbu", rd, rs1, imm),
+            Lhu { rd, rs1, imm } => memory(f, "lhu", rd, rs1, imm),
+            Lwu { rd, rs1, imm } => memory(f, "lwu", rd, rs1, imm),
+            Ldu { rd, rs1, imm } => memory(f, "ldu", rd, rs1, imm),
+            Lq { rd, rs1, imm } => memory(f, "lq", rd, rs1, imm),
+            Sb { rs1, rs2, imm } => memory(f, "sb", rs2, rs1, imm),
+            Sh { rs1, rs2, imm } => memory(f, "sh", rs2, rs1, imm),
+            Sw { rs1, rs2, imm } => memory(f, "sw", rs2, rs1, imm),
+            Sd { rs1, rs2, imm } => memory(f, "sd", rs2, rs1, imm),
+            Sq { rs1, rs2, imm } => memory(f, "sq", rs2, rs1, imm),
+
+            Jal { rd, imm } => write!(f, "jal {}, {}", x(rd), signed(imm)),
+            Jalr { rd, rs1, imm } => memory(f, "jalr", rd, rs1, imm),
+            Beq { rs1, rs2, imm } => b(f, "beq", rs1, rs2, imm),
+            Bne { rs1, rs2, imm } => b(f, "bne", rs1, rs2, imm),
+            Blt { rs1, rs2, imm } => b(f, "blt", rs1, rs2, imm),
+            Bge { rs1, rs2, imm } => b(f, "bge", rs1, rs2, imm),
+            Bltu { rs1, rs2, imm } => b(f, "bltu", rs1, rs2, imm),
+            Bgeu { rs1, rs2, imm } => b(f, "bgeu", rs1, rs2, imm),
+
+            Fence { fm: 0b1000, pred: 0b0011, succ: 0b0011 } => write!(f, "fence.tso"),
+            Fence { fm: 0, pred: 0b0001, succ: 0 } => write!(f, "pause"),
+            Fence { pred: 0b1111, succ: 0b1111, .. } => write!(f, "fence"),
+            Fence { pred, succ, .. } => {
+                let set = |f: &mut fmt::Formatter<'_>, set: u8| if set == 0 {
+                    write!(f, "0")
+                } else {
+                    "iorw".chars().enumerate().filter(|&(bit, _)| set & 0b1000 >> bit != 0).try_for_each(|(_, access)| write!(f, "{}", access))
+                };
+                write!(f, "fence ")?;
+                set(f, pred)?;
+                write!(f, ", ")?;
+                set(f, succ)
+            },
+            FenceI => write!(f, "fence.i"),
+            #[cfg(feature = "ext-zicbom")]
+            CboClean { rs1 } => write!(f, "cbo.clean ({})", x(rs1)),
+            #[cfg(feature = "ext-zicbom")]
+            CboFlush { rs1 } => write!(f, "cbo.flush ({})", x(rs1)),
+            #[cfg(feature = "ext-zicbom")]
+            CboInval { rs1 } => write!(f, "cbo.inval ({})", x(rs1)),
+            #[cfg(feature = "ext-zicboz")]
+            CboZero { rs1 } => write!(f, "cbo.zero ({})", x(rs1)),
+            Ecall => write!(f, "ecall"),
+            Ebreak => write!(f, "ebreak"),
+            #[cfg(feature = "ext-csr")]
+            Mret => write!(f, "mret"),
+            #[cfg(feature = "ext-csr")]
+            Wfi => write!(f, "wfi"),
+
+            #[cfg(feature = "ext-csr")]
+            Csrrw { rd, rs1, csr: index } => csr(f, "csrrw", rd, index, &x(rs1)),
+            #[cfg(feature = "ext-csr")]
+            Csrrs { rd, rs1, csr: index } => csr(f, "csrrs", rd, index, &x(rs1)),
+            #[cfg(feature = "ext-csr")]
+            Csrrc { rd, rs1, csr: index } => csr(f, "csrrc", rd, index, &x(rs1)),
+            #[cfg(feature = "ext-csr")]
+            Csrrwi { rd, uimm, csr: index } => csr(f, "csrrwi", rd, index, &uimm),
+            #[cfg(feature = "ext-csr")]
+            Csrrsi { rd, uimm, csr: index } => csr(f, "csrrsi", rd, index, &uimm),
+            #[cfg(feature = "ext-csr")]
+            Csrrci { rd, uimm, csr: index } => csr(f, "csrrci", rd, index, &uimm),
+
+            #[cfg(feature = "ext-zmmul")]
+            Mul { rd, rs1, rs2 } => r(f, "mul", rd, rs1, rs2),
+            #[cfg(feature = "ext-zmmul")]
+            Mulh { rd, rs1, rs2 } => r(f, "mulh", rd, rs1, rs2),
+            #[cfg(feature = "ext-zmmul")]
+            Mulhsu { rd, rs1, rs2 } => r(f, "mulhsu", rd, rs1, rs2),
+            #[cfg(feature = "ext-zmmul")]
+            Mulhu { rd, rs1, rs2 } => r(f, "mulhu", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Div { rd, rs1, rs2 } => r(f, "div", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Divu { rd, rs1, rs2 } => r(f, "divu", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Rem { rd, rs1, rs2 } => r(f, "rem", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Remu { rd, rs1, rs2 } => r(f, "remu", rd, rs1, rs2),
+            #[cfg(feature = "ext-zmmul")]
+            Mulw { rd, rs1, rs2 } => r(f, "mulw", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Divw { rd, rs1, rs2 } => r(f, "divw", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Divuw { rd, rs1, rs2 } => r(f, "divuw", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Remw { rd, rs1, rs2 } => r(f, "remw", rd, rs1, rs2),
+            #[cfg(feature = "ext-m")]
+            Remuw { rd, rs1, rs2 } => r(f, "remuw", rd, rs1, rs2),
+
+            #[cfg(feature = "ext-a")]
+            Lr { rd, rs1, double, aq, rl } => write!(f, "lr.{}{} {}, ({})", size(double), ordering(aq, rl), x(rd), x(rs1)),
+            #[cfg(feature = "ext-a")]
+            Sc { rd, rs1, rs2, double, aq, rl } => write!(f, "sc.{}{} {}, {}, ({})", size(double), ordering(aq, rl), x(rd), x(rs2), x(rs1)),
+            #[cfg(feature = "ext-a")]
+            Amo { op, rd, rs1, rs2, double, aq, rl } => {
+                let op = match op {
+                    AmoOp::Swap => "swap",
+                    AmoOp::Add => "add",
+                    AmoOp::Xor => "xor",
+                    AmoOp::And => "and",
+                    AmoOp::Or => "or",
+                    AmoOp::Min => "min",
+                    AmoOp::Max => "max",
+                    AmoOp::Minu => "minu",
+                    AmoOp::Maxu => "maxu"
+                };
+                write!(f, "amo{}.{}{} {}, {}, ({})", op, size(double), ordering(aq, rl), x(rd), x(rs2), x(rs1))
+            },
+
+            #[cfg(feature = "ext-zbkb")]
+            Pack { rd, rs1, rs2 } => r(f, "pack", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbkb")]
+            Packw { rd, rs1, rs2 } => r(f, "packw", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbkb")]
+            Packh { rd, rs1, rs2 } => r(f, "packh", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbkb")]
+            Brev8 { rd, rs1 } => write!(f, "brev8 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zbkb")]
+            Zip { rd, rs1 } => write!(f, "zip {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zbkb")]
+            Unzip { rd, rs1 } => write!(f, "unzip {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zbkx")]
+            Xperm4 { rd, rs1, rs2 } => r(f, "xperm4", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbkx")]
+            Xperm8 { rd, rs1, rs2 } => r(f, "xperm8", rd, rs1, rs2),
+
+            #[cfg(feature = "ext-zknh")]
+            Sha256sum0 { rd, rs1 } => write!(f, "sha256sum0 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha256sum1 { rd, rs1 } => write!(f, "sha256sum1 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha256sig0 { rd, rs1 } => write!(f, "sha256sig0 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha256sig1 { rd, rs1 } => write!(f, "sha256sig1 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sum0 { rd, rs1 } => write!(f, "sha512sum0 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sum1 { rd, rs1 } => write!(f, "sha512sum1 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sig0 { rd, rs1 } => write!(f, "sha512sig0 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sig1 { rd, rs1 } => write!(f, "sha512sig1 {}, {}", x(rd), x(rs1)),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sum0r { rd, rs1, rs2 } => r(f, "sha512sum0r", rd, rs1, rs2),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sum1r { rd, rs1, rs2 } => r(f, "sha512sum1r", rd, rs1, rs2),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sig0l { rd, rs1, rs2 } => r(f, "sha512sig0l", rd, rs1, rs2),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sig1l { rd, rs1, rs2 } => r(f, "sha512sig1l", rd, rs1, rs2),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sig0h { rd, rs1, rs2 } => r(f, "sha512sig0h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zknh")]
+            Sha512sig1h { rd, rs1, rs2 } => r(f, "sha512sig1h", rd, rs1, rs2),
+
+            #[cfg(feature = "ext-zbc")]
+            Clmul { rd, rs1, rs2 } => r(f, "clmul", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbc")]
+            Clmulh { rd, rs1, rs2 } => r(f, "clmulh", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbc")]
+            Clmulr { rd, rs1, rs2 } => r(f, "clmulr", rd, rs1, rs2),
+
+            #[cfg(feature = "ext-zbs")]
+            Bset { rd, rs1, rs2 } => r(f, "bset", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbs")]
+            Bclr { rd, rs1, rs2 } => r(f, "bclr", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbs")]
+            Binv { rd, rs1, rs2 } => r(f, "binv", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbs")]
+            Bext { rd, rs1, rs2 } => r(f, "bext", rd, rs1, rs2),
+            #[cfg(feature = "ext-zbs")]
+            Bseti { rd, rs1, shamt } => shift(f, "bseti", rd, rs1, shamt),
+            #[cfg(feature = "ext-zbs")]
+            Bclri { rd, rs1, shamt } => shift(f, "bclri", rd, rs1, shamt),
+            #[cfg(feature = "ext-zbs")]
+            Binvi { rd, rs1, shamt } => shift(f, "binvi", rd, rs1, shamt),
+            #[cfg(feature = "ext-zbs")]
+            Bexti { rd, rs1, shamt } => shift(f, "bexti", rd, rs1, shamt),
+
+            #[cfg(feature = "ext-zawrs")]
+            WrsNto => write!(f, "wrs.nto"),
+            #[cfg(feature = "ext-zawrs")]
+            WrsSto => write!(f, "wrs.sto"),
+
+            #[cfg(feature = "ext-f")]
+            Flw { rd, rs1, imm } => write!(f, "flw {}, {}({})", fr(rd), signed(imm), x(rs1)),
+            #[cfg(feature = "ext-f")]
+            Fsw { rs1, rs2, imm } => write!(f, "fsw {}, {}({})", fr(rs2), signed(imm), x(rs1)),
+            #[cfg(feature = "ext-f")]
+            FmaddS { rd, rs1, rs2, rs3, rm } => fma(f, "fmadd.s", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-f")]
+            FmsubS { rd, rs1, rs2, rs3, rm } => fma(f, "fmsub.s", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-f")]
+            FnmsubS { rd, rs1, rs2, rs3, rm } => fma(f, "fnmsub.s", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-f")]
+            FnmaddS { rd, rs1, rs2, rs3, rm } => fma(f, "fnmadd.s", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-f")]
+            FaddS { rd, rs1, rs2, rm: mode } => write!(f, "fadd.s {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FsubS { rd, rs1, rs2, rm: mode } => write!(f, "fsub.s {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FmulS { rd, rs1, rs2, rm: mode } => write!(f, "fmul.s {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FdivS { rd, rs1, rs2, rm: mode } => write!(f, "fdiv.s {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FsqrtS { rd, rs1, rm: mode } => write!(f, "fsqrt.s {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FsgnjS { rd, rs1, rs2 } => fp(f, "fsgnj.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FsgnjnS { rd, rs1, rs2 } => fp(f, "fsgnjn.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FsgnjxS { rd, rs1, rs2 } => fp(f, "fsgnjx.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FminS { rd, rs1, rs2 } => fp(f, "fmin.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FmaxS { rd, rs1, rs2 } => fp(f, "fmax.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FcvtWS { rd, rs1, rm: mode } => write!(f, "fcvt.w.s {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtWuS { rd, rs1, rm: mode } => write!(f, "fcvt.wu.s {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtLS { rd, rs1, rm: mode } => write!(f, "fcvt.l.s {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtLuS { rd, rs1, rm: mode } => write!(f, "fcvt.lu.s {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtSW { rd, rs1, rm: mode } => write!(f, "fcvt.s.w {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtSWu { rd, rs1, rm: mode } => write!(f, "fcvt.s.wu {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtSL { rd, rs1, rm: mode } => write!(f, "fcvt.s.l {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FcvtSLu { rd, rs1, rm: mode } => write!(f, "fcvt.s.lu {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-f")]
+            FmvXW { rd, rs1 } => write!(f, "fmv.x.w {}, {}", x(rd), fr(rs1)),
+            #[cfg(feature = "ext-f")]
+            FmvWX { rd, rs1 } => write!(f, "fmv.w.x {}, {}", fr(rd), x(rs1)),
+            #[cfg(feature = "ext-f")]
+            FclassS { rd, rs1 } => write!(f, "fclass.s {}, {}", x(rd), fr(rs1)),
+            #[cfg(feature = "ext-f")]
+            FeqS { rd, rs1, rs2 } => compare(f, "feq.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FltS { rd, rs1, rs2 } => compare(f, "flt.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-f")]
+            FleS { rd, rs1, rs2 } => compare(f, "fle.s", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            Fld { rd, rs1, imm } => write!(f, "fld {}, {}({})", fr(rd), signed(imm), x(rs1)),
+            #[cfg(feature = "ext-d")]
+            Fsd { rs1, rs2, imm } => write!(f, "fsd {}, {}({})", fr(rs2), signed(imm), x(rs1)),
+            #[cfg(feature = "ext-d")]
+            FmaddD { rd, rs1, rs2, rs3, rm } => fma(f, "fmadd.d", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-d")]
+            FmsubD { rd, rs1, rs2, rs3, rm } => fma(f, "fmsub.d", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-d")]
+            FnmsubD { rd, rs1, rs2, rs3, rm } => fma(f, "fnmsub.d", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-d")]
+            FnmaddD { rd, rs1, rs2, rs3, rm } => fma(f, "fnmadd.d", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-d")]
+            FaddD { rd, rs1, rs2, rm: mode } => write!(f, "fadd.d {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FsubD { rd, rs1, rs2, rm: mode } => write!(f, "fsub.d {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FmulD { rd, rs1, rs2, rm: mode } => write!(f, "fmul.d {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FdivD { rd, rs1, rs2, rm: mode } => write!(f, "fdiv.d {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FsqrtD { rd, rs1, rm: mode } => write!(f, "fsqrt.d {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FsgnjD { rd, rs1, rs2 } => fp(f, "fsgnj.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FsgnjnD { rd, rs1, rs2 } => fp(f, "fsgnjn.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FsgnjxD { rd, rs1, rs2 } => fp(f, "fsgnjx.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FminD { rd, rs1, rs2 } => fp(f, "fmin.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FmaxD { rd, rs1, rs2 } => fp(f, "fmax.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FcvtSD { rd, rs1, rm: mode } => write!(f, "fcvt.s.d {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtDS { rd, rs1, rm: mode } => write!(f, "fcvt.d.s {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtWD { rd, rs1, rm: mode } => write!(f, "fcvt.w.d {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtWuD { rd, rs1, rm: mode } => write!(f, "fcvt.wu.d {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtLD { rd, rs1, rm: mode } => write!(f, "fcvt.l.d {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtLuD { rd, rs1, rm: mode } => write!(f, "fcvt.lu.d {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtDW { rd, rs1, rm: mode } => write!(f, "fcvt.d.w {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtDWu { rd, rs1, rm: mode } => write!(f, "fcvt.d.wu {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtDL { rd, rs1, rm: mode } => write!(f, "fcvt.d.l {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FcvtDLu { rd, rs1, rm: mode } => write!(f, "fcvt.d.lu {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-d")]
+            FmvXD { rd, rs1 } => write!(f, "fmv.x.d {}, {}", x(rd), fr(rs1)),
+            #[cfg(feature = "ext-d")]
+            FmvDX { rd, rs1 } => write!(f, "fmv.d.x {}, {}", fr(rd), x(rs1)),
+            #[cfg(feature = "ext-d")]
+            FclassD { rd, rs1 } => write!(f, "fclass.d {}, {}", x(rd), fr(rs1)),
+            #[cfg(feature = "ext-d")]
+            FeqD { rd, rs1, rs2 } => compare(f, "feq.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FltD { rd, rs1, rs2 } => compare(f, "flt.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-d")]
+            FleD { rd, rs1, rs2 } => compare(f, "fle.d", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            Flh { rd, rs1, imm } => write!(f, "flh {}, {}({})", fr(rd), signed(imm), x(rs1)),
+            #[cfg(feature = "ext-zfh")]
+            Fsh { rs1, rs2, imm } => write!(f, "fsh {}, {}({})", fr(rs2), signed(imm), x(rs1)),
+            #[cfg(feature = "ext-zfh")]
+            FmaddH { rd, rs1, rs2, rs3, rm } => fma(f, "fmadd.h", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-zfh")]
+            FmsubH { rd, rs1, rs2, rs3, rm } => fma(f, "fmsub.h", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-zfh")]
+            FnmsubH { rd, rs1, rs2, rs3, rm } => fma(f, "fnmsub.h", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-zfh")]
+            FnmaddH { rd, rs1, rs2, rs3, rm } => fma(f, "fnmadd.h", rd, rs1, rs2, rs3, rm),
+            #[cfg(feature = "ext-zfh")]
+            FaddH { rd, rs1, rs2, rm: mode } => write!(f, "fadd.h {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FsubH { rd, rs1, rs2, rm: mode } => write!(f, "fsub.h {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FmulH { rd, rs1, rs2, rm: mode } => write!(f, "fmul.h {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FdivH { rd, rs1, rs2, rm: mode } => write!(f, "fdiv.h {}, {}, {}{}", fr(rd), fr(rs1), fr(rs2), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FsqrtH { rd, rs1, rm: mode } => write!(f, "fsqrt.h {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FsgnjH { rd, rs1, rs2 } => fp(f, "fsgnj.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FsgnjnH { rd, rs1, rs2 } => fp(f, "fsgnjn.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FsgnjxH { rd, rs1, rs2 } => fp(f, "fsgnjx.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FminH { rd, rs1, rs2 } => fp(f, "fmin.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FmaxH { rd, rs1, rs2 } => fp(f, "fmax.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FcvtSH { rd, rs1, rm: mode } => write!(f, "fcvt.s.h {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtHS { rd, rs1, rm: mode } => write!(f, "fcvt.h.s {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+            FcvtDH { rd, rs1, rm: mode } => write!(f, "fcvt.d.h {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+            FcvtHD { rd, rs1, rm: mode } => write!(f, "fcvt.h.d {}, {}{}", fr(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtWH { rd, rs1, rm: mode } => write!(f, "fcvt.w.h {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtWuH { rd, rs1, rm: mode } => write!(f, "fcvt.wu.h {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtLH { rd, rs1, rm: mode } => write!(f, "fcvt.l.h {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtLuH { rd, rs1, rm: mode } => write!(f, "fcvt.lu.h {}, {}{}", x(rd), fr(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtHW { rd, rs1, rm: mode } => write!(f, "fcvt.h.w {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtHWu { rd, rs1, rm: mode } => write!(f, "fcvt.h.wu {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtHL { rd, rs1, rm: mode } => write!(f, "fcvt.h.l {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FcvtHLu { rd, rs1, rm: mode } => write!(f, "fcvt.h.lu {}, {}{}", fr(rd), x(rs1), rm(mode)),
+            #[cfg(feature = "ext-zfh")]
+            FmvXH { rd, rs1 } => write!(f, "fmv.x.h {}, {}", x(rd), fr(rs1)),
+            #[cfg(feature = "ext-zfh")]
+            FmvHX { rd, rs1 } => write!(f, "fmv.h.x {}, {}", fr(rd), x(rs1)),
+            #[cfg(feature = "ext-zfh")]
+            FclassH { rd, rs1 } => write!(f, "fclass.h {}, {}", x(rd), fr(rs1)),
+            #[cfg(feature = "ext-zfh")]
+            FeqH { rd, rs1, rs2 } => compare(f, "feq.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FltH { rd, rs1, rs2 } => compare(f, "flt.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-zfh")]
+            FleH { rd, rs1, rs2 } => compare(f, "fle.h", rd, rs1, rs2),
+            #[cfg(feature = "ext-v")]
+            Vsetvli { rd, rs1, vtype: bits } => match Vtype::decode(bits as u64) {
+                Some(vtype) => write!(f, "vsetvli {}, {}, {}", x(rd), x(rs1), vtype),
+                None => write!(f, "vsetvli {}, {}, {:#x}", x(rd), x(rs1), bits)
+            },
+            #[cfg(feature = "ext-v")]
+            Vsetivli { rd, avl, vtype: bits } => match Vtype::decode(bits as u64) {
+                Some(vtype) => write!(f, "vsetivli {}, {}, {}", x(rd), avl, vtype),
+                None => write!(f, "vsetivli {}, {}, {:#x}", x(rd), avl, bits)
+            },
+            #[cfg(feature = "ext-v")]
+            Vsetvl { rd, rs1, rs2 } => r(f, "vsetvl", rd, rs1, rs2),
+            #[cfg(feature = "ext-v")]
+            Vector { op, vd, vs2, operand, vm } => {
+                let suffix = match (op.kind(), operand) {
+                    (VectorKind::Reduction, _) => ".vs",
+                    (VectorKind::Mask, _) => ".mm",
+                    (_, VectorOperand::Vector(_)) => ".vv",
+                    (_, VectorOperand::Scalar(_)) => ".vx",
+                    (_, VectorOperand::Immediate(_)) => ".vi"
+                };
+                // VMERGE selects with the mask, which is written as a third operand, and is VMV.V without vs2 if unmasked
+                match (op, vm) {
+                    (VectorOp::Merge, true) => write!(f, "vmv.v.{} v{}, ", &suffix[2..], vd)?,
+                    (VectorOp::Merge, false) => write!(f, "vmerge{}m v{}, v{}, ", suffix, vd, vs2)?,
+                    _ => write!(f, "{}{} v{}, v{}, ", op.mnemonic(), suffix, vd, vs2)?
+                }
+                match operand {
+                    VectorOperand::Vector(vs1) => write!(f, "v{}", vs1)?,
+                    VectorOperand::Scalar(rs1) => write!(f, "{}", x(rs1))?,
+                    VectorOperand::Immediate(imm) => write!(f, "{}", op.immediate(imm))?
+                }
+                match (op, vm) {
+                    (_, true) => Ok(()),
+                    (VectorOp::Merge, false) => write!(f, ", v0"),
+                    (_, false) => write!(f, ", v0.t")
+                }
+            },
+            #[cfg(feature = "ext-v")]
+            VmvXS { rd, vs2 } => write!(f, "vmv.x.s {}, v{}", x(rd), vs2),
+            #[cfg(feature = "ext-v")]
+            VmvSX { vd, rs1 } => write!(f, "vmv.s.x v{}, {}", vd, x(rs1)),
+            #[cfg(feature = "ext-v")]
+            Vcpop { rd, vs2, vm } => write!(f, "vcpop.m {}, v{}{}", x(rd), vs2, if vm { "" } else { ", v0.t" }),
+            #[cfg(feature = "ext-v")]
+            Vfirst { rd, vs2, vm } => write!(f, "vfirst.m {}, v{}{}", x(rd), vs2, if vm { "" } else { ", v0.t" }),
+            #[cfg(feature = "ext-v")]
+            Vid { vd, vm } => write!(f, "vid.v v{}{}", vd, if vm { "" } else { ", v0.t" }),
+            #[cfg(feature = "ext-v")]
+            VectorLoad { access, vd: register, rs1, rs2, vm } | VectorStore { access, vs3: register, rs1, rs2, vm } => {
+                let load = matches!(self, VectorLoad { .. });
+                write!(f, "{}", if load { "vl" } else { "vs" })?;
+                // Segments of more than one field are named by their number of fields
+                let segment = |f: &mut fmt::Formatter<'_>| if access.fields > 1 { write!(f, "seg{}", access.fields) } else { Ok(()) };
+                match access.addressing {
+                    VectorAddressing::UnitStride | VectorAddressing::FaultOnlyFirst => {
+                        segment(f)?;
+                        write!(f, "e{}", access.width)?;
+                        if access.addressing == VectorAddressing::FaultOnlyFirst {
+                            write!(f, "ff")?
+                        }
+                    },
+                    VectorAddressing::Strided => {
+                        write!(f, "s")?;
+                        segment(f)?;
+                        write!(f, "e{}", access.width)?
+                    },
+                    VectorAddressing::Indexed { ordered } => {
+                        write!(f, "{}", if ordered { "ox" } else { "ux" })?;
+                        segment(f)?;
+                        write!(f, "ei{}", access.width)?
+                    },
+                    // Whole register stores are always of bytes, so do not name the width
+                    VectorAddressing::WholeRegister if load => write!(f, "{}re{}", access.fields, access.width)?,
+                    VectorAddressing::WholeRegister => write!(f, "{}r", access.fields)?,
+                    VectorAddressing::Mask => write!(f, "m")?
+                }
+                write!(f, ".v v{}, ({})", register, x(rs1))?;
+                match access.addressing {
+                    VectorAddressing::Strided => write!(f, ", {}", x(rs2))?,
+                    VectorAddressing::Indexed { .. } => write!(f, ", v{}", rs2)?,
+                    _ => ()
+                }
+                if vm { Ok(()) } else { write!(f, ", v0.t") }
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Double-precision floating-point arithmetic for the D extension.
+//!
+//! There is no wider host type to compute in, so each operation is performed at double precision with the host's
+//! round to nearest, and the rounding error is recovered with an error-free transformation. The error selects the
+//! neighbouring value for the directed rounding modes and determines the exception flags.
+//! The error of a product or quotient cannot be recovered exactly when it is below the smallest subnormal, and the
+//! error of a fused multiply-add is summed from its parts, so in those cases only the sign of the error is reliable.
+
+use crate::float::{ RoundingMode, SignInjection, NV, DZ, OF, UF, NX };
+
+/// The quiet NaN produced by every operation with a double-precision NaN result
+pub const CANONICAL_NAN: u64 = 0x7FF8_0000_0000_0000;
+
+/// Returns true if the value is a signaling NaN
+pub fn is_signaling(value: f64) -> bool {
+    value.is_nan() && value.to_bits() & 0x0008_0000_0000_0000 == 0
+}
+
+/// Round a value to double precision, where `value` is the exact result rounded to nearest and `value + error` is exact
+fn round(value: f64, error: f64, rm: RoundingMode) -> (f64, u8) {
+    if error == 0.0 {
+        return (value, 0)
+    }
+    let above = error > 0.0;
+    let (lower, upper) = if above { (value, value.next_up()) } else { (value.next_down(), value) };
+    let result = match rm {
+        RoundingMode::NearestEven => value,
+        RoundingMode::TowardZero => if lower >= 0.0 { lower } else { upper },
+        RoundingMode::Down => lower,
+        RoundingMode::Up => upper,
+        RoundingMode::NearestMaxMagnitude => {
+            let tie = (upper - lower) == 2.0 * error.abs();
+            match (tie, value > 0.0) {
+                (true, true) => upper,
+                (true, false) => lower,
+                (false, _) => value
+            }
+        }
+    };
+    let overflow = result.is_infinite();
+    let underflow = result.abs() < f64::MIN_POSITIVE;
+    (result, NX | if overflow { OF } else { 0 } | if underflow { UF } else { 0 })
+}
+
+/// The result of a finite operation that overflowed to infinity when rounded to nearest
+fn overflow(negative: bool, rm: RoundingMode) -> (f64, u8) {
+    let infinite = match rm {
+        RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+        RoundingMode::TowardZero => false,
+        RoundingMode::Down => negative,
+        RoundingMode::Up => !negative
+    };
+    let magnitude = if infinite { f64::INFINITY } else { f64::MAX };
+    (if negative { -magnitude } else { magnitude }, OF | NX)
+}
+
+/// Round the result of an arithmetic operation, producing the canonical NaN if it is not a number.
+/// Invalid if an operand is a signaling NaN, or if the operation produced a NaN from numbers
+fn arithmetic(operands: &[f64], value: f64, error: f64, rm: RoundingMode) -> (f64, u8) {
+    if value.is_nan() {
+        let invalid = operands.iter().any(|&operand| is_signaling(operand)) || !operands.iter().any(|operand| operand.is_nan());
+        (f64::from_bits(CANONICAL_NAN), if invalid { NV } else { 0 })
+    } else if value.is_infinite() && operands.iter().all(|operand| operand.is_finite()) {
+        overflow(value.is_sign_negative(), rm)
+    } else {
+        round(value, error, rm)
+    }
+}
+
+/// The rounding error of a sum, recovered exactly
+fn sum_error(a: f64, b: f64, sum: f64) -> f64 {
+    if !sum.is_finite() {
+        return 0.0
+    }
+    let b_virtual = sum - a;
+    (a - (sum - b_virtual)) + (b - b_virtual)
+}
+
+pub fn add(a: f64, b: f64, rm: RoundingMode) -> (f64, u8) {
+    let sum = a + b;
+    arithmetic(&[a, b], sum, sum_error(a, b, sum), rm)
+}
+
+pub fn sub(a: f64, b: f64, rm: RoundingMode) -> (f64, u8) {
+    add(a, -b, rm)
+}
+
+pub fn mul(a: f64, b: f64, rm: RoundingMode) -> (f64, u8) {
+    let product = a * b;
+    let error = if product.is_finite() { a.mul_add(b, -product) } else { 0.0 };
+    arithmetic(&[a, b], product, error, rm)
+}
+
+pub fn div(a: f64, b: f64, rm: RoundingMode) -> (f64, u8) {
+    let quotient = a / b;
+    if b == 0.0 && a.is_finite() && a != 0.0 {
+        return (quotient, DZ)
+    }
+    let error = if quotient.is_finite() && b != 0.0 {
+        (-quotient).mul_add(b, a) / b
+    } else {
+        0.0
+    };
+    arithmetic(&[a, b], quotient, error, rm)
+}
+
+pub fn sqrt(a: f64, rm: RoundingMode) -> (f64, u8) {
+    let root = a.sqrt();
+    let error = if root.is_finite() && root > 0.0 {
+        (-root).mul_add(root, a) / (2.0 * root)
+    } else {
+        0.0
+    };
+    arithmetic(&[a], root, error, rm)
+}
+
+/// Compute `a * b + c` with a single rounding
+pub fn fused_multiply_add(a: f64, b: f64, c: f64, rm: RoundingMode) -> (f64, u8) {
+    // The product of infinity and zero is invalid even if the addend is a quiet NaN
+    if (a.is_infinite() && b == 0.0) || (a == 0.0 && b.is_infinite()) {
+        return (f64::from_bits(CANONICAL_NAN), NV)
+    }
+    let result = a.mul_add(b, c);
+    // The exact result is `product + product_error + c`, of which the rounded result is subtracted in order of magnitude.
+    // The error cannot be recovered when an intermediate overflows, in which case the fused result is taken as is
+    let product = a * b;
+    let sum = product + c;
+    let error = if result.is_finite() && product.is_finite() && sum.is_finite() {
+        let product_error = a.mul_add(b, -product);
+        (sum - result) + sum_error(product, c, sum) + product_error
+    } else {
+        0.0
+    };
+    arithmetic(&[a, b, c], result, error, rm)
+}
+
+/// The smaller operand, where negative zero is less than positive zero and a NaN is only returned if both operands are NaN
+pub fn min(a: f64, b: f64) -> (f64, u8) {
+    select(a, b, |a, b| a < b || (a == b && a.is_sign_negative()))
+}
+
+/// The larger operand, where positive zero is greater than negative zero and a NaN is only returned if both operands are NaN
+pub fn max(a: f64, b: f64) -> (f64, u8) {
+    select(a, b, |a, b| a > b || (a == b && a.is_sign_positive()))
+}
+
+fn select(a: f64, b: f64, prefer_a: fn(f64, f64) -> bool) -> (f64, u8) {
+    let flags = if is_signaling(a) || is_signaling(b) { NV } else { 0 };
+    let result = match (a.is_nan(), b.is_nan()) {
+        (true, true) => f64::from_bits(CANONICAL_NAN),
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => if prefer_a(a, b) { a } else { b }
+    };
+    (result, flags)
+}
+
+/// Quiet equality, only invalid for signaling NaN operands
+pub fn eq(a: f64, b: f64) -> (bool, u8) {
+    (a == b, if is_signaling(a) || is_signaling(b) { NV } else { 0 })
+}
+
+/// Signaling less than, invalid for any NaN operand
+pub fn lt(a: f64, b: f64) -> (bool, u8) {
+    (a < b, if a.is_nan() || b.is_nan() { NV } else { 0 })
+}
+
+/// Signaling less than or equal, invalid for any NaN operand
+pub fn le(a: f64, b: f64) -> (bool, u8) {
+    (a <= b, if a.is_nan() || b.is_nan() { NV } else { 0 })
+}
+
+/// The class of a value as a mask with a single bit set, as given by FCLASS.D
+pub fn classify(value: f64) -> u32 {
+    let negative = value.is_sign_negative();
+    1 << match value {
+        _ if is_signaling(value) => 8,
+        _ if value.is_nan() => 9,
+        _ if value.is_infinite() => if negative { 0 } else { 7 },
+        _ if value == 0.0 => if negative { 3 } else { 4 },
+        _ if value.is_subnormal() => if negative { 2 } else { 5 },
+        _ => if negative { 1 } else { 6 }
+    }
+}
+
+/// Convert to an integer of `bits` width, returned as its two's complement bit pattern.
+/// Values out of range, including infinities, saturate and NaN converts to the largest integer, both raising the invalid flag
+pub fn to_integer(value: f64, signed: bool, bits: u32, rm: RoundingMode) -> (u64, u8) {
+    let (min, max) = if signed {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    } else {
+        (0, (1i128 << bits) - 1)
+    };
+    if value.is_nan() {
+        return (max as u64, NV)
+    }
+    let rounded = match rm {
+        RoundingMode::NearestEven => value.round_ties_even(),
+        RoundingMode::TowardZero => value.trunc(),
+        RoundingMode::Down => value.floor(),
+        RoundingMode::Up => value.ceil(),
+        RoundingMode::NearestMaxMagnitude => value.round()
+    };
+    // The bounds are powers of two so are exact at double precision
+    if rounded < min as f64 {
+        (min as u64, NV)
+    } else if rounded >= max as f64 + 1.0 {
+        (max as u64, NV)
+    } else {
+        (rounded as i128 as u64, if rounded != value { NX } else { 0 })
+    }
+}
+
+/// Convert an integer, given by its magnitude and sign, to double precision
+pub fn from_integer(magnitude: u64, negative: bool, rm: RoundingMode) -> (f64, u8) {
+    let sign = |value: f64| if negative { -value } else { value };
+    let bits = 64 - magnitude.leading_zeros();
+    if bits <= f64::MANTISSA_DIGITS {
+        return (sign(magnitude as f64), 0)
+    }
+    // Round away the bits below the precision of a double
+    let shift = bits - f64::MANTISSA_DIGITS;
+    let mut mantissa = magnitude >> shift;
+    let rest = magnitude & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let up = match rm {
+        RoundingMode::NearestEven => rest > half || (rest == half && mantissa & 1 != 0),
+        RoundingMode::TowardZero => false,
+        RoundingMode::Down => negative && rest != 0,
+        RoundingMode::Up => !negative && rest != 0,
+        RoundingMode::NearestMaxMagnitude => rest >= half
+    };
+    if up {
+        mantissa += 1
+    }
+    (sign(mantissa as f64 * 2f64.powi(shift as i32)), if rest != 0 { NX } else { 0 })
+}
+
+/// Widen a single-precision value, which is exact unless it is a NaN
+pub fn from_single(value: f32) -> (f64, u8) {
+    if value.is_nan() {
+        (f64::from_bits(CANONICAL_NAN), if crate::float::is_signaling(value) { NV } else { 0 })
+    } else {
+        (value as f64, 0)
+    }
+}
+
+/// Narrow a double-precision value to single precision with the given rounding mode
+pub fn to_single(value: f64, rm: RoundingMode) -> (f32, u8) {
+    if value.is_nan() {
+        (f32::from_bits(crate::float::CANONICAL_NAN), if is_signaling(value) { NV } else { 0 })
+    } else {
+        crate::float::round(value, 0.0, rm)
+    }
+}
+
+/// Combine the magnitude of `a` with a sign derived from the signs of both operands
+pub fn inject_sign(a: u64, b: u64, operation: SignInjection) -> u64 {
+    const SIGN: u64 = 1 << 63;
+    let sign = match operation {
+        SignInjection::Copy => b & SIGN,
+        SignInjection::Negate => !b & SIGN,
+        SignInjection::Xor => (a ^ b) & SIGN
+    };
+    a & !SIGN | sign
+}
@@ -0,0 +1,319 @@
+//! Single-precision floating-point arithmetic for the F extension.
+//!
+//! Operands are widened to double precision on the host, where every operation used is either exact or has an
+//! error that can be recovered exactly. The result is then rounded to single precision with the requested rounding
+//! mode, accruing the exception flags of the operation.
+
+/// Invalid operation exception flag in fflags
+pub const NV: u8 = 1 << 4;
+/// Divide by zero exception flag in fflags
+pub const DZ: u8 = 1 << 3;
+/// Overflow exception flag in fflags
+pub const OF: u8 = 1 << 2;
+/// Underflow exception flag in fflags
+pub const UF: u8 = 1 << 1;
+/// Inexact exception flag in fflags
+pub const NX: u8 = 1;
+
+/// The quiet NaN produced by every operation with a NaN result
+pub const CANONICAL_NAN: u32 = 0x7FC0_0000;
+
+/// A floating-point register holding the raw bit pattern of a single-precision value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FpRegister(pub u32);
+impl FpRegister {
+    /// The value of the register as a single-precision float
+    pub fn f32(self) -> f32 {
+        f32::from_bits(self.0)
+    }
+}
+impl From<f32> for FpRegister {
+    fn from(value: f32) -> Self {
+        Self(value.to_bits())
+    }
+}
+
+/// The floating-point control and status register, holding the dynamic rounding mode and the accrued exception flags
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fcsr {
+    /// The dynamic rounding mode, as encoded in the rm field of an instruction
+    pub frm: u8,
+    /// The exception flags accrued since they were last cleared
+    pub fflags: u8
+}
+impl Fcsr {
+    /// The value of fcsr as read by a CSR instruction
+    pub fn bits(self) -> u8 {
+        self.frm << 5 | self.fflags
+    }
+    /// Write fcsr as a CSR instruction would, ignoring the reserved upper bits
+    pub fn set_bits(&mut self, value: u8) {
+        self.frm = value >> 5;
+        self.fflags = value & 0x1F
+    }
+}
+
+/// The rounding modes of the rm field and frm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round to nearest, ties to even
+    NearestEven,
+    /// Round towards zero
+    TowardZero,
+    /// Round down, towards negative infinity
+    Down,
+    /// Round up, towards positive infinity
+    Up,
+    /// Round to nearest, ties to max magnitude
+    NearestMaxMagnitude
+}
+impl RoundingMode {
+    /// The rounding mode of an encoding, or `None` for the reserved encodings and the dynamic mode
+    pub fn decode(rm: u8) -> Option<Self> {
+        Some(match rm {
+            0b000 => Self::NearestEven,
+            0b001 => Self::TowardZero,
+            0b010 => Self::Down,
+            0b011 => Self::Up,
+            0b100 => Self::NearestMaxMagnitude,
+            _ => return None
+        })
+    }
+}
+
+/// Returns true if the value is a signaling NaN
+pub fn is_signaling(value: f32) -> bool {
+    value.is_nan() && value.to_bits() & 0x0040_0000 == 0
+}
+
+/// Round an exact value to single precision.
+/// `error` corrects a value that could not be represented exactly, such that the exact value is `value + error`.
+pub(crate) fn round(value: f64, error: f64, rm: RoundingMode) -> (f32, u8) {
+    let nearest = value as f32;
+    if value.is_infinite() {
+        return (nearest, 0)
+    }
+    // Exact as the nearest single is within a factor of 2 of the value, or infinite
+    let difference = value - nearest as f64;
+    let above = if difference != 0.0 {
+        difference > 0.0
+    } else if error != 0.0 {
+        error > 0.0
+    } else {
+        return (nearest, 0)
+    };
+    let (lower, upper) = if above { (nearest, nearest.next_up()) } else { (nearest.next_down(), nearest) };
+    // A value exactly between two singles is only a tie if it is also exact, otherwise the error breaks it
+    let other = if above { upper } else { lower };
+    let midpoint = (other as f64 - value).abs() == difference.abs();
+    let result = match rm {
+        RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude if midpoint && error != 0.0 => {
+            if error > 0.0 { upper } else { lower }
+        },
+        RoundingMode::NearestEven => nearest,
+        RoundingMode::TowardZero => if value > 0.0 { lower } else { upper },
+        RoundingMode::Down => lower,
+        RoundingMode::Up => upper,
+        RoundingMode::NearestMaxMagnitude => match (midpoint, value > 0.0) {
+            (true, true) => upper,
+            (true, false) => lower,
+            (false, _) => nearest
+        }
+    };
+    // Overflow when the result rounded with an unbounded exponent would exceed the largest finite value
+    let overflow = result.is_infinite() || value.abs() >= 2f64.powi(128);
+    let underflow = result.abs() < f32::MIN_POSITIVE;
+    (result, NX | if overflow { OF } else { 0 } | if underflow { UF } else { 0 })
+}
+
+/// Round the result of an arithmetic operation, producing the canonical NaN if it is not a number.
+/// Invalid if an operand is a signaling NaN, or if the operation produced a NaN from numbers
+fn arithmetic(operands: &[f32], value: f64, error: f64, rm: RoundingMode) -> (f32, u8) {
+    if value.is_nan() {
+        let invalid = operands.iter().any(|&operand| is_signaling(operand)) || !operands.iter().any(|operand| operand.is_nan());
+        (f32::from_bits(CANONICAL_NAN), if invalid { NV } else { 0 })
+    } else {
+        round(value, error, rm)
+    }
+}
+
+/// The rounding error of a double-precision sum, recovered exactly
+fn sum_error(a: f64, b: f64, sum: f64) -> f64 {
+    if !sum.is_finite() {
+        return 0.0
+    }
+    let b_virtual = sum - a;
+    (a - (sum - b_virtual)) + (b - b_virtual)
+}
+
+pub fn add(a: f32, b: f32, rm: RoundingMode) -> (f32, u8) {
+    let sum = a as f64 + b as f64;
+    arithmetic(&[a, b], sum, sum_error(a as f64, b as f64, sum), rm)
+}
+
+pub fn sub(a: f32, b: f32, rm: RoundingMode) -> (f32, u8) {
+    add(a, -b, rm)
+}
+
+pub fn mul(a: f32, b: f32, rm: RoundingMode) -> (f32, u8) {
+    // The product of two singles is exact at double precision
+    arithmetic(&[a, b], a as f64 * b as f64, 0.0, rm)
+}
+
+pub fn div(a: f32, b: f32, rm: RoundingMode) -> (f32, u8) {
+    let quotient = a as f64 / b as f64;
+    if b == 0.0 && a.is_finite() && a != 0.0 {
+        return (quotient as f32, DZ)
+    }
+    let error = if quotient.is_finite() && b != 0.0 {
+        -quotient.mul_add(b as f64, -(a as f64)) / b as f64
+    } else {
+        0.0
+    };
+    arithmetic(&[a, b], quotient, error, rm)
+}
+
+pub fn sqrt(a: f32, rm: RoundingMode) -> (f32, u8) {
+    let root = (a as f64).sqrt();
+    let error = if root.is_finite() && root > 0.0 {
+        -root.mul_add(root, -(a as f64)) / (2.0 * root)
+    } else {
+        0.0
+    };
+    arithmetic(&[a], root, error, rm)
+}
+
+/// Compute `a * b + c` with a single rounding
+pub fn fused_multiply_add(a: f32, b: f32, c: f32, rm: RoundingMode) -> (f32, u8) {
+    // The product of infinity and zero is invalid even if the addend is a quiet NaN
+    if (a.is_infinite() && b == 0.0) || (a == 0.0 && b.is_infinite()) {
+        return (f32::from_bits(CANONICAL_NAN), NV)
+    }
+    let product = a as f64 * b as f64;
+    let sum = product + c as f64;
+    arithmetic(&[a, b, c], sum, sum_error(product, c as f64, sum), rm)
+}
+
+/// The smaller operand, where negative zero is less than positive zero and a NaN is only returned if both operands are NaN
+pub fn min(a: f32, b: f32) -> (f32, u8) {
+    select(a, b, |a, b| a < b || (a == b && a.is_sign_negative()))
+}
+
+/// The larger operand, where positive zero is greater than negative zero and a NaN is only returned if both operands are NaN
+pub fn max(a: f32, b: f32) -> (f32, u8) {
+    select(a, b, |a, b| a > b || (a == b && a.is_sign_positive()))
+}
+
+fn select(a: f32, b: f32, prefer_a: fn(f32, f32) -> bool) -> (f32, u8) {
+    let flags = if is_signaling(a) || is_signaling(b) { NV } else { 0 };
+    let result = match (a.is_nan(), b.is_nan()) {
+        (true, true) => f32::from_bits(CANONICAL_NAN),
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => if prefer_a(a, b) { a } else { b }
+    };
+    (result, flags)
+}
+
+/// Quiet equality, only invalid for signaling NaN operands
+pub fn eq(a: f32, b: f32) -> (bool, u8) {
+    (a == b, if is_signaling(a) || is_signaling(b) { NV } else { 0 })
+}
+
+/// Signaling less than, invalid for any NaN operand
+pub fn lt(a: f32, b: f32) -> (bool, u8) {
+    (a < b, if a.is_nan() || b.is_nan() { NV } else { 0 })
+}
+
+/// Signaling less than or equal, invalid for any NaN operand
+pub fn le(a: f32, b: f32) -> (bool, u8) {
+    (a <= b, if a.is_nan() || b.is_nan() { NV } else { 0 })
+}
+
+/// The class of a value as a mask with a single bit set, as given by FCLASS.S
+pub fn classify(value: f32) -> u32 {
+    let negative = value.is_sign_negative();
+    1 << match value {
+        _ if is_signaling(value) => 8,
+        _ if value.is_nan() => 9,
+        _ if value.is_infinite() => if negative { 0 } else { 7 },
+        _ if value == 0.0 => if negative { 3 } else { 4 },
+        _ if value.is_subnormal() => if negative { 2 } else { 5 },
+        _ => if negative { 1 } else { 6 }
+    }
+}
+
+/// Convert to an integer of `bits` width, returned as its two's complement bit pattern.
+/// Values out of range, including infinities, saturate and NaN converts to the largest integer, both raising the invalid flag
+pub fn to_integer(value: f32, signed: bool, bits: u32, rm: RoundingMode) -> (u64, u8) {
+    let (min, max) = if signed {
+        (-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1)
+    } else {
+        (0, (1i128 << bits) - 1)
+    };
+    if value.is_nan() {
+        return (max as u64, NV)
+    }
+    let value = value as f64;
+    let rounded = match rm {
+        RoundingMode::NearestEven => value.round_ties_even(),
+        RoundingMode::TowardZero => value.trunc(),
+        RoundingMode::Down => value.floor(),
+        RoundingMode::Up => value.ceil(),
+        RoundingMode::NearestMaxMagnitude => value.round()
+    };
+    // The bounds are powers of two so are exact at double precision
+    if rounded < min as f64 {
+        (min as u64, NV)
+    } else if rounded >= max as f64 + 1.0 {
+        (max as u64, NV)
+    } else {
+        (rounded as i128 as u64, if rounded != value { NX } else { 0 })
+    }
+}
+
+/// Convert an integer, given by its magnitude and sign, to single precision
+pub fn from_integer(magnitude: u64, negative: bool, rm: RoundingMode) -> (f32, u8) {
+    let sign = |value: f32| if negative { -value } else { value };
+    let bits = 64 - magnitude.leading_zeros();
+    if bits <= f32::MANTISSA_DIGITS {
+        return (sign(magnitude as f32), 0)
+    }
+    // Round away the bits below the precision of a single
+    let shift = bits - f32::MANTISSA_DIGITS;
+    let mut mantissa = magnitude >> shift;
+    let rest = magnitude & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    let up = match rm {
+        RoundingMode::NearestEven => rest > half || (rest == half && mantissa & 1 != 0),
+        RoundingMode::TowardZero => false,
+        RoundingMode::Down => negative && rest != 0,
+        RoundingMode::Up => !negative && rest != 0,
+        RoundingMode::NearestMaxMagnitude => rest >= half
+    };
+    if up {
+        mantissa += 1
+    }
+    (sign(mantissa as f32 * 2f32.powi(shift as i32)), if rest != 0 { NX } else { 0 })
+}
+
+/// Combine the magnitude of `a` with a sign derived from the signs of both operands
+pub fn inject_sign(a: u32, b: u32, operation: SignInjection) -> u32 {
+    const SIGN: u32 = 1 << 31;
+    let sign = match operation {
+        SignInjection::Copy => b & SIGN,
+        SignInjection::Negate => !b & SIGN,
+        SignInjection::Xor => (a ^ b) & SIGN
+    };
+    a & !SIGN | sign
+}
+
+/// The sign given to the result of FSGNJ.S, FSGNJN.S and FSGNJX.S
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignInjection {
+    Copy,
+    Negate,
+    Xor
+}
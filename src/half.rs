@@ -0,0 +1,282 @@
+//! Half-precision floating-point arithmetic for the Zfh extension.
+//!
+//! The host has no half-precision type, so values are held as their raw bits. Operands are widened to double
+//! precision, where sums and products of halves are exact and the error of every other operation can be recovered.
+//! The result is then rounded to half precision with the requested rounding mode, accruing the exception flags.
+
+use crate::float::{ self, RoundingMode, SignInjection, NV, DZ, OF, UF, NX };
+
+/// The quiet NaN produced by every operation with a half-precision NaN result
+pub const CANONICAL_NAN: u16 = 0x7E00;
+
+const SIGN: u16 = 1 << 15;
+const EXPONENT: u16 = 0x7C00;
+const MANTISSA: u16 = 0x03FF;
+/// The bit distinguishing quiet from signaling NaNs
+const QUIET: u16 = 1 << 9;
+/// The largest finite half, 65504
+const MAX: u16 = 0x7BFF;
+
+/// Returns true if the value is a NaN
+pub fn is_nan(value: u16) -> bool {
+    value & EXPONENT == EXPONENT && value & MANTISSA != 0
+}
+
+/// Returns true if the value is a signaling NaN
+pub fn is_signaling(value: u16) -> bool {
+    is_nan(value) && value & QUIET == 0
+}
+
+/// The exact value of a half at double precision
+pub fn to_f64(value: u16) -> f64 {
+    let exponent = (value & EXPONENT) >> 10;
+    let mantissa = (value & MANTISSA) as f64;
+    let magnitude = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        0x1F if value & MANTISSA == 0 => f64::INFINITY,
+        0x1F => f64::NAN,
+        _ => (1024.0 + mantissa) * 2f64.powi(exponent as i32 - 25)
+    };
+    if value & SIGN != 0 { -magnitude } else { magnitude }
+}
+
+/// Round an exact value to half precision.
+/// `error` corrects a value that could not be represented exactly, such that the exact value is `value + error`.
+pub(crate) fn round(value: f64, error: f64, rm: RoundingMode) -> (u16, u8) {
+    let negative = value.is_sign_negative();
+    let sign = if negative { SIGN } else { 0 };
+    if value.is_infinite() {
+        return (sign | EXPONENT, 0)
+    }
+    let magnitude = value.abs();
+    let error = if negative { -error } else { error };
+    // The spacing of halves around the magnitude, which is that of the subnormals below the smallest normal
+    let exponent = if magnitude >= 2f64.powi(-14) { (magnitude.to_bits() >> 52) as i32 - 1023 } else { -14 };
+    let quantum = 2f64.powi(exponent - 10);
+    // Exact, as scaling by a power of two and taking the integer part are exact
+    let scaled = magnitude / quantum;
+    let mut steps = scaled.trunc();
+    let fraction = scaled - steps;
+
+    // Where the exact magnitude lies between `steps` and the step above it
+    let (exact, half, above_half) = if fraction == 0.0 && error < 0.0 {
+        steps -= 1.0;
+        (false, false, true)
+    } else {
+        let half = fraction == 0.5 && error == 0.0;
+        (fraction == 0.0 && error == 0.0, half, fraction > 0.5 || (fraction == 0.5 && error > 0.0))
+    };
+    if !exact {
+        let up = match rm {
+            RoundingMode::NearestEven => above_half || (half && steps % 2.0 != 0.0),
+            RoundingMode::NearestMaxMagnitude => above_half || half,
+            RoundingMode::TowardZero => false,
+            RoundingMode::Down => negative,
+            RoundingMode::Up => !negative
+        };
+        if up {
+            steps += 1.0
+        }
+    }
+    let rounded = steps * quantum;
+    let inexact = if exact { 0 } else { NX };
+
+    if rounded > to_f64(MAX) {
+        let infinite = match rm {
+            RoundingMode::NearestEven | RoundingMode::NearestMaxMagnitude => true,
+            RoundingMode::TowardZero => false,
+            RoundingMode::Down => negative,
+            RoundingMode::Up => !negative
+        };
+        return (sign | if infinite { EXPONENT } else { MAX }, OF | NX)
+    }
+    let bits = if rounded >= 2f64.powi(-14) {
+        let exponent = (rounded.to_bits() >> 52) as i32 - 1023;
+        ((exponent + 15) as u16) << 10 | (rounded / 2f64.powi(exponent - 10) - 1024.0) as u16
+    } else {
+        (rounded / 2f64.powi(-24)) as u16
+    };
+    let underflow = if inexact != 0 && rounded < 2f64.powi(-14) { UF } else { 0 };
+    (sign | bits, inexact | underflow)
+}
+
+/// Round the result of an arithmetic operation, producing the canonical NaN if it is not a number.
+/// Invalid if an operand is a signaling NaN, or if the operation produced a NaN from numbers
+fn arithmetic(operands: &[u16], value: f64, error: f64, rm: RoundingMode) -> (u16, u8) {
+    if value.is_nan() {
+        let invalid = operands.iter().any(|&operand| is_signaling(operand)) || !operands.iter().any(|&operand| is_nan(operand));
+        (CANONICAL_NAN, if invalid { NV } else { 0 })
+    } else {
+        round(value, error, rm)
+    }
+}
+
+/// The rounding error of a sum, recovered exactly
+fn sum_error(a: f64, b: f64, sum: f64) -> f64 {
+    if !sum.is_finite() {
+        return 0.0
+    }
+    let b_virtual = sum - a;
+    (a - (sum - b_virtual)) + (b - b_virtual)
+}
+
+pub fn add(a: u16, b: u16, rm: RoundingMode) -> (u16, u8) {
+    // The sum of two halves is exact at double precision
+    arithmetic(&[a, b], to_f64(a) + to_f64(b), 0.0, rm)
+}
+
+pub fn sub(a: u16, b: u16, rm: RoundingMode) -> (u16, u8) {
+    add(a, b ^ SIGN, rm)
+}
+
+pub fn mul(a: u16, b: u16, rm: RoundingMode) -> (u16, u8) {
+    // The product of two halves is exact at double precision
+    arithmetic(&[a, b], to_f64(a) * to_f64(b), 0.0, rm)
+}
+
+pub fn div(a: u16, b: u16, rm: RoundingMode) -> (u16, u8) {
+    let (dividend, divisor) = (to_f64(a), to_f64(b));
+    let quotient = dividend / divisor;
+    if divisor == 0.0 && dividend.is_finite() && dividend != 0.0 {
+        return (if quotient.is_sign_negative() { SIGN } else { 0 } | EXPONENT, DZ)
+    }
+    let error = if quotient.is_finite() && divisor != 0.0 {
+        (-quotient).mul_add(divisor, dividend) / divisor
+    } else {
+        0.0
+    };
+    arithmetic(&[a, b], quotient, error, rm)
+}
+
+pub fn sqrt(a: u16, rm: RoundingMode) -> (u16, u8) {
+    let value = to_f64(a);
+    let root = value.sqrt();
+    let error = if root.is_finite() && root > 0.0 {
+        (-root).mul_add(root, value) / (2.0 * root)
+    } else {
+        0.0
+    };
+    arithmetic(&[a], root, error, rm)
+}
+
+/// Compute `a * b + c` with a single rounding
+pub fn fused_multiply_add(a: u16, b: u16, c: u16, rm: RoundingMode) -> (u16, u8) {
+    let (a_value, b_value, c_value) = (to_f64(a), to_f64(b), to_f64(c));
+    // The product of infinity and zero is invalid even if the addend is a quiet NaN
+    if (a_value.is_infinite() && b_value == 0.0) || (a_value == 0.0 && b_value.is_infinite()) {
+        return (CANONICAL_NAN, NV)
+    }
+    let product = a_value * b_value;
+    let sum = product + c_value;
+    arithmetic(&[a, b, c], sum, sum_error(product, c_value, sum), rm)
+}
+
+/// The smaller operand, where negative zero is less than positive zero and a NaN is only returned if both operands are NaN
+pub fn min(a: u16, b: u16) -> (u16, u8) {
+    select(a, b, |a, b| a < b || (a == b && a.is_sign_negative()))
+}
+
+/// The larger operand, where positive zero is greater than negative zero and a NaN is only returned if both operands are NaN
+pub fn max(a: u16, b: u16) -> (u16, u8) {
+    select(a, b, |a, b| a > b || (a == b && a.is_sign_positive()))
+}
+
+fn select(a: u16, b: u16, prefer_a: fn(f64, f64) -> bool) -> (u16, u8) {
+    let flags = if is_signaling(a) || is_signaling(b) { NV } else { 0 };
+    let result = match (is_nan(a), is_nan(b)) {
+        (true, true) => CANONICAL_NAN,
+        (true, false) => b,
+        (false, true) => a,
+        (false, false) => if prefer_a(to_f64(a), to_f64(b)) { a } else { b }
+    };
+    (result, flags)
+}
+
+/// Quiet equality, only invalid for signaling NaN operands
+pub fn eq(a: u16, b: u16) -> (bool, u8) {
+    (to_f64(a) == to_f64(b), if is_signaling(a) || is_signaling(b) { NV } else { 0 })
+}
+
+/// Signaling less than, invalid for any NaN operand
+pub fn lt(a: u16, b: u16) -> (bool, u8) {
+    (to_f64(a) < to_f64(b), if is_nan(a) || is_nan(b) { NV } else { 0 })
+}
+
+/// Signaling less than or equal, invalid for any NaN operand
+pub fn le(a: u16, b: u16) -> (bool, u8) {
+    (to_f64(a) <= to_f64(b), if is_nan(a) || is_nan(b) { NV } else { 0 })
+}
+
+/// The class of a value as a mask with a single bit set, as given by FCLASS.H
+pub fn classify(value: u16) -> u32 {
+    let negative = value & SIGN != 0;
+    1 << match value & !SIGN {
+        _ if is_signaling(value) => 8,
+        _ if is_nan(value) => 9,
+        EXPONENT => if negative { 0 } else { 7 },
+        0 => if negative { 3 } else { 4 },
+        magnitude if magnitude & EXPONENT == 0 => if negative { 2 } else { 5 },
+        _ => if negative { 1 } else { 6 }
+    }
+}
+
+/// Convert to an integer of `bits` width, returned as its two's complement bit pattern.
+/// Every half is exact as a single, so this is the conversion of the equivalent single
+pub fn to_integer(value: u16, signed: bool, bits: u32, rm: RoundingMode) -> (u64, u8) {
+    float::to_integer(to_single(value).0, signed, bits, rm)
+}
+
+/// Convert an integer, given by its magnitude and sign, to half precision.
+/// Any integer too large to be exact at double precision overflows a half regardless of the rounding
+pub fn from_integer(magnitude: u64, negative: bool, rm: RoundingMode) -> (u16, u8) {
+    let value = magnitude as f64;
+    round(if negative { -value } else { value }, 0.0, rm)
+}
+
+/// Widen to single precision, which is exact unless the value is a NaN
+pub fn to_single(value: u16) -> (f32, u8) {
+    if is_nan(value) {
+        (f32::from_bits(float::CANONICAL_NAN), if is_signaling(value) { NV } else { 0 })
+    } else {
+        (to_f64(value) as f32, 0)
+    }
+}
+
+/// Narrow a single-precision value to half precision with the given rounding mode
+pub fn from_single(value: f32, rm: RoundingMode) -> (u16, u8) {
+    if value.is_nan() {
+        (CANONICAL_NAN, if float::is_signaling(value) { NV } else { 0 })
+    } else {
+        round(value as f64, 0.0, rm)
+    }
+}
+
+/// Widen to double precision, which is exact unless the value is a NaN
+#[cfg(feature = "ext-d")]
+pub fn to_double(value: u16) -> (f64, u8) {
+    if is_nan(value) {
+        (f64::from_bits(crate::double::CANONICAL_NAN), if is_signaling(value) { NV } else { 0 })
+    } else {
+        (to_f64(value), 0)
+    }
+}
+
+/// Narrow a double-precision value to half precision with the given rounding mode
+#[cfg(feature = "ext-d")]
+pub fn from_double(value: f64, rm: RoundingMode) -> (u16, u8) {
+    if value.is_nan() {
+        (CANONICAL_NAN, if crate::double::is_signaling(value) { NV } else { 0 })
+    } else {
+        round(value, 0.0, rm)
+    }
+}
+
+/// Combine the magnitude of `a` with a sign derived from the signs of both operands
+pub fn inject_sign(a: u16, b: u16, operation: SignInjection) -> u16 {
+    let sign = match operation {
+        SignInjection::Copy => b & SIGN,
+        SignInjection::Negate => !b & SIGN,
+        SignInjection::Xor => (a ^ b) & SIGN
+    };
+    a & !SIGN | sign
+}
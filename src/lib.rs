@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::unit_arg)]
 //! Rysk Core assists in the creation of RISCV virtual machines, providing virtual harts.
 //! 
@@ -5,16 +6,48 @@
 //! - Implement the `system::Mmu` trait
 //! - Create an instance of `system::Core` with `register::Register*` as the generic type
 //! - Execute instructions using `system::Core::execute()`
+//!
+//! Instructions may also be decoded without a core or MMU using `decode::decode()`, such as for static analysis.
+//!
+//! `register::RegisterSize` is not available on 16-bit hosts as there is no 16-bit RISCV base ISA;
+//! use `register::Register32` or `register::Register64` there instead.
+//!
+//! Without the default `std` feature the crate is `no_std`. The `alloc` feature, enabled by `std`, provides stateful trap
+//! strategies, hooks, the instruction cache, core dumps, `Core::diff`, `Core::isa_string`, `decode::validate` and the
+//! V extension; without it the crate needs no allocator. The F, D and Zfh extensions require `std` for their
+//! floating-point arithmetic.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod variant;
+pub mod decode;
 pub mod register;
 pub mod system;
 
-pub use system::{ Core, Mmu };
-pub use register::{ Register, Register32, Register64, RegisterSize };
+pub use system::{ AccessInfo, ArrayRam, CacheBlockOperation, Core, CoreDumpError, HostReturn, MemoryFault, Mmu, Profile, Rom, RunResult, StepOutcome, TrapStrategy };
+#[cfg(feature = "alloc")]
+pub use system::StateDiff;
+#[cfg(feature = "ext-csr")]
+pub use system::CsrHandled;
+#[cfg(feature = "test-util")]
+pub use system::{ run_program, RUN_PROGRAM_WATCHDOG };
+pub use register::{ Register, Register32, Register64, Register128 };
+#[cfg(not(target_pointer_width = "16"))]
+pub use register::RegisterSize;
 
 #[cfg(feature = "ext-csr")]
 pub mod csr;
+#[cfg(feature = "ext-c")]
+pub mod compressed;
+#[cfg(feature = "ext-f")]
+pub mod float;
+#[cfg(feature = "ext-d")]
+pub mod double;
+#[cfg(feature = "ext-zfh")]
+pub mod half;
+#[cfg(feature = "ext-v")]
+pub mod vector;
 
 pub mod version {
     pub const PATCH: u8 = 3;
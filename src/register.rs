@@ -32,6 +32,30 @@ macro_rules! impl_multiply {
 
 impl_multiply!{(i32, u32, * = 4) -> (i64, u64), (i64, u64, * = 8) -> (i128, u128)}
 
+/// There is no wider type to multiply 128-bit integers in, so the product is built from the products of their 64-bit halves
+impl Multiply<i128, u128> for i128 {
+    fn muls(first: i128, second: i128) -> (i128, i128) {
+        let (low, high) = i128::mulu(first as u128, second as u128);
+        // Correct the high bits of the unsigned product for each negative operand
+        let high = high.wrapping_sub(if first < 0 { second as u128 } else { 0 }).wrapping_sub(if second < 0 { first as u128 } else { 0 });
+        (low as i128, high as i128)
+    }
+    fn mulu(first: u128, second: u128) -> (u128, u128) {
+        let half = |value: u128| (value & u64::MAX as u128, value >> 64);
+        let ((first_low, first_high), (second_low, second_high)) = (half(first), half(second));
+        let (low_low, low_high) = half(first_low * second_low);
+        let (cross1_low, cross1_high) = half(first_low * second_high);
+        let (cross2_low, cross2_high) = half(first_high * second_low);
+        let middle = low_high + cross1_low + cross2_low;
+        (low_low | middle << 64, first_high * second_high + cross1_high + cross2_high + (middle >> 64))
+    }
+    fn mulsu(first: i128, second: u128) -> (i128, i128) {
+        let (low, high) = i128::mulu(first as u128, second);
+        let high = high.wrapping_sub(if first < 0 { second } else { 0 });
+        (low as i128, high as i128)
+    }
+}
+
 #[cfg(target_pointer_width = "32")]
 impl_multiply!{(isize, usize, * = 4) -> (i64, u64)}
 #[cfg(target_pointer_width = "64")]
@@ -44,6 +68,10 @@ pub trait Integer: Default {
     fn sub(self, other: Self) -> Self;
     fn shl(self, other: Self) -> Self;
     fn shr(self, other: Self) -> Self;
+    /// Rotate left by `other` modulo the width of the type
+    fn rotl(self, other: Self) -> Self;
+    /// Rotate right by `other` modulo the width of the type
+    fn rotr(self, other: Self) -> Self;
 
     fn div(self, other: Self) -> Self;
     fn rem(self, other: Self) -> Self;
@@ -57,6 +85,9 @@ pub trait Integer: Default {
     fn or(self, other: Self) -> Self;
     fn xor(self, other: Self) -> Self;
     fn not(self) -> Self;
+
+    /// Carry-less multiply, returning the low and the high bits of the product
+    fn clmul(self, other: Self) -> (Self, Self);
 }
 macro_rules! impl_integer {
     ($($name:ident(* = $shift:expr, $larger_type:ident)),*) => {
@@ -71,12 +102,16 @@ macro_rules! impl_integer {
                 fn shl(self, other: Self) -> Self { $name::wrapping_shl(self, other as _) }
                 #[inline(always)]
                 fn shr(self, other: Self) -> Self { $name::wrapping_shr(self, other as _) }
+                #[inline(always)]
+                fn rotl(self, other: Self) -> Self { $name::rotate_left(self, other as u32 & ($name::BITS - 1)) }
+                #[inline(always)]
+                fn rotr(self, other: Self) -> Self { $name::rotate_right(self, other as u32 & ($name::BITS - 1)) }
 
                 #[inline(always)]
                 fn div(self, other: Self) -> Self {
                     if other == 0 {
                         -1 as _
-                    } else if self == std::$name::MIN && other == -1 as _ {
+                    } else if self == $name::MIN && other == -1 as _ {
                         self
                     } else {
                         self / other
@@ -86,7 +121,7 @@ macro_rules! impl_integer {
                 fn rem(self, other: Self) -> Self {
                     if other == 0 {
                         self
-                    } else if self == std::$name::MIN && other == -1 as _ {
+                    } else if self == $name::MIN && other == -1 as _ {
                         0
                     } else {
                         self % other
@@ -110,16 +145,25 @@ macro_rules! impl_integer {
                 fn xor(self, other: Self) -> Self { self ^ other }
                 #[inline(always)]
                 fn not(self) -> Self { !self }
+
+                fn clmul(self, other: Self) -> (Self, Self) {
+                    (0..$name::BITS).filter(|&bit| (other >> bit) & 1 != 0).fold((0, 0), |(low, high), bit| {
+                        // The bits of self shifted beyond the width, masking any sign extension
+                        let overflow = if bit == 0 { 0 } else { (self >> ($name::BITS - bit)) & !(!0 << bit) };
+                        (low ^ (self << bit), high ^ overflow)
+                    })
+                }
             }
         )*
     };
 }
-impl_integer! { u32(* = 4, u64), i32(* = 4, i64), u64(* = 8, u128), i64(* = 4, u128), usize(* = 8, usize), isize(* = 8, usize) }
+impl_integer! { u32(* = 4, u64), i32(* = 4, i64), u64(* = 8, u128), i64(* = 8, i128), u128(* = 16, u128), i128(* = 16, i128), usize(* = 8, usize), isize(* = 8, usize) }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum RegisterWidth {
     Bits32,
-    Bits64
+    Bits64,
+    Bits128
 }
 
 /// Byte order independent interpretations for a register
@@ -178,23 +222,31 @@ pub trait Register: Xlen + Sized + Default + Copy {
     fn sha(self, other: Self) -> Self {
         Self::from_signed(self.signed().shr(other.signed()))
     }
+    /// Rotate left by a number of bits, using only as many bits of the amount as needed to address the register
+    fn rotl(self, other: Self) -> Self {
+        Self::from_unsigned(self.unsigned().rotl(other.unsigned()))
+    }
+    /// Rotate right by a number of bits, using only as many bits of the amount as needed to address the register
+    fn rotr(self, other: Self) -> Self {
+        Self::from_unsigned(self.unsigned().rotr(other.unsigned()))
+    }
 
-    #[cfg(feature = "ext-m")]
+    #[cfg(feature = "ext-zmmul")]
     /// Multiplication returning the low bits
     fn mul(self, other: Self) -> Self {
         Self::from_signed(Self::Signed::muls(self.signed(), other.signed()).0)
     }
-    #[cfg(feature = "ext-m")]
+    #[cfg(feature = "ext-zmmul")]
     /// Signed multiplication returning the high bits
     fn mulh(self, other: Self) -> Self {
         Self::from_signed(Self::Signed::muls(self.signed(), other.signed()).1)
     }
-    #[cfg(feature = "ext-m")]
+    #[cfg(feature = "ext-zmmul")]
     /// Unsigned multiplication returning the high bits
     fn mulhu(self, other: Self) -> Self {
         Self::from_unsigned(Self::Signed::mulu(self.unsigned(), other.unsigned()).1)
     }
-    #[cfg(feature = "ext-m")]
+    #[cfg(feature = "ext-zmmul")]
     /// Signed-Unsigned multiplication returning the high bits
     fn mulhsu(self, other: Self) -> Self {
         Self::from_signed(Self::Signed::mulsu(self.signed(), other.unsigned()).1)
@@ -220,6 +272,212 @@ pub trait Register: Xlen + Sized + Default + Copy {
         Self::from_unsigned(self.unsigned().rem(other.unsigned()))
     }
 
+    #[cfg(feature = "ext-zbkb")]
+    /// Pack the lower halves of self and other into the lower and upper halves of the result respectively
+    fn pack(self, other: Self) -> Self {
+        match Self::WIDTH {
+            RegisterWidth::Bits32 => {
+                let (low, high) = (self.half(), other.half());
+                Self::zero_extended_word([low[0], low[1], high[0], high[1]])
+            },
+            RegisterWidth::Bits64 => {
+                let (low, high) = (self.word(), other.word());
+                Self::zero_extended_double([low[0], low[1], low[2], low[3], high[0], high[1], high[2], high[3]])
+            },
+            RegisterWidth::Bits128 => {
+                let mut quad = [0; 16];
+                quad[..8].copy_from_slice(&self.double());
+                quad[8..].copy_from_slice(&other.double());
+                Self::from_quad(quad)
+            }
+        }
+    }
+    #[cfg(feature = "ext-zbkb")]
+    /// Pack the lowest bytes of self and other into the lower half of the result, zeroing the rest
+    fn packh(self, other: Self) -> Self {
+        Self::zero_extended_half([self.byte(), other.byte()])
+    }
+    #[cfg(feature = "ext-zbkb")]
+    /// Reverse the order of the bits within each byte
+    fn brev8(self) -> Self {
+        match Self::WIDTH {
+            RegisterWidth::Bits32 => {
+                let mut word = self.word();
+                word.iter_mut().for_each(|byte| *byte = byte.reverse_bits());
+                Self::zero_extended_word(word)
+            },
+            RegisterWidth::Bits64 => {
+                let mut double = self.double();
+                double.iter_mut().for_each(|byte| *byte = byte.reverse_bits());
+                Self::zero_extended_double(double)
+            },
+            RegisterWidth::Bits128 => {
+                let mut quad = self.quad();
+                quad.iter_mut().for_each(|byte| *byte = byte.reverse_bits());
+                Self::from_quad(quad)
+            }
+        }
+    }
+    #[cfg(feature = "ext-zbkb")]
+    /// Interleave the bits of the lower and upper halves of the lowest word, the lower half taking the even bits
+    fn zip(self) -> Self {
+        let word = u32::from_le_bytes(self.word());
+        let zipped = (0..16).fold(0, |zipped, bit| {
+            zipped | ((word >> bit) & 1) << (2 * bit) | ((word >> (bit + 16)) & 1) << (2 * bit + 1)
+        });
+        Self::sign_extended_word(u32::to_le_bytes(zipped))
+    }
+    #[cfg(feature = "ext-zbkb")]
+    /// The inverse of `zip`, gathering the even bits of the lowest word into the lower half and the odd bits into the upper half
+    fn unzip(self) -> Self {
+        let word = u32::from_le_bytes(self.word());
+        let unzipped = (0..16).fold(0, |unzipped, bit| {
+            unzipped | ((word >> (2 * bit)) & 1) << bit | ((word >> (2 * bit + 1)) & 1) << (bit + 16)
+        });
+        Self::sign_extended_word(u32::to_le_bytes(unzipped))
+    }
+
+    #[cfg(feature = "ext-zbkx")]
+    /// Replace each nibble of other with the nibble of self it indexes, or with zero if it indexes beyond the register
+    fn xperm4(self, other: Self) -> Self {
+        xperm(self, other, 4)
+    }
+    #[cfg(feature = "ext-zbkx")]
+    /// Replace each byte of other with the byte of self it indexes, or with zero if it indexes beyond the register
+    fn xperm8(self, other: Self) -> Self {
+        xperm(self, other, 8)
+    }
+
+    #[cfg(feature = "ext-zbc")]
+    /// Carry-less multiplication returning the low bits
+    fn clmul(self, other: Self) -> Self {
+        Self::from_unsigned(self.unsigned().clmul(other.unsigned()).0)
+    }
+    #[cfg(feature = "ext-zbc")]
+    /// Carry-less multiplication returning the high bits
+    fn clmulh(self, other: Self) -> Self {
+        Self::from_unsigned(self.unsigned().clmul(other.unsigned()).1)
+    }
+    #[cfg(feature = "ext-zbc")]
+    /// Carry-less multiplication returning the bits below the highest bit of the product, from bit XLEN-1 to 2*XLEN-2
+    fn clmulr(self, other: Self) -> Self {
+        let (low, high) = self.unsigned().clmul(other.unsigned());
+        let width = match Self::WIDTH {
+            RegisterWidth::Bits32 => 32,
+            RegisterWidth::Bits64 => 64,
+            RegisterWidth::Bits128 => 128
+        };
+        Self::from_unsigned(high).shl(Self::zero_extended_byte(1)).or(Self::from_unsigned(low).shr(Self::zero_extended_byte(width - 1)))
+    }
+
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-256 σ0 function of the lowest word, sign-extended
+    fn sha256sig0(self) -> Self {
+        let x = u32::from_le_bytes(self.word());
+        Self::sign_extended_word((x.rotate_right(7) ^ x.rotate_right(18) ^ x >> 3).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-256 σ1 function of the lowest word, sign-extended
+    fn sha256sig1(self) -> Self {
+        let x = u32::from_le_bytes(self.word());
+        Self::sign_extended_word((x.rotate_right(17) ^ x.rotate_right(19) ^ x >> 10).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-256 Σ0 function of the lowest word, sign-extended
+    fn sha256sum0(self) -> Self {
+        let x = u32::from_le_bytes(self.word());
+        Self::sign_extended_word((x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-256 Σ1 function of the lowest word, sign-extended
+    fn sha256sum1(self) -> Self {
+        let x = u32::from_le_bytes(self.word());
+        Self::sign_extended_word((x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-512 σ0 function. Only valid on RV64
+    fn sha512sig0(self) -> Self {
+        let x = u64::from_le_bytes(self.double());
+        Self::zero_extended_double((x.rotate_right(1) ^ x.rotate_right(8) ^ x >> 7).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-512 σ1 function. Only valid on RV64
+    fn sha512sig1(self) -> Self {
+        let x = u64::from_le_bytes(self.double());
+        Self::zero_extended_double((x.rotate_right(19) ^ x.rotate_right(61) ^ x >> 6).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-512 Σ0 function. Only valid on RV64
+    fn sha512sum0(self) -> Self {
+        let x = u64::from_le_bytes(self.double());
+        Self::zero_extended_double((x.rotate_right(28) ^ x.rotate_right(34) ^ x.rotate_right(39)).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The SHA-512 Σ1 function. Only valid on RV64
+    fn sha512sum1(self) -> Self {
+        let x = u64::from_le_bytes(self.double());
+        Self::zero_extended_double((x.rotate_right(14) ^ x.rotate_right(18) ^ x.rotate_right(41)).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// One half of the SHA-512 Σ0 function on RV32. Given the low word of the input in self and the high word in other
+    /// the result is the low word, while given the high word in self and the low word in other it is the high word
+    fn sha512sum0r(self, other: Self) -> Self {
+        let (a, b) = (u32::from_le_bytes(self.word()), u32::from_le_bytes(other.word()));
+        Self::sign_extended_word((a << 25 ^ a << 30 ^ a >> 28 ^ b >> 7 ^ b >> 2 ^ b << 4).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// One half of the SHA-512 Σ1 function on RV32. Given the low word of the input in self and the high word in other
+    /// the result is the low word, while given the high word in self and the low word in other it is the high word
+    fn sha512sum1r(self, other: Self) -> Self {
+        let (a, b) = (u32::from_le_bytes(self.word()), u32::from_le_bytes(other.word()));
+        Self::sign_extended_word((a << 23 ^ a >> 14 ^ a >> 18 ^ b >> 9 ^ b << 18 ^ b << 14).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The low word of the SHA-512 σ0 function on RV32, given the low word of the input in self and the high word in other
+    fn sha512sig0l(self, other: Self) -> Self {
+        let (a, b) = (u32::from_le_bytes(self.word()), u32::from_le_bytes(other.word()));
+        Self::sign_extended_word((a >> 1 ^ a >> 7 ^ a >> 8 ^ b << 31 ^ b << 25 ^ b << 24).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The high word of the SHA-512 σ0 function on RV32, given the high word of the input in self and the low word in other
+    fn sha512sig0h(self, other: Self) -> Self {
+        let (a, b) = (u32::from_le_bytes(self.word()), u32::from_le_bytes(other.word()));
+        Self::sign_extended_word((a >> 1 ^ a >> 7 ^ a >> 8 ^ b << 31 ^ b << 24).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The low word of the SHA-512 σ1 function on RV32, given the low word of the input in self and the high word in other
+    fn sha512sig1l(self, other: Self) -> Self {
+        let (a, b) = (u32::from_le_bytes(self.word()), u32::from_le_bytes(other.word()));
+        Self::sign_extended_word((a << 3 ^ a >> 6 ^ a >> 19 ^ b >> 29 ^ b << 26 ^ b << 13).to_le_bytes())
+    }
+    #[cfg(feature = "ext-zknh")]
+    /// The high word of the SHA-512 σ1 function on RV32, given the high word of the input in self and the low word in other
+    fn sha512sig1h(self, other: Self) -> Self {
+        let (a, b) = (u32::from_le_bytes(self.word()), u32::from_le_bytes(other.word()));
+        Self::sign_extended_word((a << 3 ^ a >> 6 ^ a >> 19 ^ b >> 29 ^ b << 13).to_le_bytes())
+    }
+
+    #[cfg(feature = "ext-zbs")]
+    /// Set the bit indexed by other modulo the width of the register
+    fn bset(self, other: Self) -> Self {
+        self.or(Self::zero_extended_byte(1).shl(other))
+    }
+    #[cfg(feature = "ext-zbs")]
+    /// Clear the bit indexed by other modulo the width of the register
+    fn bclr(self, other: Self) -> Self {
+        self.and(Self::zero_extended_byte(1).shl(other).not())
+    }
+    #[cfg(feature = "ext-zbs")]
+    /// Invert the bit indexed by other modulo the width of the register
+    fn binv(self, other: Self) -> Self {
+        self.xor(Self::zero_extended_byte(1).shl(other))
+    }
+    #[cfg(feature = "ext-zbs")]
+    /// Extract the bit indexed by other modulo the width of the register into the lowest bit, zeroing the rest
+    fn bext(self, other: Self) -> Self {
+        self.shr(other).and(Self::zero_extended_byte(1))
+    }
+
     /// Applies the bitwise AND operation to self and other
     fn and(self, other: Self) -> Self {
         Self::from_unsigned(self.unsigned().and(other.unsigned()))
@@ -287,10 +545,41 @@ pub trait Register: Xlen + Sized + Default + Copy {
     fn word(self) -> [u8; 4];
     /// Get the lowest double
     fn double(self) -> [u8; 8];
+
+    /// Create a register from a quad. Only valid on RV128
+    fn from_quad(_quad: [u8; 16]) -> Self {
+        panic!("Cannot create a register narrower than 128 bits from a 128 bit value")
+    }
+    /// Get the quad held by the register. Only valid on RV128
+    fn quad(self) -> [u8; 16] {
+        panic!("Cannot get a 128 bit value from a register narrower than 128 bits")
+    }
+}
+
+/// Replace each element of `bits` bits in indices with the element of table it indexes, as for `xperm4` and `xperm8`
+#[cfg(feature = "ext-zbkx")]
+fn xperm<R: Register>(table: R, indices: R, bits: u32) -> R {
+    let (table, indices, width) = match R::WIDTH {
+        RegisterWidth::Bits32 => (u32::from_le_bytes(table.word()) as u128, u32::from_le_bytes(indices.word()) as u128, 32),
+        RegisterWidth::Bits64 => (u64::from_le_bytes(table.double()) as u128, u64::from_le_bytes(indices.double()) as u128, 64),
+        RegisterWidth::Bits128 => (u128::from_le_bytes(table.quad()), u128::from_le_bytes(indices.quad()), 128)
+    };
+    let mask = (1 << bits) - 1;
+    let permuted = (0..width / bits).fold(0, |permuted, element| {
+        let index = indices >> (element * bits) & mask;
+        let value = if index < (width / bits) as u128 { table >> (index as u32 * bits) & mask } else { 0 };
+        permuted | value << (element * bits)
+    });
+    match R::WIDTH {
+        RegisterWidth::Bits32 => R::zero_extended_word((permuted as u32).to_le_bytes()),
+        RegisterWidth::Bits64 => R::zero_extended_double((permuted as u64).to_le_bytes()),
+        RegisterWidth::Bits128 => R::from_quad(permuted.to_le_bytes())
+    }
 }
 
 /// A 32-bit value with byte-order and sign independent operations
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Register32(pub [u8; 4]);
 impl Xlen for Register32 {
     type Signed = i32;
@@ -309,14 +598,14 @@ impl Xlen for Register32 {
         Self(from.to_le_bytes())
     }
     fn append(self, value: usize) -> u32 {
-        self.unsigned() + value as u32
+        self.unsigned().wrapping_add(value as u32)
     }
     fn usize(self) -> usize {
         self.unsigned() as usize
     }
     #[cfg(feature = "ext-csr")]
     fn trap_cause(cause: u8, interrupt: bool) -> Self {
-        Self([if interrupt { 0x80 } else { 0 }, 0, 0, cause])
+        Self([cause, 0, 0, if interrupt { 0x80 } else { 0 }])
     }
 }
 impl Register for Register32 {
@@ -364,11 +653,6 @@ impl Register for Register32 {
     #[inline(always)]
     fn double(self) -> [u8; 8] { panic!("Cannot get a 64 bit value from a 32 bit register") }
 }
-impl Default for Register32 {
-    fn default() -> Self {
-        Self([0, 0, 0, 0])
-    }
-}
 impl From<u32> for Register32 {
     fn from(value: u32) -> Self {
         Self::from_unsigned(value)
@@ -381,7 +665,8 @@ impl From<i32> for Register32 {
 }
 
 /// A 64-bit value with byte-order and sign independent operations
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Register64(pub [u8; 8]);
 impl Register64 {
     /// Split the 64 bit register into 2 32 bit registers
@@ -393,7 +678,7 @@ impl Register64 {
 impl Xlen for Register64 {
     type Signed = i64;
     type Unsigned = u64;
-    const WIDTH: RegisterWidth = RegisterWidth::Bits32;
+    const WIDTH: RegisterWidth = RegisterWidth::Bits64;
     fn signed(self) -> i64 {
         i64::from_le_bytes(self.0)
     }
@@ -407,14 +692,14 @@ impl Xlen for Register64 {
         Self(from.to_le_bytes())
     }
     fn append(self, value: usize) -> u64 {
-        self.unsigned() + value as u64
+        self.unsigned().wrapping_add(value as u64)
     }
     fn usize(self) -> usize {
         self.unsigned() as usize
     }
     #[cfg(feature = "ext-csr")]
     fn trap_cause(cause: u8, interrupt: bool) -> Self {
-        Self([if interrupt { 0x80 } else { 0 }, 0, 0, 0, 0, 0, 0, cause])
+        Self([cause, 0, 0, 0, 0, 0, 0, if interrupt { 0x80 } else { 0 }])
     }
 }
 impl Register for Register64 {
@@ -463,16 +748,124 @@ impl Register for Register64 {
     #[inline(always)]
     fn double(self) -> [u8; 8] { self.0 }
 }
-impl Default for Register64 {
-    fn default() -> Self {
-        Self([0, 0, 0, 0, 0, 0, 0, 0])
+impl From<u64> for Register64 {
+    fn from(value: u64) -> Self {
+        Self::from_unsigned(value)
+    }
+}
+impl From<i64> for Register64 {
+    fn from(value: i64) -> Self {
+        Self::from_signed(value)
+    }
+}
+
+/// A 128-bit value with byte-order and sign independent operations, matching the RV128I base ISA
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Register128(pub [u8; 16]);
+impl Register128 {
+    /// Split the 128 bit register into 2 64 bit registers
+    /// The lower double is returned as the first item in the tuple
+    pub fn split(self) -> (Register64, Register64) {
+        (Register64(self.double()), Register64(((self.unsigned() >> 64) as u64).to_le_bytes()))
+    }
+}
+impl Xlen for Register128 {
+    type Signed = i128;
+    type Unsigned = u128;
+    const WIDTH: RegisterWidth = RegisterWidth::Bits128;
+    fn signed(self) -> i128 {
+        i128::from_le_bytes(self.0)
+    }
+    fn unsigned(self) -> u128 {
+        u128::from_le_bytes(self.0)
+    }
+    fn from_signed(from: i128) -> Self {
+        Self(from.to_le_bytes())
+    }
+    fn from_unsigned(from: u128) -> Self {
+        Self(from.to_le_bytes())
+    }
+    fn append(self, value: usize) -> u128 {
+        self.unsigned().wrapping_add(value as u128)
+    }
+    fn usize(self) -> usize {
+        self.unsigned() as usize
+    }
+    #[cfg(feature = "ext-csr")]
+    fn trap_cause(cause: u8, interrupt: bool) -> Self {
+        let mut bytes = [0; 16];
+        bytes[0] = cause;
+        bytes[15] = if interrupt { 0x80 } else { 0 };
+        Self(bytes)
+    }
+}
+impl Register for Register128 {
+    #[inline]
+    fn sign_extended_byte(byte: u8) -> Self {
+        Self::from_signed(byte as i8 as i128)
+    }
+    #[inline]
+    fn zero_extended_byte(byte: u8) -> Self {
+        Self::from_unsigned(byte as u128)
+    }
+    #[inline]
+    fn sign_extended_half(half: [u8; 2]) -> Self {
+        Self::from_signed(i16::from_le_bytes(half) as i128)
+    }
+    #[inline]
+    fn zero_extended_half(half: [u8; 2]) -> Self {
+        Self::from_unsigned(u16::from_le_bytes(half) as u128)
+    }
+    #[inline]
+    fn sign_extended_word(word: [u8; 4]) -> Self {
+        Self::from_signed(i32::from_le_bytes(word) as i128)
+    }
+    #[inline]
+    fn zero_extended_word(word: [u8; 4]) -> Self {
+        Self::from_unsigned(u32::from_le_bytes(word) as u128)
+    }
+    #[inline]
+    fn sign_extended_double(double: [u8; 8]) -> Self {
+        Self::from_signed(i64::from_le_bytes(double) as i128)
+    }
+    #[inline]
+    fn zero_extended_double(double: [u8; 8]) -> Self {
+        Self::from_unsigned(u64::from_le_bytes(double) as u128)
+    }
+    #[inline(always)]
+    fn from_quad(quad: [u8; 16]) -> Self {
+        Self(quad)
+    }
+
+    #[inline(always)]
+    fn byte(self) -> u8 { self.0[0] }
+    #[inline(always)]
+    fn half(self) -> [u8; 2] { [self.0[0], self.0[1]] }
+    #[inline(always)]
+    fn word(self) -> [u8; 4] { [self.0[0], self.0[1], self.0[2], self.0[3]] }
+    #[inline(always)]
+    fn double(self) -> [u8; 8] { [self.0[0], self.0[1], self.0[2], self.0[3], self.0[4], self.0[5], self.0[6], self.0[7]] }
+    #[inline(always)]
+    fn quad(self) -> [u8; 16] { self.0 }
+}
+impl From<u128> for Register128 {
+    fn from(value: u128) -> Self {
+        Self::from_unsigned(value)
+    }
+}
+impl From<i128> for Register128 {
+    fn from(value: i128) -> Self {
+        Self::from_signed(value)
     }
 }
 
 /// A native register-sized value with byte-order and sign independent actions
+/// Only available on 32 and 64-bit hosts, matching the RV32I and RV64I base ISAs
 #[cfg(not(target_pointer_width = "16"))]
-#[derive(Clone, Copy, Debug)]
-pub struct RegisterSize(pub [u8; std::mem::size_of::<usize>()]);
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegisterSize(pub [u8; core::mem::size_of::<usize>()]);
 #[cfg(not(target_pointer_width = "16"))]
 impl Xlen for RegisterSize {
     type Signed = isize;
@@ -496,7 +889,7 @@ impl Xlen for RegisterSize {
         Self(from.to_le_bytes())
     }
     fn append(self, value: usize) -> usize {
-        self.unsigned() + value as usize
+        self.unsigned().wrapping_add(value)
     }
     fn usize(self) -> usize {
         self.unsigned()
@@ -505,9 +898,9 @@ impl Xlen for RegisterSize {
     fn trap_cause(cause: u8, interrupt: bool) -> Self {
         let msb = if interrupt { 0x80 } else { 0 };
         #[cfg(target_pointer_width = "32")]
-        { Self([msb, 0, 0, cause]) }
+        { Self([cause, 0, 0, msb]) }
         #[cfg(target_pointer_width = "64")]
-        { Self([msb, 0, 0, 0, 0, 0, 0, cause]) }
+        { Self([cause, 0, 0, 0, 0, 0, 0, msb]) }
     }
 }
 #[cfg(not(target_pointer_width = "16"))]
@@ -544,11 +937,13 @@ impl Register for RegisterSize {
     }
     #[inline(always)]
     fn sign_extended_word(word: [u8; 4]) -> Self {
-        let extended = if word[3] & 0x80 != 0 { 0xFF } else { 0 };
         #[cfg(target_pointer_width = "32")]
         {Self(word)}
         #[cfg(target_pointer_width = "64")]
-        {Self([word[0], word[1], word[2], word[3], extended, extended, extended, extended])}
+        {
+            let extended = if word[3] & 0x80 != 0 { 0xFF } else { 0 };
+            Self([word[0], word[1], word[2], word[3], extended, extended, extended, extended])
+        }
     }
     #[inline(always)]
     fn zero_extended_word(word: [u8; 4]) -> Self {
@@ -558,6 +953,7 @@ impl Register for RegisterSize {
         {Self([word[0], word[1], word[2], word[3], 0, 0, 0, 0])}
     }
     #[inline(always)]
+    #[cfg_attr(target_pointer_width = "32", allow(unused_variables))]
     fn sign_extended_double(double: [u8; 8]) -> Self {
         #[cfg(target_pointer_width = "32")]
         {panic!("Cannot create a 32 bit register from a 64 bit value")}
@@ -565,6 +961,7 @@ impl Register for RegisterSize {
         {Self(double)}
     }
     #[inline(always)]
+    #[cfg_attr(target_pointer_width = "32", allow(unused_variables))]
     fn zero_extended_double(double: [u8; 8]) -> Self {
         #[cfg(target_pointer_width = "32")]
         {panic!("Cannot create a 32 bit register from a 64 bit value")}
@@ -586,12 +983,3 @@ impl Register for RegisterSize {
         {panic!("Cannot create a 64 bit value from a 32 bit register")}
     }
 }
-#[cfg(not(target_pointer_width = "16"))]
-impl Default for RegisterSize {
-    fn default() -> Self {
-        #[cfg(target_pointer_width = "32")]
-        {Self([0, 0, 0, 0])}
-        #[cfg(target_pointer_width = "64")]
-        {Self([0, 0, 0, 0, 0, 0, 0, 0])}
-    }
-}
\ No newline at end of file
@@ -1,786 +1,3709 @@
-use crate::register::{ Register, Register32, RegisterWidth };
+use crate::register::{ Integer, Register, Register32, RegisterWidth };
 use crate::variant::{ self, Variant };
+use crate::decode::Instruction;
+use core::{ convert::TryFrom, ops::Range };
+#[cfg(feature = "alloc")]
+use alloc::{ boxed::Box, rc::Rc, string::String, vec::Vec };
+#[cfg(feature = "ext-c")]
+use crate::compressed;
 #[cfg(feature = "ext-csr")]
-use crate::{ csr::Csr, register::Register64, version };
+use crate::{ csr::{ Csr, HpmEvent, Interrupt, Privilege }, register::{ Register64, Xlen }, version };
+#[cfg(all(feature = "ext-csr", any(feature = "ext-zicbom", feature = "ext-zicboz")))]
+use crate::csr::{ MENVCFG_CBCFE, MENVCFG_CBIE, MENVCFG_CBZE };
+#[cfg(feature = "ext-f")]
+use crate::float::{ self, Fcsr, FpRegister, RoundingMode, SignInjection };
+#[cfg(feature = "ext-d")]
+use crate::double;
+#[cfg(feature = "ext-zfh")]
+use crate::half;
+#[cfg(feature = "ext-v")]
+use crate::vector::{ Vector, VectorAddressing, Vtype, DEFAULT_VLEN };
+#[cfg(feature = "ext-a")]
+use crate::decode::AmoOp;
 
-/// Wraps a trap handler as traps are not handled internally without the csr-extension
-#[cfg(feature = "ext-csr")]
+/// The upper half of a floating-point register holding a single-precision value, which is NaN-boxed to FLEN bits
+#[cfg(feature = "ext-f")]
+const NAN_BOX: u64 = 0xFFFF_FFFF_0000_0000;
+/// The upper bits of a floating-point register holding a NaN-boxed half-precision value
+#[cfg(feature = "ext-zfh")]
+const HALF_NAN_BOX: u64 = 0xFFFF_FFFF_FFFF_0000;
+
+/// Raise a trap through the core's trap strategy, returning from `execute` with the trap if the strategy returns it
 macro_rules! trap {
-    (Instruction Address Misaligned; $core:expr) => {
-        // TODO: Shall be virtual address when implemented
-        $core.csr.mtval = $core.pc;
-        $core.trap(0, false);
+    (Instruction Address Misaligned; $core:expr, $target:expr) => {
+        return $core.trap(Trap::InstructionMisaligned(Self::address_value($target)), $target)
+    };
+    (Instruction Access Fault; $core:expr) => {
+        return $core.trap(Trap::InstructionAccessFault(Self::address_value($core.pc)), $core.pc)
     };
     (Illegal Instruction; $core:expr) => {
-        $core.trap(2, false);
+        return $core.trap(Trap::IllegalInstruction, R::default())
     };
-    (System Call) => {
-        unimplemented!()
+    (Load Fault; $core:expr, $fault:expr, $address:expr) => {
+        return $core.trap(match $fault {
+            MemoryFault::Misaligned => Trap::LoadMisaligned(Self::address_value($address)),
+            MemoryFault::AccessFault => Trap::LoadAccessFault(Self::address_value($address))
+        }, $address)
+    };
+    (Store Fault; $core:expr, $fault:expr, $address:expr) => {
+        return $core.trap(match $fault {
+            MemoryFault::Misaligned => Trap::StoreMisaligned(Self::address_value($address)),
+            MemoryFault::AccessFault => Trap::StoreAccessFault(Self::address_value($address))
+        }, $address)
+    };
+    (System Call; $core:expr) => {
+        return $core.trap(Trap::SystemCall, R::default())
     };
     (Breakpoint; $core:expr) => {
+        return $core.trap(Trap::Breakpoint, $core.pc)
+    };
+}
+
+/// Jump to a target address, linking the address of the next instruction into a register.
+/// Jumps to a target not aligned to an instruction boundary trap instead.
+macro_rules! jump {
+    ($core:expr, $target:expr) => {
+        jump!($core, $target, 0)
+    };
+    ($core:expr, $target:expr, $link:expr) => {
+        {
+            let target = $target;
+            if Self::aligned(target) {
+                $core.set($link, $core.next());
+                $core.pc = target
+            } else {
+                trap!(Instruction Address Misaligned; $core, target)
+            }
+        }
+    };
+}
+
+/// Take a conditional branch by the offset if `taken`, otherwise step to the next instruction, reporting the outcome
+/// to the branch hook
+macro_rules! branch {
+    ($core:expr, $taken:expr, $offset:expr) => {
         {
-            // TODO: Shall be virtual address when implemented
-            $core.csr.mtval = $core.pc;
-            $core.trap(3, false);
+            let taken = $taken;
+            let pc = $core.pc;
+            #[cfg(feature = "alloc")]
+            if let Some(hook) = &mut $core.on_branch {
+                hook(pc, taken)
+            }
+            #[cfg(feature = "ext-csr")]
+            {
+                $core.csr.count_event(HpmEvent::Branch);
+                if taken {
+                    $core.csr.count_event(HpmEvent::TakenBranch)
+                }
+            }
+            if taken {
+                jump!($core, pc.add_signed($offset))
+            } else {
+                $core.step()
+            }
         }
     };
 }
+
+/// The alignment of instructions in bytes. The C extension allows instructions on any 2-byte boundary.
+#[cfg(feature = "ext-c")]
+const IALIGN: u8 = 2;
+#[cfg(not(feature = "ext-c"))]
+const IALIGN: u8 = 4;
+
+/// A condition checked against the state of a core
+#[cfg(feature = "alloc")]
+type Invariant<R, const REGS: usize> = Box<dyn Fn(&Core<R, REGS>) -> bool>;
+/// Called with the index of the CSR, whether it was written, and the value read or written
+#[cfg(all(feature = "alloc", feature = "ext-csr"))]
+type CsrHook<R> = Box<dyn FnMut(usize, bool, R)>;
+/// Called with the index of the register, its old value and its new value. Returns true to stop `run` after the instruction
+#[cfg(feature = "register-hook")]
+type RegisterHook<R> = Box<dyn FnMut(usize, R, R) -> bool>;
+/// Called with the address of a conditional branch and whether it was taken
+#[cfg(feature = "alloc")]
+type BranchHook<R> = Box<dyn FnMut(R, bool)>;
+/// Called for each PAUSE executed, in place of yielding the host thread
+#[cfg(feature = "alloc")]
+type PauseHook = Box<dyn FnMut()>;
+/// The operand of a CSR instruction's write and the operation combining it with the CSR's value
 #[cfg(feature = "ext-csr")]
-type UnprivilegedTrap = ();
+type CsrWrite<R> = (R, fn(R, R) -> R);
 
+/// The magic bytes starting a core dump
+const CORE_DUMP_MAGIC: &[u8] = b"RYSK";
+/// The CSRs saved by a core dump, skipping those which are constant or do not exist at the core's XLEN
+#[cfg(feature = "ext-csr")]
+const CORE_DUMP_CSRS: [usize; 19] = [0x003, 0x300, 0x310, 0x302, 0x303, 0x304, 0x305, 0x306, 0x30A, 0x340, 0x341, 0x342, 0x343, 0x344, 0xB00, 0xB80, 0xB02, 0xB82, 0xF14];
+/// Every CSR saved by a core dump, which after `CORE_DUMP_CSRS` are the programmable counters, their high halves on RV32
+/// and the events they count, then vtype, vl, vstart and vcsr with the V extension
+#[cfg(feature = "ext-csr")]
+fn core_dump_csrs() -> impl Iterator<Item = usize> {
+    CORE_DUMP_CSRS.iter().copied()
+        .chain(0xB03..=0xB1F)
+        .chain(0xB83..=0xB9F)
+        .chain(0x323..=0x33F)
+        .chain([0xC21, 0xC20, 0x008, 0x00F].iter().copied())
+}
 
-#[cfg(not(feature = "ext-csr"))]
-macro_rules! trap {
-    (Instruction Address Misaligned; $core:expr) => {
-        return Some(Trap::InstructionMisaligned);
-    };
-    (Illegal Instruction; $core:expr) => {
-        return Some(Trap::IllegalInstruction);
-    };
-    (System Call) => {
-        return Some(Trap::SystemCall);
-    };
-    (Breakpoint; $core:expr) => {
-        return Some(Trap::Breakpoint);
-    };
+/// Reads the fields of a core dump in order
+struct DumpReader<'a>(&'a [u8]);
+impl<'a> DumpReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], CoreDumpError> {
+        if self.0.len() < length {
+            return Err(CoreDumpError::Malformed)
+        }
+        let (taken, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(taken)
+    }
+    fn byte(&mut self) -> Result<u8, CoreDumpError> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+    fn register<R: Register>(&mut self) -> Result<R, CoreDumpError> {
+        Ok(match R::WIDTH {
+            RegisterWidth::Bits32 => {
+                let bytes = self.take(4)?;
+                R::zero_extended_word([bytes[0], bytes[1], bytes[2], bytes[3]])
+            },
+            RegisterWidth::Bits64 => {
+                let bytes = self.take(8)?;
+                R::zero_extended_double([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]])
+            },
+            RegisterWidth::Bits128 => {
+                let mut quad = [0; 16];
+                quad.copy_from_slice(self.take(16)?);
+                R::from_quad(quad)
+            }
+        })
+    }
+    /// A CSR's address as a `u16` followed by its value
+    fn csr<R: Register>(&mut self) -> Result<(usize, R), CoreDumpError> {
+        let index = u16::from_le_bytes([self.byte()?, self.byte()?]) as usize;
+        Ok((index, self.register()?))
+    }
+}
+
+/// An instruction fetched from the address `pc` and expanded if it was compressed, along with its decoding under the
+/// effective XLEN `xlen`. Only instructions that decoded successfully are cached
+/// Without `alloc` nothing is cached, so the fields identifying an entry are never read
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+struct CachedInstruction<R: Register> {
+    pc: R::Unsigned,
+    xlen: RegisterWidth,
+    instruction: [u8; 4],
+    length: u8,
+    decoded: Instruction<R>
+}
+
+/// The trap strategy held by a core. Without `alloc` it cannot be boxed, so a stateless strategy is recreated for each trap
+#[cfg(feature = "alloc")]
+type SharedStrategy<R, const REGS: usize> = Rc<dyn TrapStrategy<R, REGS>>;
+#[cfg(not(feature = "alloc"))]
+type SharedStrategy<R, const REGS: usize> = fn(&mut Core<R, REGS>, Trap, R) -> Option<Trap>;
+
+/// The trap strategy of a new core; traps are handled by the program only with the csr-extension
+fn default_trap_strategy<R: Register, const REGS: usize>() -> SharedStrategy<R, REGS> {
+    #[cfg(all(feature = "alloc", feature = "ext-csr"))]
+    { Rc::new(CsrHandled) }
+    #[cfg(all(feature = "alloc", not(feature = "ext-csr")))]
+    { Rc::new(HostReturn) }
+    #[cfg(all(not(feature = "alloc"), feature = "ext-csr"))]
+    { stateless_trap::<CsrHandled, R, REGS> }
+    #[cfg(all(not(feature = "alloc"), not(feature = "ext-csr")))]
+    { stateless_trap::<HostReturn, R, REGS> }
+}
+
+/// Handle a trap with a new instance of the stateless strategy `S`
+#[cfg(not(feature = "alloc"))]
+fn stateless_trap<S: TrapStrategy<R, REGS> + Default, R: Register, const REGS: usize>(core: &mut Core<R, REGS>, trap: Trap, value: R) -> Option<Trap> {
+    S::default().trap(core, trap, value)
+}
+
+/// Serializes the register file as a sequence, as serde only implements arrays of fixed sizes
+#[cfg(feature = "serde")]
+mod register_file {
+    use serde::{ Deserialize, Deserializer, Serialize, Serializer, de::Error };
+    use core::convert::TryInto;
+    use alloc::vec::Vec;
+
+    pub fn serialize<R: Serialize, S: Serializer, const REGS: usize>(registers: &[R; REGS], serializer: S) -> Result<S::Ok, S::Error> {
+        registers[..].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, R: Deserialize<'de>, D: Deserializer<'de>, const REGS: usize>(deserializer: D) -> Result<[R; REGS], D::Error> {
+        let registers = Vec::<R>::deserialize(deserializer)?;
+        let length = registers.len();
+        registers.try_into().map_err(|_| D::Error::invalid_length(length, &"a register file of the core's size"))
+    }
 }
-#[cfg(not(feature = "ext-csr"))]
-type UnprivilegedTrap = Option<Trap>;
 
 /// A single RISCV core.
-/// Includes a single program counter and 32 registers.
-/// Const generics will allow support of the E extensions for 16 registers.
-pub struct Core<R: Register> {
-    /// The 32 general-purpose registers.
+/// Includes a single program counter and `REGS` registers, which is 32 for the I base ISAs or 16 for the E base ISAs.
+/// Instructions naming a register beyond the register file are illegal.
+///
+/// With the `serde` feature the state of a core may be saved and restored to resume execution later.
+/// The invariant and CSR hook are not saved and the default trap strategy is restored, as a strategy may hold state
+/// that cannot be serialized. A core using another strategy must have it set again with `with_trap_strategy`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(
+    serialize = "R: serde::Serialize, R::Unsigned: serde::Serialize",
+    deserialize = "R: serde::Deserialize<'de>, R::Unsigned: serde::Deserialize<'de>"
+)))]
+pub struct Core<R: Register, const REGS: usize = 32> {
+    /// The general-purpose registers.
     /// Although all registers are general purpose in RISCV, their usage is still dictated by the standard calling convention.
     /// Register 0 always has a value of 0.
-    registers: [R; 32],
+    #[cfg_attr(feature = "serde", serde(with = "register_file"))]
+    registers: [R; REGS],
 
     /// The program counter
     pub pc: R,
 
     /// CSR registers
     #[cfg(feature = "ext-csr")]
-    csr: Csr<R>
+    csr: Csr<R>,
+    /// The address of the non-maskable interrupt handler
+    #[cfg(feature = "ext-csr")]
+    nmi_vector: R,
+
+    /// The 32 floating-point registers, 64 bits wide so they may hold the values of the D extension
+    #[cfg(feature = "ext-f")]
+    f: [u64; 32],
+    /// The floating-point rounding mode and accrued exceptions
+    #[cfg(feature = "ext-f")]
+    fcsr: Fcsr,
+    /// Whether floating-point instructions operate on the integer registers in place of `f`, as with Zfinx
+    #[cfg(feature = "ext-f")]
+    zfinx: bool,
+    /// Whether the core has the Ztso extension, promising total store ordering of its memory accesses
+    ztso: bool,
+    /// The vector registers and the state of the vector CSRs
+    #[cfg(feature = "ext-v")]
+    vector: Vector,
+
+    /// The range of addresses instructions may be fetched from, or `None` if all addresses are executable
+    executable: Option<Range<R::Unsigned>>,
+
+    /// The number of instructions `run` may execute before stopping, or `None` to run indefinitely
+    watchdog: Option<u64>,
+    /// The number of instructions executed by `run` since the watchdog was set
+    executed: u64,
+    /// The lowest address the stack may grow to, or `None` if the stack is unguarded
+    stack_limit: Option<R::Unsigned>,
+    /// The address of an access beyond the stack limit made by the instruction `run` is executing
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stack_overflow: Option<R>,
+    /// The outcome of the most recent call to `execute`
+    outcome: StepOutcome,
+    /// Set when the instruction being executed writes minstret, which is done in place of counting the instruction
+    #[cfg(feature = "ext-csr")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    instret_written: bool,
+    /// The trap returned by the most recent call to `execute`, if it trapped
+    last_trap: Option<Trap>,
+    /// Decides whether traps are handled by the program or returned to the execution environment
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_trap_strategy"))]
+    trap_strategy: SharedStrategy<R, REGS>,
+    /// The size in bytes of the instruction being executed
+    instruction_length: u8,
+    /// A condition which must hold after every instruction executed by `run`
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    invariant: Option<Invariant<R, REGS>>,
+    /// Observes every CSR access made by a CSR instruction
+    #[cfg(all(feature = "alloc", feature = "ext-csr"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    on_csr_access: Option<CsrHook<R>>,
+    /// Observes the outcome of every conditional branch
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    on_branch: Option<BranchHook<R>>,
+    /// Backs off the host for each PAUSE executed
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    on_pause: Option<PauseHook>,
+    /// Observes every write of a general-purpose register
+    #[cfg(feature = "register-hook")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    on_register_write: Option<RegisterHook<R>>,
+    /// Set when the register hook asks for `run` to stop, cleared before each instruction `run` executes
+    #[cfg(feature = "register-hook")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    register_break: bool,
+    /// Direct-mapped cache of fetched instructions indexed by pc, empty if disabled
+    #[cfg(feature = "alloc")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    icache: Vec<Option<CachedInstruction<R>>>,
+
+    /// The address and size in bytes of the reservation held by the last LR, if it is still valid
+    #[cfg(feature = "ext-a")]
+    reservation: Option<(R, u8)>
 }
-impl<R: Register + Default + Copy + Clone> Core<R> {
+impl<R: Register + Default + Copy + Clone, const REGS: usize> Core<R, REGS> {
+    /// Only the 32 registers of the I base ISAs and the 16 registers of the E base ISAs are valid
+    const REGISTER_FILE: () = assert!(REGS == 32 || REGS == 16, "A core must have 32 or 16 registers");
+
     /// Creates a new core starting execution at the given address.
     /// address must be aligned to 4 bytes else a panic will occur during execution.
+    /// Traps are returned to the execution environment by the `HostReturn` strategy.
     #[cfg(not(feature = "ext-csr"))]
     pub fn new(address: R::Unsigned) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::REGISTER_FILE;
         Self {
-            registers: [Default::default(); 32],
-            pc: R::from_unsigned(address)
+            registers: [Default::default(); REGS],
+            pc: R::from_unsigned(address),
+            ztso: false,
+            executable: None,
+            watchdog: None,
+            executed: 0,
+            stack_limit: None,
+            stack_overflow: None,
+            outcome: StepOutcome::Retired,
+            last_trap: None,
+            trap_strategy: default_trap_strategy(),
+            instruction_length: 4,
+            #[cfg(feature = "alloc")]
+            invariant: None,
+            #[cfg(feature = "alloc")]
+            on_branch: None,
+            #[cfg(feature = "alloc")]
+            on_pause: None,
+            #[cfg(feature = "register-hook")]
+            on_register_write: None,
+            #[cfg(feature = "register-hook")]
+            register_break: false,
+            #[cfg(feature = "alloc")]
+            icache: Vec::new(),
+            #[cfg(feature = "ext-a")]
+            reservation: None
         }
     }
 
     /// Creates a new core starting execution at the given address with the given hart ID.
     /// Hart ID's must be unique to ensure correct program behaviour. There must be a hart with ID 0 on a given system.
     /// `address` must be aligned to 4 bytes else a panic will occur during execution.
+    /// Traps are handled by the program with the `CsrHandled` strategy.
     #[cfg(feature = "ext-csr")]
     pub fn new(address: R::Unsigned, hart: R::Unsigned) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::REGISTER_FILE;
         Self {
-            registers: [Default::default(); 32],
+            registers: [Default::default(); REGS],
             pc: R::from_unsigned(address),
-            csr: Csr::new(hart, address)
+            csr: Csr::new(hart, address),
+            nmi_vector: R::from_unsigned(address),
+            #[cfg(feature = "ext-f")]
+            f: [NAN_BOX; 32],
+            #[cfg(feature = "ext-f")]
+            fcsr: Fcsr::default(),
+            #[cfg(feature = "ext-f")]
+            zfinx: false,
+            ztso: false,
+            #[cfg(feature = "ext-v")]
+            vector: Vector::new(DEFAULT_VLEN),
+            executable: None,
+            watchdog: None,
+            executed: 0,
+            stack_limit: None,
+            stack_overflow: None,
+            outcome: StepOutcome::Retired,
+            instret_written: false,
+            last_trap: None,
+            trap_strategy: default_trap_strategy(),
+            instruction_length: 4,
+            #[cfg(feature = "alloc")]
+            invariant: None,
+            #[cfg(feature = "alloc")]
+            on_branch: None,
+            #[cfg(feature = "alloc")]
+            on_pause: None,
+            #[cfg(feature = "register-hook")]
+            on_register_write: None,
+            #[cfg(feature = "register-hook")]
+            register_break: false,
+            #[cfg(feature = "alloc")]
+            icache: Vec::new(),
+            #[cfg(feature = "alloc")]
+            on_csr_access: None,
+            #[cfg(feature = "ext-a")]
+            reservation: None
+        }
+    }
+
+    /// Declare that the core implements the standard `profile`, checking it against the register width and enabled extensions.
+    /// Extensions are selected at compile time, such as with the `rv32imac` feature, so this documents intent rather than
+    /// configuring the core.
+    /// # Safety
+    /// A panic will occur if the profile is for a different register width or an extension it requires is not enabled
+    /// ```rust
+    /// use rysk_core::{ Core, Profile, Register32 };
+    /// # #[cfg(all(feature = "ext-csr", feature = "rv32imac"))]
+    /// let core = Core::<Register32>::new(0, 0).with_profile(Profile::Rv32imac);
+    /// ```
+    pub fn with_profile(self, profile: Profile) -> Self {
+        assert!(profile.width() == R::WIDTH, "{:?} requires a register width of {:?}", profile, profile.width());
+        assert!(profile.is_enabled(), "{:?} requires extensions that are not enabled", profile);
+        self
+    }
+
+    /// Use `strategy` to decide whether traps are handled by the program or returned to the execution environment
+    /// ```rust
+    /// use rysk_core::{ Core, HostReturn, Register32 };
+    /// # #[cfg(feature = "ext-csr")]
+    /// let core = Core::<Register32>::new(0, 0).with_trap_strategy(HostReturn);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn with_trap_strategy(mut self, strategy: impl TrapStrategy<R, REGS> + 'static) -> Self {
+        self.trap_strategy = Rc::new(strategy);
+        self
+    }
+
+    /// Use `strategy` to decide whether traps are handled by the program or returned to the execution environment.
+    /// Without `alloc` the strategy cannot be boxed, so must hold no state and is recreated with `Default` for each trap
+    /// # Safety
+    /// A panic will occur if the strategy is not zero-sized
+    #[cfg(not(feature = "alloc"))]
+    pub fn with_trap_strategy<S: TrapStrategy<R, REGS> + Default + 'static>(mut self, _strategy: S) -> Self {
+        assert!(core::mem::size_of::<S>() == 0, "Without alloc a trap strategy must hold no state");
+        self.trap_strategy = stateless_trap::<S, R, REGS>;
+        self
+    }
+
+    /// Cache up to `entries` decoded instructions so that executing them again skips fetching, expanding and decoding them.
+    /// Cached instructions are only fetched again after `flush_icache` or `on_context_switch`, so the cache must be
+    /// flushed when memory holding instructions is modified or remapped.
+    /// # Safety
+    /// A panic will occur if `entries` is not a power of two
+    /// ```rust
+    /// use rysk_core::{ Core, Register32 };
+    /// # #[cfg(feature = "ext-csr")]
+    /// let core = Core::<Register32>::new(0, 0).with_icache(1024);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn with_icache(mut self, entries: usize) -> Self {
+        assert!(entries.is_power_of_two(), "The instruction cache size must be a power of two");
+        self.icache = alloc::vec![None; entries];
+        self
+    }
+
+    /// Whether floating-point instructions operate on the integer registers instead of the floating-point registers,
+    /// as with the Zfinx extension of low-cost embedded cores. Disabled by default.
+    /// The floating-point loads, stores and moves are then illegal, as are the instructions of the D extension.
+    /// Half-precision instructions of the Zfh extension operate on the integer registers as with Zhinx.
+    /// `decode` does not know of this mode, so continues to accept the instructions it makes illegal.
+    /// ```rust
+    /// use rysk_core::{ Core, Register32 };
+    /// # #[cfg(feature = "ext-f")]
+    /// let core = Core::<Register32>::new(0, 0).with_zfinx(true);
+    /// ```
+    #[cfg(feature = "ext-f")]
+    pub fn with_zfinx(mut self, enabled: bool) -> Self {
+        self.zfinx = enabled;
+        self
+    }
+
+    /// Whether the core has the Ztso extension, so programs may assume its loads and stores are totally store ordered.
+    /// Disabled by default. There is no misa bit for Ztso, so it is advertised through `isa_string`.
+    /// A single core always observes its own accesses in order, so the ordering is only enforced through `Mmu::fence`,
+    /// which is called before each load with reads as both sets and before each store with reads and writes as the
+    /// predecessor set and writes as the successor set. Memory shared between cores which implements `fence` then
+    /// orders every access except a load after a store, as TSO requires.
+    /// ```rust
+    /// use rysk_core::{ Core, Register64 };
+    /// # #[cfg(feature = "ext-csr")]
+    /// let core = Core::<Register64>::new(0, 0).with_ztso(true);
+    /// # #[cfg(all(feature = "ext-csr", feature = "alloc"))]
+    /// assert!(core.isa_string().ends_with("_ztso"));
+    /// ```
+    pub fn with_ztso(mut self, enabled: bool) -> Self {
+        self.ztso = enabled;
+        self
+    }
+
+    /// Use vector registers of `vlen` bits in place of the default of 128 bits, clearing the vector state
+    /// # Safety
+    /// A panic will occur if `vlen` is not a power of two from 64 to 65536
+    /// ```rust
+    /// use rysk_core::{ Core, Register32 };
+    /// # #[cfg(feature = "ext-v")]
+    /// let core = Core::<Register32>::new(0, 0).with_vlen(256);
+    /// ```
+    #[cfg(feature = "ext-v")]
+    pub fn with_vlen(mut self, vlen: usize) -> Self {
+        assert!(vlen.is_power_of_two() && (64..=65536).contains(&vlen), "VLEN must be a power of two from 64 to 65536");
+        self.vector = Vector::new(vlen);
+        self
+    }
+
+    /// Discard every cached instruction, such as after modifying instructions in memory
+    pub fn flush_icache(&mut self) {
+        #[cfg(feature = "alloc")]
+        for entry in self.icache.iter_mut() {
+            *entry = None
+        }
+    }
+
+    /// The index into the instruction cache of the instruction at `pc`, or `None` if the cache is disabled
+    #[cfg(feature = "alloc")]
+    fn icache_index(&self, pc: R) -> Option<usize> {
+        if self.icache.is_empty() {
+            None
+        } else {
+            Some((pc.usize() >> 1) & (self.icache.len() - 1))
+        }
+    }
+
+    /// The cached instruction at `pc`, if it was cached and decoded under the current effective XLEN
+    fn cached_instruction(&self, pc: R) -> Option<CachedInstruction<R>> {
+        #[cfg(feature = "alloc")]
+        {
+            self.icache_index(pc)
+                .and_then(|index| self.icache[index])
+                .filter(|cached| cached.pc.eq(pc.unsigned()) && cached.xlen == self.xlen())
+        }
+        #[cfg(not(feature = "alloc"))]
+        { let _ = pc; None }
+    }
+
+    /// Cache the decoded instruction if the cache is enabled
+    fn cache_instruction(&mut self, cached: CachedInstruction<R>) {
+        #[cfg(feature = "alloc")]
+        if let Some(index) = self.icache_index(R::from_unsigned(cached.pc)) {
+            self.icache[index] = Some(cached)
         }
+        #[cfg(not(feature = "alloc"))]
+        let _ = cached;
     }
 
-    /// Increments the program counter by the instruction size of 4 bytes
+    /// Increments the program counter by the size of the current instruction, 4 bytes or 2 for a compressed instruction
     pub fn step(&mut self) {
-        self.pc = self.pc.add_unsigned(R::zero_extended_byte(4))
+        self.pc = self.next()
+    }
+
+    /// The address of the instruction following the current instruction
+    fn next(&self) -> R {
+        self.pc.add_unsigned(R::zero_extended_byte(self.instruction_length))
+    }
+
+    /// Returns true if `address` is on an instruction boundary
+    fn aligned(address: R) -> bool {
+        address.word()[0] & (IALIGN - 1) == 0
+    }
+
+    /// Fetch the raw bytes of the instruction at the program counter without decoding or executing it.
+    /// Intended for inspection by debuggers; the core is left untouched.
+    pub fn current_instruction(&self, mmu: &dyn Mmu<R>) -> Result<[u8; 4], MemoryFault> {
+        mmu.fetch(self.pc)
+    }
+
+    /// Restrict instruction fetches to the given range of addresses.
+    /// Fetching an instruction from outside of the range raises an instruction access fault, preventing a sandboxed program from executing its data.
+    /// Every byte of the instruction must be within the range, not just its first.
+    pub fn set_executable_range(&mut self, range: Range<R::Unsigned>) {
+        self.executable = Some(range)
+    }
+
+    /// Allow instructions to be fetched from any address
+    pub fn clear_executable_range(&mut self) {
+        self.executable = None
+    }
+
+    /// Stop `run` with `RunResult::WatchdogExpired` once it has executed `limit` instructions from now.
+    /// Guards against a program that never terminates, such as in tests or while fuzzing.
+    /// Instructions which trap are counted when the trap is handled by the program, so a trap handler that faults on itself is stopped too.
+    ///
+    /// `run` stops before executing the instruction that would exceed the limit, so the pc, registers and counters
+    /// are left exactly as after the last instruction executed. Setting the watchdog again and calling `run` resumes
+    /// at the next instruction, giving the same state as a run that was never stopped.
+    pub fn set_watchdog(&mut self, limit: u64) {
+        self.watchdog = Some(limit);
+        self.executed = 0
+    }
+
+    /// Allow `run` to execute indefinitely
+    pub fn clear_watchdog(&mut self) {
+        self.watchdog = None
+    }
+
+    /// Stop `run` with `RunResult::StackOverflow` after an instruction accesses the stack below `limit`.
+    /// An access is to the stack when it is at or above the stack pointer, so a stack that has grown past the limit is caught
+    /// while data placed below the limit, such as a heap, may still be accessed freely.
+    pub fn set_stack_limit(&mut self, limit: R::Unsigned) {
+        self.stack_limit = Some(limit)
+    }
+
+    /// Allow the stack to grow without limit
+    pub fn clear_stack_limit(&mut self) {
+        self.stack_limit = None
+    }
+
+    /// Check `invariant` after every instruction executed by `run`, stopping with `RunResult::InvariantViolated` when it does not hold.
+    /// Useful for finding the instruction responsible for corrupting state, such as a misaligned stack pointer.
+    #[cfg(feature = "alloc")]
+    pub fn set_invariant(&mut self, invariant: impl Fn(&Self) -> bool + 'static) {
+        self.invariant = Some(Box::new(invariant))
+    }
+
+    /// Stop checking the invariant set by `set_invariant`
+    #[cfg(feature = "alloc")]
+    pub fn clear_invariant(&mut self) {
+        self.invariant = None
+    }
+
+    /// Call `hook` with the index, whether it is a write, and the value of every CSR read and write made by a CSR instruction.
+    /// A write reports the value the CSR holds afterwards, with any bits it cannot hold masked.
+    /// Allows a host to trace how privileged software touches registers such as mstatus, mie and mtvec.
+    #[cfg(all(feature = "alloc", feature = "ext-csr"))]
+    pub fn set_csr_hook(&mut self, hook: impl FnMut(usize, bool, R) + 'static) {
+        self.on_csr_access = Some(Box::new(hook))
+    }
+    /// Stop calling the hook set by `set_csr_hook`
+    #[cfg(all(feature = "alloc", feature = "ext-csr"))]
+    pub fn clear_csr_hook(&mut self) {
+        self.on_csr_access = None
+    }
+
+    /// Call `hook` with the address and outcome of every conditional branch executed, including compressed branches,
+    /// so a host may build a profile of how often each branch is taken
+    #[cfg(feature = "alloc")]
+    pub fn set_branch_hook(&mut self, hook: impl FnMut(R, bool) + 'static) {
+        self.on_branch = Some(Box::new(hook))
+    }
+    /// Stop calling the hook set by `set_branch_hook`
+    #[cfg(feature = "alloc")]
+    pub fn clear_branch_hook(&mut self) {
+        self.on_branch = None
+    }
+
+    /// Call `hook` for every PAUSE executed in place of the default backoff, which yields the host thread with `std`
+    /// and otherwise does nothing. Spin loops execute PAUSE while waiting, so the hook may sleep or run other cores
+    /// rather than spinning the host at full speed
+    #[cfg(feature = "alloc")]
+    pub fn set_pause_hook(&mut self, hook: impl FnMut() + 'static) {
+        self.on_pause = Some(Box::new(hook))
+    }
+    /// Stop calling the hook set by `set_pause_hook`, returning to the default backoff
+    #[cfg(feature = "alloc")]
+    pub fn clear_pause_hook(&mut self) {
+        self.on_pause = None
+    }
+
+    /// Call `hook` with the index, old value and new value of every write to a general-purpose register through `set`,
+    /// including writes that leave the value unchanged. Writes to x0 are discarded and are not reported.
+    /// `run` stops with `RunResult::RegisterBreakpoint` after an instruction for which the hook returns true,
+    /// allowing a debugger to break when a register takes a particular value.
+    #[cfg(feature = "register-hook")]
+    pub fn set_register_hook(&mut self, hook: impl FnMut(usize, R, R) -> bool + 'static) {
+        self.on_register_write = Some(Box::new(hook))
+    }
+    /// Stop calling the hook set by `set_register_hook`
+    #[cfg(feature = "register-hook")]
+    pub fn clear_register_hook(&mut self) {
+        self.on_register_write = None
+    }
+
+    /// Tests if an instruction of `length` bytes may be fetched from the address, which requires both its first and last
+    /// bytes to be within the executable range
+    fn executable(&self, address: R, length: u8) -> bool {
+        match &self.executable {
+            Some(range) => {
+                let last = address.add_unsigned(R::zero_extended_byte(length - 1)).unsigned();
+                address.unsigned().gte(range.start) && address.unsigned().lt(range.end) && last.gte(range.start) && last.lt(range.end)
+            },
+            None => true
+        }
+    }
+
+    /// The effective XLEN of the current privilege mode, which is narrower than the register in user mode when mstatus.UXL is 32.
+    /// SXL is not supported as there is no supervisor mode. Compressed instructions are always expanded for the full XLEN
+    #[inline(always)]
+    pub fn xlen(&self) -> RegisterWidth {
+        #[cfg(feature = "ext-csr")]
+        {
+            if self.csr.privilege == Privilege::User {
+                return self.csr.user_width()
+            }
+        }
+        R::WIDTH
+    }
+
+    /// The ISA string naming the base ISA and every extension the core implements, as passed to `-march` or listed in a
+    /// device tree, such as `rv32imac_zicsr_zifencei`. Unlike misa it also names the multi-letter extensions, such as Ztso
+    #[cfg(feature = "alloc")]
+    pub fn isa_string(&self) -> String {
+        let mut isa = String::from(match R::WIDTH {
+            RegisterWidth::Bits32 => "rv32",
+            RegisterWidth::Bits64 => "rv64",
+            RegisterWidth::Bits128 => "rv128"
+        });
+        // The E base ISA replaces I with the 16 register file
+        isa.push(if REGS == 16 { 'e' } else { 'i' });
+        #[cfg(feature = "ext-f")]
+        let zfinx = self.zfinx;
+        #[cfg(not(feature = "ext-f"))]
+        let zfinx = false;
+        let letters = [
+            ('m', cfg!(feature = "ext-m")),
+            ('a', cfg!(feature = "ext-a")),
+            ('f', cfg!(feature = "ext-f") && !zfinx),
+            ('d', cfg!(feature = "ext-d") && !zfinx),
+            ('c', cfg!(feature = "ext-c")),
+            ('v', cfg!(feature = "ext-v"))
+        ];
+        isa.extend(letters.iter().filter(|(_, enabled)| *enabled).map(|(letter, _)| letter));
+        // Multi-letter extensions are ordered by the single-letter extension they relate to, then alphabetically
+        let extensions = [
+            ("zicbom", cfg!(feature = "ext-zicbom")),
+            ("zicboz", cfg!(feature = "ext-zicboz")),
+            ("zicntr", cfg!(feature = "ext-csr")),
+            ("zicsr", cfg!(feature = "ext-csr")),
+            ("zifencei", true),
+            ("zihintpause", true),
+            ("zihpm", cfg!(feature = "ext-csr")),
+            ("zmmul", cfg!(feature = "ext-zmmul") && !cfg!(feature = "ext-m")),
+            ("zawrs", cfg!(feature = "ext-zawrs")),
+            ("zfh", cfg!(feature = "ext-zfh") && !zfinx),
+            ("zfinx", zfinx),
+            ("zbc", cfg!(feature = "ext-zbc")),
+            ("zbkb", cfg!(feature = "ext-zbkb")),
+            ("zbkx", cfg!(feature = "ext-zbkx")),
+            ("zbs", cfg!(feature = "ext-zbs")),
+            ("zknh", cfg!(feature = "ext-zknh")),
+            ("ztso", self.ztso),
+            ("zhinx", cfg!(feature = "ext-zfh") && zfinx)
+        ];
+        for (name, _) in extensions.iter().filter(|(_, enabled)| *enabled) {
+            isa.push('_');
+            isa.push_str(name)
+        }
+        isa
     }
 
-    /// Get the register `x{index}`
+    /// Sign-extend the value from the effective XLEN if it is narrower than the register
+    #[inline(always)]
+    fn effective(&self, register: R) -> R {
+        if self.xlen() != R::WIDTH {
+            R::sign_extended_word(register.word())
+        } else {
+            register
+        }
+    }
+
+    /// Get the register `x{index}`.
+    /// While the effective XLEN is narrower than the register the upper bits are ignored, reading as the sign-extended value
     /// # Safety
     /// A panic will occur if index is larger than 31
     #[inline(always)]
     pub fn get(&self, index: usize) -> R {
-        self.registers[index]
+        self.effective(self.registers[index])
     }
 
-    /// Set register `x{index}` to be equal to `register`
+    /// Set register `x{index}` to be equal to `register`.
+    /// While the effective XLEN is narrower than the register the value is sign-extended from the effective XLEN
     /// # Safety
     /// A panic will occur if index is larger than 31
     #[inline(always)]
     pub fn set(&mut self, index: usize, register: R) {
         if index > 0 {
-            self.registers[index] = register
+            let value = self.effective(register);
+            #[cfg(feature = "register-hook")]
+            {
+                if let Some(hook) = &mut self.on_register_write {
+                    if hook(index, self.registers[index], value) {
+                        self.register_break = true
+                    }
+                }
+            }
+            self.registers[index] = value
         }
     }
 
-    /// Get a value from a CSR. May have side-effects
-    #[cfg(feature = "ext-csr")]
-    pub fn get_csr(&self, index: usize) -> Result<R, Trap> {
-        match index {
-            // mstatus
-            0x300 => unimplemented!(),
-            // misa
-            0x301 => {
-                const I: u8 = 1 << 7;
+    /// The general-purpose register file, indexed by register number
+    pub fn registers(&self) -> &[R; REGS] {
+        &self.registers
+    }
 
-                let isa0 = I;
-                let isa1 = 0;
-                let isa2 = 0;
-                let isa3 = 0;
+    /// Iterate the general-purpose registers as `(index, ABI name, value)`
+    pub fn abi_registers(&self) -> impl Iterator<Item = (usize, &'static str, R)> + '_ {
+        self.registers.iter()
+            .enumerate()
+            .map(|(index, &register)| (index, crate::decode::ABI_NAMES[index], register))
+    }
 
-                const MXLEN32: u8 = 1;
-                const MXLEN64: u8 = 2;
-                const _MXLEN128: u8 = 3;
-                Ok(
-                    match R::WIDTH {
-                        RegisterWidth::Bits32 => R::zero_extended_word([isa0, isa1, isa2, isa3 | MXLEN32 << 6]),
-                        RegisterWidth::Bits64 => R::zero_extended_double([isa0, isa1, isa2, isa3, 0, 0, 0, MXLEN64 << 6]),
-                    }
-                )
-            },
-            // medeleg
-            0x302 => Ok(self.csr.medeleg),
-            // mideleg
-            0x303 => Ok(self.csr.mideleg),
-            // mie
-            0x304 => Ok(self.csr.mie),
-            // mtvec
-            0x305 => Ok(self.csr.mtvec),
-            // mcounteren
-            0x306 => Ok(R::zero_extended_word(self.csr.mcounteren.word())),
+    /// Compare the architectural state of this core against another, listing everything that differs.
+    /// Intended for differential testing against a reference model.
+    #[cfg(feature = "alloc")]
+    pub fn diff(&self, other: &Self) -> StateDiff<R> {
+        let registers = (0..REGS)
+            .filter(|&index| self.get(index).neq(other.get(index)))
+            .map(|index| (index, self.get(index), other.get(index)))
+            .collect();
+        let pc = if self.pc.neq(other.pc) { Some((self.pc, other.pc)) } else { None };
 
-            // mscratch
-            0x340 => Ok(self.csr.mscratch),
-            // mepc
-            0x341 => Ok(self.csr.mepc),
-            // mcause
-            0x342 => Ok(self.csr.mcause),
-            // mtval
-            0x343 => Ok(self.csr.mtval),
-            // mip
-            0x344 => Ok(self.csr.mip),
+        #[cfg(feature = "ext-csr")]
+        {
+            // Counters are compared even if the privilege mode may not read them
+            let read = |core: &Self, index| match index {
+                0xC00..=0xC1F | 0xC80..=0xC9F => core.counter_csr(index),
+                _ => core.get_csr(index)
+            };
+            let csrs = Self::state_csrs()
+                .filter_map(|index| match (read(self, index), read(other, index)) {
+                    (Ok(left), Ok(right)) if left.neq(right) => Some((index, left, right)),
+                    _ => None
+                })
+                .collect();
+            StateDiff { registers, pc, csrs }
+        }
+        #[cfg(not(feature = "ext-csr"))]
+        StateDiff { registers, pc }
+    }
 
-            // mcycle and mcycleh
-            0xB00 if R::WIDTH != RegisterWidth::Bits32 => Ok(R::zero_extended_double(self.csr.mcycle.double())),
-            0xB00 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mcycle.split().0).0)),
-            0xB80 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mcycle.split().1).0)),
-            // minstret - Currently the same as mcycle
-            0xB02 if R::WIDTH != RegisterWidth::Bits32 => Ok(R::zero_extended_double(self.csr.mcycle.double())),
-            0xB02 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mcycle.split().0).0)),
-            0xB82 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mcycle.split().1).0)),
-            // Unused performance counters
-            0xB03..=0xB1F => Ok(R::default()),
-            0xB83..=0xB9F if R::WIDTH == RegisterWidth::Bits32 => Ok(R::default()),
-            // Unused performance event selectors
-            0xB23..=0xB3F => Ok(R::default()),
+    /// Produce a compact binary dump of the core's state and optionally the bytes of memory within `range`, for crash analysis.
+    /// Every value is little-endian and registers are XLEN bits wide. The dump is framed as:
+    /// - the magic bytes `RYSK`, then the XLEN in bits and the number of registers as single bytes
+    /// - the program counter followed by each general-purpose register
+    /// - the number of floating-point registers as a byte, which is 0 without the F extension, followed by each register
+    /// - the privilege mode as encoded in MPP, then the number of CSRs as a `u16` followed by each CSR's address as a `u16` and its value
+    /// - the address of the first byte of memory and the number of bytes as a `u64`, followed by the bytes
+    #[cfg(feature = "alloc")]
+    pub fn core_dump(&self, mmu: &dyn Mmu<R>, range: Option<Range<R::Unsigned>>) -> Vec<u8> {
+        let mut dump = Vec::new();
+        dump.extend_from_slice(CORE_DUMP_MAGIC);
+        dump.push(match R::WIDTH { RegisterWidth::Bits32 => 32, RegisterWidth::Bits64 => 64, RegisterWidth::Bits128 => 128 });
+        dump.push(REGS as u8);
+        Self::dump_register(&mut dump, self.pc);
+        for &register in &self.registers {
+            Self::dump_register(&mut dump, register)
+        }
 
-            // mvendorid
-            // Requires a JEDEC vendor ID
-            0xF11 => Ok(R::default()),
-            // marchid
-            // In the future an Architecture ID shall be requested
-            0xF12 => Ok(R::default()),
-            // mimpid
-            // The version of rysk-core
-            0xF13 => Ok(R::zero_extended_word([version::PATCH, version::MINOR, version::MAJOR, 0])),
-            // mhartid
-            0xF14 => Ok(self.csr.mhartid),
-            _ => Err(Trap::IllegalInstruction)
+        // Floating-point registers are FLEN bits, so are only as wide as a double with the D extension
+        #[cfg(feature = "ext-f")]
+        {
+            dump.push(self.f.len() as u8);
+            dump.push(Self::FLEN_BYTES as u8);
+            for register in &self.f {
+                dump.extend_from_slice(&register.to_le_bytes()[..Self::FLEN_BYTES])
+            }
         }
-    }
+        #[cfg(not(feature = "ext-f"))]
+        dump.push(0);
 
-    /// Set a CSR to the specified value with program-defined access. May have side-effects
-    #[cfg(feature = "ext-csr")]
-    pub fn set_csr(&mut self, index: usize, value: R) {
-        match index {
-            // mie
-            0x304 => {
-                // WPRI fields must be hardwired to zero
-                self.csr.mie = value.and(R::zero_extended_half([!0x44, !0xF4]))
-            },
-            // mip
-            0x344 => {
-                // WPRI fields must be hardwired to zero
-                self.csr.mip = value.and(R::zero_extended_half([!0x44, !0xF4]))
-            },
-            _ => ()
+        #[cfg(feature = "ext-csr")]
+        {
+            dump.push(self.csr.privilege.bits());
+            let csrs: Vec<(usize, R)> = core_dump_csrs().filter_map(|index| self.get_csr(index).ok().map(|value| (index, value))).collect();
+            dump.extend_from_slice(&(csrs.len() as u16).to_le_bytes());
+            for (index, value) in csrs {
+                dump.extend_from_slice(&(index as u16).to_le_bytes());
+                Self::dump_register(&mut dump, value)
+            }
+        }
+        #[cfg(not(feature = "ext-csr"))]
+        {
+            // Without the csr-extension the core always executes in machine mode
+            dump.push(0b11);
+            dump.extend_from_slice(&0u16.to_le_bytes())
         }
-    }
 
-    #[cfg(feature = "ext-csr")]
-    fn trap(&mut self, cause: u8, interrupt: bool) {
-        self.csr.mcause = R::trap_cause(cause, interrupt);
-        let base = self.csr.mtvec.and(R::sign_extended_byte(0xFC));
-        let address = if self.csr.mtvec.byte() & 1 == 1 {
-            // Address if vectored
-            base.add_unsigned(R::zero_extended_half(u16::to_le_bytes(4 * (cause as u16))))
-        } else {
-            // Address if direct
-            base
+        let (start, length) = match range {
+            Some(range) => (R::from_unsigned(range.start), R::from_unsigned(range.end).usize().saturating_sub(R::from_unsigned(range.start).usize())),
+            None => (R::default(), 0)
         };
-        self.pc = address;
+        Self::dump_register(&mut dump, start);
+        dump.extend_from_slice(&(length as u64).to_le_bytes());
+        dump.extend((0..length).map(|offset| mmu.get(start.append(offset))));
+        dump
     }
 
-    /// Decode and execute an instruction
-    #[allow(clippy::cognitive_complexity)]
-    pub fn execute(&mut self, mmu: &mut dyn Mmu<R>) -> UnprivilegedTrap {
-        let instruction = mmu.fetch(self.pc);
-        let opcode = instruction[0] & 0x7F;
-        let funct3 = (instruction[1] & 0x70) >> 4;
-        let funct7 = (instruction[3] & 0xFE) >> 1;
+    /// Restore the state of the core and the memory saved by `core_dump`.
+    /// The dump must have been taken from a core with the same XLEN, register file and extensions.
+    /// The core is left unchanged if the dump cannot be loaded. Otherwise the instruction cache and reservation are cleared,
+    /// as memory may have changed.
+    pub fn load_core_dump(&mut self, dump: &[u8], mmu: &mut dyn Mmu<R>) -> Result<(), CoreDumpError> {
+        let mut reader = DumpReader(dump);
+        if reader.take(CORE_DUMP_MAGIC.len())? != CORE_DUMP_MAGIC {
+            return Err(CoreDumpError::Malformed)
+        }
+        let xlen = match R::WIDTH { RegisterWidth::Bits32 => 32, RegisterWidth::Bits64 => 64, RegisterWidth::Bits128 => 128 };
+        if reader.byte()? != xlen || reader.byte()? as usize != REGS {
+            return Err(CoreDumpError::Mismatch)
+        }
+        let pc = reader.register()?;
+        let mut registers = [R::default(); REGS];
+        for register in registers.iter_mut() {
+            *register = reader.register()?
+        }
+
+        let fp_registers = reader.byte()? as usize;
+        #[cfg(feature = "ext-f")]
+        let f = {
+            if fp_registers != self.f.len() || reader.byte()? as usize != Self::FLEN_BYTES {
+                return Err(CoreDumpError::Mismatch)
+            }
+            let mut f = [NAN_BOX; 32];
+            for register in f.iter_mut() {
+                let mut bytes = NAN_BOX.to_le_bytes();
+                bytes[..Self::FLEN_BYTES].copy_from_slice(reader.take(Self::FLEN_BYTES)?);
+                *register = u64::from_le_bytes(bytes)
+            }
+            f
+        };
+        #[cfg(not(feature = "ext-f"))]
+        {
+            if fp_registers != 0 {
+                return Err(CoreDumpError::Mismatch)
+            }
+        }
+
+        let privilege = reader.byte()?;
+        if !matches!(privilege, 0b00 | 0b11) {
+            return Err(CoreDumpError::Malformed)
+        }
+        let count = u16::from_le_bytes([reader.byte()?, reader.byte()?]);
+        #[cfg(not(feature = "ext-csr"))]
+        {
+            if count != 0 {
+                return Err(CoreDumpError::Mismatch)
+            }
+        }
+        // The CSRs are read again to restore them once the whole dump is known to be valid
+        let csrs = reader.0;
+        for _ in 0..count {
+            let (index, _) = reader.csr::<R>()?;
+            #[cfg(feature = "ext-csr")]
+            {
+                if !core_dump_csrs().any(|csr| csr == index) {
+                    return Err(CoreDumpError::Malformed)
+                }
+            }
+            #[cfg(not(feature = "ext-csr"))]
+            let _ = index;
+        }
+
+        let start: R = reader.register()?;
+        let mut length = [0; 8];
+        length.copy_from_slice(reader.take(8)?);
+        // A length beyond the host's address space cannot be followed by that many bytes
+        let length = usize::try_from(u64::from_le_bytes(length)).map_err(|_| CoreDumpError::Malformed)?;
+        let memory = reader.take(length)?;
+        if !reader.0.is_empty() {
+            return Err(CoreDumpError::Malformed)
+        }
 
-        // Increment the cycle counter
+        self.pc = pc;
+        self.registers = registers;
+        #[cfg(feature = "ext-f")]
+        {
+            self.f = f
+        }
         #[cfg(feature = "ext-csr")]
-        {self.csr.mcycle = self.csr.mcycle.add_unsigned(Register64::zero_extended_byte(1))}
+        {
+            self.csr.privilege = if privilege == Privilege::Machine.bits() { Privilege::Machine } else { Privilege::User };
+            let mut csrs = DumpReader(csrs);
+            for _ in 0..count {
+                let (index, value) = csrs.csr::<R>()?;
+                self.restore_csr(index, value)
+            }
+        }
+        #[cfg(not(feature = "ext-csr"))]
+        let _ = (privilege, csrs);
+        for (offset, &byte) in memory.iter().enumerate() {
+            mmu.set(start.append(offset), byte)
+        }
+        self.on_context_switch();
+        Ok(())
+    }
 
-        #[allow(clippy::unreadable_literal)]
+    /// Append the XLEN bits of a register to a core dump
+    #[cfg(feature = "alloc")]
+    fn dump_register(dump: &mut Vec<u8>, register: R) {
+        match R::WIDTH {
+            RegisterWidth::Bits32 => dump.extend_from_slice(&register.word()),
+            RegisterWidth::Bits64 => dump.extend_from_slice(&register.double()),
+            RegisterWidth::Bits128 => dump.extend_from_slice(&register.quad())
+        }
+    }
+
+    /// Write the storage behind a CSR listed in a core dump directly, bypassing the masking of read-only bits
+    #[cfg(feature = "ext-csr")]
+    fn restore_csr(&mut self, index: usize, value: R) {
+        match index {
+            #[cfg(feature = "ext-f")]
+            0x003 => self.fcsr.set_bits(value.byte()),
+            0x300 => self.csr.mstatus = value,
+            0x310 => self.csr.mstatush = Register32(value.word()),
+            0x302 => self.csr.medeleg = value,
+            0x303 => self.csr.mideleg = value,
+            0x304 => self.csr.mie = value,
+            0x305 => self.csr.mtvec = value,
+            0x306 => self.csr.mcounteren = Register32(value.word()),
+            0x30A => self.csr.write_menvcfg(value.byte()),
+            0x340 => self.csr.mscratch = value,
+            0x341 => self.csr.mepc = value,
+            0x342 => self.csr.mcause = value,
+            0x343 => self.csr.mtval = value,
+            0x344 => self.csr.mip = value,
+            0xB00 => self.csr.mcycle = Self::write_counter(self.csr.mcycle, value, false),
+            0xB80 => self.csr.mcycle = Self::write_counter(self.csr.mcycle, value, true),
+            0xB02 => self.csr.minstret = Self::write_counter(self.csr.minstret, value, false),
+            0xB82 => self.csr.minstret = Self::write_counter(self.csr.minstret, value, true),
+            0xB03..=0xB1F => self.csr.mhpmcounter[index - 0xB03] = Self::write_counter(self.csr.mhpmcounter[index - 0xB03], value, false),
+            0xB83..=0xB9F => self.csr.mhpmcounter[index - 0xB83] = Self::write_counter(self.csr.mhpmcounter[index - 0xB83], value, true),
+            0x323..=0x33F => self.csr.write_mhpmevent(index - 0x320, value),
+            // vtype is restored as is rather than configured, as that would clear vl and vstart
+            #[cfg(feature = "ext-v")]
+            0xC21 => self.vector.vtype = if value.signed().lt(R::default().signed()) { None } else { Vtype::decode(Self::address_value(value)) },
+            #[cfg(feature = "ext-v")]
+            0xC20 => self.vector.vl = Self::address_value(value),
+            #[cfg(feature = "ext-v")]
+            0x008 | 0x00F => self.set_csr(index, value),
+            0xF14 => self.csr.mhartid = value,
+            _ => ()
+        }
+    }
+
+    /// Set the byte at `byte_offset` within register `x{index}`, preserving the rest of the register.
+    /// Intended for debuggers; writes to `x0` are ignored as with `set`.
+    /// # Safety
+    /// A panic will occur if index is larger than 31 or the byte does not fit within the register
+    pub fn set_register_byte(&mut self, index: usize, byte_offset: usize, value: u8) {
+        self.set_register_part(index, byte_offset, 1, R::zero_extended_byte(0xFF), R::zero_extended_byte(value))
+    }
+
+    /// Set the half at `byte_offset` within register `x{index}`, preserving the rest of the register.
+    /// # Safety
+    /// A panic will occur if index is larger than 31 or the half does not fit within the register
+    pub fn set_register_half(&mut self, index: usize, byte_offset: usize, value: [u8; 2]) {
+        self.set_register_part(index, byte_offset, 2, R::zero_extended_half([0xFF; 2]), R::zero_extended_half(value))
+    }
+
+    /// Set the word at `byte_offset` within register `x{index}`, preserving the rest of the register.
+    /// # Safety
+    /// A panic will occur if index is larger than 31 or the word does not fit within the register
+    pub fn set_register_word(&mut self, index: usize, byte_offset: usize, value: [u8; 4]) {
+        self.set_register_part(index, byte_offset, 4, R::zero_extended_word([0xFF; 4]), R::zero_extended_word(value))
+    }
+
+    /// Replace `size` bytes, selected by `mask`, at `byte_offset` within a register with `value`
+    fn set_register_part(&mut self, index: usize, byte_offset: usize, size: usize, mask: R, value: R) {
+        let width = match R::WIDTH {
+            RegisterWidth::Bits32 => 4,
+            RegisterWidth::Bits64 => 8,
+            RegisterWidth::Bits128 => 16
+        };
+        assert!(byte_offset + size <= width, "Cannot set bytes beyond the width of the register");
+
+        let shift = R::zero_extended_byte(byte_offset as u8 * 8);
+        let register = self.get(index).and(mask.shl(shift).not()).or(value.shl(shift));
+        self.set(index, register)
+    }
+
+    /// The storage backing the CSRs, accessed without the side-effects of `get_csr`
+    #[cfg(feature = "ext-csr")]
+    pub fn csr(&self) -> &Csr<R> {
+        &self.csr
+    }
+
+    /// Mutable access to the storage backing the CSRs, bypassing the access rules of `set_csr`
+    #[cfg(feature = "ext-csr")]
+    pub fn csr_mut(&mut self) -> &mut Csr<R> {
+        &mut self.csr
+    }
+
+    /// The size in bytes of a floating-point register
+    #[cfg(feature = "ext-f")]
+    const FLEN_BYTES: usize = if cfg!(feature = "ext-d") { 8 } else { 4 };
+
+    /// Get the single-precision value of the floating-point register `f{index}`.
+    /// A register which does not hold a NaN-boxed single-precision value, such as one holding a double, reads as the canonical NaN.
+    /// With Zfinx this is the low 32 bits of the integer register `x{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    #[cfg(feature = "ext-f")]
+    pub fn get_f(&self, index: usize) -> FpRegister {
+        if self.zfinx {
+            return FpRegister(u32::from_le_bytes(self.get(index).word()))
+        }
+        let bits = self.f[index];
+        if bits & NAN_BOX == NAN_BOX {
+            FpRegister(bits as u32)
+        } else {
+            FpRegister(float::CANONICAL_NAN)
+        }
+    }
+
+    /// Set the floating-point register `f{index}` to the single-precision value of `register`, NaN-boxing it.
+    /// With Zfinx the value is instead sign-extended into the integer register `x{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    #[cfg(feature = "ext-f")]
+    pub fn set_f(&mut self, index: usize, register: FpRegister) {
+        if self.zfinx {
+            return self.set(index, R::sign_extended_word(register.0.to_le_bytes()))
+        }
+        self.f[index] = NAN_BOX | register.0 as u64
+    }
+
+    /// Get the double-precision value of the floating-point register `f{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    #[cfg(feature = "ext-d")]
+    pub fn get_d(&self, index: usize) -> f64 {
+        f64::from_bits(self.f[index])
+    }
+
+    /// Get the raw bits of the half-precision value of the floating-point register `f{index}`.
+    /// A register which does not hold a NaN-boxed half-precision value reads as the canonical NaN.
+    /// With Zfinx this is the low 16 bits of the integer register `x{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    #[cfg(feature = "ext-zfh")]
+    pub fn get_h(&self, index: usize) -> u16 {
+        if self.zfinx {
+            return u16::from_le_bytes(self.get(index).half())
+        }
+        let bits = self.f[index];
+        if bits & HALF_NAN_BOX == HALF_NAN_BOX {
+            bits as u16
+        } else {
+            half::CANONICAL_NAN
+        }
+    }
+
+    /// Set the floating-point register `f{index}` to the half-precision value with the raw bits `value`, NaN-boxing it.
+    /// With Zfinx the value is instead sign-extended into the integer register `x{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    #[cfg(feature = "ext-zfh")]
+    pub fn set_h(&mut self, index: usize, value: u16) {
+        if self.zfinx {
+            return self.set(index, R::sign_extended_half(value.to_le_bytes()))
+        }
+        self.f[index] = HALF_NAN_BOX | value as u64
+    }
+
+    /// Set the floating-point register `f{index}` to the double-precision `value`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    #[cfg(feature = "ext-d")]
+    pub fn set_d(&mut self, index: usize, value: f64) {
+        self.f[index] = value.to_bits()
+    }
+
+    /// The floating-point rounding mode and accrued exception flags
+    #[cfg(feature = "ext-f")]
+    pub fn fcsr(&self) -> Fcsr {
+        self.fcsr
+    }
+
+    /// Mutable access to the floating-point rounding mode and accrued exception flags
+    #[cfg(feature = "ext-f")]
+    pub fn fcsr_mut(&mut self) -> &mut Fcsr {
+        &mut self.fcsr
+    }
+
+    /// The vector registers and the state of the vector CSRs
+    #[cfg(feature = "ext-v")]
+    pub fn vector(&self) -> &Vector {
+        &self.vector
+    }
+
+    /// Mutable access to the vector registers and the state of the vector CSRs
+    #[cfg(feature = "ext-v")]
+    pub fn vector_mut(&mut self) -> &mut Vector {
+        &mut self.vector
+    }
+
+    /// The rounding mode given by the rm field of an instruction, or `None` if it is reserved.
+    /// The dynamic mode uses frm, which may itself hold a reserved mode
+    #[cfg(feature = "ext-f")]
+    fn rounding_mode(&self, rm: u8) -> Option<RoundingMode> {
+        RoundingMode::decode(if rm == 0b111 { self.fcsr.frm } else { rm })
+    }
+
+    /// Write the result of a floating-point operation to `f{index}`, accruing its exception flags
+    #[cfg(feature = "ext-f")]
+    fn set_f_result(&mut self, index: usize, (value, flags): (f32, u8)) {
+        self.set_f(index, value.into());
+        self.fcsr.fflags |= flags
+    }
+
+    /// Write the result of a half-precision operation to `f{index}`, accruing its exception flags
+    #[cfg(feature = "ext-zfh")]
+    fn set_h_result(&mut self, index: usize, (value, flags): (u16, u8)) {
+        self.set_h(index, value);
+        self.fcsr.fflags |= flags
+    }
+
+    /// Write the result of a double-precision operation to `f{index}`, accruing its exception flags
+    #[cfg(feature = "ext-d")]
+    fn set_d_result(&mut self, index: usize, (value, flags): (f64, u8)) {
+        self.set_d(index, value);
+        self.fcsr.fflags |= flags
+    }
+
+    /// Get a value from a CSR. May have side-effects
+    #[cfg(feature = "ext-csr")]
+    pub fn get_csr(&self, index: usize) -> Result<R, Trap> {
+        match index {
+            // fflags
+            #[cfg(feature = "ext-f")]
+            0x001 => Ok(R::zero_extended_byte(self.fcsr.fflags)),
+            // frm
+            #[cfg(feature = "ext-f")]
+            0x002 => Ok(R::zero_extended_byte(self.fcsr.frm)),
+            // fcsr
+            #[cfg(feature = "ext-f")]
+            0x003 => Ok(R::zero_extended_byte(self.fcsr.bits())),
+            // vstart
+            #[cfg(feature = "ext-v")]
+            0x008 => Ok(Self::from_value(self.vector.vstart)),
+            // vxsat
+            #[cfg(feature = "ext-v")]
+            0x009 => Ok(R::zero_extended_byte(self.vector.vxsat as u8)),
+            // vxrm
+            #[cfg(feature = "ext-v")]
+            0x00A => Ok(R::zero_extended_byte(self.vector.vxrm)),
+            // vcsr, holding vxrm above vxsat
+            #[cfg(feature = "ext-v")]
+            0x00F => Ok(R::zero_extended_byte(self.vector.vxrm << 1 | self.vector.vxsat as u8)),
+
+            // mstatus
+            0x300 => Ok(self.csr.mstatus),
+            // misa
+            0x301 => {
+                // Bit 4 of the lowest byte
+                const E: u8 = 1 << 4;
+                // Bit 8, in the second byte
+                const I: u8 = 1;
+                // Bit 12, in the second byte. Zmmul alone is not reported
+                #[cfg(feature = "ext-m")]
+                const M: u8 = 1 << 4;
+                #[cfg(not(feature = "ext-m"))]
+                const M: u8 = 0;
+                // Bit 20, in the third byte
+                const U: u8 = 1 << 4;
+                #[cfg(feature = "ext-a")]
+                const A: u8 = 1;
+                #[cfg(not(feature = "ext-a"))]
+                const A: u8 = 0;
+                #[cfg(feature = "ext-c")]
+                const C: u8 = 1 << 2;
+                #[cfg(not(feature = "ext-c"))]
+                const C: u8 = 0;
+                #[cfg(feature = "ext-f")]
+                const F: u8 = 1 << 5;
+                #[cfg(not(feature = "ext-f"))]
+                const F: u8 = 0;
+                #[cfg(feature = "ext-d")]
+                const D: u8 = 1 << 3;
+                #[cfg(not(feature = "ext-d"))]
+                const D: u8 = 0;
+                // Bit 21, in the third byte
+                #[cfg(feature = "ext-v")]
+                const V: u8 = 1 << 5;
+                #[cfg(not(feature = "ext-v"))]
+                const V: u8 = 0;
+
+                // The E base ISA replaces I with the 16 register file
+                let (e, i) = if REGS == 16 { (E, 0) } else { (0, I) };
+                // Zfinx has no floating-point registers, so is not reported as F
+                #[cfg(feature = "ext-f")]
+                let (f, d) = if self.zfinx { (0, 0) } else { (F, D) };
+                #[cfg(not(feature = "ext-f"))]
+                let (f, d) = (F, D);
+                let isa0 = e | f | d | C | A;
+                let isa1 = i | M;
+                let isa2 = U | V;
+                let isa3 = 0;
+
+                const MXLEN32: u8 = 1;
+                const MXLEN64: u8 = 2;
+                const MXLEN128: u8 = 3;
+                Ok(
+                    match R::WIDTH {
+                        RegisterWidth::Bits32 => R::zero_extended_word([isa0, isa1, isa2, isa3 | MXLEN32 << 6]),
+                        RegisterWidth::Bits64 => R::zero_extended_double([isa0, isa1, isa2, isa3, 0, 0, 0, MXLEN64 << 6]),
+                        RegisterWidth::Bits128 => R::from_quad([isa0, isa1, isa2, isa3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, MXLEN128 << 6]),
+                    }
+                )
+            },
+            // medeleg
+            0x302 => Ok(self.csr.medeleg),
+            // mideleg
+            0x303 => Ok(self.csr.mideleg),
+            // mie
+            0x304 => Ok(self.csr.mie),
+            // mtvec
+            0x305 => Ok(self.csr.mtvec),
+            // mcounteren
+            0x306 => Ok(R::zero_extended_word(self.csr.mcounteren.word())),
+            // menvcfg, of which only the cache-block enables are implemented
+            0x30A => Ok(R::zero_extended_byte(self.csr.menvcfg.byte())),
+            // menvcfgh, which holds no implemented fields
+            0x31A if R::WIDTH == RegisterWidth::Bits32 => Ok(R::default()),
+            // mstatush
+            0x310 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word(self.csr.mstatush.word())),
+
+            // mscratch
+            0x340 => Ok(self.csr.mscratch),
+            // mepc
+            0x341 => Ok(self.csr.mepc),
+            // mcause
+            0x342 => Ok(self.csr.mcause),
+            // mtval
+            0x343 => Ok(self.csr.mtval),
+            // mip
+            0x344 => Ok(self.csr.mip),
+
+            // mcycle and mcycleh
+            0xB00 if R::WIDTH != RegisterWidth::Bits32 => Ok(R::zero_extended_double(self.csr.mcycle.double())),
+            0xB00 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mcycle.split().0).0)),
+            0xB80 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mcycle.split().1).0)),
+            // minstret and minstreth
+            0xB02 if R::WIDTH != RegisterWidth::Bits32 => Ok(R::zero_extended_double(self.csr.minstret.double())),
+            0xB02 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.minstret.split().0).0)),
+            0xB82 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.minstret.split().1).0)),
+            // mhpmcounter3 to mhpmcounter31, and their high halves
+            0xB03..=0xB1F if R::WIDTH != RegisterWidth::Bits32 => Ok(R::zero_extended_double(self.csr.mhpmcounter[index - 0xB03].double())),
+            0xB03..=0xB1F => Ok(R::zero_extended_word((self.csr.mhpmcounter[index - 0xB03].split().0).0)),
+            0xB83..=0xB9F if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mhpmcounter[index - 0xB83].split().1).0)),
+            // mhpmevent3 to mhpmevent31
+            0x323..=0x33F => Ok(R::zero_extended_byte(self.csr.mhpmevent[index - 0x323])),
+
+            // cycle, time, instret and hpmcounter3 to hpmcounter31, with the high halves on RV32, are read-only.
+            // time reads the platform's mtime, while the others are shadows of the machine counters.
+            // User mode may only read those enabled in mcounteren
+            0xC00..=0xC1F | 0xC80..=0xC9F => {
+                if self.csr.privilege == Privilege::User && self.csr.mcounteren.0[(index & 0x1F) / 8] & 1 << (index % 8) == 0 {
+                    return Err(Trap::IllegalInstruction)
+                }
+                self.counter_csr(index)
+            },
+
+            // vl
+            #[cfg(feature = "ext-v")]
+            0xC20 => Ok(Self::from_value(self.vector.vl)),
+            // vtype, of which vill is the highest bit
+            #[cfg(feature = "ext-v")]
+            0xC21 => Ok(match self.vector.vtype {
+                Some(vtype) => Self::from_value(vtype.bits()),
+                None => match R::WIDTH {
+                    RegisterWidth::Bits32 => Self::from_value(1 << 31),
+                    RegisterWidth::Bits64 => Self::from_value(1 << 63),
+                    RegisterWidth::Bits128 => R::from_quad((1u128 << 127).to_le_bytes())
+                }
+            }),
+            // vlenb
+            #[cfg(feature = "ext-v")]
+            0xC22 => Ok(Self::from_value(self.vector.vlenb() as u64)),
+
+            // mvendorid
+            // Requires a JEDEC vendor ID
+            0xF11 => Ok(R::default()),
+            // marchid
+            // In the future an Architecture ID shall be requested
+            0xF12 => Ok(R::default()),
+            // mimpid
+            // The version of rysk-core
+            0xF13 => Ok(R::zero_extended_word([version::PATCH, version::MINOR, version::MAJOR, 0])),
+            // mhartid
+            0xF14 => Ok(self.csr.mhartid),
+            _ => Err(Trap::IllegalInstruction)
+        }
+    }
+
+    /// Read one of the user-level counters regardless of whether the privilege mode may access it
+    #[cfg(feature = "ext-csr")]
+    fn counter_csr(&self, index: usize) -> Result<R, Trap> {
+        match index {
+            0xC01 if R::WIDTH != RegisterWidth::Bits32 => Ok(R::zero_extended_double(self.csr.mtime.double())),
+            0xC01 => Ok(R::zero_extended_word((self.csr.mtime.split().0).0)),
+            0xC81 if R::WIDTH == RegisterWidth::Bits32 => Ok(R::zero_extended_word((self.csr.mtime.split().1).0)),
+            _ => self.get_csr(index - 0x100)
+        }
+    }
+
+    /// The addresses of the CSRs holding state of the core, which `diff` compares. The rest are constant or mirror these.
+    /// Includes CSRs which may not exist at the core's XLEN or with its extensions, which are illegal to read
+    #[cfg(feature = "ext-csr")]
+    fn state_csrs() -> impl Iterator<Item = usize> {
+        // fcsr, then vstart, vcsr, vl and vtype, which hold every floating-point and vector CSR
+        const USER: [usize; 5] = [0x003, 0x008, 0x00F, 0xC20, 0xC21];
+        const MACHINE: [usize; 14] = [0x300, 0x310, 0x302, 0x303, 0x304, 0x305, 0x306, 0x30A, 0x340, 0x341, 0x342, 0x343, 0x344, 0xF14];
+        // mcycle, minstret and the programmable counters with their high halves, the events they count, then time
+        USER.iter().chain(MACHINE.iter()).copied()
+            .chain(0xB00..=0xB1F)
+            .chain(0xB80..=0xB9F)
+            .chain(0x323..=0x33F)
+            .chain([0xC01, 0xC81].iter().copied())
+    }
+
+    /// Set a CSR to the specified value with program-defined access. May have side-effects
+    #[cfg(feature = "ext-csr")]
+    pub fn set_csr(&mut self, index: usize, value: R) {
+        match index {
+            // fflags
+            #[cfg(feature = "ext-f")]
+            0x001 => self.fcsr.fflags = value.byte() & 0x1F,
+            // frm
+            #[cfg(feature = "ext-f")]
+            0x002 => self.fcsr.frm = value.byte() & 0b111,
+            // fcsr
+            #[cfg(feature = "ext-f")]
+            0x003 => self.fcsr.set_bits(value.byte()),
+            // vstart, which need only hold the index of an element of the largest register group
+            #[cfg(feature = "ext-v")]
+            0x008 => self.vector.vstart = Self::address_value(value) & (self.vector.vlenb() as u64 * 8 - 1),
+            // vxsat
+            #[cfg(feature = "ext-v")]
+            0x009 => self.vector.vxsat = value.byte() & 1 != 0,
+            // vxrm
+            #[cfg(feature = "ext-v")]
+            0x00A => self.vector.vxrm = value.byte() & 0b11,
+            // vcsr
+            #[cfg(feature = "ext-v")]
+            0x00F => {
+                self.vector.vxsat = value.byte() & 1 != 0;
+                self.vector.vxrm = (value.byte() >> 1) & 0b11
+            },
+
+            // mstatus
+            0x300 => self.csr.write_mstatus(value),
+            // mstatush
+            0x310 if R::WIDTH == RegisterWidth::Bits32 => self.csr.write_mstatush(Register32(value.word())),
+            // mie
+            0x304 => self.csr.write_mie(value),
+            // mcounteren
+            0x306 => self.csr.mcounteren = Register32(value.word()),
+            // menvcfg
+            0x30A => self.csr.write_menvcfg(value.byte()),
+            // mtvec
+            // Modes other than direct and vectored are reserved, so only the low mode bit is writable
+            0x305 => self.csr.mtvec = value.and(R::sign_extended_byte(0xFD)),
+            // mscratch
+            0x340 => self.csr.mscratch = value,
+            // mepc
+            // Can only hold addresses aligned to IALIGN
+            0x341 => self.csr.mepc = value.and(R::sign_extended_byte(!(IALIGN - 1))),
+            // mcause
+            0x342 => self.csr.mcause = value,
+            // mtval
+            0x343 => self.csr.mtval = value,
+            // mcycle and mcycleh
+            0xB00 => self.csr.mcycle = Self::write_counter(self.csr.mcycle, value, false),
+            0xB80 if R::WIDTH == RegisterWidth::Bits32 => self.csr.mcycle = Self::write_counter(self.csr.mcycle, value, true),
+            // minstret and minstreth
+            0xB02 => {
+                self.csr.minstret = Self::write_counter(self.csr.minstret, value, false);
+                self.instret_written = true
+            },
+            0xB82 if R::WIDTH == RegisterWidth::Bits32 => self.csr.minstret = Self::write_counter(self.csr.minstret, value, true),
+            // mhpmcounter3 to mhpmcounter31, and their high halves
+            0xB03..=0xB1F => self.csr.mhpmcounter[index - 0xB03] = Self::write_counter(self.csr.mhpmcounter[index - 0xB03], value, false),
+            0xB83..=0xB9F if R::WIDTH == RegisterWidth::Bits32 => self.csr.mhpmcounter[index - 0xB83] = Self::write_counter(self.csr.mhpmcounter[index - 0xB83], value, true),
+            // mhpmevent3 to mhpmevent31
+            0x323..=0x33F => self.csr.write_mhpmevent(index - 0x320, value),
+            // mip
+            0x344 => self.csr.write_mip(value),
+            _ => ()
+        }
+    }
+
+    /// Enter the machine-mode trap handler as if the instruction at the program counter raised `trap`, with `value` written
+    /// to mtval, regardless of the core's trap strategy. Allows a test to run a trap handler through to MRET without
+    /// crafting an instruction that faults
+    #[cfg(all(feature = "test-util", feature = "ext-csr"))]
+    pub fn enter_trap_for_test(&mut self, trap: Trap, value: R) {
+        CsrHandled.trap(self, trap, value);
+    }
+
+    /// Take a machine interrupt with the given cause immediately, regardless of whether it is pending and enabled.
+    /// Execution resumes at the interrupt's handler as given by mtvec.
+    #[cfg(feature = "ext-csr")]
+    pub fn take_interrupt(&mut self, cause: u8) {
+        self.enter_trap_handler(cause, true)
+    }
+    /// Set the address of the handler `nmi` vectors to, which defaults to the address execution started at
+    /// ```rust
+    /// use rysk_core::{ Core, Register32 };
+    /// # #[cfg(feature = "ext-csr")]
+    /// let core = Core::<Register32>::new(0, 0).with_nmi_vector(0x100);
+    /// ```
+    #[cfg(feature = "ext-csr")]
+    pub fn with_nmi_vector(mut self, address: R::Unsigned) -> Self {
+        self.nmi_vector = R::from_unsigned(address);
+        self
+    }
+    /// Take a non-maskable interrupt immediately, such as one raised by a platform watchdog.
+    /// The NMI CSRs are not modelled, so the pc of the next instruction to execute is saved to mepc and mcause is set to
+    /// an interrupt of unknown cause before vectoring to the NMI handler. The NMI may overwrite the state of an active trap handler.
+    #[cfg(feature = "ext-csr")]
+    pub fn nmi(&mut self) {
+        self.outcome = StepOutcome::Trapped;
+        self.csr.mepc = self.pc;
+        self.csr.mcause = R::trap_cause(0, true);
+        self.csr.enter_trap();
+        self.pc = self.nmi_vector;
+    }
+    /// Make an interrupt pending. It is taken before the next instruction once enabled by mie and mstatus
+    #[cfg(feature = "ext-csr")]
+    pub fn raise_interrupt(&mut self, interrupt: Interrupt) {
+        self.set_interrupt_pending(interrupt, true)
+    }
+    /// Clear a pending interrupt, as the platform does once its source is serviced
+    #[cfg(feature = "ext-csr")]
+    pub fn clear_interrupt(&mut self, interrupt: Interrupt) {
+        self.set_interrupt_pending(interrupt, false)
+    }
+    #[cfg(feature = "ext-csr")]
+    fn set_interrupt_pending(&mut self, interrupt: Interrupt, pending: bool) {
+        match interrupt {
+            Interrupt::Software => self.csr.set_msip(pending),
+            Interrupt::Timer => self.csr.set_mtip(pending),
+            Interrupt::External => self.csr.set_meip(pending)
+        }
+    }
+    /// Drive the machine external interrupt line, such as from an interrupt controller. The line stays pending in mip.MEIP until released
+    #[cfg(feature = "ext-csr")]
+    pub fn set_external_pending(&mut self, pending: bool) {
+        self.set_interrupt_pending(Interrupt::External, pending)
+    }
+    /// Drive the machine timer interrupt line, such as from a timer comparing mtime against mtimecmp. Reflected in mip.MTIP
+    #[cfg(feature = "ext-csr")]
+    pub fn set_timer_pending(&mut self, pending: bool) {
+        self.set_interrupt_pending(Interrupt::Timer, pending)
+    }
+    /// Set the value of the platform's real-time counter, such as mtime of a CLINT, which the time CSR reads.
+    /// Unlike mcycle it does not advance as instructions execute, so the platform should set it as time passes
+    #[cfg(feature = "ext-csr")]
+    pub fn set_time(&mut self, mtime: u64) {
+        self.csr.set_time(mtime)
+    }
+    /// Drive the machine software interrupt line, such as from an inter-processor interrupt device. Reflected in mip.MSIP
+    #[cfg(feature = "ext-csr")]
+    pub fn set_software_pending(&mut self, pending: bool) {
+        self.set_interrupt_pending(Interrupt::Software, pending)
+    }
+
+    /// Write a 64-bit counter CSR. On RV32 only the low or high half is written, while on RV128 the upper bits are ignored
+    #[cfg(feature = "ext-csr")]
+    fn write_counter(counter: Register64, value: R, high: bool) -> Register64 {
+        let mut bytes = counter.0;
+        match R::WIDTH {
+            RegisterWidth::Bits32 if high => bytes[4..].copy_from_slice(&value.word()),
+            RegisterWidth::Bits32 => bytes[..4].copy_from_slice(&value.word()),
+            RegisterWidth::Bits64 | RegisterWidth::Bits128 => bytes = value.double()
+        }
+        Register64(bytes)
+    }
+
+    /// Perform the access of a CSR instruction, reporting it to the CSR hook.
+    /// The CSR is read into `destination` if `read`, then written with `write`'s operation applied to the value read and
+    /// the operand. Returns false if the instruction is illegal as the CSR does not exist or is read-only.
+    #[cfg(feature = "ext-csr")]
+    fn csr_instruction(&mut self, index: usize, destination: usize, read: bool, write: Option<CsrWrite<R>>) -> bool {
+        // Bits 8 and 9 of the index give the lowest privilege mode which may access the CSR
+        if (index >> 8) as u8 & 0b11 > self.csr.privilege.bits() {
+            return false
+        }
+        let current = match self.get_csr(index) {
+            Ok(current) => current,
+            Err(_) => return false
+        };
+        // The top 2 bits of the index are set for read-only CSRs
+        if write.is_some() && index >> 10 == 0b11 {
+            return false
+        }
+        if read {
+            #[cfg(feature = "alloc")]
+            if let Some(hook) = &mut self.on_csr_access {
+                hook(index, false, current)
+            }
+        }
+        if let Some((operand, operation)) = write {
+            self.set_csr(index, operation(current, operand));
+            // Report the value held once WARL fields have been masked, rather than the value written
+            #[cfg(feature = "alloc")]
+            if let Ok(stored) = self.get_csr(index) {
+                if let Some(hook) = &mut self.on_csr_access {
+                    hook(index, true, stored)
+                }
+            }
+        }
+        if read {
+            self.set(destination, current)
+        }
+        true
+    }
+
+    /// Enter the trap handler given by mtvec. The pc of the instruction that trapped, or the next to execute for an
+    /// interrupt, is saved to mepc so MRET can return to it
+    #[cfg(feature = "ext-csr")]
+    fn enter_trap_handler(&mut self, cause: u8, interrupt: bool) {
+        self.outcome = StepOutcome::Trapped;
+        self.csr.mepc = self.pc;
+        self.csr.mcause = R::trap_cause(cause, interrupt);
+        self.csr.enter_trap();
+        // The base is 4-byte aligned with the mode in the low 2 bits. Sign extension keeps every upper bit on RV64
+        let base = self.csr.mtvec.and(R::sign_extended_byte(0xFC));
+        let address = if interrupt && self.csr.mtvec.byte() & 0b11 == 1 {
+            // Address if vectored, which only applies to interrupts
+            base.add_unsigned(R::zero_extended_half(u16::to_le_bytes(4 * (cause as u16))))
+        } else {
+            // Address if direct
+            base
+        };
+        self.pc = address;
+    }
+
+    /// Apply an operation at the effective XLEN, which on RV64 with an effective XLEN of 32 is the RV32 operation on the low words.
+    /// Used for operations such as the upper half of a product, where the low word of the full-width result differs
+    #[cfg(any(feature = "ext-zmmul", feature = "ext-zbkb", feature = "ext-zbkx", feature = "ext-zbs", feature = "ext-zbc"))]
+    fn effective_operation(&self, source1: R, source2: R, word: fn(Register32, Register32) -> Register32, full: fn(R, R) -> R) -> R {
+        if self.xlen() != R::WIDTH {
+            R::sign_extended_word(word(Register32(source1.word()), Register32(source2.word())).word())
+        } else {
+            full(source1, source2)
+        }
+    }
+
+    /// The opcode an instruction executes as under the effective XLEN, or None if it is illegal at that width.
+    /// With an effective XLEN of 32 on RV64, instructions with a word variant execute as that variant, which has the semantics of
+    /// the RV32 instruction, and instructions only present in RV64 are illegal
+    fn effective_opcode(&self, opcode: u8, funct3: u8, funct7: u8) -> Option<u8> {
+        if self.xlen() == R::WIDTH {
+            return Some(opcode)
+        }
+        #[allow(clippy::unreadable_literal)]
         match (opcode, funct3, funct7) {
-            // ADD
-            (0b0110011, 0b000, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).add_unsigned(self.get(source2)));
+            // OP-32, OP-IMM-32, LWU, LD, SD and the double-word atomics
+            (0b0111011, _, _) | (0b0011011, _, _) | (0b0000011, 0b110, _) | (0b0000011, 0b011, _) | (0b0100011, 0b011, _) | (0b0101111, 0b011, _) => None,
+            // ADD, SUB, SLL, SRL and SRA
+            (0b0110011, 0b000, 0b0000000) | (0b0110011, 0b000, 0b0100000) | (0b0110011, 0b001, 0b0000000) | (0b0110011, 0b101, 0b0000000) | (0b0110011, 0b101, 0b0100000)
+            // MUL, DIV, DIVU, REM and REMU
+            | (0b0110011, 0b000, 0b0000001) | (0b0110011, 0b100, 0b0000001) | (0b0110011, 0b101, 0b0000001) | (0b0110011, 0b110, 0b0000001) | (0b0110011, 0b111, 0b0000001)
+            // ADDI, and SLLI, SRLI and SRAI where the shift amount fits in a word
+            | (0b0010011, 0b000, _) | (0b0010011, 0b001, 0b0000000) | (0b0010011, 0b101, 0b0000000) | (0b0010011, 0b101, 0b0100000) => Some(opcode | 0b1000),
+            _ => Some(opcode)
+        }
+    }
+
+    /// Tests if every register the instruction names is within the register file, which is always true with 32 registers
+    #[allow(clippy::unreadable_literal)]
+    fn in_register_file(instruction: [u8; 4], opcode: u8, funct3: u8, funct7: u8) -> bool {
+        if REGS == 32 {
+            return true
+        }
+        let variant::R { destination, source1, source2 } = Variant::decode(instruction);
+        let (uses_destination, uses_source1, uses_source2) = match (opcode, funct3) {
+            // OP, OP-32 and AMO
+            (0b0110011, _) | (0b0111011, _) | (0b0101111, _) => (true, true, true),
+            // OP-IMM, OP-IMM-32, LOAD and JALR
+            (0b0010011, _) | (0b0011011, _) | (0b0000011, _) | (0b1100111, _) => (true, true, false),
+            // STORE and BRANCH
+            (0b0100011, _) | (0b1100011, _) => (false, true, true),
+            // LUI, AUIPC and JAL
+            (0b0110111, _) | (0b0010111, _) | (0b1101111, _) => (true, false, false),
+            // CSR instructions, where the immediate forms hold an immediate in place of the source
+            (0b1110011, 0b001..=0b011) => (true, true, false),
+            (0b1110011, 0b101..=0b111) => (true, false, false),
+            // Strided vector loads and stores, with the stride in an integer register
+            (0b0000111, _) | (0b0100111, _) if matches!(funct3, 0b000 | 0b101..=0b111) && (instruction[3] >> 2) & 0b11 == 0b10 => (false, true, true),
+            // Floating-point and vector loads and stores, addressed by an integer register
+            (0b0000111, _) | (0b0100111, _) => (false, true, false),
+            // Floating-point instructions of any format writing an integer register: FCVT to an integer, FMV to an integer, FCLASS and comparisons
+            (0b1010011, _) if matches!(funct7 & !0b11, 0b1100000 | 0b1110000 | 0b1010000) => (true, false, false),
+            // Floating-point instructions of any format reading an integer register: FCVT from an integer and FMV from an integer
+            (0b1010011, _) if matches!(funct7 & !0b11, 0b1101000 | 0b1111000) => (false, true, false),
+            // VSETVLI and VSETIVLI, which holds an immediate in place of rs1, and VSETVL which also reads rs2
+            (0b1010111, 0b111) => (true, instruction[3] >> 6 != 0b11, instruction[3] >> 6 == 0b10),
+            // Vector instructions with a scalar operand
+            (0b1010111, 0b100) | (0b1010111, 0b110) => (false, true, false),
+            // VMV.X.S, VCPOP.M and VFIRST.M
+            (0b1010111, 0b010) if funct7 >> 1 == 0b010000 => (true, false, false),
+            _ => (false, false, false)
+        };
+        (!uses_destination || destination < REGS) && (!uses_source1 || source1 < REGS) && (!uses_source2 || source2 < REGS)
+    }
+
+    /// Tests if a floating-point instruction exists with Zfinx, where it must not name a floating-point register file.
+    /// The loads, stores and moves between register files are removed, the D extension is not supported, and every
+    /// register named must be within the integer register file. Instructions other than floating-point are unaffected
+    #[cfg(feature = "ext-f")]
+    fn zfinx_legal(instruction: [u8; 4], opcode: u8, funct3: u8, funct7: u8) -> bool {
+        let variant::R4 { destination, source1, source2, source3 } = Variant::decode(instruction);
+        let in_register_file = destination < REGS && source1 < REGS && source2 < REGS;
+        // The fmt field of funct7, of which 01 is double precision
+        let double = funct7 & 0b11 == 0b01;
+        match opcode {
+            // FLW, FSW, FLD, FSD, FLH and FSH, though the vector loads and stores sharing their opcodes remain
+            0b0000111 | 0b0100111 => cfg!(feature = "ext-v") && matches!(funct3, 0b000 | 0b101..=0b111),
+            // FMADD, FMSUB, FNMSUB and FNMADD
+            0b1000011 | 0b1000111 | 0b1001011 | 0b1001111 => !double && in_register_file && source3 < REGS,
+            0b1010011 => {
+                // FMV.X.W, FMV.W.X and the moves of other formats
+                let fmv = funct3 == 0b000 && matches!(funct7 & !0b11, 0b1110000 | 0b1111000);
+                // Conversions between formats select the source format with rs2, such as FCVT.S.D
+                let from_double = matches!(funct7 & !0b11, 0b0100000) && source2 == 1;
+                !fmv && !double && !from_double && in_register_file
+            },
+            _ => true
+        }
+    }
+
+    /// The value of an address as carried by a trap. Only the low 64 bits of an address are carried on RV128
+    fn address_value(address: R) -> u64 {
+        match R::WIDTH {
+            RegisterWidth::Bits32 => u32::from_le_bytes(address.word()) as u64,
+            RegisterWidth::Bits64 | RegisterWidth::Bits128 => u64::from_le_bytes(address.double())
+        }
+    }
+
+    /// A register holding the low XLEN bits of a value
+    #[cfg(feature = "ext-v")]
+    fn from_value(value: u64) -> R {
+        match R::WIDTH {
+            RegisterWidth::Bits32 => R::zero_extended_word((value as u32).to_le_bytes()),
+            RegisterWidth::Bits64 | RegisterWidth::Bits128 => R::zero_extended_double(value.to_le_bytes())
+        }
+    }
+
+    /// The value of a register sign-extended to 64 bits, as scalar operands are extended to elements wider than XLEN.
+    /// Elements are at most 64 bits, so only the low 64 bits of a register are used on RV128
+    #[cfg(feature = "ext-v")]
+    fn sign_extended_value(register: R) -> u64 {
+        match R::WIDTH {
+            RegisterWidth::Bits32 => i32::from_le_bytes(register.word()) as i64 as u64,
+            RegisterWidth::Bits64 | RegisterWidth::Bits128 => u64::from_le_bytes(register.double())
+        }
+    }
+
+    /// Raise a trap through the trap strategy, returning the trap if the execution environment must handle it.
+    /// `value` is the faulting address or zero, as written to mtval
+    fn trap(&mut self, trap: Trap, value: R) -> Option<Trap> {
+        self.outcome = StepOutcome::Trapped;
+        #[cfg(feature = "ext-csr")]
+        self.csr.count_event(HpmEvent::Exception);
+        #[cfg(feature = "alloc")]
+        let strategy = Rc::clone(&self.trap_strategy);
+        #[cfg(feature = "alloc")]
+        { self.last_trap = strategy.trap(self, trap, value) }
+        #[cfg(not(feature = "alloc"))]
+        { self.last_trap = (self.trap_strategy)(self, trap, value) }
+        self.last_trap
+    }
+
+    /// The trap returned by the most recent call to `execute`, or `None` if it did not trap.
+    /// Allows the trap to be inspected after the fact without the host storing it separately.
+    pub fn last_trap(&self) -> Option<Trap> {
+        self.last_trap
+    }
+
+    /// The outcome of the most recent call to `execute`
+    pub fn last_outcome(&self) -> StepOutcome {
+        self.outcome
+    }
+
+    /// Advance past the ECALL or EBREAK that raised the trap returned by the most recent call to `execute`.
+    /// The program counter is left at the instruction that trapped, so a host that has handled a `SystemCall` or
+    /// `Breakpoint` calls this before executing again to resume at the following instruction.
+    /// Returns false, leaving the program counter untouched, if the most recent trap was neither.
+    pub fn skip_trapped_instruction(&mut self) -> bool {
+        match self.last_trap {
+            Some(Trap::SystemCall) | Some(Trap::Breakpoint) => {
+                self.step();
+                self.last_trap = None;
+                true
+            },
+            _ => false
+        }
+    }
+
+    /// The shift amount encoded by a shift immediate, or `None` if it is reserved as it reaches beyond the register
+    fn shamt(&self, shamt: u32) -> Option<R> {
+        let bits = match self.xlen() {
+            RegisterWidth::Bits32 => 32,
+            RegisterWidth::Bits64 => 64,
+            RegisterWidth::Bits128 => 128
+        };
+        if shamt < bits { Some(R::zero_extended_byte(shamt as u8)) } else { None }
+    }
+
+    /// Account for a load, which with Ztso is ordered after every earlier load
+    #[inline(always)]
+    fn access(&mut self, mmu: &mut dyn Mmu<R>, address: R::Unsigned) {
+        if self.ztso {
+            mmu.fence(0b0010, 0b0010)
+        }
+        #[cfg(feature = "ext-csr")]
+        self.csr.count_event(HpmEvent::Load);
+        self.account(mmu, address)
+    }
+
+    /// Account for a memory access, stalling for the latency the MMU reports for the address.
+    /// Without the cycle counter provided by the csr-extension there is nothing to stall.
+    #[inline(always)]
+    #[cfg_attr(not(feature = "ext-csr"), allow(unused_variables))]
+    fn account(&mut self, mmu: &dyn Mmu<R>, address: R::Unsigned) {
+        if let Some(limit) = self.stack_limit {
+            if address.lt(limit) && address.gte(self.get(2).unsigned()) && self.stack_overflow.is_none() {
+                self.stack_overflow = Some(R::from_unsigned(address))
+            }
+        }
+        #[cfg(feature = "ext-csr")]
+        {
+            let latency = mmu.latency(address);
+            if latency != 0 {
+                self.csr.mcycle = self.csr.mcycle.add_unsigned(Register64::from_unsigned(latency))
+            }
+        }
+    }
+
+    /// Account for a store, which also invalidates the reservation held by the hart.
+    /// With Ztso the store is ordered after every earlier load and store
+    #[inline(always)]
+    fn store_access(&mut self, mmu: &mut dyn Mmu<R>, address: R::Unsigned) {
+        if self.ztso {
+            mmu.fence(0b0011, 0b0001)
+        }
+        #[cfg(feature = "ext-a")]
+        {
+            self.reservation = None
+        }
+        #[cfg(feature = "ext-csr")]
+        self.csr.count_event(HpmEvent::Store);
+        self.account(mmu, address)
+    }
+
+    /// Signal that the host has switched address spaces, such as by passing a different MMU to `execute`.
+    ///
+    /// The core holds no state derived from the MMU except for the reservation held by LR,
+    /// which is cleared so that an SC cannot succeed against memory in a different address space.
+    pub fn on_context_switch(&mut self) {
+        self.flush_icache();
+        #[cfg(feature = "ext-a")]
+        {
+            self.reservation = None
+        }
+    }
+
+    /// Load `info.width` bytes for a load instruction, raising address-misaligned before the memory is touched
+    /// when the access is misaligned and the memory does not emulate it, so that it takes priority over an access fault
+    fn load(mmu: &dyn Mmu<R>, address: R, bytes: &mut [u8], info: AccessInfo) -> Result<(), MemoryFault> {
+        if address.word()[0] & (info.width - 1) != 0 && !mmu.emulates_misaligned(address.unsigned()) {
+            return Err(MemoryFault::Misaligned)
+        }
+        mmu.load_bytes(address.unsigned(), bytes, info)
+    }
+    /// Store `info.width` bytes for a store instruction, with the same priority for address-misaligned as `load`
+    fn store(mmu: &mut dyn Mmu<R>, address: R, bytes: &[u8], info: AccessInfo) -> Result<(), MemoryFault> {
+        if address.word()[0] & (info.width - 1) != 0 && !mmu.emulates_misaligned(address.unsigned()) {
+            return Err(MemoryFault::Misaligned)
+        }
+        mmu.store_bytes(address.unsigned(), bytes, info)
+    }
+
+    /// Whether an LR, SC or AMO of `size` bytes is misaligned. These are never emulated byte by byte, as they could not
+    /// then be atomic, so raise an address-misaligned exception even if the memory emulates misaligned loads and stores
+    #[cfg(feature = "ext-a")]
+    fn misaligned_atomic(address: R, size: u8) -> bool {
+        address.word()[0] & (size - 1) != 0
+    }
+    /// Load a sign extended word or double for the A extension
+    #[cfg(feature = "ext-a")]
+    fn load_sized(mmu: &dyn Mmu<R>, address: R, size: u8) -> Result<R, MemoryFault> {
+        if Self::misaligned_atomic(address, size) {
+            return Err(MemoryFault::Misaligned)
+        }
+        if size == 4 {
+            let mut value = [0; 4];
+            mmu.load_bytes(address.unsigned(), &mut value, AccessInfo::load(4, true)).map(|()| R::sign_extended_word(value))
+        } else {
+            let mut value = [0; 8];
+            mmu.load_bytes(address.unsigned(), &mut value, AccessInfo::load(8, true)).map(|()| R::sign_extended_double(value))
+        }
+    }
+    /// Store the lowest word or double of a register for the A extension
+    #[cfg(feature = "ext-a")]
+    fn store_sized(mmu: &mut dyn Mmu<R>, address: R, value: R, size: u8) -> Result<(), MemoryFault> {
+        if Self::misaligned_atomic(address, size) {
+            return Err(MemoryFault::Misaligned)
+        }
+        if size == 4 {
+            mmu.store_bytes(address.unsigned(), &value.word(), AccessInfo::store(4))
+        } else {
+            mmu.store_bytes(address.unsigned(), &value.double(), AccessInfo::store(8))
+        }
+    }
+    /// Sign extend the word or double held by `bytes` for the A extension
+    #[cfg(feature = "ext-a")]
+    fn from_sized(bytes: &[u8]) -> R {
+        let mut double = [0; 8];
+        double[..bytes.len()].copy_from_slice(bytes);
+        if bytes.len() == 4 {
+            R::sign_extended_word([double[0], double[1], double[2], double[3]])
+        } else {
+            R::sign_extended_double(double)
+        }
+    }
+    /// Decode the SHA-512 instructions only present in RV32, which `decode` rejects for a wider register but which are
+    /// legal on a wider core under an effective XLEN of 32
+    #[cfg(feature = "ext-zknh")]
+    fn rv32_sha512(instruction: [u8; 4]) -> Option<Instruction<R>> {
+        use crate::decode::Instruction::*;
+        Some(match variant::decode_instruction::<Register32>(instruction).ok()? {
+            Sha512sum0r { rd, rs1, rs2 } => Sha512sum0r { rd, rs1, rs2 },
+            Sha512sum1r { rd, rs1, rs2 } => Sha512sum1r { rd, rs1, rs2 },
+            Sha512sig0l { rd, rs1, rs2 } => Sha512sig0l { rd, rs1, rs2 },
+            Sha512sig1l { rd, rs1, rs2 } => Sha512sig1l { rd, rs1, rs2 },
+            Sha512sig0h { rd, rs1, rs2 } => Sha512sig0h { rd, rs1, rs2 },
+            Sha512sig1h { rd, rs1, rs2 } => Sha512sig1h { rd, rs1, rs2 },
+            _ => return None
+        })
+    }
+    /// Compute the value an AMO stores
+    #[cfg(feature = "ext-a")]
+    fn amo(op: AmoOp, loaded: R, source: R) -> R {
+        match op {
+            AmoOp::Swap => source,
+            AmoOp::Add => loaded.add_unsigned(source),
+            AmoOp::Xor => loaded.xor(source),
+            AmoOp::And => loaded.and(source),
+            AmoOp::Or => loaded.or(source),
+            AmoOp::Min => if loaded.lt_signed(source) { loaded } else { source },
+            AmoOp::Max => if loaded.lt_signed(source) { source } else { loaded },
+            AmoOp::Minu => if loaded.lt_unsigned(source) { loaded } else { source },
+            AmoOp::Maxu => if loaded.lt_unsigned(source) { source } else { loaded }
+        }
+    }
+    /// Perform CBO.CLEAN, CBO.FLUSH or CBO.INVAL of Zicbom, or CBO.ZERO of Zicboz, on the cache block holding the address
+    /// in rs1. Below machine mode menvcfg enables each operation, and may have CBO.INVAL flush the block instead
+    #[cfg(any(feature = "ext-zicbom", feature = "ext-zicboz"))]
+    fn cache_block_operation(&mut self, mmu: &mut dyn Mmu<R>, operation: CacheBlockOperation, rs1: usize) -> Option<Trap> {
+        #[cfg(feature = "ext-csr")]
+        let operation = if self.csr.privilege == Privilege::Machine { operation } else {
+            let envcfg = self.csr.menvcfg.byte();
+            match operation {
+                CacheBlockOperation::Invalidate if envcfg >> MENVCFG_CBIE & 0b11 == 0b01 => CacheBlockOperation::Flush,
+                CacheBlockOperation::Invalidate if envcfg >> MENVCFG_CBIE & 0b11 == 0b00 => trap!(Illegal Instruction; self),
+                CacheBlockOperation::Clean | CacheBlockOperation::Flush if envcfg & 1 << MENVCFG_CBCFE == 0 => trap!(Illegal Instruction; self),
+                CacheBlockOperation::Zero if envcfg & 1 << MENVCFG_CBZE == 0 => trap!(Illegal Instruction; self),
+                _ => operation
+            }
+        };
+        let address = self.get(rs1);
+        let block = address.and(R::sign_extended_byte(!(CACHE_BLOCK_SIZE as u8 - 1))).unsigned();
+        if operation == CacheBlockOperation::Zero {
+            self.store_access(mmu, block)
+        } else {
+            self.account(mmu, block)
+        }
+        match mmu.cache_block(operation, block) {
+            Ok(()) => self.step(),
+            Err(fault) => trap!(Store Fault; self, fault, address)
+        }
+        None
+    }
+    /// The AVL requested by VSETVLI or VSETVL, which is x[rs1], all elements if only rs1 is x0, or `None` to keep the
+    /// current vl if both rd and rs1 are x0
+    #[cfg(feature = "ext-v")]
+    fn vector_avl(&self, rd: usize, rs1: usize) -> Option<u64> {
+        match (rs1, rd) {
+            (0, 0) => None,
+            (0, _) => Some(u64::MAX),
+            _ => Some(Self::address_value(self.get(rs1)))
+        }
+    }
+
+    /// Decode and execute an instruction
+    ///
+    /// The core keeps no state derived from `mmu` between calls, so a different MMU may be passed to each call,
+    /// for example to switch address spaces. The exception is the LR reservation, cleared by `on_context_switch`.
+    pub fn execute(&mut self, mmu: &mut dyn Mmu<R>) -> Option<Trap> {
+        #[cfg(feature = "ext-csr")]
+        {
+            self.instret_written = false
+        }
+        let trap = self.execute_instruction(mmu);
+        // Only an instruction that did not trap retires. An instruction writing minstret does so in place of the increment
+        #[cfg(feature = "ext-csr")]
+        if self.outcome != StepOutcome::Trapped && !self.instret_written {
+            self.csr.minstret = self.csr.minstret.add_unsigned(Register64::zero_extended_byte(1))
+        }
+        trap
+    }
+
+    #[allow(clippy::cognitive_complexity)]
+    fn execute_instruction(&mut self, mmu: &mut dyn Mmu<R>) -> Option<Trap> {
+        self.outcome = StepOutcome::Retired;
+        self.last_trap = None;
+        // Interrupts are taken between instructions, so mepc holds the instruction yet to execute
+        #[cfg(feature = "ext-csr")]
+        {
+            if let Some(cause) = self.csr.pending_interrupt() {
+                self.take_interrupt(cause);
+                return None
+            }
+        }
+        // Addresses are sign-extended from the effective XLEN, so jumps wrap within a narrower address space
+        self.pc = self.effective(self.pc);
+        if !self.executable(self.pc, 1) {
+            trap!(Instruction Access Fault; self);
+        }
+        let cached = self.cached_instruction(self.pc);
+        let (instruction, length) = match cached {
+            Some(cached) => (cached.instruction, cached.length),
+            None => {
+                #[cfg(feature = "ext-c")]
+                let fetched = mmu.fetch_parcel(self.pc).and_then(|parcel| if compressed::is_compressed(parcel) {
+                    // Reserved encodings expand to the all-zero instruction, which is defined to be illegal
+                    Ok((compressed::expand::<R>(parcel).unwrap_or([0; 4]), 2))
+                } else {
+                    mmu.fetch(self.pc).map(|instruction| (instruction, 4))
+                });
+                #[cfg(not(feature = "ext-c"))]
+                let fetched = mmu.fetch(self.pc).map(|instruction| (instruction, 4));
+                let (instruction, length) = match fetched {
+                    Ok(fetched) => fetched,
+                    Err(_) => {
+                        trap!(Instruction Access Fault; self);
+                    }
+                };
+                (instruction, length)
+            }
+        };
+        // An instruction straddling the end of the executable range faults even though its first parcel is executable
+        if !self.executable(self.pc, length) {
+            trap!(Instruction Access Fault; self);
+        }
+        self.instruction_length = length;
+
+        // Increment the cycle counter. minstret is incremented by `execute` once the instruction retires
+        #[cfg(feature = "ext-csr")]
+        {
+            self.csr.mcycle = self.csr.mcycle.add_unsigned(Register64::zero_extended_byte(1))
+        }
+
+        let decoded = match cached {
+            Some(cached) => cached.decoded,
+            None => {
+                let opcode = instruction[0] & 0x7F;
+                let funct3 = (instruction[1] & 0x70) >> 4;
+                let funct7 = (instruction[3] & 0xFE) >> 1;
+                if !Self::in_register_file(instruction, opcode, funct3, funct7) {
+                    trap!(Illegal Instruction; self)
+                }
+                #[cfg(feature = "ext-f")]
+                if self.zfinx && !Self::zfinx_legal(instruction, opcode, funct3, funct7) {
+                    trap!(Illegal Instruction; self)
+                }
+                // Under an effective XLEN of 32, instructions with a word variant are decoded as that variant
+                let effective = match self.effective_opcode(opcode, funct3, funct7) {
+                    Some(opcode) => [instruction[0] & 0x80 | opcode, instruction[1], instruction[2], instruction[3]],
+                    None => trap!(Illegal Instruction; self)
+                };
+                let decoded = match variant::decode_instruction::<R>(effective) {
+                    Ok(decoded) => decoded,
+                    #[cfg(feature = "ext-zknh")]
+                    Err(_) if self.xlen() == RegisterWidth::Bits32 => match Self::rv32_sha512(effective) {
+                        Some(decoded) => decoded,
+                        None => trap!(Illegal Instruction; self)
+                    },
+                    Err(_) => trap!(Illegal Instruction; self)
+                };
+                self.cache_instruction(CachedInstruction { pc: self.pc.unsigned(), xlen: self.xlen(), instruction, length, decoded });
+                decoded
+            }
+        };
+
+        use crate::decode::Instruction::*;
+        match decoded {
+            Add { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).add_unsigned(self.get(rs2)));
+                self.step()
+            },
+            Addw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).add_unsigned(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            Sub { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).sub_unsigned(self.get(rs2)));
+                self.step()
+            },
+            Subw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).sub_unsigned(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            Slt { rd, rs1, rs2 } => {
+                self.set(rd, if self.get(rs1).lt_signed(self.get(rs2)) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+                self.step()
+            },
+            Sltu { rd, rs1, rs2 } => {
+                self.set(rd, if self.get(rs1).lt_unsigned(self.get(rs2)) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+                self.step()
+            },
+            Addi { rd, rs1, imm } => {
+                self.set(rd, self.get(rs1).add_signed(imm));
+                self.step()
+            },
+            Addiw { rd, rs1, imm } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).add_signed(Register32(imm.word())).word()));
+                self.step()
+            },
+            Slti { rd, rs1, imm } => {
+                self.set(rd, if self.get(rs1).lt_signed(imm) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+                self.step()
+            },
+            Sltiu { rd, rs1, imm } => {
+                self.set(rd, if self.get(rs1).lt_unsigned(imm) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+                self.step()
+            },
+
+            Xor { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).xor(self.get(rs2)));
+                self.step()
+            },
+            Or { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).or(self.get(rs2)));
+                self.step()
+            },
+            And { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).and(self.get(rs2)));
+                self.step()
+            },
+            Xori { rd, rs1, imm } => {
+                self.set(rd, self.get(rs1).xor(imm));
+                self.step()
+            },
+            Ori { rd, rs1, imm } => {
+                self.set(rd, self.get(rs1).or(imm));
+                self.step()
+            },
+            Andi { rd, rs1, imm } => {
+                self.set(rd, self.get(rs1).and(imm));
+                self.step()
+            },
+
+            Sll { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).shl(self.get(rs2)));
+                self.step()
+            },
+            Sllw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).shl(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            Srl { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).shr(self.get(rs2)));
+                self.step()
+            },
+            Srlw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).shr(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            Sra { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).sha(self.get(rs2)));
+                self.step()
+            },
+            Sraw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).sha(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            // Zbkb Extension
+            #[cfg(feature = "ext-zbkb")]
+            Pack { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::pack, R::pack));
+                self.step()
+            },
+            // PACKW, packing the lower halves of the low words into a sign-extended word
+            #[cfg(feature = "ext-zbkb")]
+            Packw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).pack(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbkb")]
+            Packh { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).packh(self.get(rs2)));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbkb")]
+            Brev8 { rd, rs1 } => {
+                self.set(rd, self.get(rs1).brev8());
+                self.step()
+            },
+            #[cfg(feature = "ext-zbkb")]
+            Zip { rd, rs1 } => {
+                self.set(rd, self.get(rs1).zip());
+                self.step()
+            },
+            #[cfg(feature = "ext-zbkb")]
+            Unzip { rd, rs1 } => {
+                self.set(rd, self.get(rs1).unzip());
+                self.step()
+            },
+            // Zbkx Extension
+            #[cfg(feature = "ext-zbkx")]
+            Xperm4 { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::xperm4, R::xperm4));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbkx")]
+            Xperm8 { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::xperm8, R::xperm8));
+                self.step()
+            },
+            // Zbc Extension
+            #[cfg(feature = "ext-zbc")]
+            Clmul { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::clmul, R::clmul));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbc")]
+            Clmulr { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::clmulr, R::clmulr));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbc")]
+            Clmulh { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::clmulh, R::clmulh));
+                self.step()
+            },
+            // Zknh Extension
+            // SHA256SUM0, SHA256SUM1, SHA256SIG0 and SHA256SIG1, and on RV64 SHA512SUM0, SHA512SUM1, SHA512SIG0 and SHA512SIG1,
+            // which are illegal under an effective XLEN of 32
+            #[cfg(feature = "ext-zknh")]
+            Sha256sum0 { rd, rs1 } | Sha256sum1 { rd, rs1 } | Sha256sig0 { rd, rs1 } | Sha256sig1 { rd, rs1 }
+            | Sha512sum0 { rd, rs1 } | Sha512sum1 { rd, rs1 } | Sha512sig0 { rd, rs1 } | Sha512sig1 { rd, rs1 } => {
+                if self.xlen() == RegisterWidth::Bits32 && matches!(decoded, Sha512sum0 { .. } | Sha512sum1 { .. } | Sha512sig0 { .. } | Sha512sig1 { .. }) {
+                    trap!(Illegal Instruction; self)
+                }
+                let source = self.get(rs1);
+                let result = match decoded {
+                    Sha256sum0 { .. } => source.sha256sum0(),
+                    Sha256sum1 { .. } => source.sha256sum1(),
+                    Sha256sig0 { .. } => source.sha256sig0(),
+                    Sha256sig1 { .. } => source.sha256sig1(),
+                    Sha512sum0 { .. } => source.sha512sum0(),
+                    Sha512sum1 { .. } => source.sha512sum1(),
+                    Sha512sig0 { .. } => source.sha512sig0(),
+                    _ => source.sha512sig1()
+                };
+                self.set(rd, result);
+                self.step()
+            },
+            // SHA512SUM0R, SHA512SUM1R, SHA512SIG0L, SHA512SIG0H, SHA512SIG1L and SHA512SIG1H, each computing one word of a
+            // SHA-512 function on RV32 from the two words of its input. Wider cores only decode them under an effective XLEN of 32
+            #[cfg(feature = "ext-zknh")]
+            Sha512sum0r { rd, rs1, rs2 } | Sha512sum1r { rd, rs1, rs2 } | Sha512sig0l { rd, rs1, rs2 }
+            | Sha512sig1l { rd, rs1, rs2 } | Sha512sig0h { rd, rs1, rs2 } | Sha512sig1h { rd, rs1, rs2 } => {
+                let (source1, source2) = (self.get(rs1), self.get(rs2));
+                let result = match decoded {
+                    Sha512sum0r { .. } => source1.sha512sum0r(source2),
+                    Sha512sum1r { .. } => source1.sha512sum1r(source2),
+                    Sha512sig0l { .. } => source1.sha512sig0l(source2),
+                    Sha512sig1l { .. } => source1.sha512sig1l(source2),
+                    Sha512sig0h { .. } => source1.sha512sig0h(source2),
+                    _ => source1.sha512sig1h(source2)
+                };
+                self.set(rd, result);
+                self.step()
+            },
+            // Zbs Extension
+            // BSET, BCLR, BINV and BEXT, where the bit index is taken modulo the effective XLEN
+            #[cfg(feature = "ext-zbs")]
+            Bset { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::bset, R::bset));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbs")]
+            Bclr { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::bclr, R::bclr));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbs")]
+            Binv { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::binv, R::binv));
+                self.step()
+            },
+            #[cfg(feature = "ext-zbs")]
+            Bext { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::bext, R::bext));
+                self.step()
+            },
+            // BSETI, BCLRI, BINVI and BEXTI, where the index must be within the effective XLEN
+            #[cfg(feature = "ext-zbs")]
+            Bseti { rd, rs1, shamt } | Bclri { rd, rs1, shamt } | Binvi { rd, rs1, shamt } | Bexti { rd, rs1, shamt } => {
+                match self.shamt(shamt) {
+                    Some(index) => {
+                        let operation = match decoded {
+                            Bseti { .. } => R::bset,
+                            Bclri { .. } => R::bclr,
+                            Binvi { .. } => R::binv,
+                            _ => R::bext
+                        };
+                        self.set(rd, operation(self.get(rs1), index));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
+            },
+
+            Slli { rd, rs1, shamt } => {
+                match self.shamt(shamt) {
+                    Some(shamt) => {
+                        self.set(rd, self.get(rs1).shl(shamt));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
+            },
+            Slliw { rd, rs1, shamt } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).shl(Register32::zero_extended_byte(shamt as u8)).word()));
+                self.step()
+            },
+            Srli { rd, rs1, shamt } => {
+                match self.shamt(shamt) {
+                    Some(shamt) => {
+                        self.set(rd, self.get(rs1).shr(shamt));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
+            },
+            Srliw { rd, rs1, shamt } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).shr(Register32::zero_extended_byte(shamt as u8)).word()));
+                self.step()
+            },
+            Srai { rd, rs1, shamt } => {
+                match self.shamt(shamt) {
+                    Some(shamt) => {
+                        self.set(rd, self.get(rs1).sha(shamt));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
+            },
+            Sraiw { rd, rs1, shamt } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).sha(Register32::zero_extended_byte(shamt as u8)).word()));
+                self.step()
+            },
+
+            Lui { rd, imm } => {
+                self.set(rd, imm);
+                self.step()
+            },
+            Auipc { rd, imm } => {
+                self.set(rd, self.pc.add_signed(imm));
+                self.step()
+            },
+
+            Lb { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 1];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(1, true)) {
+                    Ok(()) => {
+                        self.set(rd, R::sign_extended_byte(value[0]));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Lbu { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 1];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(1, false)) {
+                    Ok(()) => {
+                        self.set(rd, R::zero_extended_byte(value[0]));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Lh { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 2];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(2, true)) {
+                    Ok(()) => {
+                        self.set(rd, R::sign_extended_half(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Lhu { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 2];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(2, false)) {
+                    Ok(()) => {
+                        self.set(rd, R::zero_extended_half(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Lw { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 4];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(4, true)) {
+                    Ok(()) => {
+                        self.set(rd, R::sign_extended_word(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Lwu { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 4];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(4, false)) {
+                    Ok(()) => {
+                        self.set(rd, R::zero_extended_word(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Ld { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 8];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(8, true)) {
+                    Ok(()) => {
+                        self.set(rd, R::sign_extended_double(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            Ldu { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 8];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(8, false)) {
+                    Ok(()) => {
+                        self.set(rd, R::zero_extended_double(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            // LQ, which on RV128 takes the MISC-MEM encoding otherwise used by the CBO instructions
+            Lq { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 16];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(16, false)) {
+                    Ok(()) => {
+                        self.set(rd, R::from_quad(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+
+            Sb { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &[self.get(rs2).byte()], AccessInfo::store(1)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+            Sh { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &self.get(rs2).half(), AccessInfo::store(2)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+            Sw { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &self.get(rs2).word(), AccessInfo::store(4)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+            Sd { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &self.get(rs2).double(), AccessInfo::store(8)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+            Sq { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &self.get(rs2).quad(), AccessInfo::store(16)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+
+            Jal { rd, imm } => {
+                jump!(self, self.pc.add_signed(imm), rd)
+            },
+            Jalr { rd, rs1, imm } => {
+                // The least significant bit of the target is always cleared
+                let target = self.get(rs1).add_signed(imm).and(R::sign_extended_byte(0xFE));
+                jump!(self, target, rd)
+            },
+
+            Beq { rs1, rs2, imm } => {
+                branch!(self, self.get(rs1).eq(self.get(rs2)), imm)
+            },
+            Bne { rs1, rs2, imm } => {
+                branch!(self, self.get(rs1).neq(self.get(rs2)), imm)
+            },
+            Blt { rs1, rs2, imm } => {
+                branch!(self, self.get(rs1).lt_signed(self.get(rs2)), imm)
+            },
+            Bltu { rs1, rs2, imm } => {
+                branch!(self, self.get(rs1).lt_unsigned(self.get(rs2)), imm)
+            },
+            Bge { rs1, rs2, imm } => {
+                branch!(self, self.get(rs1).gte_signed(self.get(rs2)), imm)
+            },
+            Bgeu { rs1, rs2, imm } => {
+                branch!(self, self.get(rs1).gte_unsigned(self.get(rs2)), imm)
+            },
+
+            // Zawrs Extension
+            // While a reservation is held the core waits for another hart to invalidate it, through `Mmu::wait_on_reservation`.
+            // Without one there is nothing to wait for, so the instruction completes immediately. Waiting is unbounded for
+            // WRS.NTO, so below machine mode it is illegal while mstatus.TW is set
+            #[cfg(feature = "ext-zawrs")]
+            WrsNto | WrsSto => {
+                #[cfg(feature = "ext-a")]
+                {
+                    if let Some((address, size)) = self.reservation {
+                        let short = matches!(decoded, WrsSto);
+                        #[cfg(feature = "ext-csr")]
+                        {
+                            if !short && self.csr.privilege != Privilege::Machine && self.csr.timeout_wait() {
+                                trap!(Illegal Instruction; self)
+                            }
+                        }
+                        self.outcome = StepOutcome::Waiting;
+                        mmu.wait_on_reservation(address.unsigned(), size, short)
+                    }
+                }
                 self.step()
             },
-            // ADDW
-            (0b0111011, 0b000, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).add_unsigned(Register32(self.get(source2).word())).word()));
+
+            // FENCE, including FENCE.TSO and PAUSE. Reserved values of fm, rs1 and rd are ignored so the fence is a normal fence.
+            // PAUSE of Zihintpause additionally backs off the host through the pause hook, as the hart is in a spin loop
+            Fence { pred, succ, .. } => {
+                mmu.fence(pred, succ);
+                if u32::from_le_bytes(instruction) == 0x0100_000F {
+                    self.outcome = StepOutcome::Waiting;
+                    #[cfg(feature = "alloc")]
+                    let hook = self.on_pause.as_mut();
+                    #[cfg(not(feature = "alloc"))]
+                    let hook: Option<&mut fn()> = None;
+                    match hook {
+                        Some(hook) => hook(),
+                        #[cfg(feature = "std")]
+                        None => std::thread::yield_now(),
+                        #[cfg(not(feature = "std"))]
+                        None => ()
+                    }
+                }
+                self.step()
+            },
+            // FENCE.I, which makes stores to instructions visible to the instructions fetched after it.
+            // The immediate, rs1 and rd fields are reserved and ignored
+            FenceI => {
+                self.flush_icache();
+                mmu.fence_i();
+                self.step()
+            },
+            // CBO.CLEAN, CBO.FLUSH and CBO.INVAL of Zicbom, and CBO.ZERO of Zicboz
+            #[cfg(feature = "ext-zicbom")]
+            CboClean { rs1 } => return self.cache_block_operation(mmu, CacheBlockOperation::Clean, rs1),
+            #[cfg(feature = "ext-zicbom")]
+            CboFlush { rs1 } => return self.cache_block_operation(mmu, CacheBlockOperation::Flush, rs1),
+            #[cfg(feature = "ext-zicbom")]
+            CboInval { rs1 } => return self.cache_block_operation(mmu, CacheBlockOperation::Invalidate, rs1),
+            #[cfg(feature = "ext-zicboz")]
+            CboZero { rs1 } => return self.cache_block_operation(mmu, CacheBlockOperation::Zero, rs1),
+            Ecall => {
+                trap!(System Call; self)
+            },
+            Ebreak => {
+                trap!(Breakpoint; self)
+            },
+            #[cfg(feature = "ext-csr")]
+            Mret => {
+                if self.csr.privilege != Privilege::Machine {
+                    trap!(Illegal Instruction; self)
+                }
+                self.csr.return_from_trap();
+                self.pc = self.csr.mepc
+            },
+            // Interrupts are checked before every instruction, so waiting completes immediately as the spec permits.
+            // Below machine mode WFI is illegal while mstatus.TW is set
+            #[cfg(feature = "ext-csr")]
+            Wfi => {
+                if self.csr.privilege != Privilege::Machine && self.csr.timeout_wait() {
+                    trap!(Illegal Instruction; self)
+                }
+                self.step()
+            },
+
+            // M Extension
+            // The multiplications are also provided alone by Zmmul
+            #[cfg(feature = "ext-zmmul")]
+            Mul { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).mul(self.get(rs2)));
+                self.step()
+            },
+            #[cfg(feature = "ext-zmmul")]
+            Mulh { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::mulh, R::mulh));
+                self.step()
+            },
+            #[cfg(feature = "ext-zmmul")]
+            Mulhsu { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::mulhsu, R::mulhsu));
+                self.step()
+            },
+            #[cfg(feature = "ext-zmmul")]
+            Mulhu { rd, rs1, rs2 } => {
+                self.set(rd, self.effective_operation(self.get(rs1), self.get(rs2), Register32::mulhu, R::mulhu));
+                self.step()
+            },
+            #[cfg(feature = "ext-zmmul")]
+            Mulw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).mul(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            #[cfg(feature = "ext-m")]
+            Div { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).div(self.get(rs2)));
                 self.step()
             },
-            // SUB
-            (0b0110011, 0b000, 0b0100000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).sub_unsigned(self.get(source2)));
+            #[cfg(feature = "ext-m")]
+            Divu { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).divu(self.get(rs2)));
+                self.step()
+            },
+            #[cfg(feature = "ext-m")]
+            Divw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).div(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            #[cfg(feature = "ext-m")]
+            Divuw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).divu(Register32(self.get(rs2).word())).word()));
+                self.step()
+            },
+            #[cfg(feature = "ext-m")]
+            Rem { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).rem(self.get(rs2)));
+                self.step()
+            },
+            #[cfg(feature = "ext-m")]
+            Remu { rd, rs1, rs2 } => {
+                self.set(rd, self.get(rs1).remu(self.get(rs2)));
                 self.step()
             },
-            // SUBW
-            (0b0111011, 0b000, 0b0100000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).sub_unsigned(Register32(self.get(source2).word())).word()));
+            #[cfg(feature = "ext-m")]
+            Remw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).rem(Register32(self.get(rs2).word())).word()));
                 self.step()
             },
-            // SLT
-            (0b0110011, 0b010, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, if self.get(source1).lt_signed(self.get(source2)) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+            #[cfg(feature = "ext-m")]
+            Remuw { rd, rs1, rs2 } => {
+                self.set(rd, R::sign_extended_word(Register32(self.get(rs1).word()).remu(Register32(self.get(rs2).word())).word()));
                 self.step()
             },
-            // SLTU
-            (0b0110011, 0b011, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, if self.get(source1).lt_unsigned(self.get(source2)) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+
+            // A Extension
+            // The aq and rl ordering bits have no effect with a single hart
+            // LR and SC succeed deterministically; SC fails only if no reservation is held, it was invalidated by a
+            // store or a context switch, or it was made at a different address or with a different size
+            #[cfg(feature = "ext-a")]
+            Lr { rd, rs1, double, .. } => {
+                let size = if double { 8 } else { 4 };
+                let address = self.get(rs1);
+                self.access(mmu, address.unsigned());
+                match Self::load_sized(mmu, address, size) {
+                    Ok(value) => {
+                        self.reservation = Some((address, size));
+                        self.set(rd, value);
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            #[cfg(feature = "ext-a")]
+            Sc { rd, rs1, rs2, double, .. } => {
+                let size = if double { 8 } else { 4 };
+                let address = self.get(rs1);
+                // A misaligned SC faults even without a reservation
+                if Self::misaligned_atomic(address, size) {
+                    trap!(Store Fault; self, MemoryFault::Misaligned, address)
+                }
+                let reserved = match self.reservation.take() {
+                    Some((reserved, reserved_size)) => reserved.eq(address) && reserved_size == size,
+                    None => false
+                };
+                if reserved {
+                    self.store_access(mmu, address.unsigned());
+                    if let Err(fault) = Self::store_sized(mmu, address, self.get(rs2), size) {
+                        trap!(Store Fault; self, fault, address);
+                    }
+                    self.set(rd, R::zero_extended_byte(0))
+                } else {
+                    self.set(rd, R::zero_extended_byte(1))
+                }
                 self.step()
             },
-            // ADDI
-            (0b0010011, 0b000, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).add_signed(immediate));
-                self.step()
+            #[cfg(feature = "ext-a")]
+            Amo { op, rd, rs1, rs2, double, .. } => {
+                let size = if double { 8 } else { 4 };
+                let address = self.get(rs1);
+                let source = if size == 4 { R::sign_extended_word(self.get(rs2).word()) } else { self.get(rs2) };
+                self.store_access(mmu, address.unsigned());
+                if Self::misaligned_atomic(address, size) {
+                    trap!(Store Fault; self, MemoryFault::Misaligned, address)
+                }
+                let mut bytes = [0; 8];
+                let bytes = &mut bytes[..size as usize];
+                let modify = |bytes: &mut [u8]| {
+                    let result = Self::amo(op, Self::from_sized(bytes), source);
+                    if bytes.len() == 4 { bytes.copy_from_slice(&result.word()) } else { bytes.copy_from_slice(&result.double()) }
+                };
+                // Faults on either half of an AMO are reported as store/AMO faults
+                match mmu.read_modify_write(address.unsigned(), bytes, AccessInfo::store(size), &modify) {
+                    Ok(()) => {
+                        self.set(rd, Self::from_sized(bytes));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+
+            // F Extension
+            #[cfg(feature = "ext-f")]
+            Flw { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 4];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(4, false)) {
+                    Ok(()) => {
+                        self.set_f(rd, FpRegister(u32::from_le_bytes(value)));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
+            },
+            #[cfg(feature = "ext-f")]
+            Fsw { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &(self.f[rs2] as u32).to_le_bytes(), AccessInfo::store(4)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
+            },
+            // FMADD.S, FMSUB.S, FNMSUB.S and FNMADD.S
+            #[cfg(feature = "ext-f")]
+            FmaddS { rd, rs1, rs2, rs3, rm } | FmsubS { rd, rs1, rs2, rs3, rm } | FnmsubS { rd, rs1, rs2, rs3, rm } | FnmaddS { rd, rs1, rs2, rs3, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        // FMSUB.S negates the addend, FNMSUB.S the product and FNMADD.S both
+                        let (negate_product, negate_addend) = match decoded {
+                            FmaddS { .. } => (false, false),
+                            FmsubS { .. } => (false, true),
+                            FnmsubS { .. } => (true, false),
+                            _ => (true, true)
+                        };
+                        let multiplier = self.get_f(rs1).f32();
+                        let addend = self.get_f(rs3).f32();
+                        let multiplier = if negate_product { -multiplier } else { multiplier };
+                        let addend = if negate_addend { -addend } else { addend };
+                        self.set_f_result(rd, float::fused_multiply_add(multiplier, self.get_f(rs2).f32(), addend, rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
+            },
+            // FADD.S, FSUB.S, FMUL.S and FDIV.S
+            #[cfg(feature = "ext-f")]
+            FaddS { rd, rs1, rs2, rm } | FsubS { rd, rs1, rs2, rm } | FmulS { rd, rs1, rs2, rm } | FdivS { rd, rs1, rs2, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        let operation = match decoded {
+                            FaddS { .. } => float::add,
+                            FsubS { .. } => float::sub,
+                            FmulS { .. } => float::mul,
+                            _ => float::div
+                        };
+                        self.set_f_result(rd, operation(self.get_f(rs1).f32(), self.get_f(rs2).f32(), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // ADDIW
-            (0b0011011, 0b000, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source).word()).add_signed(immediate).word()));
-                self.step()
+            #[cfg(feature = "ext-f")]
+            FsqrtS { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        self.set_f_result(rd, float::sqrt(self.get_f(rs1).f32(), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // SLTI
-            (0b0010011, 0b010, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, if self.get(source).lt_signed(immediate) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+            // FSGNJ.S, FSGNJN.S and FSGNJX.S
+            #[cfg(feature = "ext-f")]
+            FsgnjS { rd, rs1, rs2 } | FsgnjnS { rd, rs1, rs2 } | FsgnjxS { rd, rs1, rs2 } => {
+                let operation = match decoded {
+                    FsgnjS { .. } => SignInjection::Copy,
+                    FsgnjnS { .. } => SignInjection::Negate,
+                    _ => SignInjection::Xor
+                };
+                self.set_f(rd, FpRegister(float::inject_sign(self.get_f(rs1).0, self.get_f(rs2).0, operation)));
                 self.step()
             },
-            // SLTIU
-            (0b0010011, 0b011, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, if self.get(source).lt_unsigned(immediate) { R::zero_extended_byte(1) } else { R::zero_extended_byte(0) });
+            // FMIN.S and FMAX.S
+            #[cfg(feature = "ext-f")]
+            FminS { rd, rs1, rs2 } | FmaxS { rd, rs1, rs2 } => {
+                let operation = if matches!(decoded, FminS { .. }) { float::min } else { float::max };
+                self.set_f_result(rd, operation(self.get_f(rs1).f32(), self.get_f(rs2).f32()));
                 self.step()
             },
-
-            // XOR
-            (0b0110011, 0b100, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).xor(self.get(source2)));
-                self.step()
+            // FCVT.W.S, FCVT.WU.S, FCVT.L.S and FCVT.LU.S, of which the double-word conversions are illegal under an effective XLEN of 32
+            #[cfg(feature = "ext-f")]
+            FcvtWS { rd, rs1, rm } | FcvtWuS { rd, rs1, rm } | FcvtLS { rd, rs1, rm } | FcvtLuS { rd, rs1, rm } => {
+                let (signed, long) = match decoded {
+                    FcvtWS { .. } => (true, false),
+                    FcvtWuS { .. } => (false, false),
+                    FcvtLS { .. } => (true, true),
+                    _ => (false, true)
+                };
+                match self.rounding_mode(rm) {
+                    Some(rm) if !long || self.xlen() != RegisterWidth::Bits32 => {
+                        let (value, flags) = float::to_integer(self.get_f(rs1).f32(), signed, if long { 64 } else { 32 }, rm);
+                        // Word results are sign-extended regardless of their signedness
+                        self.set(rd, if long { R::zero_extended_double(value.to_le_bytes()) } else { R::sign_extended_word((value as u32).to_le_bytes()) });
+                        self.fcsr.fflags |= flags;
+                        self.step()
+                    },
+                    _ => trap!(Illegal Instruction; self)
+                }
             },
-            // OR
-            (0b0110011, 0b110, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).or(self.get(source2)));
-                self.step()
+            // FCVT.S.W, FCVT.S.WU, FCVT.S.L and FCVT.S.LU
+            #[cfg(feature = "ext-f")]
+            FcvtSW { rd, rs1, rm } | FcvtSWu { rd, rs1, rm } | FcvtSL { rd, rs1, rm } | FcvtSLu { rd, rs1, rm } => {
+                let long = matches!(decoded, FcvtSL { .. } | FcvtSLu { .. });
+                match self.rounding_mode(rm) {
+                    Some(rm) if !long || self.xlen() != RegisterWidth::Bits32 => {
+                        let value = self.get(rs1);
+                        let (magnitude, negative) = match decoded {
+                            FcvtSW { .. } => {
+                                let value = i32::from_le_bytes(value.word());
+                                (value.unsigned_abs() as u64, value < 0)
+                            },
+                            FcvtSWu { .. } => (u32::from_le_bytes(value.word()) as u64, false),
+                            FcvtSL { .. } => {
+                                let value = i64::from_le_bytes(value.double());
+                                (value.unsigned_abs(), value < 0)
+                            },
+                            _ => (u64::from_le_bytes(value.double()), false)
+                        };
+                        self.set_f_result(rd, float::from_integer(magnitude, negative, rm));
+                        self.step()
+                    },
+                    _ => trap!(Illegal Instruction; self)
+                }
             },
-            // AND
-            (0b0110011, 0b111, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).and(self.get(source2)));
+            #[cfg(feature = "ext-f")]
+            FmvXW { rd, rs1 } => {
+                self.set(rd, R::sign_extended_word((self.f[rs1] as u32).to_le_bytes()));
                 self.step()
             },
-            // XORI
-            (0b0010011, 0b100, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).xor(immediate));
+            #[cfg(feature = "ext-f")]
+            FclassS { rd, rs1 } => {
+                self.set(rd, R::zero_extended_word(float::classify(self.get_f(rs1).f32()).to_le_bytes()));
                 self.step()
             },
-            // ORI
-            (0b0010011, 0b110, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).or(immediate));
+            #[cfg(feature = "ext-f")]
+            FmvWX { rd, rs1 } => {
+                self.set_f(rd, FpRegister(u32::from_le_bytes(self.get(rs1).word())));
                 self.step()
             },
-            // ANDI
-            (0b0010011, 0b111, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).and(immediate));
+            // FEQ.S, FLT.S and FLE.S
+            #[cfg(feature = "ext-f")]
+            FeqS { rd, rs1, rs2 } | FltS { rd, rs1, rs2 } | FleS { rd, rs1, rs2 } => {
+                let operation = match decoded {
+                    FeqS { .. } => float::eq,
+                    FltS { .. } => float::lt,
+                    _ => float::le
+                };
+                let (result, flags) = operation(self.get_f(rs1).f32(), self.get_f(rs2).f32());
+                self.set(rd, R::zero_extended_byte(result as u8));
+                self.fcsr.fflags |= flags;
                 self.step()
             },
 
-            // SLL
-            (0b0110011, 0b001, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).shl(self.get(source2)));
-                self.step()
+            // D Extension
+            #[cfg(feature = "ext-d")]
+            Fld { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 8];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(8, false)) {
+                    Ok(()) => {
+                        self.f[rd] = u64::from_le_bytes(value);
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
             },
-            // SLLW
-            (0b0111011, 0b001, 0b0000000) if R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).shl(Register32(self.get(source2).word())).word()));
-                self.step()
+            #[cfg(feature = "ext-d")]
+            Fsd { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &self.f[rs2].to_le_bytes(), AccessInfo::store(8)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
             },
-            // SRL
-            (0b0110011, 0b101, 0b0000000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).shr(self.get(source2)));
-                self.step()
+            // FMADD.D, FMSUB.D, FNMSUB.D and FNMADD.D
+            #[cfg(feature = "ext-d")]
+            FmaddD { rd, rs1, rs2, rs3, rm } | FmsubD { rd, rs1, rs2, rs3, rm } | FnmsubD { rd, rs1, rs2, rs3, rm } | FnmaddD { rd, rs1, rs2, rs3, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        let (negate_product, negate_addend) = match decoded {
+                            FmaddD { .. } => (false, false),
+                            FmsubD { .. } => (false, true),
+                            FnmsubD { .. } => (true, false),
+                            _ => (true, true)
+                        };
+                        let multiplier = self.get_d(rs1);
+                        let addend = self.get_d(rs3);
+                        let multiplier = if negate_product { -multiplier } else { multiplier };
+                        let addend = if negate_addend { -addend } else { addend };
+                        self.set_d_result(rd, double::fused_multiply_add(multiplier, self.get_d(rs2), addend, rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // SRLW
-            (0b0111011, 0b101, 0b0000000) if R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).shr(Register32(self.get(source2).word())).word()));
-                self.step()
+            // FADD.D, FSUB.D, FMUL.D and FDIV.D
+            #[cfg(feature = "ext-d")]
+            FaddD { rd, rs1, rs2, rm } | FsubD { rd, rs1, rs2, rm } | FmulD { rd, rs1, rs2, rm } | FdivD { rd, rs1, rs2, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        let operation = match decoded {
+                            FaddD { .. } => double::add,
+                            FsubD { .. } => double::sub,
+                            FmulD { .. } => double::mul,
+                            _ => double::div
+                        };
+                        self.set_d_result(rd, operation(self.get_d(rs1), self.get_d(rs2), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // SRA
-            (0b0110011, 0b101, 0b0100000) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).sha(self.get(source2)));
-                self.step()
+            #[cfg(feature = "ext-d")]
+            FsqrtD { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        self.set_d_result(rd, double::sqrt(self.get_d(rs1), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // SRAW
-            (0b0111011, 0b101, 0b0100000) if R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).sha(Register32(self.get(source2).word())).word()));
+            // FSGNJ.D, FSGNJN.D and FSGNJX.D
+            #[cfg(feature = "ext-d")]
+            FsgnjD { rd, rs1, rs2 } | FsgnjnD { rd, rs1, rs2 } | FsgnjxD { rd, rs1, rs2 } => {
+                let operation = match decoded {
+                    FsgnjD { .. } => SignInjection::Copy,
+                    FsgnjnD { .. } => SignInjection::Negate,
+                    _ => SignInjection::Xor
+                };
+                self.f[rd] = double::inject_sign(self.f[rs1], self.f[rs2], operation);
                 self.step()
             },
-            // SLLI
-            (0b0010011, 0b001, _) => {
-                let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).shl(immediate.and(R::zero_extended_byte(0x0E))));
+            // FMIN.D and FMAX.D
+            #[cfg(feature = "ext-d")]
+            FminD { rd, rs1, rs2 } | FmaxD { rd, rs1, rs2 } => {
+                let operation = if matches!(decoded, FminD { .. }) { double::min } else { double::max };
+                self.set_d_result(rd, operation(self.get_d(rs1), self.get_d(rs2)));
                 self.step()
             },
-            // SLLIW
-            (0b0011011, 0b001, _) if R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
-                if immediate.byte() & 0x20 != 0 {
-                    trap!(Illegal Instruction; self)
-                } else {
-                    self.set(destination, R::sign_extended_word(Register32(self.get(source).word()).shl(Register32(immediate.word()).and(Register32::zero_extended_byte(0x0E))).word()));
-                    self.step()
+            #[cfg(feature = "ext-d")]
+            FcvtSD { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        self.set_f_result(rd, double::to_single(self.get_d(rs1), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
                 }
             },
-            // SRLI
-            (0b0010011, 0b101, _) if instruction[3] & 0x40 == 0 => {
-                let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).shr(immediate.and(R::zero_extended_byte(0x0E))));
-                self.step()
+            // FCVT.D.S, which is exact but still encodes a rounding mode
+            #[cfg(feature = "ext-d")]
+            FcvtDS { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(_) => {
+                        self.set_d_result(rd, double::from_single(self.get_f(rs1).f32()));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // SRLIW
-            (0b0011011, 0b101, _) if instruction[3] & 0x40 == 0 && R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
-                if immediate.byte() & 0x20 != 0 {
-                    trap!(Illegal Instruction; self)
-                } else {
-                    self.set(destination, R::sign_extended_word(Register32(self.get(source).word()).shr(Register32(immediate.word()).and(Register32::zero_extended_byte(0x0E))).word()));
-                    self.step()
+            // FCVT.W.D, FCVT.WU.D, FCVT.L.D and FCVT.LU.D
+            #[cfg(feature = "ext-d")]
+            FcvtWD { rd, rs1, rm } | FcvtWuD { rd, rs1, rm } | FcvtLD { rd, rs1, rm } | FcvtLuD { rd, rs1, rm } => {
+                let (signed, long) = match decoded {
+                    FcvtWD { .. } => (true, false),
+                    FcvtWuD { .. } => (false, false),
+                    FcvtLD { .. } => (true, true),
+                    _ => (false, true)
+                };
+                match self.rounding_mode(rm) {
+                    Some(rm) if !long || self.xlen() != RegisterWidth::Bits32 => {
+                        let (value, flags) = double::to_integer(self.get_d(rs1), signed, if long { 64 } else { 32 }, rm);
+                        self.set(rd, if long { R::zero_extended_double(value.to_le_bytes()) } else { R::sign_extended_word((value as u32).to_le_bytes()) });
+                        self.fcsr.fflags |= flags;
+                        self.step()
+                    },
+                    _ => trap!(Illegal Instruction; self)
                 }
             },
-            // SRAI
-            (0b0010011, 0b101, _) if instruction[3] & 0x40 != 0 => {
-                let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, self.get(source).sha(immediate.and(R::zero_extended_byte(0x0E))));
-                self.step()
+            // FCVT.D.W, FCVT.D.WU, FCVT.D.L and FCVT.D.LU
+            #[cfg(feature = "ext-d")]
+            FcvtDW { rd, rs1, rm } | FcvtDWu { rd, rs1, rm } | FcvtDL { rd, rs1, rm } | FcvtDLu { rd, rs1, rm } => {
+                let long = matches!(decoded, FcvtDL { .. } | FcvtDLu { .. });
+                match self.rounding_mode(rm) {
+                    Some(rm) if !long || self.xlen() != RegisterWidth::Bits32 => {
+                        let value = self.get(rs1);
+                        let (magnitude, negative) = match decoded {
+                            FcvtDW { .. } => {
+                                let value = i32::from_le_bytes(value.word());
+                                (value.unsigned_abs() as u64, value < 0)
+                            },
+                            FcvtDWu { .. } => (u32::from_le_bytes(value.word()) as u64, false),
+                            FcvtDL { .. } => {
+                                let value = i64::from_le_bytes(value.double());
+                                (value.unsigned_abs(), value < 0)
+                            },
+                            _ => (u64::from_le_bytes(value.double()), false)
+                        };
+                        self.set_d_result(rd, double::from_integer(magnitude, negative, rm));
+                        self.step()
+                    },
+                    _ => trap!(Illegal Instruction; self)
+                }
             },
-            // SRAIW
-            (0b0011011, 0b101, _) if instruction[3] & 0x40 != 0 && R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::I::<R> { destination, source, immediate } = Variant::decode(instruction);
-                if immediate.byte() & 0x20 != 0 {
+            // FMV.X.D, only present in RV64
+            #[cfg(feature = "ext-d")]
+            FmvXD { rd, rs1 } => {
+                if self.xlen() == RegisterWidth::Bits32 {
                     trap!(Illegal Instruction; self)
-                } else {
-                    self.set(destination, R::sign_extended_word(Register32(self.get(source).word()).sha(Register32(immediate.word()).and(Register32::zero_extended_byte(0x0E))).word()));
-                    self.step()
                 }
-            },
-
-            // LUI
-            (0b0110111, _, _) => {
-                let variant::U { destination, immediate } = Variant::decode(instruction);
-                self.set(destination, immediate);
+                self.set(rd, R::zero_extended_double(self.f[rs1].to_le_bytes()));
                 self.step()
             },
-            // AUIPC
-            (0b0010111, _, _) => {
-                let variant::U { destination, immediate } = Variant::decode(instruction);
-                self.set(destination, self.pc.add_signed(immediate));
+            #[cfg(feature = "ext-d")]
+            FclassD { rd, rs1 } => {
+                self.set(rd, R::zero_extended_word(double::classify(self.get_d(rs1)).to_le_bytes()));
                 self.step()
             },
-
-            // LB
-            (0b0000011, 0b000, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_byte(mmu.get(self.get(source).add_signed(immediate).unsigned())));
+            // FMV.D.X, only present in RV64
+            #[cfg(feature = "ext-d")]
+            FmvDX { rd, rs1 } => {
+                if self.xlen() == RegisterWidth::Bits32 {
+                    trap!(Illegal Instruction; self)
+                }
+                self.f[rd] = u64::from_le_bytes(self.get(rs1).double());
                 self.step()
             },
-            // LBU
-            (0b0000011, 0b100, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                self.set(destination, R::zero_extended_byte(mmu.get(self.get(source).add_signed(immediate).unsigned())));
+            // FEQ.D, FLT.D and FLE.D
+            #[cfg(feature = "ext-d")]
+            FeqD { rd, rs1, rs2 } | FltD { rd, rs1, rs2 } | FleD { rd, rs1, rs2 } => {
+                let operation = match decoded {
+                    FeqD { .. } => double::eq,
+                    FltD { .. } => double::lt,
+                    _ => double::le
+                };
+                let (result, flags) = operation(self.get_d(rs1), self.get_d(rs2));
+                self.set(rd, R::zero_extended_byte(result as u8));
+                self.fcsr.fflags |= flags;
                 self.step()
             },
-            // LH
-            (0b0000011, 0b001, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                let address = self.get(source).add_signed(immediate);
-                self.set(destination, R::sign_extended_half([mmu.get(address.unsigned()), mmu.get(address.append(1))]));
-                self.step()
+
+            // Zfh Extension
+            #[cfg(feature = "ext-zfh")]
+            Flh { rd, rs1, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.access(mmu, address.unsigned());
+                let mut value = [0; 2];
+                match Self::load(mmu, address, &mut value, AccessInfo::load(2, false)) {
+                    Ok(()) => {
+                        self.set_h(rd, u16::from_le_bytes(value));
+                        self.step()
+                    },
+                    Err(fault) => trap!(Load Fault; self, fault, address)
+                }
             },
-            // LHU
-            (0b0000011, 0b101, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                let address = self.get(source).add_signed(immediate);
-                self.set(destination, R::zero_extended_half([mmu.get(address.unsigned()), mmu.get(address.append(1))]));
-                self.step()
+            #[cfg(feature = "ext-zfh")]
+            Fsh { rs1, rs2, imm } => {
+                let address = self.get(rs1).add_signed(imm);
+                self.store_access(mmu, address.unsigned());
+                match Self::store(mmu, address, &(self.f[rs2] as u16).to_le_bytes(), AccessInfo::store(2)) {
+                    Ok(()) => self.step(),
+                    Err(fault) => trap!(Store Fault; self, fault, address)
+                }
             },
-            // LW
-            (0b0000011, 0b010, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                let address = self.get(source).add_signed(immediate);
-                self.set(destination, R::sign_extended_word([
-                    mmu.get(address.unsigned()),
-                    mmu.get(address.append(1)),
-                    mmu.get(address.append(2)),
-                    mmu.get(address.append(3))
-                ]));
-                self.step()
+            // FMADD.H, FMSUB.H, FNMSUB.H and FNMADD.H
+            #[cfg(feature = "ext-zfh")]
+            FmaddH { rd, rs1, rs2, rs3, rm } | FmsubH { rd, rs1, rs2, rs3, rm } | FnmsubH { rd, rs1, rs2, rs3, rm } | FnmaddH { rd, rs1, rs2, rs3, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        const SIGN: u16 = 1 << 15;
+                        let (negate_product, negate_addend) = match decoded {
+                            FmaddH { .. } => (false, false),
+                            FmsubH { .. } => (false, true),
+                            FnmsubH { .. } => (true, false),
+                            _ => (true, true)
+                        };
+                        let multiplier = self.get_h(rs1) ^ if negate_product { SIGN } else { 0 };
+                        let addend = self.get_h(rs3) ^ if negate_addend { SIGN } else { 0 };
+                        self.set_h_result(rd, half::fused_multiply_add(multiplier, self.get_h(rs2), addend, rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // LWU
-            (0b0000011, 0b110, _) if R::WIDTH != RegisterWidth::Bits32 => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                let address = self.get(source).add_signed(immediate);
-                self.set(destination, R::zero_extended_word([
-                    mmu.get(address.unsigned()),
-                    mmu.get(address.append(1)),
-                    mmu.get(address.append(2)),
-                    mmu.get(address.append(3))
-                ]));
-                self.step()
+            // FADD.H, FSUB.H, FMUL.H and FDIV.H
+            #[cfg(feature = "ext-zfh")]
+            FaddH { rd, rs1, rs2, rm } | FsubH { rd, rs1, rs2, rm } | FmulH { rd, rs1, rs2, rm } | FdivH { rd, rs1, rs2, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        let operation = match decoded {
+                            FaddH { .. } => half::add,
+                            FsubH { .. } => half::sub,
+                            FmulH { .. } => half::mul,
+                            _ => half::div
+                        };
+                        self.set_h_result(rd, operation(self.get_h(rs1), self.get_h(rs2), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // LD
-            (0b0000011, 0b011, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                let address = self.get(source).add_signed(immediate);
-                self.set(destination, R::sign_extended_double([
-                    mmu.get(address.unsigned()),
-                    mmu.get(address.append(1)),
-                    mmu.get(address.append(2)),
-                    mmu.get(address.append(3)),
-                    mmu.get(address.append(4)),
-                    mmu.get(address.append(5)),
-                    mmu.get(address.append(6)),
-                    mmu.get(address.append(7))
-                ]));
-                self.step()
+            #[cfg(feature = "ext-zfh")]
+            FsqrtH { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        self.set_h_result(rd, half::sqrt(self.get_h(rs1), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-
-            // SB
-            (0b0100011, 0b000, _) => {
-                let variant::S { source1, source2, immediate } = Variant::decode(instruction);
-                let address = self.get(source1).add_signed(immediate);
-                mmu.set(address.unsigned(), self.get(source2).byte());
+            // FSGNJ.H, FSGNJN.H and FSGNJX.H
+            #[cfg(feature = "ext-zfh")]
+            FsgnjH { rd, rs1, rs2 } | FsgnjnH { rd, rs1, rs2 } | FsgnjxH { rd, rs1, rs2 } => {
+                let operation = match decoded {
+                    FsgnjH { .. } => SignInjection::Copy,
+                    FsgnjnH { .. } => SignInjection::Negate,
+                    _ => SignInjection::Xor
+                };
+                self.set_h(rd, half::inject_sign(self.get_h(rs1), self.get_h(rs2), operation));
                 self.step()
             },
-            // SH
-            (0b0100011, 0b001, _) => {
-                let variant::S { source1, source2, immediate } = Variant::decode(instruction);
-                let address = self.get(source1).add_signed(immediate);
-                let half = self.get(source2).half();
-                mmu.set(address.unsigned(), half[0]);
-                mmu.set(address.append(1), half[1]);
+            // FMIN.H and FMAX.H
+            #[cfg(feature = "ext-zfh")]
+            FminH { rd, rs1, rs2 } | FmaxH { rd, rs1, rs2 } => {
+                let operation = if matches!(decoded, FminH { .. }) { half::min } else { half::max };
+                self.set_h_result(rd, operation(self.get_h(rs1), self.get_h(rs2)));
                 self.step()
             },
-            // SW
-            (0b0100011, 0b010, _) => {
-                let variant::S { source1, source2, immediate } = Variant::decode(instruction);
-                let address = self.get(source1).add_signed(immediate);
-                let word = self.get(source2).word();
-                mmu.set(address.unsigned(), word[0]);
-                mmu.set(address.append(1), word[1]);
-                mmu.set(address.append(2), word[2]);
-                mmu.set(address.append(3), word[3]);
-                self.step()
-            },
-
-            // JAL
-            (0b1101111, _, _) => {
-                let variant::J { destination, immediate } = Variant::decode(instruction);
-                self.set(destination, self.pc.add_unsigned(R::zero_extended_byte(4)));
-                self.pc = self.pc.add_signed(immediate)
-            },
-            // JALR
-            (0b1100111, 0b000, _) => {
-                let variant::I { destination, source, immediate } = Variant::decode(instruction);
-                let to_set = self.get(source).add_signed(immediate);
-                self.set(destination, self.pc.add_unsigned(R::zero_extended_byte(4)));
-                self.pc = to_set
-            },
-
-            // BEQ
-            (0b1100011, 0b000, _) => {
-                let variant::B { source1, source2, immediate } = Variant::decode(instruction);
-                if self.get(source1).eq(self.get(source2)) {
-                    self.pc = self.pc.add_signed(immediate)
-                } else {
-                    self.step()
+            // FCVT.S.H, which is exact but still encodes a rounding mode
+            #[cfg(feature = "ext-zfh")]
+            FcvtSH { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(_) => {
+                        self.set_f_result(rd, half::to_single(self.get_h(rs1)));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
                 }
             },
-            // BNE
-            (0b1100011, 0b001, _) => {
-                let variant::B { source1, source2, immediate } = Variant::decode(instruction);
-                if self.get(source1).neq(self.get(source2)) {
-                    self.pc = self.pc.add_signed(immediate)
-                } else {
-                    self.step()
+            #[cfg(feature = "ext-zfh")]
+            FcvtHS { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        self.set_h_result(rd, half::from_single(self.get_f(rs1).f32(), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
                 }
             },
-            // BLT
-            (0b1100011, 0b100, _) => {
-                let variant::B { source1, source2, immediate } = Variant::decode(instruction);
-                if self.get(source1).lt_signed(self.get(source2)) {
-                    self.pc = self.pc.add_signed(immediate)
-                } else {
-                    self.step()
+            // FCVT.D.H, which is exact but still encodes a rounding mode
+            #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+            FcvtDH { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(_) => {
+                        self.set_d_result(rd, half::to_double(self.get_h(rs1)));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
                 }
             },
-            // BLTU
-            (0b1100011, 0b110, _) => {
-                let variant::B { source1, source2, immediate } = Variant::decode(instruction);
-                if self.get(source1).lt_unsigned(self.get(source2)) {
-                    self.pc = self.pc.add_signed(immediate)
-                } else {
-                    self.step()
+            #[cfg(all(feature = "ext-zfh", feature = "ext-d"))]
+            FcvtHD { rd, rs1, rm } => {
+                match self.rounding_mode(rm) {
+                    Some(rm) => {
+                        self.set_h_result(rd, half::from_double(self.get_d(rs1), rm));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
                 }
             },
-            // BGE
-            (0b1100011, 0b101, _) => {
-                let variant::B { source1, source2, immediate } = Variant::decode(instruction);
-                if self.get(source1).gte_signed(self.get(source2)) {
-                    self.pc = self.pc.add_signed(immediate)
-                } else {
-                    self.step()
+            // FCVT.W.H, FCVT.WU.H, FCVT.L.H and FCVT.LU.H
+            #[cfg(feature = "ext-zfh")]
+            FcvtWH { rd, rs1, rm } | FcvtWuH { rd, rs1, rm } | FcvtLH { rd, rs1, rm } | FcvtLuH { rd, rs1, rm } => {
+                let (signed, long) = match decoded {
+                    FcvtWH { .. } => (true, false),
+                    FcvtWuH { .. } => (false, false),
+                    FcvtLH { .. } => (true, true),
+                    _ => (false, true)
+                };
+                match self.rounding_mode(rm) {
+                    Some(rm) if !long || self.xlen() != RegisterWidth::Bits32 => {
+                        let (value, flags) = half::to_integer(self.get_h(rs1), signed, if long { 64 } else { 32 }, rm);
+                        self.set(rd, if long { R::zero_extended_double(value.to_le_bytes()) } else { R::sign_extended_word((value as u32).to_le_bytes()) });
+                        self.fcsr.fflags |= flags;
+                        self.step()
+                    },
+                    _ => trap!(Illegal Instruction; self)
                 }
             },
-            // BGEU
-            (0b1100011, 0b111, _) => {
-                let variant::B { source1, source2, immediate } = Variant::decode(instruction);
-                if self.get(source1).gte_unsigned(self.get(source2)) {
-                    self.pc = self.pc.add_signed(immediate)
-                } else {
-                    self.step()
+            // FCVT.H.W, FCVT.H.WU, FCVT.H.L and FCVT.H.LU
+            #[cfg(feature = "ext-zfh")]
+            FcvtHW { rd, rs1, rm } | FcvtHWu { rd, rs1, rm } | FcvtHL { rd, rs1, rm } | FcvtHLu { rd, rs1, rm } => {
+                let long = matches!(decoded, FcvtHL { .. } | FcvtHLu { .. });
+                match self.rounding_mode(rm) {
+                    Some(rm) if !long || self.xlen() != RegisterWidth::Bits32 => {
+                        let value = self.get(rs1);
+                        let (magnitude, negative) = match decoded {
+                            FcvtHW { .. } => {
+                                let value = i32::from_le_bytes(value.word());
+                                (value.unsigned_abs() as u64, value < 0)
+                            },
+                            FcvtHWu { .. } => (u32::from_le_bytes(value.word()) as u64, false),
+                            FcvtHL { .. } => {
+                                let value = i64::from_le_bytes(value.double());
+                                (value.unsigned_abs(), value < 0)
+                            },
+                            _ => (u64::from_le_bytes(value.double()), false)
+                        };
+                        self.set_h_result(rd, half::from_integer(magnitude, negative, rm));
+                        self.step()
+                    },
+                    _ => trap!(Illegal Instruction; self)
                 }
             },
-
-            // ECALL
-            (0b1110011, 0b000, _) if instruction[2] & 0x10 == 0 => {
-                trap!(System Call)
-            },
-            // EBREAK
-            (0b1110011, 0b000, _) if instruction[2] & 0x10 != 0 => {
-                trap!(Breakpoint; self)
-            },
-
-            // M Extension
-            // MUL
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b000, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).mul(self.get(source2)));
+            #[cfg(feature = "ext-zfh")]
+            FmvXH { rd, rs1 } => {
+                self.set(rd, R::sign_extended_half((self.f[rs1] as u16).to_le_bytes()));
                 self.step()
             },
-            // MULH
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b001, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).mulh(self.get(source2)));
+            #[cfg(feature = "ext-zfh")]
+            FclassH { rd, rs1 } => {
+                self.set(rd, R::zero_extended_word(half::classify(self.get_h(rs1)).to_le_bytes()));
                 self.step()
             },
-            // MULHSU
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b010, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).mulhsu(self.get(source2)));
+            #[cfg(feature = "ext-zfh")]
+            FmvHX { rd, rs1 } => {
+                self.set_h(rd, u16::from_le_bytes(self.get(rs1).half()));
                 self.step()
             },
-            // MULHU
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b011, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-               self.set(destination, self.get(source1).mulhu(self.get(source2)));
-               self.step()
-            },
-            // MULW
-            #[cfg(feature = "ext-m")]
-            (0b0111011, 0b000, 0b0000001) if R::WIDTH == RegisterWidth::Bits64 => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).mul(Register32(self.get(source2).word())).word()));
+            // FEQ.H, FLT.H and FLE.H
+            #[cfg(feature = "ext-zfh")]
+            FeqH { rd, rs1, rs2 } | FltH { rd, rs1, rs2 } | FleH { rd, rs1, rs2 } => {
+                let operation = match decoded {
+                    FeqH { .. } => half::eq,
+                    FltH { .. } => half::lt,
+                    _ => half::le
+                };
+                let (result, flags) = operation(self.get_h(rs1), self.get_h(rs2));
+                self.set(rd, R::zero_extended_byte(result as u8));
+                self.fcsr.fflags |= flags;
                 self.step()
             },
-            // DIV
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b100, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).div(self.get(source2)));
+
+            // Vector Extension
+            // A fault leaves vstart at the element that faulted so the instruction resumes from it once the trap is handled
+            #[cfg(feature = "ext-v")]
+            VectorLoad { access, vd: register, rs1, rs2, vm } | VectorStore { access, vs3: register, rs1, rs2, vm } => {
+                let stride = if access.addressing == VectorAddressing::Strided { Self::sign_extended_value(self.get(rs2)) } else { 0 };
+                let elements = match self.vector.accesses(access, register, rs2, !vm, stride) {
+                    Some(elements) => elements,
+                    None => trap!(Illegal Instruction; self)
+                };
+                let base = self.get(rs1);
+                for element in elements {
+                    let address = base.add_unsigned(Self::from_value(element.offset));
+                    let bytes = element.width as usize / 8;
+                    if let VectorStore { .. } = decoded {
+                        self.store_access(mmu, address.unsigned());
+                        let value = self.vector.element(element.register, element.index, element.width).to_le_bytes();
+                        if let Err(fault) = Self::store(mmu, address, &value[..bytes], AccessInfo::store(bytes as u8)) {
+                            self.vector.vstart = element.index as u64;
+                            trap!(Store Fault; self, fault, address)
+                        }
+                    } else {
+                        self.access(mmu, address.unsigned());
+                        let mut value = [0; 8];
+                        match Self::load(mmu, address, &mut value[..bytes], AccessInfo::load(bytes as u8, false)) {
+                            Ok(()) => self.vector.set_element(element.register, element.index, element.width, u64::from_le_bytes(value)),
+                            // Truncate vl to the elements loaded before the first fault beyond element 0
+                            Err(_) if access.addressing == VectorAddressing::FaultOnlyFirst && element.index > 0 => {
+                                self.vector.vl = element.index as u64;
+                                break
+                            },
+                            Err(fault) => {
+                                self.vector.vstart = element.index as u64;
+                                trap!(Load Fault; self, fault, address)
+                            }
+                        }
+                    }
+                }
+                self.vector.vstart = 0;
                 self.step()
             },
-            // DIVU
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b101, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).divu(self.get(source2)));
+            // VSETVLI, VSETIVLI and VSETVL, where the AVL is x[rs1], all elements if only rs1 is x0, or the current vl if both rd and rs1 are x0
+            #[cfg(feature = "ext-v")]
+            Vsetvli { rd, rs1, vtype } => {
+                let vl = self.vector.configure(self.vector_avl(rd, rs1), vtype as u64);
+                self.set(rd, Self::from_value(vl));
                 self.step()
             },
-            // DIVW
-            #[cfg(feature = "ext-m")]
-            (0b0111011, 0b100, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).div(Register32(self.get(source2).word())).word()));
+            #[cfg(feature = "ext-v")]
+            Vsetivli { rd, avl, vtype } => {
+                let vl = self.vector.configure(Some(avl as u64), vtype as u64);
+                self.set(rd, Self::from_value(vl));
                 self.step()
             },
-            // DIVUW
-            #[cfg(feature = "ext-m")]
-            (0b0111011, 0b101, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).divu(Register32(self.get(source2).word())).word()));
+            #[cfg(feature = "ext-v")]
+            Vsetvl { rd, rs1, rs2 } => {
+                let vl = self.vector.configure(self.vector_avl(rd, rs1), Self::address_value(self.get(rs2)));
+                self.set(rd, Self::from_value(vl));
                 self.step()
             },
-            // REM
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b110, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).rem(self.get(source2)));
-                self.step()
+            #[cfg(feature = "ext-v")]
+            VmvXS { rd, vs2 } => {
+                match self.vector.move_to_scalar(vs2) {
+                    Some(result) => {
+                        self.set(rd, Self::from_value(result));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // REMU
-            #[cfg(feature = "ext-m")]
-            (0b0110011, 0b111, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, self.get(source1).remu(self.get(source2)));
-                self.step()
+            #[cfg(feature = "ext-v")]
+            Vcpop { rd, vs2, vm } => {
+                match self.vector.count(vs2, !vm) {
+                    Some(result) => {
+                        self.set(rd, Self::from_value(result));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // REMW
-            #[cfg(feature = "ext-m")]
-            (0b0111011, 0b110, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).rem(Register32(self.get(source2).word())).word()));
-                self.step()
+            #[cfg(feature = "ext-v")]
+            Vfirst { rd, vs2, vm } => {
+                match self.vector.first(vs2, !vm) {
+                    Some(result) => {
+                        self.set(rd, Self::from_value(result));
+                        self.step()
+                    },
+                    None => trap!(Illegal Instruction; self)
+                }
             },
-            // REMUW
-            #[cfg(feature = "ext-m")]
-            (0b0111011, 0b111, 0b0000001) => {
-                let variant::R { destination, source1, source2 } = Variant::decode(instruction);
-                self.set(destination, R::sign_extended_word(Register32(self.get(source1).word()).remu(Register32(self.get(source2).word())).word()));
-                self.step()
+            #[cfg(feature = "ext-v")]
+            VmvSX { vd, rs1 } => {
+                if self.vector.move_from_scalar(vd, Self::sign_extended_value(self.get(rs1))) {
+                    self.step()
+                } else {
+                    trap!(Illegal Instruction; self)
+                }
+            },
+            #[cfg(feature = "ext-v")]
+            Vid { vd, vm } => {
+                if self.vector.index(vd, !vm) {
+                    self.step()
+                } else {
+                    trap!(Illegal Instruction; self)
+                }
+            },
+            // Integer arithmetic, comparisons, reductions and mask logical instructions, where vm clear masks the instruction by v0
+            #[cfg(feature = "ext-v")]
+            Vector { op, vd, vs2, operand, vm } => {
+                let operand = operand.map(|source| Self::sign_extended_value(self.get(source)));
+                if self.vector.execute(op, vd, vs2, operand, !vm) {
+                    self.step()
+                } else {
+                    trap!(Illegal Instruction; self)
+                }
             },
 
             // Zicsr Extension
-            // CSRRW
             #[cfg(feature = "ext-csr")]
-            (0b1110011, 0b001, _) => {
-                let variant::C { destination, source, csr } = Variant::decode(instruction);
-                if destination != 0 {
-                    let temporary = self.get_csr(csr).expect("TODO: Exception signaling");
-                    self.set_csr(csr, self.get(source));
-                    self.set(destination, temporary)
+            Csrrw { rd, rs1, csr } => {
+                if self.csr_instruction(csr, rd, rd != 0, Some((self.get(rs1), |_, value| value))) {
+                    self.step()
                 } else {
-                    self.set_csr(csr, self.get(source))
+                    trap!(Illegal Instruction; self)
                 }
-                self.step()
             },
-            // CSRRS
             #[cfg(feature = "ext-csr")]
-            (0b1110011, 0b010, _) => {
-                let variant::C { destination, source, csr } = Variant::decode(instruction);
-                let temporary = self.get_csr(csr).expect("TODO: Exception signaling");
-                if source != 0 {
-                    // Source is a bitmask which sets bits in the csr
-                    self.set_csr(csr, temporary.or(self.get(source)));
-                    self.set(destination, temporary)
+            Csrrs { rd, rs1, csr } => {
+                if self.csr_instruction(csr, rd, true, if rs1 != 0 { Some((self.get(rs1), R::or)) } else { None }) {
+                    self.step()
                 } else {
-                    self.set(destination, temporary)
+                    trap!(Illegal Instruction; self)
                 }
-                self.step()
             },
-            // CSRRC
             #[cfg(feature = "ext-csr")]
-            (0b1110011, 0b011, _) => {
-                let variant::C { destination, source, csr } = Variant::decode(instruction);
-                let temporary = self.get_csr(csr).expect("TODO: Exception signaling");
-                if source != 0 {
-                    // Source is a bitmask which clears bits in the csr
-                    self.set_csr(csr, temporary.and(self.get(source).not()));
-                    self.set(destination, temporary)
+            Csrrc { rd, rs1, csr } => {
+                if self.csr_instruction(csr, rd, true, if rs1 != 0 { Some((self.get(rs1), |current: R, mask: R| current.and(mask.not()))) } else { None }) {
+                    self.step()
                 } else {
-                    self.set(destination, temporary)
+                    trap!(Illegal Instruction; self)
                 }
-                self.step()
             },
-            // CSRRWI
             #[cfg(feature = "ext-csr")]
-            (0b1110011, 0b101, _) => {
-                let variant::C { destination, source, csr } = Variant::decode(instruction);
-                let immediate = R::zero_extended_byte(source as u8);
-                if destination != 0 {
-                    let temporary = self.get_csr(csr).expect("TODO: Exception signaling");
-                    self.set_csr(csr, immediate);
-                    self.set(destination, temporary)
+            Csrrwi { rd, uimm, csr } => {
+                if self.csr_instruction(csr, rd, rd != 0, Some((R::zero_extended_byte(uimm), |_, value| value))) {
+                    self.step()
                 } else {
-                    self.set_csr(csr, immediate)
+                    trap!(Illegal Instruction; self)
                 }
-                self.step()
             },
-            // CSRRSI
             #[cfg(feature = "ext-csr")]
-            (0b1110011, 0b110, _) => {
-                let variant::C { destination, source, csr } = Variant::decode(instruction);
-                let temporary = self.get_csr(csr).expect("TODO: Exception signaling");
-                if source != 0 {
-                    // Source is a bitmask which sets bits in the csr
-                    self.set_csr(csr, temporary.or(R::zero_extended_byte(source as u8)));
-                    self.set(destination, temporary)
+            Csrrsi { rd, uimm, csr } => {
+                if self.csr_instruction(csr, rd, true, if uimm != 0 { Some((R::zero_extended_byte(uimm), R::or)) } else { None }) {
+                    self.step()
                 } else {
-                    self.set(destination, temporary)
+                    trap!(Illegal Instruction; self)
                 }
-                self.step()
             },
-            // CSRRCI
             #[cfg(feature = "ext-csr")]
-            (0b1110011, 0b111, _) => {
-                let variant::C { destination, source, csr } = Variant::decode(instruction);
-                let temporary = self.get_csr(csr).expect("TODO: Exception signaling");
-                if source != 0 {
-                    // Source is a bitmask which clears bits in the csr
-                    self.set_csr(csr, temporary.and(R::zero_extended_byte(source as u8).not()));
-                    self.set(destination, temporary)
+            Csrrci { rd, uimm, csr } => {
+                if self.csr_instruction(csr, rd, true, if uimm != 0 { Some((R::zero_extended_byte(uimm), |current: R, mask: R| current.and(mask.not()))) } else { None }) {
+                    self.step()
                 } else {
-                    self.set(destination, temporary)
+                    trap!(Illegal Instruction; self)
                 }
-                self.step()
-            },
-            _ => trap!(Illegal Instruction; self)
+            }
+        }
+        None
+    }
+
+    /// Repeatedly execute instructions until execution must stop.
+    /// Traps handled by the program, as with the `CsrHandled` strategy, do not stop execution.
+    /// After `RunResult::WatchdogExpired` the limit stays expired, so set the watchdog again before calling `run` to resume.
+    pub fn run(&mut self, mmu: &mut dyn Mmu<R>) -> RunResult<R> {
+        loop {
+            if let Some(limit) = self.watchdog {
+                if self.executed >= limit {
+                    return RunResult::WatchdogExpired
+                }
+            }
+            #[cfg(feature = "alloc")]
+            let pc = self.pc;
+            #[cfg(feature = "register-hook")]
+            {
+                self.register_break = false
+            }
+            self.stack_overflow = None;
+            if let Some(trap) = self.execute(mmu) {
+                return RunResult::Trap(trap)
+            }
+            self.executed += 1;
+
+            if let Some(address) = self.stack_overflow {
+                return RunResult::StackOverflow(address)
+            }
+
+            #[cfg(feature = "register-hook")]
+            {
+                if self.register_break {
+                    return RunResult::RegisterBreakpoint(pc)
+                }
+            }
+
+            #[cfg(feature = "alloc")]
+            if let Some(invariant) = &self.invariant {
+                if !invariant(self) {
+                    return RunResult::InvariantViolated(pc)
+                }
+            }
+        }
+    }
+}
+
+/// The number of instructions `run_program` executes before giving up on a program that never traps
+#[cfg(feature = "test-util")]
+pub const RUN_PROGRAM_WATCHDOG: u64 = 1_000_000;
+
+/// Run a program on a new core with temporary memory, returning the core once the program traps for inspection.
+/// The program is loaded into 64KiB of memory at `entry` and traps are returned to the execution environment by `HostReturn`,
+/// so a program stops at an ECALL, an EBREAK or the first illegal instruction, such as the zeroed memory that follows it.
+/// A program that never traps, such as an infinite loop, is stopped after `RUN_PROGRAM_WATCHDOG` instructions.
+/// ```rust
+/// use rysk_core::{ run_program, Register32 };
+/// // addi x1, x0, 5; addi x2, x1, 2
+/// let core = run_program::<Register32>(0x1000, &[0x0050_0093, 0x0020_8113]);
+/// assert_eq!(core.get(2), 7u32.into());
+/// ```
+#[cfg(feature = "test-util")]
+pub fn run_program<R: Register>(entry: R::Unsigned, program: &[u32]) -> Core<R> {
+    let mut memory = ArrayRam::<0x10000>::new(R::from_unsigned(entry).usize());
+    for (index, instruction) in program.iter().enumerate() {
+        memory.load(memory.base + index * 4, &instruction.to_le_bytes())
+    }
+    #[cfg(feature = "ext-csr")]
+    let core = Core::new(entry, R::Unsigned::default());
+    #[cfg(not(feature = "ext-csr"))]
+    let core = Core::new(entry);
+    let mut core = core.with_trap_strategy(HostReturn);
+    core.set_watchdog(RUN_PROGRAM_WATCHDOG);
+    core.run(&mut memory);
+    core
+}
+
+/// The disposition of an instruction passed to `Core::execute`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepOutcome {
+    /// The instruction executed normally
+    Retired,
+    /// A WRS instruction of Zawrs executed while the core held a reservation, after `Mmu::wait_on_reservation` returned,
+    /// or a PAUSE executed after the pause hook returned.
+    /// A host scheduling several cores on one thread may run the others until the reservation is invalidated
+    Waiting,
+    /// The instruction raised a trap.
+    /// Depending on the core's trap strategy the trap was handled by jumping to the trap vector or returned to the execution environment
+    Trapped
+}
+
+/// A standard combination of a base ISA and extensions, enabled together by the cargo feature of the same name
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Profile {
+    /// RV32I with the M, A and C extensions
+    Rv32imac,
+    /// RV32I with the M, A, F, D, C and Zicsr extensions
+    Rv32gc,
+    /// RV64I with the M, A, F, D, C and Zicsr extensions
+    Rv64gc
+}
+impl Profile {
+    /// The register width of the base ISA
+    pub fn width(self) -> RegisterWidth {
+        match self {
+            Self::Rv32imac | Self::Rv32gc => RegisterWidth::Bits32,
+            Self::Rv64gc => RegisterWidth::Bits64
+        }
+    }
+
+    /// Whether every extension the profile requires is enabled in this build
+    pub fn is_enabled(self) -> bool {
+        let imac = cfg!(feature = "ext-m") && cfg!(feature = "ext-a") && cfg!(feature = "ext-c");
+        match self {
+            Self::Rv32imac => imac,
+            Self::Rv32gc | Self::Rv64gc => imac && cfg!(feature = "ext-d") && cfg!(feature = "ext-csr")
         }
+    }
+}
+
+/// The reason `Core::run` stopped executing
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunResult<R: Register> {
+    /// A trap must be handled by the execution environment
+    Trap(Trap),
+    /// The watchdog limit set by `Core::set_watchdog` was reached
+    WatchdogExpired,
+    /// The invariant set by `Core::set_invariant` did not hold after executing the instruction at the given address
+    #[cfg(feature = "alloc")]
+    InvariantViolated(R),
+    /// An instruction accessed the stack at the given address, below the limit set by `Core::set_stack_limit`.
+    /// Only accesses at or above the stack pointer are to the stack, so other accesses below the limit never raise it
+    StackOverflow(R),
+    /// The hook set by `Core::set_register_hook` asked to stop after the instruction at the given address wrote a register
+    #[cfg(feature = "register-hook")]
+    RegisterBreakpoint(R)
+}
+
+/// The differences between the states of two cores, as produced by `Core::diff`.
+/// Each difference is listed with the value from the left (`self`) core first.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+pub struct StateDiff<R: Register> {
+    /// Differing general-purpose registers as `(index, left, right)`
+    pub registers: Vec<(usize, R, R)>,
+    /// The differing program counters, if they differ
+    pub pc: Option<(R, R)>,
+    /// Differing CSRs as `(address, left, right)`
+    #[cfg(feature = "ext-csr")]
+    pub csrs: Vec<(usize, R, R)>
+}
+#[cfg(feature = "alloc")]
+impl<R: Register> StateDiff<R> {
+    /// True if the compared cores were in identical states
+    pub fn is_empty(&self) -> bool {
+        #[cfg(feature = "ext-csr")]
+        { self.registers.is_empty() && self.pc.is_none() && self.csrs.is_empty() }
         #[cfg(not(feature = "ext-csr"))]
-        trap!(Illegal Instruction; self)
+        { self.registers.is_empty() && self.pc.is_none() }
+    }
+}
+
+/// Lists the program counter and register file, four registers to a line, in hexadecimal
+impl<R: Register, const REGS: usize> core::fmt::Debug for Core<R, REGS> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let hex = |f: &mut core::fmt::Formatter<'_>, register: R| match R::WIDTH {
+            RegisterWidth::Bits32 => write!(f, "{:08X}", u32::from_le_bytes(register.word())),
+            RegisterWidth::Bits64 => write!(f, "{:016X}", u64::from_le_bytes(register.double())),
+            RegisterWidth::Bits128 => write!(f, "{:032X}", u128::from_le_bytes(register.quad()))
+        };
+        write!(f, "pc   ")?;
+        hex(f, self.pc)?;
+        writeln!(f)?;
+        for (index, name, register) in self.abi_registers() {
+            write!(f, "{:<4} ", name)?;
+            hex(f, register)?;
+            if index % 4 == 3 {
+                writeln!(f)?
+            } else {
+                write!(f, "  ")?
+            }
+        }
+        Ok(())
     }
 }
 
@@ -791,35 +3714,447 @@ pub trait Mmu<R: Register> {
     fn get(&self, address: R::Unsigned) -> u8;
     /// Set the byte at the given address
     fn set(&mut self, address: R::Unsigned, value: u8);
-    /// Fetch an instruction to execute
-    fn fetch(&self, address: R) -> [u8; 4] {
-        [
-            self.get(address.unsigned()),
-            self.get(address.append(1)),
-            self.get(address.append(2)),
-            self.get(address.append(3))
-        ]
+    /// Tests if the address is backed by memory or a device, without side-effects.
+    /// Lets debuggers distinguish unmapped addresses from memory that happens to read as zero. Defaults to all addresses being mapped.
+    fn is_mapped(&self, _address: R::Unsigned) -> bool {
+        true
+    }
+    /// The number of additional cycles an access to the given address takes, such as for slow memory-mapped devices.
+    /// Counted towards `mcycle` for each load or store. Defaults to no additional latency.
+    fn latency(&self, _address: R::Unsigned) -> u64 {
+        0
+    }
+    /// Whether misaligned loads and stores at the given address are performed rather than raising address-misaligned.
+    /// Defaults to true, as the default access methods emulate misaligned accesses one byte at a time.
+    fn emulates_misaligned(&self, _address: R::Unsigned) -> bool {
+        true
+    }
+    /// Load the byte at the given address, failing if the address is not mapped
+    fn get_byte(&self, address: R::Unsigned) -> Result<u8, MemoryFault> {
+        if self.is_mapped(address) {
+            Ok(self.get(address))
+        } else {
+            Err(MemoryFault::AccessFault)
+        }
+    }
+    /// Load the little-endian half at the given address. Defaults to loading each byte, so misaligned halves are emulated
+    fn get_half(&self, address: R::Unsigned) -> Result<[u8; 2], MemoryFault> {
+        let address = R::from_unsigned(address);
+        Ok([self.get_byte(address.unsigned())?, self.get_byte(address.append(1))?])
+    }
+    /// Load the little-endian word at the given address. Defaults to loading each byte, so misaligned words are emulated
+    fn get_word(&self, address: R::Unsigned) -> Result<[u8; 4], MemoryFault> {
+        let address = R::from_unsigned(address);
+        let mut word = [0; 4];
+        for (offset, byte) in word.iter_mut().enumerate() {
+            *byte = self.get_byte(address.append(offset))?
+        }
+        Ok(word)
+    }
+    /// Load the little-endian double at the given address. Defaults to loading each byte, so misaligned doubles are emulated
+    fn get_double(&self, address: R::Unsigned) -> Result<[u8; 8], MemoryFault> {
+        let address = R::from_unsigned(address);
+        let mut double = [0; 8];
+        for (offset, byte) in double.iter_mut().enumerate() {
+            *byte = self.get_byte(address.append(offset))?
+        }
+        Ok(double)
+    }
+    /// Store a byte at the given address, failing if the address is not mapped
+    fn set_byte(&mut self, address: R::Unsigned, value: u8) -> Result<(), MemoryFault> {
+        if self.is_mapped(address) {
+            self.set(address, value);
+            Ok(())
+        } else {
+            Err(MemoryFault::AccessFault)
+        }
+    }
+    /// Store a little-endian half at the given address. Defaults to storing each byte once every byte is known to be
+    /// mapped, so a store straddling unmapped memory faults without writing any of it
+    fn set_half(&mut self, address: R::Unsigned, value: [u8; 2]) -> Result<(), MemoryFault> {
+        let address = R::from_unsigned(address);
+        if !all_mapped(self, address, 2) {
+            return Err(MemoryFault::AccessFault)
+        }
+        for (offset, byte) in value.iter().enumerate() {
+            self.set_byte(address.append(offset), *byte)?
+        }
+        Ok(())
+    }
+    /// Store a little-endian word at the given address. Defaults to storing each byte once every byte is known to be
+    /// mapped, so a store straddling unmapped memory faults without writing any of it
+    fn set_word(&mut self, address: R::Unsigned, value: [u8; 4]) -> Result<(), MemoryFault> {
+        let address = R::from_unsigned(address);
+        if !all_mapped(self, address, 4) {
+            return Err(MemoryFault::AccessFault)
+        }
+        for (offset, byte) in value.iter().enumerate() {
+            self.set_byte(address.append(offset), *byte)?
+        }
+        Ok(())
+    }
+    /// Store a little-endian double at the given address. Defaults to storing each byte once every byte is known to be
+    /// mapped, so a store straddling unmapped memory faults without writing any of it
+    fn set_double(&mut self, address: R::Unsigned, value: [u8; 8]) -> Result<(), MemoryFault> {
+        let address = R::from_unsigned(address);
+        if !all_mapped(self, address, 8) {
+            return Err(MemoryFault::AccessFault)
+        }
+        for (offset, byte) in value.iter().enumerate() {
+            self.set_byte(address.append(offset), *byte)?
+        }
+        Ok(())
+    }
+    /// Load `info.width` bytes at the given address for a load instruction, filling `bytes` which is exactly that long.
+    /// Defaults to the access method for the width. A device may override it to inspect how it is accessed, such as to
+    /// fault on sub-word accesses to a register which may only be accessed as a whole word.
+    fn load_bytes(&self, address: R::Unsigned, bytes: &mut [u8], info: AccessInfo) -> Result<(), MemoryFault> {
+        match info.width {
+            1 => bytes[0] = self.get_byte(address)?,
+            2 => bytes.copy_from_slice(&self.get_half(address)?),
+            4 => bytes.copy_from_slice(&self.get_word(address)?),
+            8 => bytes.copy_from_slice(&self.get_double(address)?),
+            _ => {
+                bytes[..8].copy_from_slice(&self.get_double(address)?);
+                bytes[8..].copy_from_slice(&self.get_double(R::from_unsigned(address).append(8))?)
+            }
+        }
+        Ok(())
+    }
+    /// Store the `info.width` bytes of `bytes` at the given address for a store instruction, including the store of an AMO.
+    /// Defaults to the access method for the width, and may be overridden as with `load_bytes`
+    fn store_bytes(&mut self, address: R::Unsigned, bytes: &[u8], info: AccessInfo) -> Result<(), MemoryFault> {
+        match info.width {
+            1 => self.set_byte(address, bytes[0]),
+            2 => self.set_half(address, [bytes[0], bytes[1]]),
+            4 => self.set_word(address, [bytes[0], bytes[1], bytes[2], bytes[3]]),
+            8 => self.set_double(address, [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]),
+            _ => {
+                if !all_mapped(self, R::from_unsigned(address), 16) {
+                    return Err(MemoryFault::AccessFault)
+                }
+                self.set_double(address, [bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]])?;
+                self.set_double(R::from_unsigned(address).append(8), [bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]])
+            }
+        }
+    }
+    /// Atomically replace the `info.width` bytes at the given address for an AMO, leaving the bytes loaded in `bytes`.
+    /// `modify` transforms the loaded bytes in place into the bytes to store.
+    /// Defaults to `load_bytes` followed by `store_bytes`, which is atomic only if nothing else accesses the memory between them.
+    /// Memory shared between threads should override it to hold a lock or use a host atomic for the whole operation.
+    fn read_modify_write(&mut self, address: R::Unsigned, bytes: &mut [u8], info: AccessInfo, modify: &dyn Fn(&mut [u8])) -> Result<(), MemoryFault> {
+        self.load_bytes(address, bytes, AccessInfo::load(info.width, true))?;
+        let mut stored = [0; 8];
+        let stored = &mut stored[..bytes.len()];
+        stored.copy_from_slice(bytes);
+        modify(stored);
+        self.store_bytes(address, stored, info)
+    }
+    /// Order the memory accesses made before a FENCE in the predecessor set `pred` before those made after it in the
+    /// successor set `succ`. Each set holds device input as bit 3, device output as bit 2, reads as bit 1 and writes as bit 0.
+    /// A single core always observes its own accesses in order, so this defaults to doing nothing. Memory shared
+    /// between cores on other threads may override it, such as with a host fence.
+    fn fence(&mut self, _pred: u8, _succ: u8) {}
+    /// Synchronise instruction fetches with the stores made before a FENCE.I, after the core has flushed its own
+    /// instruction cache. Memory which caches instructions or their translations on the host should discard them.
+    /// Defaults to doing nothing, as fetches are always made from memory
+    fn fence_i(&mut self) {}
+    /// Wait while a WRS instruction executes with a reservation of `size` bytes held at the given address, which is
+    /// invalidated when another hart stores to it. May block the host thread until then, an interrupt is raised, or if
+    /// `short` is set for WRS.STO, a short timeout. Defaults to returning immediately, as a single hart cannot have its
+    /// reservation invalidated while it waits
+    fn wait_on_reservation(&mut self, _address: R::Unsigned, _size: u8, _short: bool) {}
+    /// Perform a CBO instruction on the block of `CACHE_BLOCK_SIZE` bytes starting at the given aligned address.
+    /// Memory is not cached, so cleaning, flushing and invalidating default to doing nothing, while zeroing stores zero to
+    /// each double of the block through `store_bytes`. Memory holding a contiguous slice of bytes may zero it in one call instead
+    fn cache_block(&mut self, operation: CacheBlockOperation, address: R::Unsigned) -> Result<(), MemoryFault> {
+        if operation == CacheBlockOperation::Zero {
+            let block = R::from_unsigned(address);
+            for offset in (0..CACHE_BLOCK_SIZE).step_by(8) {
+                self.store_bytes(block.append(offset), &[0; 8], AccessInfo::store(8))?
+            }
+        }
+        Ok(())
+    }
+    /// Fetch consecutive bytes of instructions starting at the given address, filling `bytes`.
+    /// Defaults to fetching each byte. Memory holding a contiguous slice of bytes should copy them in one call instead.
+    fn fetch_slice(&self, address: R, bytes: &mut [u8]) -> Result<(), MemoryFault> {
+        for (offset, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.get_byte(address.append(offset))?
+        }
+        Ok(())
+    }
+    /// Fetch the first 16-bit parcel of an instruction, which determines its length with the C extension.
+    /// Any fault raises an instruction access fault.
+    fn fetch_parcel(&self, address: R) -> Result<[u8; 2], MemoryFault> {
+        let mut parcel = [0; 2];
+        self.fetch_slice(address, &mut parcel)?;
+        Ok(parcel)
+    }
+    /// Fetch an instruction to execute. Any fault raises an instruction access fault.
+    fn fetch(&self, address: R) -> Result<[u8; 4], MemoryFault> {
+        let mut instruction = [0; 4];
+        self.fetch_slice(address, &mut instruction)?;
+        Ok(instruction)
+    }
+}
+
+/// How an instruction accesses memory through `Mmu::load_bytes` and `Mmu::store_bytes`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AccessInfo {
+    /// The size of the access in bytes; 1, 2, 4 or 8, or 16 for LQ and SQ on RV128
+    pub width: u8,
+    /// True for a store, false for a load
+    pub is_write: bool,
+    /// True if a loaded value is sign-extended into the register. Always false for stores and floating-point loads
+    pub is_signed: bool
+}
+impl AccessInfo {
+    /// A load of `width` bytes, sign-extended into the register if `is_signed`
+    pub fn load(width: u8, is_signed: bool) -> Self {
+        Self { width, is_write: false, is_signed }
+    }
+    /// A store of `width` bytes
+    pub fn store(width: u8) -> Self {
+        Self { width, is_write: true, is_signed: false }
+    }
+}
+
+/// The size in bytes of the naturally aligned cache blocks operated on by the Zicbom and Zicboz extensions
+pub const CACHE_BLOCK_SIZE: usize = 64;
+
+/// The operation a CBO instruction performs on a cache block through `Mmu::cache_block`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CacheBlockOperation {
+    /// CBO.CLEAN, writing any modified copy of the block back to memory
+    Clean,
+    /// CBO.FLUSH, writing any modified copy of the block back to memory and discarding every copy
+    Flush,
+    /// CBO.INVAL, discarding every copy of the block without writing it back
+    Invalidate,
+    /// CBO.ZERO, storing zero to every byte of the block
+    Zero
+}
+
+/// The reason `Core::load_core_dump` could not restore a core dump
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CoreDumpError {
+    /// The dump is truncated, has trailing bytes, or is not a core dump
+    Malformed,
+    /// The dump was taken from a core with a different XLEN, register file or set of extensions
+    Mismatch
+}
+
+/// Whether each of the `length` bytes from `address` is mapped
+fn all_mapped<R: Register, M: Mmu<R> + ?Sized>(mmu: &M, address: R, length: usize) -> bool {
+    (0..length).all(|offset| mmu.is_mapped(address.append(offset)))
+}
+
+/// The reason a memory access failed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryFault {
+    /// The address is not aligned to the size of the access and the memory does not emulate misaligned accesses.
+    /// Loads and stores raise it before touching memory when `Mmu::emulates_misaligned` is false, so it takes priority
+    /// over `AccessFault` as address-misaligned exceptions do in the ISA. LR, SC and AMOs always raise it when misaligned.
+    Misaligned,
+    /// The address is not mapped, or the memory does not allow the access
+    AccessFault
+}
+
+/// Fixed-size working memory mapped at a base address, requiring no allocator.
+/// Raw reads outside of the memory return zero and raw writes outside of it are ignored, while accesses by a core raise an access fault.
+pub struct ArrayRam<const N: usize> {
+    /// The address of the first byte of memory
+    pub base: usize,
+    /// The contents of memory
+    pub bytes: [u8; N]
+}
+impl<const N: usize> ArrayRam<N> {
+    /// Create zeroed memory starting at `base`
+    pub fn new(base: usize) -> Self {
+        Self { base, bytes: [0; N] }
+    }
+    /// Copy `data` into memory starting at `address`.
+    /// # Safety
+    /// A panic will occur if `data` does not fit in memory at `address`
+    pub fn load(&mut self, address: usize, data: &[u8]) {
+        let offset = address - self.base;
+        self.bytes[offset..offset + data.len()].copy_from_slice(data)
+    }
+    /// The whole of memory, starting at `base`
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes
+    }
+    /// The memory mapped over a range of addresses, or `None` if any of the range is not mapped
+    pub fn region(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.bytes.get(range.start.checked_sub(self.base)?..range.end.checked_sub(self.base)?)
+    }
+    /// The offset into memory of an address, if it is mapped
+    fn offset(&self, address: usize) -> Option<usize> {
+        address.checked_sub(self.base).filter(|&offset| offset < N)
+    }
+    /// The memory mapped over `length` bytes from `address`, or `None` if any of it is not mapped
+    fn slice(&self, address: usize, length: usize) -> Option<&[u8]> {
+        let offset = self.offset(address)?;
+        self.bytes.get(offset..offset.checked_add(length)?)
+    }
+}
+impl<R: Register, const N: usize> Mmu<R> for ArrayRam<N> {
+    fn get(&self, address: R::Unsigned) -> u8 {
+        self.offset(R::from_unsigned(address).usize()).map_or(0, |offset| self.bytes[offset])
+    }
+    fn set(&mut self, address: R::Unsigned, value: u8) {
+        if let Some(offset) = self.offset(R::from_unsigned(address).usize()) {
+            self.bytes[offset] = value
+        }
+    }
+    fn is_mapped(&self, address: R::Unsigned) -> bool {
+        self.offset(R::from_unsigned(address).usize()).is_some()
+    }
+    fn fetch_slice(&self, address: R, bytes: &mut [u8]) -> Result<(), MemoryFault> {
+        let slice = self.slice(address.usize(), bytes.len()).ok_or(MemoryFault::AccessFault)?;
+        bytes.copy_from_slice(slice);
+        Ok(())
+    }
+}
+
+/// Read-only memory mapped at a base address, such as a boot ROM.
+/// Raw reads outside of the memory return zero and raw writes are ignored. Stores executed by a core raise an access fault.
+pub struct Rom<'a> {
+    /// The address of the first byte of memory
+    pub base: usize,
+    /// The contents of memory
+    pub bytes: &'a [u8]
+}
+impl<'a> Rom<'a> {
+    /// Map `bytes` starting at `base`
+    pub fn new(base: usize, bytes: &'a [u8]) -> Self {
+        Self { base, bytes }
+    }
+    /// The whole of memory, starting at `base`
+    pub fn as_slice(&self) -> &[u8] {
+        self.bytes
+    }
+    /// The memory mapped over a range of addresses, or `None` if any of the range is not mapped
+    pub fn region(&self, range: Range<usize>) -> Option<&'a [u8]> {
+        self.bytes.get(range.start.checked_sub(self.base)?..range.end.checked_sub(self.base)?)
+    }
+    /// The offset into memory of an address, if it is mapped
+    fn offset(&self, address: usize) -> Option<usize> {
+        address.checked_sub(self.base).filter(|&offset| offset < self.bytes.len())
+    }
+    /// The memory mapped over `length` bytes from `address`, or `None` if any of it is not mapped
+    fn slice(&self, address: usize, length: usize) -> Option<&'a [u8]> {
+        let offset = self.offset(address)?;
+        self.bytes.get(offset..offset.checked_add(length)?)
+    }
+}
+impl<'a, R: Register> Mmu<R> for Rom<'a> {
+    fn get(&self, address: R::Unsigned) -> u8 {
+        self.offset(R::from_unsigned(address).usize()).map_or(0, |offset| self.bytes[offset])
+    }
+    fn set(&mut self, _address: R::Unsigned, _value: u8) {}
+    fn set_byte(&mut self, _address: R::Unsigned, _value: u8) -> Result<(), MemoryFault> {
+        Err(MemoryFault::AccessFault)
+    }
+    fn is_mapped(&self, address: R::Unsigned) -> bool {
+        self.offset(R::from_unsigned(address).usize()).is_some()
+    }
+    fn fetch_slice(&self, address: R, bytes: &mut [u8]) -> Result<(), MemoryFault> {
+        let slice = self.slice(address.usize(), bytes.len()).ok_or(MemoryFault::AccessFault)?;
+        bytes.copy_from_slice(slice);
+        Ok(())
     }
 }
 
 /// A fatal trap must be handled by the execution environment.
-/// These are 
+/// These are returned with the program counter left at the instruction that trapped, including for ECALL and EBREAK,
+/// so a host resuming after handling a `SystemCall` or `Breakpoint` must first call `Core::skip_trapped_instruction`
 #[derive(Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Trap {
-    /// The program counter was not aligned to a 4 byte boundary (2 for the C extension)
-    InstructionMisaligned,
+    /// A jump or branch to the contained address was not aligned to a 4 byte boundary (2 for the C extension)
+    InstructionMisaligned(u64),
+    /// An instruction was fetched from the contained address, which is not executable or faulted
+    InstructionAccessFault(u64),
     /// An illegal instruction was executed in unprivileged mode
     IllegalInstruction,
+    /// A load from the contained address was misaligned and the memory does not emulate misaligned accesses
+    LoadMisaligned(u64),
+    /// A load from the contained address faulted
+    LoadAccessFault(u64),
+    /// A store or AMO to the contained address was misaligned and the memory does not emulate misaligned accesses
+    StoreMisaligned(u64),
+    /// A store or AMO to the contained address faulted
+    StoreAccessFault(u64),
     /// A call to the execution environment
     SystemCall,
     /// A breakpoint to be handled by the execution environment
     Breakpoint
 }
-impl std::fmt::Debug for Trap {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Decides what happens when an instruction traps, selected per core with `Core::with_trap_strategy`
+pub trait TrapStrategy<R: Register, const REGS: usize = 32> {
+    /// Handle a trap raised by the instruction at the core's program counter.
+    /// `value` is the faulting address or zero, as written to mtval.
+    /// Returns the trap if execution must stop for the execution environment to handle it.
+    fn trap(&self, core: &mut Core<R, REGS>, trap: Trap, value: R) -> Option<Trap>;
+}
+
+/// Returns every trap to the execution environment, leaving the core at the instruction that trapped
+#[derive(Debug, Copy, Clone, Default)]
+pub struct HostReturn;
+impl<R: Register, const REGS: usize> TrapStrategy<R, REGS> for HostReturn {
+    fn trap(&self, _core: &mut Core<R, REGS>, trap: Trap, _value: R) -> Option<Trap> {
+        Some(trap)
+    }
+}
+
+/// Handles every trap in the program by entering the machine-mode trap handler given by mtvec
+#[cfg(feature = "ext-csr")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct CsrHandled;
+#[cfg(feature = "ext-csr")]
+impl<R: Register, const REGS: usize> TrapStrategy<R, REGS> for CsrHandled {
+    fn trap(&self, core: &mut Core<R, REGS>, trap: Trap, value: R) -> Option<Trap> {
+        // TODO: Shall be virtual address when implemented
+        core.csr.mtval = value;
+        let code = match (trap, core.csr.privilege) {
+            // Environment call from U-mode
+            (Trap::SystemCall, Privilege::User) => 8,
+            _ => trap.code()
+        };
+        core.enter_trap_handler(code, false);
+        None
+    }
+}
+
+impl Trap {
+    /// The exception code identifying the trap in mcause
+    pub fn code(self) -> u8 {
+        match self {
+            Self::InstructionMisaligned(_) => 0,
+            Self::InstructionAccessFault(_) => 1,
+            Self::IllegalInstruction => 2,
+            Self::Breakpoint => 3,
+            Self::LoadMisaligned(_) => 4,
+            Self::LoadAccessFault(_) => 5,
+            Self::StoreMisaligned(_) => 6,
+            Self::StoreAccessFault(_) => 7,
+            // Environment call from M-mode. Calls from U-mode are distinguished by the trap strategy
+            Self::SystemCall => 11
+        }
+    }
+}
+impl core::fmt::Debug for Trap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
-            Self::InstructionMisaligned => write!(f, "Instruction Address is Misaligned"),
+            Self::InstructionMisaligned(address) => write!(f, "Instruction Address is Misaligned at {:#X}", address),
+            Self::InstructionAccessFault(address) => write!(f, "Instruction Access Fault at {:#X}", address),
             Self::IllegalInstruction => write!(f, "Trap on Illegal Instruction"),
+            Self::LoadMisaligned(address) => write!(f, "Load Address is Misaligned at {:#X}", address),
+            Self::LoadAccessFault(address) => write!(f, "Load Access Fault at {:#X}", address),
+            Self::StoreMisaligned(address) => write!(f, "Store Address is Misaligned at {:#X}", address),
+            Self::StoreAccessFault(address) => write!(f, "Store Access Fault at {:#X}", address),
             Self::SystemCall => write!(f, "System Call"),
             Self::Breakpoint => write!(f, "Trap on External Debugger Breakpoint")
         }
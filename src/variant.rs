@@ -10,6 +10,51 @@ pub trait Variant {
     fn decode(instruction: [u8; 4]) -> Self;
 }
 
+/// Extract the immediate of a decoded variant regardless of its type, for tooling that handles all variants uniformly
+/// ```rust
+/// use rysk_core::{ variant::{ self, HasImmediate, Variant }, Register32 };
+/// // addi x0, x0, -1
+/// let instruction: variant::I<Register32> = Variant::decode([0x13, 0x00, 0xF0, 0xFF]);
+/// assert_eq!(instruction.immediate_bits(), -1);
+/// ```
+pub trait HasImmediate {
+    /// The immediate value, sign extended as it would be used during execution
+    fn immediate_bits(&self) -> i64;
+}
+/// Implement `HasImmediate` for variants with a sign-extended `immediate` register.
+/// All immediates fit in the lower 32 bits so only the word needs to be considered.
+macro_rules! impl_has_immediate {
+    ($($variant:ident),*) => {
+        $(
+            impl<R: Register> HasImmediate for $variant<R> {
+                fn immediate_bits(&self) -> i64 {
+                    i32::from_le_bytes(self.immediate.word()) as i64
+                }
+            }
+        )*
+    };
+}
+impl_has_immediate!(I, S, B, U, J);
+impl HasImmediate for C {
+    /// The CSR index, which is encoded in place of the immediate
+    fn immediate_bits(&self) -> i64 {
+        self.csr as i64
+    }
+}
+
+/// Decode a full instruction into its operation and operands, without any core.
+/// Execution dispatches on the result, with legality that depends on the core's state, such as the effective XLEN or
+/// privilege, checked afterwards. Fails with an illegal instruction trap for encodings the enabled extensions do not define.
+/// ```rust
+/// use rysk_core::{ decode::Instruction, variant, Register32 };
+/// // addi x1, x0, -1
+/// let instruction = variant::decode_instruction::<Register32>([0x93, 0x00, 0xF0, 0xFF]).unwrap();
+/// assert_eq!(instruction, Instruction::Addi { rd: 1, rs1: 0, imm: Register32([0xFF; 4]) });
+/// ```
+pub fn decode_instruction<R: Register>(instruction: [u8; 4]) -> Result<crate::decode::Instruction<R>, crate::system::Trap> {
+    crate::decode::decode(instruction)
+}
+
 /// Extract the destination register index from an instruction
 macro_rules! destination {
     ($instruction:expr) => {
@@ -51,6 +96,25 @@ impl Variant for R {
     }
 }
 
+/// The R4 instruction type of the fused multiply-add instructions, encoding a destination and 3 source registers.
+#[derive(Debug, Eq, PartialEq)]
+pub struct R4 {
+    pub destination: usize,
+    pub source1: usize,
+    pub source2: usize,
+    pub source3: usize
+}
+impl Variant for R4 {
+    fn decode(instruction: [u8; 4]) -> Self {
+        Self {
+            destination: destination!(instruction),
+            source1: source1!(instruction),
+            source2: source2!(instruction),
+            source3: (instruction[3] >> 3) as usize
+        }
+    }
+}
+
 /// The I instruction type, encoding a destination and source register as well as an immediate value.
 /// The immediate value is a sign extended 12-bit integer.
 #[derive(Debug, Eq, PartialEq)]
@@ -82,7 +146,7 @@ impl Variant for C {
         Self {
             destination: destination!(instruction),
             source: source1!(instruction),
-            csr: ((instruction[2] & 0xF0) >> 4) as usize | ((instruction[3] & 0xFF) as usize) << 4
+            csr: ((instruction[2] & 0xF0) >> 4) as usize | (instruction[3] as usize) << 4
         }
     }
 }
@@ -167,4 +231,214 @@ impl<R: Register> Variant for J<R> {
             ])
         }
     }
-}
\ No newline at end of file
+}
+/// Decode a compressed instruction encoding variant into its significant parts.
+/// Immediates are left as plain integers as compressed instructions are expanded to their 32-bit equivalents before execution.
+/// ```rust
+/// use rysk_core::variant::{ self, CompressedVariant };
+/// let instruction = [0x01, 0x00];
+/// let variant::CI { destination, immediate } = CompressedVariant::decode(instruction);
+/// ```
+#[cfg(feature = "ext-c")]
+pub trait CompressedVariant {
+    fn decode(instruction: [u8; 2]) -> Self;
+}
+
+/// Extract bits `high..=low` of a compressed instruction, shifted down to bit 0
+#[cfg(feature = "ext-c")]
+macro_rules! bits {
+    ($instruction:expr, $high:expr, $low:expr) => {
+        ((u16::from_le_bytes($instruction) >> $low) & ((1 << ($high - $low + 1)) - 1)) as u32
+    };
+}
+/// Extract a 3-bit register index, which addresses the 8 most common registers x8 to x15
+#[cfg(feature = "ext-c")]
+macro_rules! compressed_register {
+    ($instruction:expr, $low:expr) => {
+        8 + bits!($instruction, $low + 2, $low) as usize
+    };
+}
+/// Sign extend the lowest `$width` bits of an immediate
+#[cfg(feature = "ext-c")]
+macro_rules! sign_extend {
+    ($immediate:expr, $width:expr) => {
+        (($immediate << (32 - $width)) as i32) >> (32 - $width)
+    };
+}
+
+/// The CR compressed instruction type, encoding a full destination/first source register and a second source register.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CR {
+    pub destination: usize,
+    pub source: usize
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CR {
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            destination: bits!(instruction, 11, 7) as usize,
+            source: bits!(instruction, 6, 2) as usize
+        }
+    }
+}
+
+/// The CI compressed instruction type, encoding a full destination/first source register and a sign extended 6-bit immediate.
+/// Stack-relative loads and C.ADDI16SP scramble the immediate bits differently and decode them separately.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CI {
+    pub destination: usize,
+    pub immediate: i32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CI {
+    /// ```rust
+    /// use rysk_core::variant::*;
+    /// // c.li a0, -1
+    /// assert_eq!(CI { destination: 10, immediate: -1 }, CompressedVariant::decode([0x7D, 0x55]));
+    /// ```
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            destination: bits!(instruction, 11, 7) as usize,
+            immediate: sign_extend!(bits!(instruction, 12, 12) << 5 | bits!(instruction, 6, 2), 6)
+        }
+    }
+}
+
+/// The CSS compressed instruction type, encoding a source register to store at an unsigned offset from the stack pointer.
+/// The offset is scaled by the access size, so it is provided for both word and double-word stores.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CSS {
+    pub source: usize,
+    pub word_offset: u32,
+    pub double_offset: u32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CSS {
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            source: bits!(instruction, 6, 2) as usize,
+            word_offset: bits!(instruction, 12, 9) << 2 | bits!(instruction, 8, 7) << 6,
+            double_offset: bits!(instruction, 12, 10) << 3 | bits!(instruction, 9, 7) << 6
+        }
+    }
+}
+
+/// The CIW compressed instruction type, encoding a compressed destination register and a scaled, unsigned 10-bit immediate.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CIW {
+    pub destination: usize,
+    pub immediate: u32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CIW {
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            destination: compressed_register!(instruction, 2),
+            immediate: bits!(instruction, 12, 11) << 4
+                | bits!(instruction, 10, 7) << 6
+                | bits!(instruction, 6, 6) << 2
+                | bits!(instruction, 5, 5) << 3
+        }
+    }
+}
+
+/// The CL compressed instruction type, encoding compressed destination and base registers and an unsigned offset.
+/// The offset is scaled by the access size, so it is provided for both word and double-word loads.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CL {
+    pub destination: usize,
+    pub source: usize,
+    pub word_offset: u32,
+    pub double_offset: u32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CL {
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            destination: compressed_register!(instruction, 2),
+            source: compressed_register!(instruction, 7),
+            word_offset: bits!(instruction, 12, 10) << 3 | bits!(instruction, 6, 6) << 2 | bits!(instruction, 5, 5) << 6,
+            double_offset: bits!(instruction, 12, 10) << 3 | bits!(instruction, 6, 5) << 6
+        }
+    }
+}
+
+/// The CS compressed instruction type, encoding 2 compressed source registers and an unsigned offset.
+/// Offsets are laid out as in the CL type. The compressed arithmetic instructions share this layout, with `source1` also being the destination.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CS {
+    pub source1: usize,
+    pub source2: usize,
+    pub word_offset: u32,
+    pub double_offset: u32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CS {
+    fn decode(instruction: [u8; 2]) -> Self {
+        let CL { destination, source, word_offset, double_offset } = CompressedVariant::decode(instruction);
+        Self {
+            source1: source,
+            source2: destination,
+            word_offset,
+            double_offset
+        }
+    }
+}
+
+/// The CB compressed instruction type, encoding a compressed source register and a 9-bit branch offset.
+/// The shift and C.ANDI instructions share this layout but with a CI style immediate, so both are provided.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CB {
+    pub source: usize,
+    pub offset: i32,
+    pub immediate: i32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CB {
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            source: compressed_register!(instruction, 7),
+            offset: sign_extend!(
+                bits!(instruction, 12, 12) << 8
+                    | bits!(instruction, 11, 10) << 3
+                    | bits!(instruction, 6, 5) << 6
+                    | bits!(instruction, 4, 3) << 1
+                    | bits!(instruction, 2, 2) << 5,
+                9
+            ),
+            immediate: sign_extend!(bits!(instruction, 12, 12) << 5 | bits!(instruction, 6, 2), 6)
+        }
+    }
+}
+
+/// The CJ compressed instruction type, encoding a 12-bit jump offset.
+#[cfg(feature = "ext-c")]
+#[derive(Debug, Eq, PartialEq)]
+pub struct CJ {
+    pub offset: i32
+}
+#[cfg(feature = "ext-c")]
+impl CompressedVariant for CJ {
+    fn decode(instruction: [u8; 2]) -> Self {
+        Self {
+            offset: sign_extend!(
+                bits!(instruction, 12, 12) << 11
+                    | bits!(instruction, 11, 11) << 4
+                    | bits!(instruction, 10, 9) << 8
+                    | bits!(instruction, 8, 8) << 10
+                    | bits!(instruction, 7, 7) << 6
+                    | bits!(instruction, 6, 6) << 7
+                    | bits!(instruction, 5, 3) << 1
+                    | bits!(instruction, 2, 2) << 5,
+                12
+            )
+        }
+    }
+}
@@ -0,0 +1,768 @@
+//! The vector register file, integer arithmetic and memory accesses of the V extension.
+//!
+//! The vector registers are held as one array of bytes, so the register group of a vector register with an LMUL
+//! greater than one is simply the registers that follow it. Elements are little-endian within each register group.
+//! Inactive and tail elements are always left undisturbed, which satisfies both the undisturbed and agnostic policies.
+//! Loads and stores are resolved into the elements they access here, which the core then accesses through its `Mmu`.
+
+use core::fmt;
+use alloc::{ vec, vec::Vec };
+
+/// The length in bits of each vector register, VLEN, unless `Core::with_vlen` selects another
+pub const DEFAULT_VLEN: usize = 128;
+/// The widest element supported in bits, ELEN
+pub const ELEN: u32 = 64;
+
+/// The operand categories of OP-V, given by funct3
+const OPIVV: u8 = 0b000;
+const OPMVV: u8 = 0b010;
+const OPIVI: u8 = 0b011;
+const OPIVX: u8 = 0b100;
+const OPMVX: u8 = 0b110;
+
+/// The element width and register grouping selected by the `vset{i}vl{i}` instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vtype {
+    /// The selected element width in bits, SEW
+    pub sew: u32,
+    /// The base-2 logarithm of the register group multiplier LMUL, from -3 for an eighth of a register to 3 for eight registers
+    pub lmul: i8,
+    /// Whether tail elements may be overwritten, vta
+    pub tail_agnostic: bool,
+    /// Whether inactive elements may be overwritten, vma
+    pub mask_agnostic: bool
+}
+impl Vtype {
+    /// Decode the value of vtype, or `None` if it selects a reserved or unsupported configuration and so sets vill
+    pub fn decode(bits: u64) -> Option<Self> {
+        let vlmul = (bits & 0b111) as i8;
+        let vsew = (bits >> 3) & 0b111;
+        if vlmul == 0b100 || vsew > 0b011 || bits >> 8 != 0 {
+            return None
+        }
+        // vlmul is a signed field of 3 bits
+        let lmul = (vlmul << 5) >> 5;
+        let sew = 8 << vsew;
+        // A fractional LMUL must still hold an element of the widest width
+        if lmul < 0 && sew > ELEN >> -lmul {
+            return None
+        }
+        Some(Self { sew, lmul, tail_agnostic: bits & 1 << 6 != 0, mask_agnostic: bits & 1 << 7 != 0 })
+    }
+
+    /// The value of vtype
+    pub fn bits(self) -> u64 {
+        (self.lmul as u64 & 0b111) | ((self.sew / 8).trailing_zeros() as u64) << 3 | (self.tail_agnostic as u64) << 6 | (self.mask_agnostic as u64) << 7
+    }
+
+    /// The number of registers in a register group, which is one for a fractional LMUL
+    fn group(self) -> usize {
+        group(self.lmul)
+    }
+
+    /// The base-2 logarithm of the register group multiplier for elements of `width` bits, EMUL, such that the
+    /// register group holds as many elements as one of SEW bits. `None` if it is beyond the range of LMUL
+    fn emul(self, width: u32) -> Option<i8> {
+        let emul = self.lmul + width.trailing_zeros() as i8 - self.sew.trailing_zeros() as i8;
+        if (-3..=3).contains(&emul) { Some(emul) } else { None }
+    }
+}
+
+/// The number of registers in a register group with the base-2 logarithm of its multiplier `lmul`
+fn group(lmul: i8) -> usize {
+    if lmul > 0 { 1 << lmul } else { 1 }
+}
+impl fmt::Display for Vtype {
+    /// Format as the operands of `vsetvli`, such as `e32, m1, ta, mu`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "e{}, {}{}, {}, {}",
+            self.sew,
+            if self.lmul < 0 { "mf" } else { "m" },
+            1 << self.lmul.abs(),
+            if self.tail_agnostic { "ta" } else { "tu" },
+            if self.mask_agnostic { "ma" } else { "mu" }
+        )
+    }
+}
+
+/// The second source of a vector arithmetic instruction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorOperand<S> {
+    /// The vector register vs1
+    Vector(usize),
+    /// A scalar, being the index of the integer register rs1 once decoded and its value when executed
+    Scalar(S),
+    /// The 5-bit immediate held in place of rs1
+    Immediate(u8)
+}
+impl VectorOperand<usize> {
+    /// The operand of an OP-V instruction with the given funct3 and rs1 field
+    pub fn decode(funct3: u8, field: usize) -> Self {
+        match funct3 {
+            OPIVV | OPMVV => Self::Vector(field),
+            OPIVI => Self::Immediate(field as u8),
+            _ => Self::Scalar(field)
+        }
+    }
+}
+impl<S> VectorOperand<S> {
+    /// Convert a scalar operand, such as from the index of a register to its value
+    pub fn map<T>(self, scalar: impl FnOnce(S) -> T) -> VectorOperand<T> {
+        match self {
+            Self::Vector(vs1) => VectorOperand::Vector(vs1),
+            Self::Scalar(value) => VectorOperand::Scalar(scalar(value)),
+            Self::Immediate(immediate) => VectorOperand::Immediate(immediate)
+        }
+    }
+}
+
+/// How the elements of an operation combine
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorKind {
+    /// Each element of the destination is computed from the elements of the sources with the same index
+    Elementwise,
+    /// Each bit of the destination mask register compares the elements of the sources with the same index
+    Compare,
+    /// Element 0 of the destination combines element 0 of vs1 with every active element of vs2
+    Reduction,
+    /// Each bit of the destination mask register combines the bits of the source mask registers with the same index
+    Mask
+}
+
+/// An integer vector arithmetic instruction, given by funct6 and the operand category in funct3
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorOp {
+    Add,
+    Sub,
+    /// Subtract vs2 from the other operand
+    Rsub,
+    Minu,
+    Min,
+    Maxu,
+    Max,
+    And,
+    Or,
+    Xor,
+    /// VMERGE if masked, selecting the other operand for active elements and vs2 otherwise, else VMV.V
+    Merge,
+    Mseq,
+    Msne,
+    Msltu,
+    Mslt,
+    Msleu,
+    Msle,
+    Msgtu,
+    Msgt,
+    Sll,
+    Srl,
+    Sra,
+    Mul,
+    Mulh,
+    Mulhu,
+    /// The high bits of the product of vs2 as signed and the other operand as unsigned
+    Mulhsu,
+    Divu,
+    Div,
+    Remu,
+    Rem,
+    Redsum,
+    Redand,
+    Redor,
+    Redxor,
+    Redminu,
+    Redmin,
+    Redmaxu,
+    Redmax,
+    /// The bits of vs2 and not vs1
+    Mandn,
+    Mand,
+    Mor,
+    Mxor,
+    /// The bits of vs2 or not vs1
+    Morn,
+    Mnand,
+    Mnor,
+    Mxnor
+}
+impl VectorOp {
+    /// The operation of an OP-V instruction with the given funct3 and funct6, or `None` if it is not an integer
+    /// arithmetic instruction or is reserved for the operand category
+    pub fn decode(funct3: u8, funct6: u8) -> Option<Self> {
+        use VectorOp::*;
+        let integer = matches!(funct3, OPIVV | OPIVX | OPIVI);
+        let (vector, scalar, immediate) = (funct3 == OPIVV, funct3 == OPIVX, funct3 == OPIVI);
+        let multiply = matches!(funct3, OPMVV | OPMVX);
+        Some(match funct6 {
+            0b000000 if integer => Add,
+            0b000010 if vector || scalar => Sub,
+            0b000011 if scalar || immediate => Rsub,
+            0b000100 if vector || scalar => Minu,
+            0b000101 if vector || scalar => Min,
+            0b000110 if vector || scalar => Maxu,
+            0b000111 if vector || scalar => Max,
+            0b001001 if integer => And,
+            0b001010 if integer => Or,
+            0b001011 if integer => Xor,
+            0b010111 if integer => Merge,
+            0b011000 if integer => Mseq,
+            0b011001 if integer => Msne,
+            0b011010 if vector || scalar => Msltu,
+            0b011011 if vector || scalar => Mslt,
+            0b011100 if integer => Msleu,
+            0b011101 if integer => Msle,
+            0b011110 if scalar || immediate => Msgtu,
+            0b011111 if scalar || immediate => Msgt,
+            0b100101 if integer => Sll,
+            0b101000 if integer => Srl,
+            0b101001 if integer => Sra,
+            0b000000..=0b000111 if funct3 == OPMVV => [Redsum, Redand, Redor, Redxor, Redminu, Redmin, Redmaxu, Redmax][funct6 as usize],
+            0b011000..=0b011111 if funct3 == OPMVV => [Mandn, Mand, Mor, Mxor, Morn, Mnand, Mnor, Mxnor][funct6 as usize - 0b011000],
+            0b100000 if multiply => Divu,
+            0b100001 if multiply => Div,
+            0b100010 if multiply => Remu,
+            0b100011 if multiply => Rem,
+            0b100100 if multiply => Mulhu,
+            0b100101 if multiply => Mul,
+            0b100110 if multiply => Mulhsu,
+            0b100111 if multiply => Mulh,
+            _ => return None
+        })
+    }
+
+    /// How the elements of the operation combine
+    pub fn kind(self) -> VectorKind {
+        use VectorOp::*;
+        match self {
+            Mseq | Msne | Msltu | Mslt | Msleu | Msle | Msgtu | Msgt => VectorKind::Compare,
+            Redsum | Redand | Redor | Redxor | Redminu | Redmin | Redmaxu | Redmax => VectorKind::Reduction,
+            Mandn | Mand | Mor | Mxor | Morn | Mnand | Mnor | Mxnor => VectorKind::Mask,
+            _ => VectorKind::Elementwise
+        }
+    }
+
+    /// Tests if the registers and masking of an instruction are allowed regardless of vtype.
+    /// Mask logical instructions are never masked, VMV.V has no vs2, and a masked instruction writing elements may not
+    /// overwrite the mask in v0
+    pub fn is_legal(self, vd: usize, vs2: usize, masked: bool) -> bool {
+        match self.kind() {
+            VectorKind::Mask => !masked,
+            VectorKind::Elementwise if self == VectorOp::Merge && !masked => vs2 == 0,
+            VectorKind::Elementwise => !masked || vd != 0,
+            _ => true
+        }
+    }
+
+    /// The value of a 5-bit immediate operand, which is unsigned for shifts and sign-extended otherwise
+    pub fn immediate(self, immediate: u8) -> i64 {
+        match self {
+            VectorOp::Sll | VectorOp::Srl | VectorOp::Sra => immediate as i64 & 0x1F,
+            _ => signed(immediate as u64, 5)
+        }
+    }
+
+    /// The name of the instruction without the suffix giving its operand category
+    pub fn mnemonic(self) -> &'static str {
+        use VectorOp::*;
+        match self {
+            Add => "vadd",
+            Sub => "vsub",
+            Rsub => "vrsub",
+            Minu => "vminu",
+            Min => "vmin",
+            Maxu => "vmaxu",
+            Max => "vmax",
+            And => "vand",
+            Or => "vor",
+            Xor => "vxor",
+            Merge => "vmerge",
+            Mseq => "vmseq",
+            Msne => "vmsne",
+            Msltu => "vmsltu",
+            Mslt => "vmslt",
+            Msleu => "vmsleu",
+            Msle => "vmsle",
+            Msgtu => "vmsgtu",
+            Msgt => "vmsgt",
+            Sll => "vsll",
+            Srl => "vsrl",
+            Sra => "vsra",
+            Mul => "vmul",
+            Mulh => "vmulh",
+            Mulhu => "vmulhu",
+            Mulhsu => "vmulhsu",
+            Divu => "vdivu",
+            Div => "vdiv",
+            Remu => "vremu",
+            Rem => "vrem",
+            Redsum => "vredsum",
+            Redand => "vredand",
+            Redor => "vredor",
+            Redxor => "vredxor",
+            Redminu => "vredminu",
+            Redmin => "vredmin",
+            Redmaxu => "vredmaxu",
+            Redmax => "vredmax",
+            Mandn => "vmandn",
+            Mand => "vmand",
+            Mor => "vmor",
+            Mxor => "vmxor",
+            Morn => "vmorn",
+            Mnand => "vmnand",
+            Mnor => "vmnor",
+            Mxnor => "vmxnor"
+        }
+    }
+
+    /// The result of an elementwise operation or a step of a reduction, where `a` is the element of vs2
+    fn apply(self, a: u64, b: u64, sew: u32) -> u64 {
+        use VectorOp::*;
+        let (a, b) = (truncate(a, sew), truncate(b, sew));
+        let (signed_a, signed_b) = (signed(a, sew), signed(b, sew));
+        let shamt = (b & (sew as u64 - 1)) as u32;
+        let result = match self {
+            Add | Redsum => a.wrapping_add(b),
+            Sub => a.wrapping_sub(b),
+            Rsub => b.wrapping_sub(a),
+            Minu | Redminu => a.min(b),
+            Min | Redmin => signed_a.min(signed_b) as u64,
+            Maxu | Redmaxu => a.max(b),
+            Max | Redmax => signed_a.max(signed_b) as u64,
+            And | Redand => a & b,
+            Or | Redor => a | b,
+            Xor | Redxor => a ^ b,
+            Merge => b,
+            Sll => a << shamt,
+            Srl => a >> shamt,
+            Sra => (signed_a >> shamt) as u64,
+            Mul => a.wrapping_mul(b),
+            Mulh => ((signed_a as i128 * signed_b as i128) >> sew) as u64,
+            Mulhu => ((a as u128 * b as u128) >> sew) as u64,
+            Mulhsu => ((signed_a as i128 * b as i128) >> sew) as u64,
+            // Division by zero and overflow give the same results as the M extension
+            Divu => a.checked_div(b).unwrap_or(u64::MAX),
+            Div => if b == 0 { u64::MAX } else { signed_a.wrapping_div(signed_b) as u64 },
+            Remu => if b == 0 { a } else { a % b },
+            Rem => if b == 0 { a } else { signed_a.wrapping_rem(signed_b) as u64 },
+            _ => unreachable!("{:?} does not produce elements", self)
+        };
+        truncate(result, sew)
+    }
+
+    /// The result of a comparison, where `a` is the element of vs2
+    fn compare(self, a: u64, b: u64, sew: u32) -> bool {
+        use VectorOp::*;
+        let (a, b) = (truncate(a, sew), truncate(b, sew));
+        let (signed_a, signed_b) = (signed(a, sew), signed(b, sew));
+        match self {
+            Mseq => a == b,
+            Msne => a != b,
+            Msltu => a < b,
+            Mslt => signed_a < signed_b,
+            Msleu => a <= b,
+            Msle => signed_a <= signed_b,
+            Msgtu => a > b,
+            Msgt => signed_a > signed_b,
+            _ => unreachable!("{:?} is not a comparison", self)
+        }
+    }
+
+    /// The result of a mask logical operation, where `a` is the bit of vs2
+    fn logical(self, a: bool, b: bool) -> bool {
+        use VectorOp::*;
+        match self {
+            Mandn => a && !b,
+            Mand => a && b,
+            Mor => a || b,
+            Mxor => a ^ b,
+            Morn => a || !b,
+            Mnand => !(a && b),
+            Mnor => !(a || b),
+            Mxnor => a == b,
+            _ => unreachable!("{:?} is not a mask logical operation", self)
+        }
+    }
+}
+
+/// How a vector load or store addresses memory, given by the mop field and for unit-stride accesses the lumop or sumop field
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorAddressing {
+    /// Consecutive elements, with the fields of each segment adjacent
+    UnitStride,
+    /// Segments a byte stride of x[rs2] apart
+    Strided,
+    /// Segments at the byte offsets held in the elements of vs2, which are accessed in order if `ordered`
+    Indexed { ordered: bool },
+    /// Whole registers regardless of vtype and vl, as with VL<nf>RE<eew>.V and VS<nf>R.V
+    WholeRegister,
+    /// The bytes of a mask register holding vl bits, as with VLM.V and VSM.V
+    Mask,
+    /// A unit-stride load which only traps for the first element, instead truncating vl to the elements before a fault
+    FaultOnlyFirst
+}
+
+/// A vector load or store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VectorMemory {
+    pub addressing: VectorAddressing,
+    /// The width in bits of the elements in memory, EEW, or of the offsets of an indexed access whose elements are SEW bits
+    pub width: u32,
+    /// The number of fields in each segment, NFIELDS, or of registers for a whole register access
+    pub fields: usize
+}
+impl VectorMemory {
+    /// Decode a vector load or store, or `None` if the instruction is not one or is reserved.
+    /// A masked load may not overwrite the mask in v0, and whole register and mask accesses may not be masked
+    pub fn decode(instruction: u32) -> Option<Self> {
+        use VectorAddressing::*;
+        let store = instruction & 0x7F == 0b0100111;
+        let vd = (instruction >> 7) as usize & 0x1F;
+        let umop = (instruction >> 20) & 0x1F;
+        let masked = instruction & 1 << 25 == 0;
+        let mop = (instruction >> 26) & 0b11;
+        let mew = instruction & 1 << 28 != 0;
+        let fields = (instruction >> 29) as usize + 1;
+        // The other widths are those of the floating-point loads and stores
+        let width = match (instruction >> 12) & 0b111 {
+            0b000 => 8,
+            0b101 => 16,
+            0b110 => 32,
+            0b111 => 64,
+            _ => return None
+        };
+        let addressing = match mop {
+            0b00 => match umop {
+                0b00000 => UnitStride,
+                0b01000 => WholeRegister,
+                0b01011 => Mask,
+                0b10000 if !store => FaultOnlyFirst,
+                _ => return None
+            },
+            0b01 => Indexed { ordered: false },
+            0b10 => Strided,
+            _ => Indexed { ordered: true }
+        };
+        let legal = match addressing {
+            // Whole register stores only encode bytes
+            WholeRegister => !masked && fields.is_power_of_two() && vd.is_multiple_of(fields) && (!store || width == 8),
+            Mask => !masked && fields == 1 && width == 8,
+            _ => !masked || store || vd != 0
+        };
+        if mew || !legal {
+            return None
+        }
+        Some(Self { addressing, width, fields })
+    }
+}
+
+/// An element accessed by a vector load or store
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElementAccess {
+    /// The index of the element within its register group, which vstart is set to if the access faults
+    pub index: usize,
+    /// The first register of the register group holding the element
+    pub register: usize,
+    /// The width of the element in bits
+    pub width: u32,
+    /// The offset of the element from the base address in x[rs1], which wraps at XLEN
+    pub offset: u64
+}
+
+/// Sign-extend the lowest `bits` of a value
+fn signed(value: u64, bits: u32) -> i64 {
+    ((value << (64 - bits)) as i64) >> (64 - bits)
+}
+
+/// The lowest `sew` bits of a value
+fn truncate(value: u64, sew: u32) -> u64 {
+    if sew == 64 { value } else { value & ((1 << sew) - 1) }
+}
+
+/// The vector registers and the state of the vector CSRs
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vector {
+    /// The 32 vector registers of VLEN bits, one after the other
+    registers: Vec<u8>,
+    /// The number of elements operated on, vl
+    pub vl: u64,
+    /// The element width and register grouping, or `None` while vtype.vill is set as it is at reset
+    pub vtype: Option<Vtype>,
+    /// The index of the first element to operate on, vstart
+    pub vstart: u64,
+    /// The fixed-point rounding mode, vxrm
+    pub vxrm: u8,
+    /// The fixed-point saturation flag, vxsat
+    pub vxsat: bool
+}
+impl Vector {
+    /// A vector unit with registers of `vlen` bits
+    pub fn new(vlen: usize) -> Self {
+        Self {
+            registers: vec![0; vlen / 8 * 32],
+            vl: 0,
+            vtype: None,
+            vstart: 0,
+            vxrm: 0,
+            vxsat: false
+        }
+    }
+
+    /// The length of a vector register in bytes, vlenb
+    pub fn vlenb(&self) -> usize {
+        self.registers.len() / 32
+    }
+
+    /// The bytes of the vector register `v{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    pub fn register(&self, index: usize) -> &[u8] {
+        let length = self.vlenb();
+        &self.registers[index * length..(index + 1) * length]
+    }
+
+    /// Mutable access to the bytes of the vector register `v{index}`
+    /// # Safety
+    /// A panic will occur if index is larger than 31
+    pub fn register_mut(&mut self, index: usize) -> &mut [u8] {
+        let length = self.vlenb();
+        &mut self.registers[index * length..(index + 1) * length]
+    }
+
+    /// The number of elements of a register group with the given element width and grouping, VLMAX
+    pub fn vlmax(&self, vtype: Vtype) -> u64 {
+        let bits = self.vlenb() as u64 * 8;
+        let group = if vtype.lmul >= 0 { bits << vtype.lmul } else { bits >> -vtype.lmul };
+        group / vtype.sew as u64
+    }
+
+    /// Set vtype and vl as the `vset{i}vl{i}` instructions do, returning the new vl.
+    /// `avl` is the requested number of elements, or `None` to keep the current vl
+    pub fn configure(&mut self, avl: Option<u64>, vtype: u64) -> u64 {
+        self.vstart = 0;
+        match Vtype::decode(vtype) {
+            Some(vtype) => {
+                self.vl = avl.unwrap_or(self.vl).min(self.vlmax(vtype));
+                self.vtype = Some(vtype)
+            },
+            None => {
+                self.vl = 0;
+                self.vtype = None
+            }
+        }
+        self.vl
+    }
+
+    /// Element `index` of `sew` bits of the register group starting at `v{register}`
+    /// # Safety
+    /// A panic will occur if the element is beyond the last vector register
+    pub fn element(&self, register: usize, index: usize, sew: u32) -> u64 {
+        let bytes = sew as usize / 8;
+        let offset = register * self.vlenb() + index * bytes;
+        let mut value = [0; 8];
+        value[..bytes].copy_from_slice(&self.registers[offset..offset + bytes]);
+        u64::from_le_bytes(value)
+    }
+
+    /// Set element `index` of `sew` bits of the register group starting at `v{register}` to the lowest bits of `value`
+    /// # Safety
+    /// A panic will occur if the element is beyond the last vector register
+    pub fn set_element(&mut self, register: usize, index: usize, sew: u32, value: u64) {
+        let bytes = sew as usize / 8;
+        let offset = register * self.vlenb() + index * bytes;
+        self.registers[offset..offset + bytes].copy_from_slice(&value.to_le_bytes()[..bytes])
+    }
+
+    /// Bit `index` of the mask register `v{register}`
+    pub fn mask(&self, register: usize, index: usize) -> bool {
+        self.registers[register * self.vlenb() + index / 8] & 1 << (index % 8) != 0
+    }
+
+    /// Set bit `index` of the mask register `v{register}`
+    pub fn set_mask(&mut self, register: usize, index: usize, value: bool) {
+        let offset = register * self.vlenb() + index / 8;
+        let byte = &mut self.registers[offset];
+        *byte = *byte & !(1 << (index % 8)) | (value as u8) << (index % 8)
+    }
+
+    /// Tests if element `index` is active, being either unmasked or enabled by the mask in v0
+    fn active(&self, masked: bool, index: usize) -> bool {
+        !masked || self.mask(0, index)
+    }
+
+    /// The configured vtype and the elements from vstart to vl, or `None` if vtype is illegal
+    fn configuration(&self) -> Option<(Vtype, core::ops::Range<usize>)> {
+        let vtype = self.vtype?;
+        Some((vtype, self.vstart as usize..self.vl.min(self.vlmax(vtype)) as usize))
+    }
+
+    /// Execute an integer arithmetic instruction, returning false if it is illegal under the current vtype.
+    /// The elements from vstart to vl are operated on, after which vstart is reset
+    pub fn execute(&mut self, op: VectorOp, vd: usize, vs2: usize, operand: VectorOperand<u64>, masked: bool) -> bool {
+        let (vtype, elements) = match self.configuration() {
+            Some(configuration) if op.is_legal(vd, vs2, masked) => configuration,
+            _ => return false
+        };
+        let (sew, group) = (vtype.sew, vtype.group());
+        // Register groups must start at a multiple of their size
+        let aligned = |register: usize| register.is_multiple_of(group);
+        let value = |vector: &Self, index: usize| match operand {
+            VectorOperand::Vector(vs1) => vector.element(vs1, index, sew),
+            VectorOperand::Scalar(value) => value,
+            VectorOperand::Immediate(immediate) => op.immediate(immediate) as u64
+        };
+        let vs1 = match operand {
+            VectorOperand::Vector(vs1) => Some(vs1),
+            _ => None
+        };
+
+        match op.kind() {
+            VectorKind::Elementwise => {
+                if !aligned(vd) || !aligned(vs2) || !vs1.is_none_or(aligned) {
+                    return false
+                }
+                for index in elements {
+                    let active = self.active(masked, index);
+                    let result = match op {
+                        VectorOp::Merge if active => value(self, index),
+                        VectorOp::Merge => self.element(vs2, index, sew),
+                        _ if active => op.apply(self.element(vs2, index, sew), value(self, index), sew),
+                        _ => continue
+                    };
+                    self.set_element(vd, index, sew, result)
+                }
+            },
+            VectorKind::Compare => {
+                if !aligned(vs2) || !vs1.is_none_or(aligned) {
+                    return false
+                }
+                // The destination mask register may overlap a source, so every comparison is made before it is written
+                let results: Vec<_> = elements
+                    .filter(|&index| self.active(masked, index))
+                    .map(|index| (index, op.compare(self.element(vs2, index, sew), value(self, index), sew)))
+                    .collect();
+                for (index, result) in results {
+                    self.set_mask(vd, index, result)
+                }
+            },
+            VectorKind::Reduction => {
+                let vs1 = match vs1 {
+                    Some(vs1) if aligned(vs2) && self.vstart == 0 => vs1,
+                    _ => return false
+                };
+                // Without any elements the destination is not written
+                if !elements.is_empty() {
+                    let result = elements
+                        .filter(|&index| self.active(masked, index))
+                        .fold(self.element(vs1, 0, sew), |result, index| op.apply(self.element(vs2, index, sew), result, sew));
+                    self.set_element(vd, 0, sew, result)
+                }
+            },
+            VectorKind::Mask => {
+                let vs1 = match vs1 {
+                    Some(vs1) => vs1,
+                    None => return false
+                };
+                let results: Vec<_> = elements.map(|index| (index, op.logical(self.mask(vs2, index), self.mask(vs1, index)))).collect();
+                for (index, result) in results {
+                    self.set_mask(vd, index, result)
+                }
+            }
+        }
+        self.vstart = 0;
+        true
+    }
+
+    /// Element 0 of `v{vs2}` sign-extended, as VMV.X.S moves to an integer register, or `None` if vtype is illegal
+    pub fn move_to_scalar(&self, vs2: usize) -> Option<u64> {
+        let sew = self.vtype?.sew;
+        Some(signed(self.element(vs2, 0, sew), sew) as u64)
+    }
+
+    /// Set element 0 of `v{vd}` to `value` unless vstart is at or beyond vl, as VMV.S.X does.
+    /// Returns false if vtype is illegal
+    pub fn move_from_scalar(&mut self, vd: usize, value: u64) -> bool {
+        let (vtype, elements) = match self.configuration() {
+            Some(configuration) => configuration,
+            None => return false
+        };
+        if !elements.is_empty() {
+            self.set_element(vd, 0, vtype.sew, value)
+        }
+        self.vstart = 0;
+        true
+    }
+
+    /// The number of active set bits of the mask register `v{vs2}`, as counted by VCPOP.M, or `None` if it is illegal
+    pub fn count(&self, vs2: usize, masked: bool) -> Option<u64> {
+        match self.configuration()? {
+            (_, elements) if elements.start == 0 => Some(elements.filter(|&index| self.active(masked, index) && self.mask(vs2, index)).count() as u64),
+            _ => None
+        }
+    }
+
+    /// The index of the first active set bit of the mask register `v{vs2}`, or all ones if there is none, as found by
+    /// VFIRST.M. `None` if it is illegal
+    pub fn first(&self, vs2: usize, masked: bool) -> Option<u64> {
+        match self.configuration()? {
+            (_, mut elements) if elements.start == 0 => Some(elements.find(|&index| self.active(masked, index) && self.mask(vs2, index)).map_or(u64::MAX, |index| index as u64)),
+            _ => None
+        }
+    }
+
+    /// The elements a load or store to or from `v{vd}` accesses in order, from vstart to vl, or `None` if it is illegal
+    /// under the current vtype. Inactive elements are not accessed.
+    /// `vs2` holds the offsets of an indexed access and `stride` is the byte stride of a strided access
+    pub fn accesses(&self, access: VectorMemory, vd: usize, vs2: usize, masked: bool, stride: u64) -> Option<Vec<ElementAccess>> {
+        let start = self.vstart as usize;
+        let element = |index: usize, register: usize, width: u32, offset: u64| ElementAccess { index, register, width, offset };
+        if access.addressing == VectorAddressing::WholeRegister {
+            // The register group is accessed as one long register, ignoring vtype
+            let count = access.fields * self.vlenb() * 8 / access.width as usize;
+            return Some((start..count).map(|index| element(index, vd, access.width, (index * access.width as usize / 8) as u64)).collect())
+        }
+        let (vtype, elements) = self.configuration()?;
+        if access.addressing == VectorAddressing::Mask {
+            return Some((start..elements.end.div_ceil(8)).map(|index| element(index, vd, 8, index as u64)).collect())
+        }
+
+        // The elements of an indexed access are SEW bits, with the offsets in a register group of the same number of elements
+        let (width, emul) = match access.addressing {
+            VectorAddressing::Indexed { .. } => {
+                if !vs2.is_multiple_of(group(vtype.emul(access.width)?)) {
+                    return None
+                }
+                (vtype.sew, vtype.lmul)
+            },
+            _ => (access.width, vtype.emul(access.width)?)
+        };
+        let group = group(emul);
+        if access.fields * group > 8 || vd + access.fields * group > 32 || !vd.is_multiple_of(group) {
+            return None
+        }
+        let bytes = width as u64 / 8;
+        Some(elements.filter(|&index| self.active(masked, index)).flat_map(|index| (0..access.fields).map(move |field| {
+            let segment = match access.addressing {
+                VectorAddressing::Strided => (index as u64).wrapping_mul(stride),
+                VectorAddressing::Indexed { .. } => self.element(vs2, index, access.width),
+                _ => index as u64 * access.fields as u64 * bytes
+            };
+            element(index, vd + field * group, width, segment.wrapping_add(field as u64 * bytes))
+        })).collect())
+    }
+
+    /// Write the index of each active element to the element itself, as VID.V does. Returns false if it is illegal
+    pub fn index(&mut self, vd: usize, masked: bool) -> bool {
+        let (vtype, elements) = match self.configuration() {
+            Some(configuration) if vd.is_multiple_of(configuration.0.group()) && (!masked || vd != 0) => configuration,
+            _ => return false
+        };
+        for index in elements {
+            if self.active(masked, index) {
+                self.set_element(vd, index, vtype.sew, index as u64)
+            }
+        }
+        self.vstart = 0;
+        true
+    }
+}
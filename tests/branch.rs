@@ -0,0 +1,45 @@
+mod common;
+
+use common::*;
+
+/// Encode a conditional branch comparing `source1` and `source2`
+fn branch(funct3: u32, source1: u32, source2: u32, offset: i32) -> u32 {
+    let immediate = offset as u32;
+    ((immediate >> 12) & 1) << 31
+        | ((immediate >> 5) & 0x3F) << 25
+        | source2 << 20
+        | source1 << 15
+        | funct3 << 12
+        | ((immediate >> 1) & 0xF) << 8
+        | ((immediate >> 11) & 1) << 7
+        | 0b1100011
+}
+
+/// Execute a branch comparing x5 with itself, returning the resulting program counter
+fn branch_identical(funct3: u32, value: u32) -> u32 {
+    let mut memory = Memory::new(&[branch(funct3, 5, 5, 16)]);
+    let mut core = core32();
+    core.set(5, value.into());
+    core.execute(&mut memory);
+    u32::from_le_bytes(core.pc.0)
+}
+
+#[test]
+fn branch_identical_registers() {
+    const BEQ: u32 = 0b000;
+    const BNE: u32 = 0b001;
+    const BLT: u32 = 0b100;
+    const BGE: u32 = 0b101;
+    const BLTU: u32 = 0b110;
+    const BGEU: u32 = 0b111;
+
+    // Negative values would be ordered differently by signed and unsigned comparisons against other values
+    for &value in &[0, 1, 0x7FFF_FFFF, 0x8000_0000, 0xFFFF_FFFB] {
+        assert_eq!(branch_identical(BEQ, value), 16);
+        assert_eq!(branch_identical(BNE, value), 4);
+        assert_eq!(branch_identical(BLT, value), 4);
+        assert_eq!(branch_identical(BGE, value), 16);
+        assert_eq!(branch_identical(BLTU, value), 4);
+        assert_eq!(branch_identical(BGEU, value), 16);
+    }
+}
@@ -0,0 +1,80 @@
+//! Helpers shared between the integration tests
+#![allow(dead_code)]
+
+use rysk_core::*;
+
+/// Create a 32-bit core starting at address 0
+#[cfg(feature = "ext-csr")]
+pub fn core32() -> Core<Register32> {
+    Core::new(0, 0)
+}
+#[cfg(not(feature = "ext-csr"))]
+pub fn core32() -> Core<Register32> {
+    Core::new(0)
+}
+
+/// Create a 32-bit core with the 16 registers of RV32E starting at address 0
+#[cfg(feature = "ext-csr")]
+pub fn core32e() -> Core<Register32, 16> {
+    Core::new(0, 0)
+}
+#[cfg(not(feature = "ext-csr"))]
+pub fn core32e() -> Core<Register32, 16> {
+    Core::new(0)
+}
+
+/// Create a 64-bit core starting at address 0
+#[cfg(feature = "ext-csr")]
+pub fn core64() -> Core<Register64> {
+    Core::new(0, 0)
+}
+#[cfg(not(feature = "ext-csr"))]
+pub fn core64() -> Core<Register64> {
+    Core::new(0)
+}
+
+/// Create a 128-bit core starting at address 0
+#[cfg(feature = "ext-csr")]
+pub fn core128() -> Core<Register128> {
+    Core::new(0, 0)
+}
+#[cfg(not(feature = "ext-csr"))]
+pub fn core128() -> Core<Register128> {
+    Core::new(0)
+}
+
+/// Memory holding `instructions` from address 0
+pub fn program(instructions: &[u32]) -> ArrayRam<0x200> {
+    let mut memory = ArrayRam::new(0);
+    for (index, instruction) in instructions.iter().enumerate() {
+        memory.load(index * 4, &instruction.to_le_bytes());
+    }
+    memory
+}
+
+/// Flat memory where accesses at or above `slow` take `latency` extra cycles
+pub struct Memory {
+    pub bytes: Vec<u8>,
+    pub slow: u32,
+    pub latency: u64
+}
+impl Memory {
+    pub fn new(program: &[u32]) -> Self {
+        let mut bytes = vec![0; 0x2000];
+        for (index, instruction) in program.iter().enumerate() {
+            bytes[index * 4..index * 4 + 4].copy_from_slice(&instruction.to_le_bytes());
+        }
+        Self { bytes, slow: u32::MAX, latency: 0 }
+    }
+}
+impl Mmu<Register32> for Memory {
+    fn get(&self, address: u32) -> u8 {
+        self.bytes[address as usize]
+    }
+    fn set(&mut self, address: u32, value: u8) {
+        self.bytes[address as usize] = value
+    }
+    fn latency(&self, address: u32) -> u64 {
+        if address >= self.slow { self.latency } else { 0 }
+    }
+}
@@ -0,0 +1,465 @@
+#[cfg(feature = "ext-csr")]
+mod common;
+
+#[cfg(feature = "ext-csr")]
+mod csr_tests {
+
+    use super::common::*;
+    use rysk_core::{ csr::{ self, Csr, HpmEvent::*, Interrupt }, system, HostReturn, Register32, Register64 };
+    #[test]
+    fn test_interrupt_pending() {
+        let mut csr = Csr::<Register32>::new(0, 0);
+        csr.set_mtip(true);
+        assert_eq!(csr.mip, (1u32 << csr::MTIP).into());
+        csr.set_meip(true);
+        csr.set_mtip(false);
+        assert_eq!(csr.mip, (1u32 << csr::MEIP).into());
+    }
+
+    #[test]
+    fn test_read_only_interrupt_bits() {
+        // csrrw x0, mip, x1; csrrw x0, mie, x1; csrrs x2, mip, x0; csrrs x3, mie, x0
+        let mut memory = Memory::new(&[0x3440_9073, 0x3040_9073, 0x3440_2173, 0x3040_21F3]);
+        let mut core = core32();
+        core.set(1, 0xFFFF_FFFFu32.into());
+        for _ in 0..4 {
+            core.execute(&mut memory);
+        }
+        // Software may not make interrupts pending
+        assert_eq!(core.get(2), 0u32.into());
+        // Only machine interrupts may be enabled
+        assert_eq!(core.get(3), (1u32 << csr::MSIP | 1 << csr::MTIP | 1 << csr::MEIP).into());
+    }
+
+    #[test]
+    fn test_mcycle_overflow() {
+        // addi x0, x0, 0
+        let mut memory = Memory::new(&[0x0000_0013]);
+        let mut core = core32();
+        core.csr_mut().mcycle = Register64(0xFFFF_FFFFu64.to_le_bytes());
+        assert_eq!(core.get_csr(0xB00).unwrap(), 0xFFFF_FFFFu32.into());
+        assert_eq!(core.get_csr(0xB80).unwrap(), 0u32.into());
+
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0xB00).unwrap(), 0u32.into());
+        assert_eq!(core.get_csr(0xB80).unwrap(), 1u32.into());
+    }
+
+    #[test]
+    fn test_vectored_mtvec() {
+        // Illegal instruction
+        let mut memory = Memory::new(&[0x0000_0000]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x1001u32.into();
+
+        // Interrupts are vectored by their cause
+        core.take_interrupt(csr::MTIP);
+        assert_eq!(core.pc, (0x1000u32 + 28).into());
+        assert_eq!(core.get_csr(0x342).unwrap(), (0x8000_0000u32 | 7).into());
+
+        // Exceptions always go to the base
+        core.pc = 0u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x1000u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+
+        // Direct mode sends interrupts to the base
+        core.csr_mut().mtvec = 0x1000u32.into();
+        core.take_interrupt(csr::MTIP);
+        assert_eq!(core.pc, 0x1000u32.into());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_csr_hook() {
+        use std::{ cell::RefCell, rc::Rc };
+        // csrrw x0, mtvec, x1; csrrs x2, mtvec, x0; csrrw x0, mtvec, x3
+        let mut memory = Memory::new(&[0x3050_9073, 0x3050_2173, 0x3051_9073]);
+        let mut core = core32();
+        let accesses = Rc::new(RefCell::new(Vec::new()));
+        let recorded = accesses.clone();
+        core.set_csr_hook(move |index, is_write, value| recorded.borrow_mut().push((index, is_write, value)));
+        core.set(1, 0x1001u32.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(*accesses.borrow(), vec![(0x305, true, 0x1001u32.into()), (0x305, false, 0x1001u32.into())]);
+        assert_eq!(core.get(2), 0x1001u32.into());
+        // The reserved mode is masked from mtvec, and the hook sees the value stored
+        core.set(3, 0x2003u32.into());
+        core.execute(&mut memory);
+        assert_eq!(accesses.borrow()[2], (0x305, true, 0x2001u32.into()));
+
+        core.clear_csr_hook();
+        core.pc = 0u32.into();
+        core.execute(&mut memory);
+        assert_eq!(accesses.borrow().len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "test-util")]
+    fn test_enter_trap_for_test() {
+        let mut program = vec![0; 16];
+        program.extend_from_slice(&[
+            // Illegal instruction handler at 0x40, skipping the instruction
+            0x3420_2573, // csrrs x10, mcause, x0
+            0x3430_25F3, // csrrs x11, mtval, x0
+            0x3410_2673, // csrrs x12, mepc, x0
+            0x0046_0613, // addi x12, x12, 4
+            0x3416_1073, // csrrw x0, mepc, x12
+            0x3020_0073, // mret
+        ]);
+        let mut memory = Memory::new(&program);
+        let mut core = core32().with_trap_strategy(HostReturn);
+        core.set_csr(0x305, 0x40u32.into());
+        core.pc = 8u32.into();
+
+        core.enter_trap_for_test(system::Trap::IllegalInstruction, 0xFFFF_FFFFu32.into());
+        assert_eq!(core.pc, 0x40u32.into());
+        for _ in 0..6 {
+            assert_eq!(core.execute(&mut memory), None);
+        }
+        assert_eq!(core.get(10), 2u32.into());
+        assert_eq!(core.get(11), 0xFFFF_FFFFu32.into());
+        assert_eq!(core.pc, 12u32.into());
+        assert_eq!(core.csr().privilege, csr::Privilege::Machine);
+    }
+
+    #[test]
+    fn test_ecall_mret() {
+        let mut program = vec![
+            0x0400_0293, // addi x5, x0, 0x40
+            0x3052_9073, // csrrw x0, mtvec, x5
+            0x3004_6073, // csrrsi x0, mstatus, 8
+            0x0000_0073, // ecall
+            0x0010_0313, // addi x6, x0, 1
+            0x3000_26F3, // csrrs x13, mstatus, x0
+        ];
+        program.resize(16, 0);
+        program.extend_from_slice(&[
+            // Handler at 0x40
+            0x3420_2573, // csrrs x10, mcause, x0
+            0x3410_25F3, // csrrs x11, mepc, x0
+            0x3000_2673, // csrrs x12, mstatus, x0
+            0x0045_8593, // addi x11, x11, 4
+            0x3415_9073, // csrrw x0, mepc, x11
+            0x3020_0073, // mret
+        ]);
+        let mut memory = Memory::new(&program);
+        let mut core = core32();
+        for _ in 0..4 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.pc, 0x40u32.into());
+        for _ in 0..6 {
+            core.execute(&mut memory);
+        }
+        // Environment call from M-mode at the ecall
+        assert_eq!(core.get(10), 11u32.into());
+        assert_eq!(core.get(11), 0x10u32.into());
+        // Interrupts were disabled in the handler with the previous enable stacked into MPIE, and MPP is machine mode
+        assert_eq!(core.get(12), 0x1880u32.into());
+        // Returned to the instruction following the ecall
+        assert_eq!(core.pc, 0x10u32.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(6), 1u32.into());
+        // MRET leaves MPP holding user mode
+        assert_eq!(core.get(13), 0x88u32.into());
+    }
+
+    #[test]
+    fn test_illegal_csr_access() {
+        // csrrw x0, mhartid, x1; csrrs x1, 0x7FF, x0
+        let mut memory = Memory::new(&[0xF140_9073, 0x7FF0_20F3]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x100u32.into();
+        // Writes to read-only CSRs are illegal
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x100u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+        assert_eq!(core.get_csr(0x341).unwrap(), 0u32.into());
+        // As are accesses to CSRs which do not exist
+        core.pc = 4u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x100u32.into());
+        assert_eq!(core.get_csr(0x341).unwrap(), 4u32.into());
+    }
+
+    #[test]
+    fn test_read_only_set_clear() {
+        // csrrs x5, cycle, x0; csrrc x6, instret, x0; csrrs x5, cycle, x1
+        let mut memory = Memory::new(&[0xC000_22F3, 0xC020_3373, 0xC000_A2F3]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x100u32.into();
+        core.set_csr(0xB00, 7u32.into());
+
+        // Without a source register CSRRS and CSRRC only read, so are legal for read-only CSRs
+        core.execute(&mut memory);
+        // cycle already includes the reading instruction, while instret counts it only once it retires
+        assert_eq!(core.get(5), 8u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(6), 1u32.into());
+        assert_eq!(core.pc, 8u32.into());
+
+        // Any other source register attempts a write, even if x1 is zero
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x100u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+    }
+
+    #[test]
+    fn test_trapped_instructions_do_not_retire() {
+        // An illegal instruction; ecall
+        let mut memory = Memory::new(&[0x0000_0000, 0x0000_0073]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x100u32.into();
+
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+        core.pc = 4u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0x342).unwrap(), 11u32.into());
+        assert_eq!(core.pc, 0x100u32.into());
+        assert_eq!(core.get_csr(0xB00).unwrap(), 2u32.into());
+        assert_eq!(core.get_csr(0xB02).unwrap(), 0u32.into());
+    }
+
+    #[test]
+    fn test_user_counters() {
+        let mut core = core32();
+        core.csr_mut().privilege = csr::Privilege::User;
+        assert!(core.get_csr(0xC00).is_err());
+        core.set_csr(0x306, 1u32.into());
+        assert!(core.get_csr(0xC00).is_ok());
+        // instret is still disabled
+        assert!(core.get_csr(0xC02).is_err());
+        assert!(core.get_csr(0xC82).is_err());
+    }
+
+    #[test]
+    fn test_hpm_events() {
+        // lw a1, 0x400(x0); sw a1, 0x404(x0); bne x0, x0, 8; beq x0, x0, 8; ecall
+        let mut memory = Memory::new(&[0x4000_2583, 0x40B0_2223, 0x0000_1463, 0x0000_0463, 0, 0x0000_0073]);
+        let mut core = core32().with_trap_strategy(HostReturn);
+        for (counter, event) in [(3, Load), (4, Store), (5, Branch), (6, TakenBranch), (7, Exception)] {
+            core.set_csr(0x320 + counter, (event as u32).into());
+        }
+        // Unsupported events stop the counter
+        core.set_csr(0x328, 99u32.into());
+        assert_eq!(core.get_csr(0x328).unwrap(), 0u32.into());
+        for _ in 0..5 {
+            core.execute(&mut memory);
+        }
+        let counters = (0xB03..=0xB08).map(|index| core.get_csr(index).unwrap()).collect::<Vec<_>>();
+        assert_eq!(counters, [1u32, 1, 2, 1, 1, 0].map(Register32::from));
+
+        // The counters are 64 bits wide, and readable in user mode once enabled as with the fixed counters
+        core.set_csr(0xB83, 1u32.into());
+        core.csr_mut().privilege = csr::Privilege::User;
+        assert!(core.get_csr(0xC83).is_err());
+        core.set_csr(0x306, (1u32 << 3).into());
+        assert_eq!((core.get_csr(0xC03).unwrap(), core.get_csr(0xC83).unwrap()), (1u32.into(), 1u32.into()));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_hpm_core_dump() {
+        let memory = Memory::new(&[]);
+        let mut core = core32();
+        core.set_csr(0x323, (Load as u32).into());
+        core.set_csr(0x33F, (Branch as u32).into());
+        core.set_csr(0xB03, 5u32.into());
+        core.set_csr(0xB9F, 7u32.into());
+        let dump = core.core_dump(&memory, None);
+
+        let mut restored = core32();
+        assert_eq!(restored.load_core_dump(&dump, &mut Memory::new(&[])), Ok(()));
+        assert!(core.diff(&restored).is_empty());
+        assert_eq!(restored.get_csr(0x33F).unwrap(), (Branch as u32).into());
+        assert_eq!((restored.get_csr(0xB03).unwrap(), restored.get_csr(0xB9F).unwrap()), (5u32.into(), 7u32.into()));
+
+        // A counter left unrestored is reported by diff
+        restored.set_csr(0xB1F, 1u32.into());
+        assert_eq!(core.diff(&restored).csrs, [(0xB1F, 0u32.into(), 1u32.into())]);
+    }
+
+    #[test]
+    fn test_time() {
+        // rdtime a0; rdtimeh a1
+        let mut memory = Memory::new(&[0xC010_2573, 0xC810_25F3]);
+        let mut core = core32().with_trap_strategy(HostReturn);
+        core.set_time(0x1_0000_0002);
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!((core.get(10), core.get(11)), (2u32.into(), 1u32.into()));
+
+        // User mode may only read time once enabled by bit 1 of mcounteren
+        core.csr_mut().update_timer(3, 10);
+        core.csr_mut().privilege = csr::Privilege::User;
+        core.pc = 0u32.into();
+        assert_eq!(core.execute(&mut memory), Some(system::Trap::IllegalInstruction));
+        core.set_csr(0x306, 0b10u32.into());
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.get(10), 3u32.into());
+    }
+
+    #[test]
+    fn test_wfi_timeout_wait() {
+        // wfi
+        let mut memory = Memory::new(&[0x1050_0073]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x100u32.into();
+
+        // Completes immediately in user mode while TW is clear
+        core.csr_mut().privilege = csr::Privilege::User;
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 4u32.into());
+
+        core.pc = 0u32.into();
+        core.csr_mut().write_mstatus((1u32 << csr::MSTATUS_TW).into());
+        assert!(core.csr().timeout_wait());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x100u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+
+        // Machine mode is unaffected
+        core.pc = 0u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 4u32.into());
+    }
+
+    #[test]
+    fn test_deferred_interrupt() {
+        // addi x0, x0, 0; csrrsi x0, mstatus, 8; addi x0, x0, 0
+        let mut memory = Memory::new(&[0x0000_0013, 0x3004_6073, 0x0000_0013]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x101u32.into();
+        core.csr_mut().write_mie((1u32 << csr::MTIP | 1 << csr::MEIP).into());
+        core.raise_interrupt(Interrupt::Timer);
+
+        // Deferred while interrupts are globally disabled
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 4u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 8u32.into());
+
+        core.execute(&mut memory);
+        assert_eq!(core.pc, (0x100u32 + 4 * csr::MTIP as u32).into());
+        assert_eq!(core.get_csr(0x341).unwrap(), 8u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), (0x8000_0000u32 | csr::MTIP as u32).into());
+        // Interrupts are disabled within the handler
+        assert_eq!(core.csr().pending_interrupt(), None);
+    }
+
+    #[test]
+    fn test_nmi() {
+        // addi x1, x0, 1; addi x2, x0, 2
+        let mut memory = Memory::new(&[0x0010_0093, 0x0020_0113]);
+        let mut core = core32().with_nmi_vector(0x400);
+        core.csr_mut().write_mstatus((1u32 << csr::MSTATUS_MIE).into());
+        core.execute(&mut memory);
+
+        // Taken regardless of the interrupt enables
+        core.csr_mut().write_mie(0u32.into());
+        core.nmi();
+        assert_eq!(core.pc, 0x400u32.into());
+        assert_eq!(core.get_csr(0x341).unwrap(), 4u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), 0x8000_0000u32.into());
+        assert_eq!(core.csr().privilege, csr::Privilege::Machine);
+        assert!(!core.csr().interrupts_enabled());
+        assert_eq!(core.get(1), 1u32.into());
+        assert_eq!(core.get(2), 0u32.into());
+    }
+
+    #[test]
+    fn test_external_line() {
+        // addi x0, x0, 0
+        let mut memory = Memory::new(&[0x0000_0013]);
+        let mut core = core32();
+        core.csr_mut().mtvec = 0x100u32.into();
+        core.csr_mut().write_mstatus((1u32 << csr::MSTATUS_MIE).into());
+        core.csr_mut().write_mie((1u32 << csr::MEIP).into());
+
+        core.set_external_pending(true);
+        assert_eq!(core.get_csr(0x344).unwrap(), (1u32 << csr::MEIP).into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x100u32.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), (0x8000_0000u32 | csr::MEIP as u32).into());
+
+        // Lines stay pending until released by the device
+        core.set_external_pending(false);
+        core.set_timer_pending(true);
+        core.set_software_pending(true);
+        assert_eq!(core.get_csr(0x344).unwrap(), (1u32 << csr::MTIP | 1 << csr::MSIP).into());
+    }
+
+    #[test]
+    fn test_interrupt_priority() {
+        let mut core = core32();
+        core.csr_mut().write_mstatus((1u32 << csr::MSTATUS_MIE).into());
+        core.csr_mut().write_mie((1u32 << csr::MSIP | 1 << csr::MTIP).into());
+        assert_eq!(core.csr().pending_interrupt(), None);
+        core.raise_interrupt(Interrupt::Timer);
+        core.raise_interrupt(Interrupt::Software);
+        // Not enabled in mie
+        core.raise_interrupt(Interrupt::External);
+        assert_eq!(core.csr().pending_interrupt(), Some(csr::MSIP));
+        core.clear_interrupt(Interrupt::Software);
+        assert_eq!(core.csr().pending_interrupt(), Some(csr::MTIP));
+
+        core.csr_mut().update_timer(10, 20);
+        assert_eq!(core.csr().pending_interrupt(), None);
+        core.csr_mut().update_timer(20, 20);
+        assert_eq!(core.csr().pending_interrupt(), Some(csr::MTIP));
+    }
+
+    #[test]
+    fn test_counter_writes() {
+        // csrrw x0, mcycle, x0; csrrw x0, minstreth, x1; addi x0, x0, 0
+        let mut memory = Memory::new(&[0xB000_1073, 0xB820_9073, 0x0000_0013]);
+        let mut core = core32();
+        core.csr_mut().mcycle = Register64(0x1234u64.to_le_bytes());
+        core.set(1, 5u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0xB00).unwrap(), 0u32.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        // The counters continue from the written values
+        assert_eq!(core.get_csr(0xB00).unwrap(), 2u32.into());
+        // Writing the high half leaves the low half counting
+        assert_eq!(core.get_csr(0xB02).unwrap(), 3u32.into());
+        assert_eq!(core.get_csr(0xB82).unwrap(), 5u32.into());
+        assert_eq!(core.csr().cycles(), 2);
+    }
+
+    #[test]
+    fn test_vectored_mtvec_rv64() {
+        let mut core = rysk_core::Core::<Register64>::new(0, 0);
+        core.csr_mut().mtvec = Register64(0x1_0000_1001u64.to_le_bytes());
+        core.take_interrupt(csr::MTIP);
+        // The upper half of the base is kept
+        assert_eq!(core.pc, Register64(0x1_0000_101Cu64.to_le_bytes()));
+    }
+
+    #[test]
+    fn test_mstatush() {
+        // csrrw x0, mstatush, x1; csrrs x2, mstatush, x0
+        let mut memory = Memory::new(&[0x3100_9073, 0x3100_2173]);
+        let mut core = core32();
+
+        // SBE is hardwired to zero without supervisor mode
+        core.set(1, (1u32 << 4 | 1 << csr::MSTATUSH_MBE).into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(2), (1u32 << csr::MSTATUSH_MBE).into());
+        assert!(core.csr().machine_big_endian());
+        // The low half is unaffected
+        assert_eq!(core.get_csr(0x300).unwrap(), (0b11u32 << csr::MSTATUS_MPP).into());
+
+        // On RV64 the fields are held in mstatus
+        let mut core = rysk_core::Core::<Register64>::new(0, 0);
+        assert!(core.get_csr(0x310).is_err());
+        core.csr_mut().write_mstatus(Register64((1u64 << csr::MSTATUS_MBE).to_le_bytes()));
+        assert!(core.csr().machine_big_endian());
+    }
+}
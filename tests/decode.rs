@@ -0,0 +1,389 @@
+use rysk_core::*;
+
+/// A small xorshift generator so the sample is reproducible without dependencies
+struct XorShift(u32);
+impl Iterator for XorShift {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        Some(self.0)
+    }
+}
+
+#[cfg(feature = "ext-csr")]
+fn core<R: Register>() -> Core<R> {
+    Core::new(Default::default(), Default::default())
+}
+#[cfg(not(feature = "ext-csr"))]
+fn core<R: Register>() -> Core<R> {
+    Core::new(Default::default())
+}
+
+/// Execute each word as the only instruction of a fresh core
+fn execute_all<R: Register>(words: impl Iterator<Item = u32>) {
+    for word in words {
+        let mut memory = ArrayRam::<64>::new(0);
+        memory.load(0, &word.to_le_bytes());
+        let mut core = core::<R>();
+        core.execute(&mut memory);
+    }
+}
+
+#[test]
+fn execute_random_words() {
+    execute_all::<Register32>(XorShift(0x1234_5678).take(100_000));
+    execute_all::<Register64>(XorShift(0x8765_4321).take(100_000));
+    execute_all::<Register128>(XorShift(0x0F1E_2D3C).take(100_000));
+}
+
+#[test]
+fn execute_every_opcode() {
+    // Every opcode and funct3 with both all-zero and all-one remaining fields
+    let words = (0..0x400u32).flat_map(|fields| {
+        let opcode = fields & 0x7F;
+        let funct3 = (fields >> 7) << 12;
+        vec![opcode | funct3, opcode | funct3 | !0x707F]
+    });
+    execute_all::<Register32>(words.clone());
+    execute_all::<Register64>(words.clone());
+    execute_all::<Register128>(words);
+}
+
+/// Check that `decode` rejects exactly the words `execute` traps on as illegal
+fn decode_agrees<R: Register + std::fmt::Debug>(words: impl Iterator<Item = u32>) {
+    use rysk_core::{ decode::decode, system::Trap };
+    for word in words {
+        // Compressed instructions are executed once expanded, so are only decoded after expansion
+        #[cfg(feature = "ext-c")]
+        {
+            if word & 0b11 != 0b11 {
+                continue
+            }
+        }
+        let mut memory = ArrayRam::<64>::new(0);
+        memory.load(0, &word.to_le_bytes());
+        let mut core = core::<R>().with_trap_strategy(HostReturn);
+        // Vector instructions are illegal until vtype is configured, so configure the smallest elements without grouping
+        #[cfg(feature = "ext-v")]
+        core.vector_mut().configure(Some(0), 0);
+        let illegal = core.execute(&mut memory) == Some(Trap::IllegalInstruction);
+        let decoded = decode::<R>(word.to_le_bytes());
+        // Accesses to CSRs which do not exist decode, as whether a CSR exists depends on the core
+        let csr = word & 0x7F == 0b1110011 && word & 0x7000 != 0;
+        // Vector loads and stores decode regardless of whether their width is legal under vtype
+        let vector = cfg!(feature = "ext-v") && matches!(word & 0x7F, 0b0000111 | 0b0100111) && matches!((word >> 12) & 0b111, 0b000 | 0b101..=0b111);
+        assert!(decoded.is_err() == illegal || (csr || vector) && decoded.is_ok(), "{:#010X} decoded as {:?}", word, decoded);
+    }
+}
+
+#[test]
+fn decode_matches_execute() {
+    decode_agrees::<Register32>(XorShift(0x1234_5678).take(100_000));
+    decode_agrees::<Register64>(XorShift(0x8765_4321).take(100_000));
+    decode_agrees::<Register128>(XorShift(0x0F1E_2D3C).take(100_000));
+    let words = (0..0x400u32).flat_map(|fields| {
+        let opcode = fields & 0x7F;
+        let funct3 = (fields >> 7) << 12;
+        vec![opcode | funct3, opcode | funct3 | !0x707F]
+    });
+    decode_agrees::<Register32>(words.clone());
+    decode_agrees::<Register64>(words.clone());
+    decode_agrees::<Register128>(words);
+}
+
+#[test]
+fn decode_formats() {
+    use rysk_core::decode::{ decode, Instruction };
+    let decode32 = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap();
+
+    // R
+    assert_eq!(decode32(0x00B5_0533), Instruction::Add { rd: 10, rs1: 10, rs2: 11 });
+    assert_eq!(decode32(0x40B5_0533).to_string(), "sub a0, a0, a1");
+    // I
+    assert_eq!(decode32(0xFF01_0113), Instruction::Addi { rd: 2, rs1: 2, imm: (-16).into() });
+    assert_eq!(decode32(0xFF01_0113).to_string(), "addi sp, sp, -16");
+    assert_eq!(decode32(0x0041_2503).to_string(), "lw a0, 4(sp)");
+    assert_eq!(decode32(0x4015_5513), Instruction::Srai { rd: 10, rs1: 10, shamt: 1 });
+    assert_eq!(decode32(0x0000_8067).to_string(), "jalr zero, 0(ra)");
+    // S
+    assert_eq!(decode32(0x00A1_2223), Instruction::Sw { rs1: 2, rs2: 10, imm: 4.into() });
+    assert_eq!(decode32(0x00A1_2223).to_string(), "sw a0, 4(sp)");
+    // B
+    assert_eq!(decode32(0xFE05_9CE3), Instruction::Bne { rs1: 11, rs2: 0, imm: (-8).into() });
+    assert_eq!(decode32(0xFE05_9CE3).to_string(), "bne a1, zero, -8");
+    // U
+    assert_eq!(decode32(0x1234_5537), Instruction::Lui { rd: 10, imm: 0x1234_5000u32.into() });
+    assert_eq!(decode32(0x1234_5537).to_string(), "lui a0, 0x12345");
+    // J
+    assert_eq!(decode32(0x0100_00EF), Instruction::Jal { rd: 1, imm: 16.into() });
+    assert_eq!(decode32(0x0100_00EF).to_string(), "jal ra, 16");
+
+    assert_eq!(decode32(0x0000_0073).to_string(), "ecall");
+    assert_eq!(decode32(0x0310_000F), Instruction::Fence { fm: 0, pred: 0b0011, succ: 0b0001 });
+    assert_eq!(decode32(0x0310_000F).to_string(), "fence rw, w");
+    assert_eq!(decode32(0x0FF0_000F).to_string(), "fence");
+    assert_eq!(decode32(0x8330_000F).to_string(), "fence.tso");
+    assert_eq!(decode32(0x0100_000F).to_string(), "pause");
+    assert_eq!(decode32(0x0000_100F).to_string(), "fence.i");
+    assert!(decode::<Register32>([0; 4]).is_err());
+    // Shift amounts of 32 and above are reserved on RV32
+    assert!(decode::<Register32>(0x0200_9093u32.to_le_bytes()).is_err());
+    assert_eq!(decode::<Register64>(0x0200_9093u32.to_le_bytes()).unwrap().to_string(), "slli ra, ra, 32");
+    assert_eq!(decode::<Register128>(0x0640_9093u32.to_le_bytes()).unwrap().to_string(), "slli ra, ra, 100");
+    // LQ, SQ and LDU are only present on RV128
+    assert_eq!(decode::<Register128>(0x0100_210Fu32.to_le_bytes()).unwrap().to_string(), "lq sp, 16(zero)");
+    assert_eq!(decode::<Register128>(0x0010_4823u32.to_le_bytes()).unwrap().to_string(), "sq ra, 16(zero)");
+    assert_eq!(decode::<Register128>(0x0180_7183u32.to_le_bytes()).unwrap().to_string(), "ldu gp, 24(zero)");
+    assert!(decode::<Register64>(0x0010_4823u32.to_le_bytes()).is_err());
+}
+
+#[test]
+fn disassemble_immediates() {
+    use rysk_core::decode::decode;
+    let disassemble = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+
+    // Signed immediates are rendered in signed decimal, while shift amounts are unsigned
+    assert_eq!(disassemble(0xFFF1_0093), "addi ra, sp, -1");
+    assert_eq!(disassemble(0x0031_1093), "slli ra, sp, 3");
+    // The zimm of the CSR immediate instructions is zero-extended, so is unsigned even with its top bit set
+    #[cfg(feature = "ext-csr")]
+    assert_eq!(disassemble(0x340F_D0F3), "csrrwi ra, mscratch, 31");
+}
+
+#[cfg(feature = "ext-csr")]
+#[test]
+fn decode_csr_formats() {
+    use rysk_core::decode::{ decode, Instruction };
+    let decode32 = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap();
+    // C
+    assert_eq!(decode32(0x3052_9073), Instruction::Csrrw { rd: 0, rs1: 5, csr: 0x305 });
+    assert_eq!(decode32(0x3052_9073).to_string(), "csrrw zero, mtvec, t0");
+    assert_eq!(decode32(0x3004_6073).to_string(), "csrrsi zero, mstatus, 8");
+    assert_eq!(decode32(0x7FF0_20F3).to_string(), "csrrs ra, 0x7ff, zero");
+    assert_eq!(decode32(0x3020_0073), Instruction::Mret);
+    assert_eq!(decode32(0x1050_0073).to_string(), "wfi");
+}
+
+#[cfg(feature = "ext-m")]
+#[test]
+fn decode_m_formats() {
+    use rysk_core::decode::decode;
+    // mul a0, a1, a2
+    assert_eq!(decode::<Register32>(0x02C5_8533u32.to_le_bytes()).unwrap().to_string(), "mul a0, a1, a2");
+}
+
+#[cfg(feature = "ext-a")]
+#[test]
+fn decode_a_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x1005_22AF), "lr.w t0, (a0)");
+    assert_eq!(format(0x1865_23AF), "sc.w t2, t1, (a0)");
+    assert_eq!(format(0x0E05_202F), "amoswap.w.aqrl zero, zero, (a0)");
+}
+
+#[cfg(feature = "ext-f")]
+#[test]
+fn decode_f_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x1000_2087), "flw ft1, 256(zero)");
+    assert_eq!(format(0x10C5_9553), "fmul.s fa0, fa1, fa2, rtz");
+    assert_eq!(format(0x68C5_F543), "fmadd.s fa0, fa1, fa2, fa3");
+    assert_eq!(format(0xA0C5_A553), "feq.s a0, fa1, fa2");
+    assert_eq!(format(0x0010_25F3), "csrrs a1, fflags, zero");
+    // Reserved rounding modes, and double-word conversions on RV32
+    assert!(decode::<Register32>(0x0030_D153u32.to_le_bytes()).is_err());
+    assert!(decode::<Register32>(0xC020_F553u32.to_le_bytes()).is_err());
+    assert_eq!(decode::<Register64>(0xC020_F553u32.to_le_bytes()).unwrap().to_string(), "fcvt.l.s a0, ft1");
+}
+
+#[cfg(feature = "ext-d")]
+#[test]
+fn decode_d_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x1000_3087), "fld ft1, 256(zero)");
+    assert_eq!(format(0x12C5_9553), "fmul.d fa0, fa1, fa2, rtz");
+    assert_eq!(format(0x6AC5_F543), "fmadd.d fa0, fa1, fa2, fa3");
+    assert_eq!(format(0xA2C5_A553), "feq.d a0, fa1, fa2");
+    assert_eq!(format(0x4010_F153), "fcvt.s.d ft2, ft1");
+    // Moves between a double and an integer register on RV32
+    assert!(decode::<Register32>(0xE200_8553u32.to_le_bytes()).is_err());
+    assert_eq!(decode::<Register64>(0xE200_8553u32.to_le_bytes()).unwrap().to_string(), "fmv.x.d a0, ft1");
+}
+
+#[cfg(feature = "ext-zbc")]
+#[test]
+fn decode_zbc_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x0AC5_9533), "clmul a0, a1, a2");
+    assert_eq!(format(0x0AC5_B533), "clmulh a0, a1, a2");
+    assert_eq!(format(0x0AC5_A533), "clmulr a0, a1, a2");
+}
+
+#[cfg(feature = "ext-zbs")]
+#[test]
+fn decode_zbs_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x28C5_9533), "bset a0, a1, a2");
+    assert_eq!(format(0x49F5_D513), "bexti a0, a1, 31");
+    assert!(decode::<Register32>(0x4A05_9513u32.to_le_bytes()).is_err());
+    assert_eq!(decode::<Register64>(0x4A05_9513u32.to_le_bytes()).unwrap().to_string(), "bclri a0, a1, 32");
+}
+
+#[cfg(all(feature = "ext-zicbom", feature = "ext-zicboz"))]
+#[test]
+fn decode_cbo_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x0005_200F), "cbo.clean (a0)");
+    assert_eq!(format(0x0015_200F), "cbo.flush (a0)");
+    assert_eq!(format(0x0025_200F), "cbo.inval (a0)");
+    assert_eq!(format(0x0045_200F), "cbo.zero (a0)");
+    // rd is reserved and must be zero
+    assert!(decode::<Register32>(0x0045_228Fu32.to_le_bytes()).is_err());
+    assert!(decode::<Register32>(0x0035_200Fu32.to_le_bytes()).is_err());
+}
+
+#[cfg(all(feature = "ext-zbkb", feature = "ext-zbkx"))]
+#[test]
+fn decode_zbkx_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x28C5_A533), "xperm4 a0, a1, a2");
+    assert_eq!(format(0x28C5_C533), "xperm8 a0, a1, a2");
+    // PACKW is only provided on RV64
+    assert!(decode::<Register32>(0x08C5_C53Bu32.to_le_bytes()).is_err());
+    assert_eq!(decode::<Register64>(0x08C5_C53Bu32.to_le_bytes()).unwrap().to_string(), "packw a0, a1, a2");
+}
+
+#[test]
+fn decode_shift_immediates() {
+    use rysk_core::decode::decode;
+    let legal = |word: u32| (
+        decode::<Register32>(word.to_le_bytes()).is_ok(),
+        decode::<Register64>(word.to_le_bytes()).is_ok(),
+        decode::<Register128>(word.to_le_bytes()).is_ok()
+    );
+    // slli ra, ra, 1 and srai ra, ra, 1
+    assert_eq!(legal(0x0010_9093), (true, true, true));
+    assert_eq!(legal(0x4010_D093), (true, true, true));
+    // A shift amount of 32 needs RV64, and of 100 needs the 7-bit shift amount of RV128
+    assert_eq!(legal(0x0200_9093), (false, true, true));
+    assert_eq!(legal(0x0640_9093), (false, false, true));
+    // The bits above the shift amount other than the SRAI bit are reserved
+    assert_eq!(legal(0x2010_9093), (false, false, false));
+    assert_eq!(legal(0x5010_D093), (false, false, false));
+    assert_eq!(legal(0x8010_5093), (false, false, false));
+    // As they are for the word shifts of RV64, srliw ra, ra, 1 with bit 11 set
+    assert_eq!(legal(0x0010_D09B), (false, true, true));
+    assert_eq!(legal(0x8010_D09B), (false, false, false));
+}
+
+#[cfg(feature = "ext-zknh")]
+#[test]
+fn decode_zknh_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x1025_9513), "sha256sig0 a0, a1");
+    assert_eq!(format(0x50C5_8533), "sha512sum0r a0, a1, a2");
+    assert_eq!(format(0x5CB6_06B3), "sha512sig0h a3, a2, a1");
+    // The SHA-512 instructions are split by XLEN
+    assert_eq!(decode::<Register64>(0x1045_9513u32.to_le_bytes()).unwrap().to_string(), "sha512sum0 a0, a1");
+    assert!(decode::<Register64>(0x50C5_8533u32.to_le_bytes()).is_err());
+}
+
+#[cfg(feature = "ext-v")]
+#[test]
+fn decode_v_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x0D05_F557), "vsetvli a0, a1, e32, m1, ta, ma");
+    assert_eq!(format(0xC072_7557), "vsetivli a0, 4, e8, mf2, tu, mu");
+    assert_eq!(format(0x0221_80D7), "vadd.vv v1, v2, v3");
+    assert_eq!(format(0x002F_B0D7), "vadd.vi v1, v2, -1, v0.t");
+    assert_eq!(format(0x962F_B0D7), "vsll.vi v1, v2, 31");
+    assert_eq!(format(0x6E25_40D7), "vmslt.vx v1, v2, a0");
+    assert_eq!(format(0x9625_60D7), "vmul.vx v1, v2, a0");
+    assert_eq!(format(0x0221_A0D7), "vredsum.vs v1, v2, v3");
+    assert_eq!(format(0x6621_A0D7), "vmand.mm v1, v2, v3");
+    assert_eq!(format(0x5E05_40D7), "vmv.v.x v1, a0");
+    assert_eq!(format(0x5C22_B0D7), "vmerge.vim v1, v2, 5, v0");
+    assert_eq!(format(0x4220_2557), "vmv.x.s a0, v2");
+    assert_eq!(format(0x4205_60D7), "vmv.s.x v1, a0");
+    assert_eq!(format(0x4028_2557), "vcpop.m a0, v2, v0.t");
+    assert_eq!(format(0x5208_A0D7), "vid.v v1");
+    // A masked instruction may not overwrite the mask in v0
+    assert!(decode::<Register32>(0x0021_8057u32.to_le_bytes()).is_err());
+
+    assert_eq!(format(0x0205_6087), "vle32.v v1, (a0)");
+    assert_eq!(format(0x0005_6087), "vle32.v v1, (a0), v0.t");
+    assert_eq!(format(0x0305_6087), "vle32ff.v v1, (a0)");
+    assert_eq!(format(0x0AB5_6087), "vlse32.v v1, (a0), a1");
+    assert_eq!(format(0x08B5_60A7), "vsse32.v v1, (a0), a1, v0.t");
+    assert_eq!(format(0x0625_6087), "vluxei32.v v1, (a0), v2");
+    assert_eq!(format(0x0E25_0087), "vloxei8.v v1, (a0), v2");
+    assert_eq!(format(0x2205_5107), "vlseg2e16.v v2, (a0)");
+    assert_eq!(format(0x2645_5127), "vsuxseg2ei16.v v2, (a0), v4");
+    assert_eq!(format(0x2285_6107), "vl2re32.v v2, (a0)");
+    assert_eq!(format(0x2285_0127), "vs2r.v v2, (a0)");
+    assert_eq!(format(0x02B5_0087), "vlm.v v1, (a0)");
+    assert!(decode::<Register32>(0x0005_6007u32.to_le_bytes()).is_err());
+    assert!(decode::<Register32>(0x1205_6087u32.to_le_bytes()).is_err());
+}
+
+#[cfg(feature = "ext-zfh")]
+#[test]
+fn decode_zfh_formats() {
+    use rysk_core::decode::decode;
+    let format = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().to_string();
+    assert_eq!(format(0x1000_1087), "flh ft1, 256(zero)");
+    assert_eq!(format(0x1430_F153), "fmul.h ft2, ft1, ft3");
+    assert_eq!(format(0xA4C5_A553), "feq.h a0, fa1, fa2");
+    assert_eq!(format(0x4402_72D3), "fcvt.h.s ft5, ft4");
+    assert_eq!(format(0xE400_8553), "fmv.x.h a0, ft1");
+}
+
+#[test]
+fn control_flow_kind() {
+    use decode::{ decode, ControlFlowKind };
+    let kind = |word: u32| decode::<Register32>(word.to_le_bytes()).unwrap().control_flow_kind();
+    // jal ra, 16
+    assert_eq!(kind(0x0100_00EF), ControlFlowKind::Call);
+    // jalr ra, 0(a0)
+    assert_eq!(kind(0x0005_00E7), ControlFlowKind::Call);
+    // jalr zero, 0(ra)
+    assert_eq!(kind(0x0000_8067), ControlFlowKind::Return);
+    // jalr zero, 0(t0)
+    assert_eq!(kind(0x0002_8067), ControlFlowKind::TailCall);
+    // jal zero, -8
+    assert_eq!(kind(0xFF9F_F06F), ControlFlowKind::Jump);
+    // bne a0, zero, -4
+    assert_eq!(kind(0xFE05_1EE3), ControlFlowKind::Branch);
+    // addi sp, sp, -16
+    assert_eq!(kind(0xFF01_0113), ControlFlowKind::None);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn validate() {
+    use decode::{ validate, Diagnostic };
+    let mut memory = ArrayRam::<0x20>::new(0);
+    // addi t0, zero, 0; jal zero, 64; an illegal all-ones word; beq zero, zero, -8
+    for (index, word) in [0x0000_0293u32, 0x0400_006F, 0xFFFF_FFFF, 0xFE00_0CE3].iter().enumerate() {
+        memory.load(index * 4, &word.to_le_bytes());
+    }
+    assert_eq!(validate::<Register32>(&memory, 0..0x10), [
+        Diagnostic::TargetOutOfRange { address: 4u32.into(), target: 0x44u32.into() },
+        Diagnostic::IllegalInstruction(8u32.into())
+    ]);
+    // Narrowing the range takes the backward branch out of it
+    assert_eq!(validate::<Register32>(&memory, 0xC..0x10), [
+        Diagnostic::TargetOutOfRange { address: 0xCu32.into(), target: 4u32.into() }
+    ]);
+}
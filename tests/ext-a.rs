@@ -0,0 +1,181 @@
+#[cfg(feature = "ext-a")]
+mod common;
+
+#[cfg(feature = "ext-a")]
+mod ext_a_tests {
+    use super::common::*;
+    use rysk_core::{ AccessInfo, ArrayRam, HostReturn, MemoryFault, Mmu, Register32, Register64 };
+
+    fn word(memory: &ArrayRam<0x200>, address: usize) -> u32 {
+        u32::from_le_bytes([memory.bytes[address], memory.bytes[address + 1], memory.bytes[address + 2], memory.bytes[address + 3]])
+    }
+
+    #[test]
+    fn test_spinlock() {
+        let mut memory = program(&[
+            0x1000_0513, // addi a0, x0, 0x100
+            0x0010_0313, // addi t1, x0, 1
+            0x1005_22AF, // retry: lr.w t0, (a0)
+            0xFE02_9EE3, // bnez t0, retry
+            0x1865_23AF, // sc.w t2, t1, (a0)
+            0xFE03_9AE3, // bnez t2, retry
+            0x0015_8593, // addi a1, a1, 1
+            0x0E05_202F, // amoswap.w.aqrl x0, x0, (a0)
+        ]);
+        let mut core = core32();
+        // The SC succeeds on the first attempt so no instruction is retried
+        for _ in 0..8 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.pc, 32u32.into());
+        assert_eq!(core.get(7), 0u32.into());
+        assert_eq!(core.get(11), 1u32.into());
+        assert_eq!(word(&memory, 0x100), 0);
+    }
+
+    /// Execute `amo a2, a1, (a0)` against 0x8000_0000 in memory with 1 in a1, returning the value left in memory
+    fn amo(instruction: u32) -> u32 {
+        let mut memory = program(&[instruction]);
+        memory.load(0x100, &0x8000_0000u32.to_le_bytes());
+        let mut core = core32();
+        core.set(10, 0x100u32.into());
+        core.set(11, 1u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(12), 0x8000_0000u32.into());
+        word(&memory, 0x100)
+    }
+
+    #[test]
+    fn test_amo_min_max() {
+        assert_eq!(amo(0x80B5_262F), 0x8000_0000); // amomin.w
+        assert_eq!(amo(0xA0B5_262F), 1); // amomax.w
+        assert_eq!(amo(0xC0B5_262F), 1); // amominu.w
+        assert_eq!(amo(0xE0B5_262F), 0x8000_0000); // amomaxu.w
+        assert_eq!(amo(0x00B5_262F), 0x8000_0001); // amoadd.w
+    }
+
+    /// Memory counting the AMOs it performs, as memory shared between harts would hold a lock for each
+    struct Locked(ArrayRam<0x200>, usize);
+    impl Mmu<Register32> for Locked {
+        fn get(&self, address: u32) -> u8 {
+            Mmu::<Register32>::get(&self.0, address)
+        }
+        fn set(&mut self, address: u32, value: u8) {
+            Mmu::<Register32>::set(&mut self.0, address, value)
+        }
+        fn read_modify_write(&mut self, address: u32, bytes: &mut [u8], info: AccessInfo, modify: &dyn Fn(&mut [u8])) -> Result<(), MemoryFault> {
+            self.1 += 1;
+            let start = address as usize;
+            let end = start + info.width as usize;
+            bytes.copy_from_slice(&self.0.bytes[start..end]);
+            modify(&mut self.0.bytes[start..end]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_amo_read_modify_write() {
+        // amoadd.w a2, a1, (a0); lr.w t0, (a0)
+        let mut memory = Locked(program(&[0x00B5_262F, 0x1005_22AF]), 0);
+        memory.0.load(0x100, &41u32.to_le_bytes());
+        let mut core = core32();
+        core.set(10, 0x100u32.into());
+        core.set(11, 1u32.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(12), 41u32.into());
+        assert_eq!(word(&memory.0, 0x100), 42);
+        // Only the AMO is performed through read_modify_write
+        assert_eq!(memory.1, 1);
+    }
+
+    /// Execute an LR at 0x100, then `between`, then `sc.w t2, t1, (a1)` returning the SC result
+    fn sc_after(between: u32, sc_address: u32, context_switch: bool) -> u32 {
+        let mut memory = program(&[0x1005_22AF, between, 0x1865_A3AF]);
+        let mut core = core32();
+        core.set(10, 0x100u32.into());
+        core.set(11, sc_address.into());
+        core.set(6, 0x42u32.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        if context_switch {
+            core.on_context_switch()
+        }
+        core.execute(&mut memory);
+        let stored = word(&memory, sc_address as usize);
+        let result: u32 = if core.get(7) == 0u32.into() { 0 } else { 1 };
+        // A failed SC must not write memory
+        assert_eq!(stored == 0x42, result == 0);
+        result
+    }
+
+    #[test]
+    fn test_sc_conditions() {
+        const NOP: u32 = 0x0000_0013;
+        // A validly paired LR and SC succeeds
+        assert_eq!(sc_after(NOP, 0x100, false), 0);
+        // An intervening store, sw x0, 0(a0)
+        assert_eq!(sc_after(0x0005_2023, 0x100, false), 1);
+        // The reservation is cleared by a context switch
+        assert_eq!(sc_after(NOP, 0x100, true), 1);
+        // A different address
+        assert_eq!(sc_after(NOP, 0x104, false), 1);
+        // A store to any address clears the reservation, sw x0, 0x80(x0)
+        assert_eq!(sc_after(0x0800_2023, 0x100, false), 1);
+    }
+
+    #[test]
+    fn test_sc_size_mismatch() {
+        // lr.d t0, (a0); sc.w t2, t1, (a0); lr.d t0, (a0); sc.d t2, t1, (a0)
+        let mut memory = program(&[0x1005_32AF, 0x1865_23AF, 0x1005_32AF, 0x1865_33AF]);
+        let mut core = core64();
+        core.set(10, Register64(0x100u64.to_le_bytes()));
+        core.set(6, Register64(0x1_0000_0042u64.to_le_bytes()));
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(7), Register64(1u64.to_le_bytes()));
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(7), Register64([0; 8]));
+        assert_eq!(Mmu::<Register64>::get(&memory, 0x104), 1);
+    }
+
+    #[test]
+    fn test_misaligned() {
+        use rysk_core::system::Trap;
+        // ArrayRam emulates misaligned loads and stores, but never those of LR, SC or an AMO
+        for &(instruction, trap) in &[
+            (0x1005_22AF, Trap::LoadMisaligned(0x102)), // lr.w t0, (a0)
+            (0x1865_23AF, Trap::StoreMisaligned(0x102)), // sc.w t2, t1, (a0)
+            (0x00B5_262F, Trap::StoreMisaligned(0x102)) // amoadd.w a2, a1, (a0)
+        ] {
+            let mut memory = program(&[instruction]);
+            let mut core = core32().with_trap_strategy(HostReturn);
+            core.set(10, 0x102u32.into());
+            core.set(11, 1u32.into());
+            assert_eq!(core.execute(&mut memory), Some(trap));
+            assert_eq!(word(&memory, 0x100), 0);
+        }
+    }
+
+    #[test]
+    fn test_double_illegal_on_rv32() {
+        // lr.d t0, (a0)
+        let mut memory = program(&[0x1005_32AF]);
+        let mut core = core32();
+        #[cfg(not(feature = "ext-csr"))]
+        assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::IllegalInstruction));
+        #[cfg(feature = "ext-csr")]
+        {
+            core.execute(&mut memory);
+            assert_eq!(core.last_outcome(), rysk_core::StepOutcome::Trapped);
+        }
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn test_misa() {
+        use rysk_core::Register;
+        assert_eq!(core32().get_csr(0x301).unwrap().word()[0] & 1, 1);
+    }
+}
@@ -0,0 +1,236 @@
+#[cfg(feature = "ext-c")]
+mod common;
+
+#[cfg(feature = "ext-c")]
+mod ext_c_tests {
+    use super::common::*;
+    use rysk_core::{ compressed::expand, ArrayRam, Register32, Register64 };
+
+    fn expand32(parcel: u16) -> Option<u32> {
+        expand::<Register32>(parcel.to_le_bytes()).map(u32::from_le_bytes)
+    }
+    fn expand64(parcel: u16) -> Option<u32> {
+        expand::<Register64>(parcel.to_le_bytes()).map(u32::from_le_bytes)
+    }
+
+    #[test]
+    fn test_expand_common() {
+        let pairs: &[(u16, u32)] = &[
+            (0x0800, 0x0101_0413), // c.addi4spn s0, sp, 16
+            (0x41C8, 0x0045_A503), // c.lw a0, 4(a1)
+            (0xC1C8, 0x00A5_A223), // c.sw a0, 4(a1)
+            (0x0001, 0x0000_0013), // c.nop
+            (0x0505, 0x0015_0513), // c.addi a0, 1
+            (0x557D, 0xFFF0_0513), // c.li a0, -1
+            (0x713D, 0xFE01_0113), // c.addi16sp sp, -32
+            (0x6505, 0x0000_1537), // c.lui a0, 0x1
+            (0x8105, 0x0015_5513), // c.srli a0, 1
+            (0x8505, 0x4015_5513), // c.srai a0, 1
+            (0x997D, 0xFFF5_7513), // c.andi a0, -1
+            (0x8D0D, 0x40B5_0533), // c.sub a0, a1
+            (0x8D2D, 0x00B5_4533), // c.xor a0, a1
+            (0x8D4D, 0x00B5_6533), // c.or a0, a1
+            (0x8D6D, 0x00B5_7533), // c.and a0, a1
+            (0xA001, 0x0000_006F), // c.j 0
+            (0xC101, 0x0005_0063), // c.beqz a0, 0
+            (0xE101, 0x0005_1063), // c.bnez a0, 0
+            (0x0506, 0x0015_1513), // c.slli a0, 1
+            (0x4512, 0x0041_2503), // c.lwsp a0, 4(sp)
+            (0xC22A, 0x00A1_2223), // c.swsp a0, 4(sp)
+            (0x8082, 0x0000_8067), // c.jr ra
+            (0x852E, 0x00B0_0533), // c.mv a0, a1
+            (0x9002, 0x0010_0073), // c.ebreak
+            (0x9502, 0x0005_00E7), // c.jalr a0
+            (0x952E, 0x00B5_0533), // c.add a0, a1
+        ];
+        for &(parcel, instruction) in pairs {
+            assert_eq!(expand32(parcel), Some(instruction), "RV32 {:#06X}", parcel);
+            assert_eq!(expand64(parcel), Some(instruction), "RV64 {:#06X}", parcel);
+        }
+    }
+
+    #[test]
+    fn test_expand_width_specific() {
+        // c.jal 4 on RV32, c.addiw a0, 1 on RV64
+        assert_eq!(expand32(0x2011), Some(0x0040_00EF));
+        assert_eq!(expand64(0x2505), Some(0x0015_051B));
+        // The same parcel expands to a JAL on RV32
+        assert_eq!(expand32(0x2505), Some(0x6200_00EF));
+
+        let rv64: &[(u16, u32)] = &[
+            (0x6588, 0x0085_B503), // c.ld a0, 8(a1)
+            (0xE588, 0x00A5_B423), // c.sd a0, 8(a1)
+            (0x6522, 0x0081_3503), // c.ldsp a0, 8(sp)
+            (0xE42A, 0x00A1_3423), // c.sdsp a0, 8(sp)
+            (0x9D0D, 0x40B5_053B), // c.subw a0, a1
+            (0x9D2D, 0x00B5_053B), // c.addw a0, a1
+        ];
+        for (index, &(parcel, instruction)) in rv64.iter().enumerate() {
+            assert_eq!(expand64(parcel), Some(instruction), "RV64 {:#06X}", parcel);
+            // These encode floating-point or reserved instructions on RV32
+            if !cfg!(feature = "ext-f") || index >= 4 {
+                assert_eq!(expand32(parcel), None, "RV32 {:#06X}", parcel);
+            }
+        }
+        #[cfg(feature = "ext-f")]
+        {
+            assert_eq!(expand32(0x6588), Some(0x0085_A507)); // c.flw fa0, 8(a1)
+            assert_eq!(expand32(0xE588), Some(0x00A5_A427)); // c.fsw fa0, 8(a1)
+            assert_eq!(expand32(0x6522), Some(0x0081_2507)); // c.flwsp fa0, 8(sp)
+            assert_eq!(expand32(0xE42A), Some(0x00A1_2427)); // c.fswsp fa0, 8(sp)
+        }
+        #[cfg(feature = "ext-d")]
+        {
+            let rv32_and_rv64: &[(u16, u32)] = &[
+                (0x2588, 0x0085_B507), // c.fld fa0, 8(a1)
+                (0xA588, 0x00A5_B427), // c.fsd fa0, 8(a1)
+                (0x2522, 0x0081_3507), // c.fldsp fa0, 8(sp)
+                (0xA42A, 0x00A1_3427), // c.fsdsp fa0, 8(sp)
+            ];
+            for &(parcel, instruction) in rv32_and_rv64 {
+                assert_eq!(expand32(parcel), Some(instruction), "RV32 {:#06X}", parcel);
+                assert_eq!(expand64(parcel), Some(instruction), "RV64 {:#06X}", parcel);
+            }
+        }
+
+        // Shift amounts of 32 and above are only valid on RV64
+        assert_eq!(expand32(0x1506), None); // c.slli a0, 33
+        assert_eq!(expand64(0x1506), Some(0x0215_1513));
+    }
+
+    #[test]
+    fn test_expand_reserved() {
+        // The all-zero parcel, c.addi4spn with a zero immediate
+        assert_eq!(expand32(0x0000), None);
+        // c.addi16sp with a zero immediate
+        assert_eq!(expand32(0x6101), None);
+        // c.lui with a zero immediate
+        assert_eq!(expand32(0x6501), None);
+        // c.lwsp to x0
+        assert_eq!(expand32(0x4012), None);
+        // c.jr x0
+        assert_eq!(expand32(0x8002), None);
+        // c.addiw to x0
+        assert_eq!(expand64(0x2005), None);
+        // c.fld without the D extension
+        #[cfg(not(feature = "ext-d"))]
+        assert_eq!(expand32(0x2000 | 0x0400), None);
+
+        // HINTs expand to instructions without effect
+        assert_eq!(expand32(0x0081), Some(0x0000_8093)); // c.addi x1, 0
+        assert_eq!(expand32(0x4005), Some(0x0010_0013)); // c.li x0, 1
+    }
+
+    #[test]
+    fn test_expand_branches() {
+        let pairs: &[(u16, u32)] = &[
+            (0xD001, 0xF004_00E3), // c.beqz s0, -256
+            (0xDFFD, 0xFE07_8FE3), // c.beqz a5, -2
+            (0xD965, 0xFE05_08E3), // c.beqz a0, -16
+            (0xECFD, 0x0E04_9F63), // c.bnez s1, 254
+            (0xEA39, 0x0406_1B63), // c.bnez a2, 86
+        ];
+        for &(parcel, instruction) in pairs {
+            assert_eq!(expand32(parcel), Some(instruction), "RV32 {:#06X}", parcel);
+            assert_eq!(expand64(parcel), Some(instruction), "RV64 {:#06X}", parcel);
+        }
+    }
+
+    #[test]
+    fn test_execute_branches() {
+        let mut memory = ArrayRam::<0x40>::new(0);
+        memory.load(0x20, &[
+            0x65, 0xD9, // c.beqz a0, -16
+            0x65, 0xF9, // c.bnez a0, -16
+        ]);
+        let mut core = core32();
+
+        // Taken backwards when the register is zero
+        core.pc = 0x20u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x10u32.into());
+
+        // Falls through by the length of the parcel otherwise
+        core.pc = 0x22u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x24u32.into());
+        core.set(10, 1u32.into());
+        core.pc = 0x22u32.into();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x12u32.into());
+    }
+
+    #[test]
+    fn test_execute_mixed_lengths() {
+        let mut memory = ArrayRam::<32>::new(0);
+        memory.load(0, &[
+            0x7D, 0x55, // c.li a0, -1
+            0x93, 0x05, 0x50, 0x00, // addi a1, x0, 5
+            0x2E, 0x95, // c.add a0, a1
+            0x11, 0x20, // c.jal 4
+            0x01, 0x00, // c.nop
+            0x82, 0x80, // c.jr ra
+        ]);
+        let mut core = core32();
+
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 2u32.into());
+        assert_eq!(core.get(10), (-1i32 as u32).into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 6u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 8u32.into());
+        assert_eq!(core.get(10), 4u32.into());
+        // The link address is the next compressed instruction
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 12u32.into());
+        assert_eq!(core.get(1), 10u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 10u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 12u32.into());
+    }
+
+    #[test]
+    fn test_two_byte_alignment() {
+        // jal x0, 6
+        let mut memory = Memory::new(&[0x0060_006F]);
+        let mut core = core32();
+        #[cfg(not(feature = "ext-csr"))]
+        assert_eq!(core.execute(&mut memory), None);
+        #[cfg(feature = "ext-csr")]
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 6u32.into());
+    }
+
+    #[test]
+    fn test_jal_addiw_by_width() {
+        // c.jal 1568 on RV32, c.addiw a0, 1 on RV64
+        let mut memory = ArrayRam::<16>::new(0);
+        memory.load(0, &[0x05, 0x25]);
+
+        let mut core = core32();
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 1568u32.into());
+        assert_eq!(core.get(1), 2u32.into());
+
+        #[cfg(feature = "ext-csr")]
+        let mut core = rysk_core::Core::<Register64>::new(0, 0);
+        #[cfg(not(feature = "ext-csr"))]
+        let mut core = rysk_core::Core::<Register64>::new(0);
+        core.set(10, Register64(0x7FFF_FFFFu64.to_le_bytes()));
+        core.execute(&mut memory);
+        assert_eq!(core.pc, Register64(2u64.to_le_bytes()));
+        assert_eq!(core.get(1), Register64([0; 8]));
+        // The 32-bit result is sign extended
+        assert_eq!(core.get(10), Register64(0xFFFF_FFFF_8000_0000u64.to_le_bytes()));
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn test_misa() {
+        use rysk_core::Register;
+        let core = core32();
+        assert_eq!(core.get_csr(0x301).unwrap().word()[0] & 1 << 2, 1 << 2);
+    }
+}
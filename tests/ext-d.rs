@@ -0,0 +1,132 @@
+#[cfg(feature = "ext-d")]
+mod common;
+
+#[cfg(feature = "ext-d")]
+mod ext_d_tests {
+    use super::common::*;
+    use rysk_core::{ ArrayRam, Core, Register, Register64, float::{ self, FpRegister }, double };
+
+    #[test]
+    fn test_nan_boxing() {
+        // fadd.s f2, f1, f3; fadd.d f4, f1, f3
+        let mut memory = Memory::new(&[0x0030_F153, 0x0230_F253]);
+        let mut core = core32();
+
+        // A double is not a valid single, so reads as the canonical NaN without signalling
+        core.set_d(1, 1.0);
+        core.set_f(3, 1.0.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(2), FpRegister(float::CANONICAL_NAN));
+        assert_eq!(core.fcsr().fflags, 0);
+
+        // Singles are NaN-boxed, so are a NaN when read as a double
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(4).to_bits(), double::CANONICAL_NAN);
+        assert_eq!(core.fcsr().fflags, 0);
+        core.set_f(5, 1.0.into());
+        assert_eq!(core.get_d(5).to_bits(), 0xFFFF_FFFF_3F80_0000);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // fadd.d f2, f1, f3; fmul.d f2, f1, f3; fdiv.d f2, f1, f3; fsqrt.d f2, f1
+        let mut memory = Memory::new(&[0x0230_F153, 0x1230_F153, 0x1A30_F153, 0x5A00_F153]);
+        let mut core = core32();
+        core.set_d(1, 9.0);
+        core.set_d(3, 2.0);
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(2), 11.0);
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(2), 18.0);
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(2), 4.5);
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(2), 3.0);
+        assert_eq!(core.fcsr().fflags, 0);
+
+        // The result of dividing by zero is infinite
+        assert_eq!(double::div(1.0, 0.0, float::RoundingMode::NearestEven), (f64::INFINITY, float::DZ));
+        let (value, flags) = double::sqrt(-1.0, float::RoundingMode::NearestEven);
+        assert_eq!((value.to_bits(), flags), (double::CANONICAL_NAN, float::NV));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // fadd.d f2, f1, f3, rtz; fadd.d f2, f1, f3, rup
+        let mut memory = Memory::new(&[0x0230_9153, 0x0230_B153]);
+        let mut core = core32();
+        core.set_d(1, 1.0);
+        core.set_d(3, 2f64.powi(-60));
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(2), 1.0);
+        assert_eq!(core.fcsr().fflags, float::NX);
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(2), 1.0 + f64::EPSILON);
+
+        // Overflow saturates to the largest finite value when rounding towards zero
+        assert_eq!(double::mul(f64::MAX, 2.0, float::RoundingMode::TowardZero), (f64::MAX, float::OF | float::NX));
+        assert_eq!(double::mul(f64::MAX, 2.0, float::RoundingMode::NearestEven), (f64::INFINITY, float::OF | float::NX));
+        // The product 3 * (1 + 2^-52) is not representable, so is rounded down to even and up with directed rounding
+        let operand = 1.0 + f64::EPSILON;
+        assert_eq!(double::mul(3.0, operand, float::RoundingMode::Down).0, 3.0 + 2.0 * f64::EPSILON);
+        assert_eq!(double::mul(3.0, operand, float::RoundingMode::Up).0, 3.0 + 4.0 * f64::EPSILON);
+        assert_eq!(double::fused_multiply_add(3.0, operand, -3.0, float::RoundingMode::NearestEven), (3.0 * f64::EPSILON, 0));
+
+        // The product overflows though the fused result is exact, so is neither inexact nor rounded away
+        let exact = 2f64.powi(1023) + 2f64.powi(971);
+        for &rm in &[float::RoundingMode::NearestEven, float::RoundingMode::Up, float::RoundingMode::Down] {
+            assert_eq!(double::fused_multiply_add(2f64.powi(1023), 3.0, -f64::MAX, rm), (exact, 0));
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        // fcvt.s.d f2, f1; fcvt.d.s f4, f2
+        let mut memory = Memory::new(&[0x4010_F153, 0x4201_0253]);
+        let mut core = core32();
+        core.set_d(1, 0.1);
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(2).f32(), 0.1f32);
+        assert_eq!(core.fcsr().fflags, float::NX);
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(4), 0.1f32 as f64);
+
+        assert_eq!(double::to_integer(-2.5, true, 32, float::RoundingMode::NearestEven), (-2i64 as u64, float::NX));
+        assert_eq!(double::to_integer(1e10, true, 32, float::RoundingMode::NearestEven), (0x7FFF_FFFF, float::NV));
+        assert_eq!(double::from_integer(u64::MAX, false, float::RoundingMode::TowardZero), (2f64.powi(64) - 2048.0, float::NX));
+    }
+
+    #[test]
+    fn test_load_store() {
+        // fld f1, 0x100(zero); fsd f1, 0x108(zero)
+        let mut memory = Memory::new(&[0x1000_3087, 0x1010_3427]);
+        memory.bytes[0x100..0x108].copy_from_slice(&(-1.25f64).to_le_bytes());
+        let mut core = core32();
+        core.execute(&mut memory);
+        assert_eq!(core.get_d(1), -1.25);
+        core.execute(&mut memory);
+        assert_eq!(memory.bytes[0x108..0x110], (-1.25f64).to_le_bytes());
+    }
+
+    #[test]
+    fn test_move_rv64() {
+        // fmv.x.d a0, f1
+        let mut memory = ArrayRam::<0x40>::new(0);
+        memory.load(0, &0xE200_8553u32.to_le_bytes());
+        let mut core: Core<Register64> = Core::new(0, 0);
+        core.set_d(1, -2.0);
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), Register64((-2.0f64).to_bits().to_le_bytes()));
+
+        // There are no 64-bit integer registers to move to on RV32
+        let mut memory = Memory::new(&[0xE200_8553]);
+        let mut core = core32();
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), rysk_core::StepOutcome::Trapped);
+    }
+
+    #[test]
+    fn test_misa() {
+        assert_eq!(core32().get_csr(0x301).unwrap().word()[0] & 1 << 3, 1 << 3);
+    }
+}
@@ -0,0 +1,196 @@
+#[cfg(feature = "ext-f")]
+mod common;
+
+#[cfg(feature = "ext-f")]
+mod ext_f_tests {
+    use super::common::*;
+    use rysk_core::{ Register, HostReturn, float::{ self, FpRegister } };
+    use rysk_core::system::Trap;
+
+    const SIGNALING_NAN: u32 = 0x7FA0_0000;
+    const QUIET_NAN: u32 = 0x7FC0_1234;
+
+    #[test]
+    fn test_nan_propagation() {
+        // fadd.s f2, f1, f3
+        let mut memory = Memory::new(&[0x0030_F153, 0x0030_F153]);
+        let mut core = core32();
+
+        // NaN results are always canonical, discarding the payload of a quiet NaN operand without signalling
+        core.set_f(1, FpRegister(QUIET_NAN));
+        core.set_f(3, 1.0.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(2), FpRegister(float::CANONICAL_NAN));
+        assert_eq!(core.fcsr().fflags, 0);
+
+        // Signalling NaN operands raise the invalid operation flag
+        core.set_f(1, FpRegister(SIGNALING_NAN));
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(2), FpRegister(float::CANONICAL_NAN));
+        assert_eq!(core.fcsr().fflags, float::NV);
+
+        // Infinities of opposite sign generate a NaN
+        assert_eq!(float::add(f32::INFINITY, f32::NEG_INFINITY, float::RoundingMode::NearestEven).1, float::NV);
+        assert!(float::mul(f32::INFINITY, 0.0, float::RoundingMode::NearestEven).0.is_nan());
+    }
+
+    #[test]
+    fn test_min_max() {
+        // fmin.s f4, f1, f3; fmax.s f5, f1, f3
+        let mut memory = Memory::new(&[0x2830_8253, 0x2830_92D3]);
+        let mut core = core32();
+
+        // -0 is less than +0
+        core.set_f(1, (-0.0).into());
+        core.set_f(3, 0.0.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(4), FpRegister(0x8000_0000));
+        assert_eq!(core.get_f(5), FpRegister(0));
+        assert_eq!(core.fcsr().fflags, 0);
+
+        // A single NaN operand is ignored, though a signalling NaN still raises the invalid operation flag
+        assert_eq!(float::min(f32::NAN, 2.0), (2.0, 0));
+        assert_eq!(float::max(f32::from_bits(SIGNALING_NAN), 2.0), (2.0, float::NV));
+        assert_eq!(float::min(f32::NAN, f32::NAN).0.to_bits(), float::CANONICAL_NAN);
+    }
+
+    #[test]
+    fn test_convert_saturates() {
+        // fcvt.w.s a0, f1, rtz; fcvt.wu.s a0, f1; csrrs a1, fflags, zero
+        let mut memory = Memory::new(&[0xC000_9553, 0xC010_F553, 0x0010_25F3]);
+        let mut core = core32();
+
+        core.set_f(1, 3e9.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x7FFF_FFFFu32.into());
+        assert_eq!(core.fcsr().fflags, float::NV);
+
+        // Negative values are out of range of unsigned conversions
+        core.set_f(1, (-1.5).into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0.into());
+
+        // The flags accrue until cleared
+        core.execute(&mut memory);
+        assert_eq!(core.get(11), (float::NV as u32).into());
+
+        assert_eq!(float::to_integer(f32::NAN, true, 32, float::RoundingMode::NearestEven), (0x7FFF_FFFF, float::NV));
+        assert_eq!(float::to_integer(2.5, true, 32, float::RoundingMode::NearestEven), (2, float::NX));
+        assert_eq!(float::to_integer(-2.5, true, 32, float::RoundingMode::Down), (-3i64 as u64, float::NX));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // fsub.s f2, f1, f3, rne; fadd.s f2, f1, f3 with a reserved rounding mode
+        let mut memory = Memory::new(&[0x0830_8153, 0x0030_D153]);
+        let mut core = core32().with_trap_strategy(HostReturn);
+
+        // 1 - 2^-30 is not representable, so is rounded to 1
+        core.set_f(1, 1.0.into());
+        core.set_f(3, (2.0f32).powi(-30).into());
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.get_f(2).f32(), 1.0);
+        assert_eq!(core.fcsr().fflags, float::NX);
+        assert_eq!(float::sub(1.0, (2.0f32).powi(-30), float::RoundingMode::TowardZero).0, 1.0 - f32::EPSILON / 2.0);
+
+        assert_eq!(core.execute(&mut memory), Some(Trap::IllegalInstruction));
+    }
+
+    #[test]
+    fn test_fused_multiply_add() {
+        use float::RoundingMode::*;
+        // fmadd.s f4, f1, f2, f3, rne
+        let mut memory = Memory::new(&[0x1820_8243]);
+        let mut core = core32();
+
+        // 97 * 172961 = 2^24 + 1 lies halfway between two singles, so the tiny addend must break the tie upwards
+        core.set_f(1, 97.0.into());
+        core.set_f(2, 172961.0.into());
+        core.set_f(3, 1e-30.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(4).f32(), 16777218.0);
+        assert_eq!(core.fcsr().fflags, float::NX);
+
+        assert_eq!(float::fused_multiply_add(97.0, 172961.0, -1e-30, NearestEven), (16777216.0, float::NX));
+        assert_eq!(float::fused_multiply_add(97.0, 172961.0, -1e-30, NearestMaxMagnitude), (16777216.0, float::NX));
+        assert_eq!(float::fused_multiply_add(-97.0, 172961.0, 1e-30, NearestMaxMagnitude), (-16777216.0, float::NX));
+        // Exact ties are still broken by the rounding mode
+        assert_eq!(float::fused_multiply_add(97.0, 172961.0, 0.0, NearestEven), (16777216.0, float::NX));
+        assert_eq!(float::fused_multiply_add(97.0, 172961.0, 0.0, NearestMaxMagnitude), (16777218.0, float::NX));
+        // Exact results raise no flags
+        assert_eq!(float::fused_multiply_add(3.0, 5.0, -15.0, NearestEven), (0.0, 0));
+        assert_eq!(float::fused_multiply_add(f32::INFINITY, 0.0, 1.0, NearestEven).1, float::NV);
+    }
+
+    #[test]
+    fn test_load_store() {
+        // flw f1, 0x100(zero); fsw f1, 0x104(zero)
+        let mut memory = Memory::new(&[0x1000_2087, 0x1010_2227]);
+        memory.bytes[0x100..0x104].copy_from_slice(&1.5f32.to_le_bytes());
+        let mut core = core32();
+
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(1).f32(), 1.5);
+        core.execute(&mut memory);
+        assert_eq!(memory.bytes[0x104..0x108], 1.5f32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_sqrt_compare_classify() {
+        // fsqrt.s f2, f1; feq.s a0, f1, f3; flt.s a1, f1, f3; fle.s a2, f1, f3; fclass.s a3, f1
+        let program = [0x5800_8153, 0xA030_A553, 0xA030_95D3, 0xA030_8653, 0xE000_96D3];
+        let mut memory = Memory::new(&program);
+        let mut core = core32();
+        core.set_f(1, 4.0.into());
+        core.set_f(3, 4.0.into());
+        for _ in 0..program.len() {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.get_f(2).f32(), 2.0);
+        assert_eq!([core.get(10), core.get(11), core.get(12)], [1u32.into(), 0u32.into(), 1u32.into()]);
+        // A positive normal number
+        assert_eq!(core.get(13), (1u32 << 6).into());
+        assert_eq!(core.fcsr().fflags, 0);
+
+        // Only the ordered comparisons signal on a quiet NaN, and the square root of a negative number is invalid
+        let mut memory = Memory::new(&program);
+        let mut core = core32();
+        core.set_f(1, FpRegister(QUIET_NAN));
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0u32.into());
+        assert_eq!(core.fcsr().fflags, 0);
+        core.execute(&mut memory);
+        assert_eq!(core.get(11), 0u32.into());
+        assert_eq!(core.fcsr().fflags, float::NV);
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.get(13), (1u32 << 9).into());
+        let (root, flags) = float::sqrt(-1.0, float::RoundingMode::NearestEven);
+        assert_eq!((root.to_bits(), flags), (float::CANONICAL_NAN, float::NV));
+    }
+
+    #[test]
+    fn test_misa() {
+        assert_eq!(core32().get_csr(0x301).unwrap().word()[0] & 1 << 5, 1 << 5);
+        assert_eq!(core32().with_zfinx(true).get_csr(0x301).unwrap().word()[0] & 1 << 5, 0);
+    }
+
+    #[test]
+    fn test_zfinx() {
+        // fadd.s a0, a1, a2; flw ft0, 0(zero); fmv.x.w a0, ft1
+        let mut memory = Memory::new(&[0x00C5_F553, 0x0000_2007, 0xE000_8553]);
+        let mut core = core32().with_zfinx(true).with_trap_strategy(HostReturn);
+        core.set(11, 1.5f32.to_bits().into());
+        core.set(12, 2.0f32.to_bits().into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 3.5f32.to_bits().into());
+        assert_eq!(core.get_f(10).f32(), 3.5);
+
+        // There are no floating-point registers to load into or move from
+        assert_eq!(core.execute(&mut memory), Some(Trap::IllegalInstruction));
+        core.pc = 8u32.into();
+        assert_eq!(core.execute(&mut memory), Some(Trap::IllegalInstruction));
+    }
+}
@@ -27,7 +27,7 @@ mod ext_m_tests {
         assert_eq!(thirty_five.divu(0.into()), 0xFFFF_FFFFu32.into());
 
         // Overflow
-        let max_neg: Register32 = std::i32::MIN.into();
+        let max_neg: Register32 = i32::MIN.into();
         assert_eq!(max_neg.div((-1).into()), max_neg);
     }
 
@@ -50,4 +50,41 @@ mod ext_m_tests {
         let max_neg: Register32 = (-2i32).pow(31).into();
         assert_eq!(max_neg.rem((-1).into()), 0.into());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_div64() {
+        let thirty_five: Register64 = 35i64.into();
+        assert_eq!(thirty_five.div((-9i64).into()), (-3i64).into());
+        assert_eq!(thirty_five.divu(9i64.into()), 3i64.into());
+
+        // Division by zero
+        assert_eq!(thirty_five.div(0i64.into()), (-1i64).into());
+        assert_eq!(thirty_five.divu(0i64.into()), u64::MAX.into());
+
+        // Overflow
+        let max_neg: Register64 = i64::MIN.into();
+        assert_eq!(max_neg.div((-1i64).into()), max_neg);
+        // A word that is only negative at 32 bits is divided as positive
+        let word_min: Register64 = (i32::MIN as u32 as u64).into();
+        assert_eq!(word_min.div((-1i64).into()), (i32::MIN as i64).into());
+    }
+
+    #[test]
+    fn test_rem64() {
+        let neg_nine: Register64 = (-9i64).into();
+        let thirty_five: Register64 = 35i64.into();
+
+        // Sign is of the dividend
+        assert_eq!(neg_nine.rem(4i64.into()), (-1i64).into());
+        assert_eq!(thirty_five.rem((-6i64).into()), 5i64.into());
+        assert_eq!(neg_nine.remu(4i64.into()), 3i64.into());
+
+        // Remainder from division by 0 is the dividend
+        assert_eq!(thirty_five.rem(0i64.into()), thirty_five);
+        assert_eq!(neg_nine.remu(0i64.into()), neg_nine);
+
+        // Overflow
+        let max_neg: Register64 = i64::MIN.into();
+        assert_eq!(max_neg.rem((-1i64).into()), 0i64.into());
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(feature = "ext-v")]
+mod common;
+
+#[cfg(feature = "ext-v")]
+mod ext_v_tests {
+    use super::common::*;
+    use rysk_core::*;
+    use rysk_core::vector::Vtype;
+
+    /// Execute the program with a core configured by `vsetvli zero, a0, vtype` for `vl` elements
+    fn configured(program: &[u32], vl: u64, vtype: u64) -> (Core<Register32>, Memory) {
+        let mut core = core32();
+        core.vector_mut().configure(Some(vl), vtype);
+        (core, Memory::new(program))
+    }
+
+    #[test]
+    fn test_vsetvli() {
+        // vsetvli a0, a1, e32, m1, ta, ma; vsetvli a0, a1, e32, m2, ta, ma; vsetvli a0, zero, e32, m1, ta, ma; vsetvli a0, a1, <reserved>
+        let mut memory = Memory::new(&[0x0D05_F557, 0x0D15_F557, 0x0D00_7557, 0x0045_F557]);
+        let mut core = core32();
+        core.set(11, 10u32.into());
+
+        // VLMAX is VLEN / SEW * LMUL, which the AVL is clamped to
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 4u32.into());
+        assert_eq!(core.vector().vtype, Some(Vtype { sew: 32, lmul: 0, tail_agnostic: true, mask_agnostic: true }));
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 8u32.into());
+        // An AVL of x0 requests every element
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 4u32.into());
+
+        // A reserved vtype sets vill and clears vl
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0u32.into());
+        assert_eq!(core.vector().vtype, None);
+        assert_eq!(core.get_csr(0xC21).unwrap(), 0x8000_0000u32.into());
+    }
+
+    #[test]
+    fn test_illegal_without_vtype() {
+        // vadd.vv v1, v2, v3
+        let mut memory = Memory::new(&[0x0221_80D7]);
+        let mut core = core32();
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // vadd.vv v1, v2, v3; vadd.vi v1, v2, -1, v0.t
+        let (mut core, mut memory) = configured(&[0x0221_80D7, 0x002F_B0D7], 3, 0b010_000);
+        for index in 0..4 {
+            core.vector_mut().set_element(2, index, 32, 10 * index as u64);
+            core.vector_mut().set_element(3, index, 32, index as u64);
+        }
+        core.vector_mut().set_element(1, 3, 32, 0xAAAA);
+        core.execute(&mut memory);
+        let elements = |core: &Core<Register32>| (0..4).map(|index| core.vector().element(1, index, 32)).collect::<Vec<_>>();
+        // The tail beyond vl is undisturbed
+        assert_eq!(elements(&core), [0, 11, 22, 0xAAAA]);
+
+        // Only the elements enabled by v0 are written
+        core.vector_mut().register_mut(0)[0] = 0b0101;
+        core.execute(&mut memory);
+        assert_eq!(elements(&core), [0xFFFF_FFFF, 11, 19, 0xAAAA]);
+    }
+
+    #[test]
+    fn test_register_groups() {
+        // vadd.vv v2, v4, v6; vadd.vv v1, v2, v3
+        let (mut core, mut memory) = configured(&[0x0243_0157, 0x0221_80D7], 8, 0b010_001);
+        for index in 0..8 {
+            core.vector_mut().set_element(4, index, 32, index as u64);
+        }
+        core.execute(&mut memory);
+        // The group of v2 continues into v3
+        assert_eq!(core.vector().element(3, 3, 32), 7);
+
+        // Register groups must be aligned to their size
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+    }
+
+    #[test]
+    fn test_compare_and_reduce() {
+        // vmslt.vx v1, v2, a0; vredsum.vs v1, v2, v3
+        let (mut core, mut memory) = configured(&[0x6E25_40D7, 0x0221_A0D7], 4, 0);
+        core.vector_mut().register_mut(2)[..4].copy_from_slice(&[1, 0xFE, 5, 3]);
+        core.vector_mut().register_mut(3)[0] = 100;
+        core.set(10, 2u32.into());
+        core.execute(&mut memory);
+        // 0xFE is -2 as a signed byte
+        assert_eq!(core.vector().register(1)[0], 0b0011);
+
+        core.execute(&mut memory);
+        assert_eq!(core.vector().element(1, 0, 8), (100 + 1 + 0xFE + 5 + 3) & 0xFF);
+    }
+
+    #[test]
+    fn test_moves() {
+        // vmv.x.s a0, v2; vid.v v1; vmv.s.x v1, a0
+        let (mut core, mut memory) = configured(&[0x4220_2557, 0x5208_A0D7, 0x4205_60D7], 4, 0b001_000);
+        core.vector_mut().set_element(2, 0, 16, 0x8001);
+        core.execute(&mut memory);
+        // The element is sign-extended to XLEN
+        assert_eq!(core.get(10), 0xFFFF_8001u32.into());
+
+        core.execute(&mut memory);
+        assert_eq!((0..4).map(|index| core.vector().element(1, index, 16)).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        core.execute(&mut memory);
+        assert_eq!(core.vector().element(1, 0, 16), 0x8001);
+    }
+
+    #[test]
+    fn test_memcpy() {
+        // loop: vsetvli t0, a2, e8, m8, ta, ma; vle8.v v8, (a1); add a1, a1, t0; sub a2, a2, t0; vse8.v v8, (a0);
+        // add a0, a0, t0; bnez a2, loop
+        let mut memory = Memory::new(&[0x0C36_72D7, 0x0205_8407, 0x0055_85B3, 0x4056_0633, 0x0205_0427, 0x0055_0533, 0xFE06_14E3]);
+        let source: Vec<u8> = (0..300u32).map(|byte| (byte * 7) as u8).collect();
+        memory.bytes[0x400..0x400 + source.len()].copy_from_slice(&source);
+        let mut core = core32();
+        core.set(10, 0x800u32.into());
+        core.set(11, 0x400u32.into());
+        core.set(12, (source.len() as u32).into());
+        // Each iteration copies up to VLMAX of 128 bytes
+        while core.pc != 28u32.into() {
+            core.execute(&mut memory);
+            assert_eq!(core.last_outcome(), StepOutcome::Retired);
+        }
+        assert_eq!(memory.bytes[0x800..0x800 + source.len()], source[..]);
+        assert_eq!(memory.bytes[0x800 + source.len()], 0);
+    }
+
+    #[test]
+    fn test_strided_and_indexed() {
+        // vlse32.v v1, (a0), a1; vluxei32.v v1, (a0), v2; vloxei8.v v1, (a0), v2
+        let (mut core, mut memory) = configured(&[0x0AB5_6087, 0x0625_6087, 0x0E25_0087], 4, 0b010_000);
+        for word in 0..16u32 {
+            memory.bytes[0x400 + word as usize * 4..][..4].copy_from_slice(&(word + 100).to_le_bytes());
+        }
+        core.set(10, 0x400u32.into());
+        core.set(11, 12u32.into());
+        core.execute(&mut memory);
+        let elements = |core: &Core<Register32>, sew: u32| (0..4).map(|index| core.vector().element(1, index, sew)).collect::<Vec<_>>();
+        assert_eq!(elements(&core, 32), [100, 103, 106, 109]);
+
+        // The offsets of an indexed load are in bytes
+        for (index, offset) in [8, 0, 60, 4].iter().enumerate() {
+            core.vector_mut().set_element(2, index, 32, *offset);
+        }
+        core.execute(&mut memory);
+        assert_eq!(elements(&core, 32), [102, 100, 115, 101]);
+
+        // Offsets may be narrower than the elements they load, which are always SEW bits
+        core.vector_mut().register_mut(2)[..4].copy_from_slice(&[4, 8, 12, 0]);
+        core.execute(&mut memory);
+        assert_eq!(elements(&core, 32), [101, 102, 103, 100]);
+    }
+
+    #[test]
+    fn test_segments() {
+        // vlseg2e16.v v2, (a0); vsseg2e16.v v2, (a0)
+        let (mut core, mut memory) = configured(&[0x2205_5107, 0x2205_5127], 3, 0b001_000);
+        for half in 0..6u16 {
+            memory.bytes[0x400 + half as usize * 2..][..2].copy_from_slice(&half.to_le_bytes());
+        }
+        core.set(10, 0x400u32.into());
+        core.execute(&mut memory);
+        // The fields of each segment are split between consecutive register groups
+        assert_eq!((0..3).map(|index| core.vector().element(2, index, 16)).collect::<Vec<_>>(), [0, 2, 4]);
+        assert_eq!((0..3).map(|index| core.vector().element(3, index, 16)).collect::<Vec<_>>(), [1, 3, 5]);
+
+        core.set(10, 0x800u32.into());
+        core.execute(&mut memory);
+        assert_eq!(memory.bytes[0x800..0x80C], memory.bytes[0x400..0x40C]);
+    }
+
+    #[test]
+    fn test_masked_load() {
+        // vle32.v v1, (a0), v0.t
+        let (mut core, mut memory) = configured(&[0x0005_6087], 4, 0b010_000);
+        for word in 0..4u32 {
+            memory.bytes[0x400 + word as usize * 4..][..4].copy_from_slice(&(word + 1).to_le_bytes());
+        }
+        core.set(10, 0x400u32.into());
+        core.vector_mut().register_mut(0)[0] = 0b1010;
+        core.execute(&mut memory);
+        assert_eq!((0..4).map(|index| core.vector().element(1, index, 32)).collect::<Vec<_>>(), [0, 2, 0, 4]);
+    }
+
+    #[test]
+    fn test_faults() {
+        // vle32.v v1, (a0); vle32ff.v v1, (a0)
+        let mut memory = ArrayRam::<0x100>::new(0);
+        memory.load(0, &0x0205_6087u32.to_le_bytes());
+        memory.load(4, &0x0305_6087u32.to_le_bytes());
+        let mut core = core32().with_trap_strategy(HostReturn);
+        core.vector_mut().configure(Some(4), 0b010_000);
+        core.set(10, 0xF8u32.into());
+
+        // The third element is beyond memory, so the load resumes from it once the trap is handled
+        assert_eq!(core.execute(&mut memory), Some(system::Trap::LoadAccessFault(0x100)));
+        assert_eq!(core.vector().vstart, 2);
+
+        // A fault-only-first load instead truncates vl
+        core.vector_mut().vstart = 0;
+        core.pc = 4u32.into();
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.vector().vl, 2);
+        core.vector_mut().vl = 4;
+        core.set(10, 0x100u32.into());
+        core.pc = 4u32.into();
+        assert_eq!(core.execute(&mut memory), Some(system::Trap::LoadAccessFault(0x100)));
+    }
+
+    #[test]
+    fn test_csrs() {
+        let mut core = core32().with_vlen(256);
+        core.vector_mut().configure(Some(100), 0b1101_0000);
+        assert_eq!(core.get_csr(0xC22).unwrap(), 32u32.into());
+        assert_eq!(core.get_csr(0xC20).unwrap(), 8u32.into());
+        assert_eq!(core.get_csr(0xC21).unwrap(), 0b1101_0000u32.into());
+        core.set_csr(0x00F, 0b101u32.into());
+        assert_eq!(core.get_csr(0x00A).unwrap(), 0b10u32.into());
+        assert_eq!(core.get_csr(0x009).unwrap(), 1u32.into());
+        assert_eq!(core.get_csr(0x301).unwrap().word()[2] & 1 << 5, 1 << 5);
+    }
+
+    #[test]
+    fn test_core_dump() {
+        let memory = Memory::new(&[]);
+        let mut core = core32();
+        core.vector_mut().configure(Some(3), 0b1101_0000);
+        core.set_csr(0x008, 2u32.into());
+        core.set_csr(0x00F, 0b101u32.into());
+        let dump = core.core_dump(&memory, None);
+
+        let mut restored = core32();
+        assert_eq!(restored.load_core_dump(&dump, &mut Memory::new(&[])), Ok(()));
+        assert!(core.diff(&restored).is_empty());
+        assert_eq!(restored.vector().vtype, Vtype::decode(0b1101_0000));
+        assert_eq!((restored.vector().vl, restored.vector().vstart), (3, 2));
+        assert_eq!(restored.get_csr(0x00F).unwrap(), 0b101u32.into());
+
+        // An unconfigured vtype is restored with vill set
+        let dump = core32().core_dump(&memory, None);
+        assert_eq!(restored.load_core_dump(&dump, &mut Memory::new(&[])), Ok(()));
+        assert_eq!(restored.vector().vtype, None);
+        assert_eq!(restored.get_csr(0xC21).unwrap(), (1u32 << 31).into());
+        assert_eq!(core.diff(&restored).csrs.iter().map(|&(index, _, _)| index).collect::<Vec<_>>(), [0x008, 0x00F, 0xC20, 0xC21]);
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(feature = "ext-zawrs")]
+mod common;
+
+#[cfg(feature = "ext-zawrs")]
+mod ext_zawrs_tests {
+
+    use super::common::*;
+    #[cfg(feature = "ext-a")]
+    use rysk_core::*;
+
+    /// Memory recording the reservation and the kind of each wait
+    #[cfg(feature = "ext-a")]
+    struct Waits(Memory, Vec<(u32, u8, bool)>);
+    #[cfg(feature = "ext-a")]
+    impl Mmu<Register32> for Waits {
+        fn get(&self, address: u32) -> u8 {
+            self.0.get(address)
+        }
+        fn set(&mut self, address: u32, value: u8) {
+            self.0.set(address, value)
+        }
+        fn wait_on_reservation(&mut self, address: u32, size: u8, short: bool) {
+            self.1.push((address, size, short))
+        }
+    }
+
+    #[test]
+    fn test_wrs() {
+        // wrs.nto; wrs.sto
+        let mut memory = Memory::new(&[0x00D0_0073, 0x01D0_0073]);
+        let mut core = core32();
+
+        #[cfg(not(feature = "ext-csr"))]
+        assert_eq!(core.execute(&mut memory), None);
+        #[cfg(feature = "ext-csr")]
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 4u32.into());
+
+        #[cfg(not(feature = "ext-csr"))]
+        assert_eq!(core.execute(&mut memory), None);
+        #[cfg(feature = "ext-csr")]
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 8u32.into());
+    }
+
+    #[cfg(feature = "ext-a")]
+    #[test]
+    fn test_wait_on_reservation() {
+        // lr.w a1, (a0); wrs.nto; wrs.sto; sw a1, 0(a0); wrs.sto
+        let mut memory = Waits(Memory::new(&[0x1005_25AF, 0x00D0_0073, 0x01D0_0073, 0x00B5_2023, 0x01D0_0073]), Vec::new());
+        let mut core = core32();
+        core.set(10, 0x400u32.into());
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), StepOutcome::Waiting);
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), StepOutcome::Waiting);
+
+        // The store invalidates the reservation, leaving nothing to wait for
+        core.execute(&mut memory);
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), StepOutcome::Retired);
+        assert_eq!(core.pc, 20u32.into());
+        assert_eq!(memory.1, [(0x400, 4, false), (0x400, 4, true)]);
+    }
+
+    #[cfg(all(feature = "ext-a", feature = "ext-csr"))]
+    #[test]
+    fn test_timeout_wait() {
+        // lr.w a1, (a0); wrs.sto; wrs.nto
+        let mut memory = Memory::new(&[0x1005_25AF, 0x01D0_0073, 0x00D0_0073]);
+        let mut core = core32().with_trap_strategy(HostReturn);
+        core.set_csr(0x300, (1u32 << 21).into());
+        core.csr_mut().privilege = csr::Privilege::User;
+        core.set(10, 0x400u32.into());
+        // Only the unbounded wait of WRS.NTO is illegal below machine mode while mstatus.TW is set
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.execute(&mut memory), Some(system::Trap::IllegalInstruction));
+    }
+}
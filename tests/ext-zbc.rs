@@ -0,0 +1,37 @@
+#[cfg(feature = "ext-zbc")]
+mod common;
+
+#[cfg(feature = "ext-zbc")]
+mod ext_zbc_tests {
+    use super::common::*;
+    use rysk_core::*;
+    use rysk_core::register::Integer;
+
+    #[test]
+    fn test_clmul() {
+        assert_eq!(0b101u32.clmul(0b11), (0b1111, 0));
+        assert_eq!(u64::MAX.clmul(2), (u64::MAX - 1, 1));
+        // Sign extension of a signed operand does not reach the high bits
+        assert_eq!((-1i32).clmul(2), (-2, 1));
+
+        let top: Register32 = 0x8000_0000u32.into();
+        assert_eq!(top.clmul(top), 0u32.into());
+        assert_eq!(top.clmulh(top), 0x4000_0000u32.into());
+        assert_eq!(top.clmulr(top), 0x8000_0000u32.into());
+    }
+
+    #[test]
+    fn test_execute() {
+        // clmul a0, a1, a2; clmulh a0, a1, a2; clmulr a0, a1, a2
+        let mut memory = Memory::new(&[0x0AC5_9533, 0x0AC5_B533, 0x0AC5_A533]);
+        let mut core = core32();
+        core.set(11, 0xFFFF_FFFFu32.into());
+        core.set(12, 0x3u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x0000_0001u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x0000_0001u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x0000_0002u32.into());
+    }
+}
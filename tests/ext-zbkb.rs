@@ -0,0 +1,29 @@
+#[cfg(feature = "ext-zbkb")]
+mod ext_zbkb_tests {
+
+    use rysk_core::*;
+    #[test]
+    fn test_pack() {
+        let low: Register32 = 0xDEAD_1234u32.into();
+        let high: Register32 = 0xBEEF_5678u32.into();
+
+        assert_eq!(low.pack(high), 0x5678_1234u32.into());
+        assert_eq!(low.packh(high), 0x7834u32.into());
+    }
+
+    #[test]
+    fn test_brev8() {
+        let bytes: Register32 = 0x0180_F00Fu32.into();
+        assert_eq!(bytes.brev8(), 0x8001_0FF0u32.into());
+    }
+
+    #[test]
+    fn test_zip() {
+        let halves: Register32 = 0xFFFF_0000u32.into();
+        assert_eq!(halves.zip(), 0xAAAA_AAAAu32.into());
+        assert_eq!(halves.zip().unzip(), halves);
+
+        let mixed: Register32 = 0x1234_5678u32.into();
+        assert_eq!(mixed.zip().unzip(), mixed);
+    }
+}
@@ -0,0 +1,24 @@
+#[cfg(feature = "ext-zbkx")]
+mod ext_zbkx_tests {
+
+    use rysk_core::*;
+    #[test]
+    fn test_xperm4() {
+        let table: Register32 = 0xFEDC_BA98u32.into();
+        // Nibbles beyond the 8 in the register are replaced by zero
+        assert_eq!(table.xperm4(0x0000_0123u32.into()), 0x8888_89ABu32.into());
+        assert_eq!(table.xperm4(0x0000_0009u32.into()), 0x8888_8880u32.into());
+
+        let identity: Register64 = 0xFEDC_BA98_7654_3210u64.into();
+        assert_eq!(identity.xperm4(0x0123_4567_89AB_CDEFu64.into()), 0x0123_4567_89AB_CDEFu64.into());
+    }
+
+    #[test]
+    fn test_xperm8() {
+        let table: Register32 = 0x4433_2211u32.into();
+        assert_eq!(table.xperm8(0x0400_0301u32.into()), 0x0011_4422u32.into());
+
+        let table: Register64 = 0x8877_6655_4433_2211u64.into();
+        assert_eq!(table.xperm8(0x0007_0605_0403_0208u64.into()), 0x1188_7766_5544_3300u64.into());
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(feature = "ext-zbs")]
+mod common;
+
+#[cfg(feature = "ext-zbs")]
+mod ext_zbs_tests {
+    use super::common::*;
+    use rysk_core::*;
+
+    #[test]
+    fn test_single_bit() {
+        let value: Register32 = 0x8000_00F0u32.into();
+        assert_eq!(value.bset(3u32.into()), 0x8000_00F8u32.into());
+        assert_eq!(value.bclr(31u32.into()), 0x0000_00F0u32.into());
+        assert_eq!(value.binv(4u32.into()), 0x8000_00E0u32.into());
+        assert_eq!(value.bext(7u32.into()), 1u32.into());
+        // The index is taken modulo the width of the register
+        assert_eq!(value.bset(35u32.into()), 0x8000_00F8u32.into());
+    }
+
+    #[test]
+    fn test_execute() {
+        // bset a0, a1, a2; bexti a0, a1, 31; bclri a0, a1, 32
+        let mut memory = Memory::new(&[0x28C5_9533, 0x49F5_D513, 0x4A05_9513]);
+        let mut core = core32();
+        core.set(11, 0x8000_0000u32.into());
+        core.set(12, 1u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x8000_0002u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 1u32.into());
+        // There is no bit 32 on RV32
+        core.execute(&mut memory);
+        assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(feature = "ext-zfh")]
+mod common;
+
+#[cfg(feature = "ext-zfh")]
+mod ext_zfh_tests {
+    use super::common::*;
+    use rysk_core::{ Register, float, half };
+
+    #[test]
+    fn test_nan_boxing() {
+        // fadd.h f2, f1, f3
+        let mut memory = Memory::new(&[0x0430_F153]);
+        let mut core = core32();
+
+        // A single is not a valid half, so reads as the canonical NaN without signalling
+        core.set_f(1, 1.0.into());
+        core.set_h(3, 0x3C00);
+        core.execute(&mut memory);
+        assert_eq!(core.get_h(2), half::CANONICAL_NAN);
+        assert_eq!(core.fcsr().fflags, 0);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // fadd.h f2, f1, f3; fmul.h f2, f1, f3
+        let mut memory = Memory::new(&[0x0430_F153, 0x1430_F153]);
+        let mut core = core32();
+        core.set_h(1, 0x3C00);
+        core.set_h(3, 0x4000);
+        core.execute(&mut memory);
+        assert_eq!(core.get_h(2), 0x4200);
+        core.execute(&mut memory);
+        assert_eq!(core.get_h(2), 0x4000);
+        assert_eq!(core.fcsr().fflags, 0);
+
+        assert_eq!(half::div(0x3C00, 0x4200, float::RoundingMode::NearestEven), (0x3555, float::NX));
+        assert_eq!(half::sqrt(0xBC00, float::RoundingMode::NearestEven), (half::CANONICAL_NAN, float::NV));
+        // Subnormal results are exact when representable
+        assert_eq!(half::mul(0x0400, 0x3800, float::RoundingMode::NearestEven), (0x0200, 0));
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        // 1 + 2^-24 is not representable, so is rounded down to nearest and up when rounding up
+        assert_eq!(half::add(0x3C00, 0x0001, float::RoundingMode::NearestEven), (0x3C00, float::NX));
+        assert_eq!(half::add(0x3C00, 0x0001, float::RoundingMode::Up), (0x3C01, float::NX));
+        // Overflow saturates to the largest finite value when rounding towards zero
+        assert_eq!(half::mul(0x7BFF, 0x4000, float::RoundingMode::NearestEven), (0x7C00, float::OF | float::NX));
+        assert_eq!(half::mul(0x7BFF, 0x4000, float::RoundingMode::TowardZero), (0x7BFF, float::OF | float::NX));
+        assert_eq!(half::from_integer(70000, false, float::RoundingMode::NearestEven), (0x7C00, float::OF | float::NX));
+    }
+
+    #[test]
+    fn test_convert() {
+        // fcvt.h.s f5, f4; fcvt.s.h f4, f5; fcvt.w.h a0, f1, rtz
+        let mut memory = Memory::new(&[0x4402_72D3, 0x4022_F253, 0xC400_9553]);
+        let mut core = core32();
+        core.set_f(4, 0.1.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get_h(5), 0x2E66);
+        assert_eq!(core.fcsr().fflags, float::NX);
+        core.execute(&mut memory);
+        assert_eq!(core.get_f(4).f32(), 0.099975586);
+
+        core.set_h(1, 0xC100);
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), (-2i32).into());
+    }
+
+    #[test]
+    fn test_load_store_move() {
+        // flh f1, 0x100(zero); fsh f1, 0x108(zero); fmv.x.h a0, f1
+        let mut memory = Memory::new(&[0x1000_1087, 0x1010_1427, 0xE400_8553]);
+        memory.bytes[0x100..0x102].copy_from_slice(&0xC100u16.to_le_bytes());
+        let mut core = core32();
+        core.execute(&mut memory);
+        assert_eq!(core.get_h(1), 0xC100);
+        core.execute(&mut memory);
+        assert_eq!(memory.bytes[0x108..0x10A], 0xC100u16.to_le_bytes());
+        // The half is sign-extended into the integer register
+        core.execute(&mut memory);
+        assert_eq!(core.get(10).word(), 0xFFFF_C100u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(feature = "ext-zicbom")]
+mod common;
+
+#[cfg(feature = "ext-zicbom")]
+mod ext_zicbom_tests {
+    use super::common::*;
+    use rysk_core::*;
+    use rysk_core::CacheBlockOperation::*;
+
+    /// Memory recording the operation and block address of each CBO instruction
+    struct Blocks(Memory, Vec<(CacheBlockOperation, u32)>);
+    impl Mmu<Register32> for Blocks {
+        fn get(&self, address: u32) -> u8 {
+            self.0.get(address)
+        }
+        fn set(&mut self, address: u32, value: u8) {
+            self.0.set(address, value)
+        }
+        fn cache_block(&mut self, operation: CacheBlockOperation, address: u32) -> Result<(), MemoryFault> {
+            self.1.push((operation, address));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cbo() {
+        // cbo.clean (a0); cbo.flush (a0); cbo.inval (a0)
+        let mut memory = Blocks(Memory::new(&[0x0005_200F, 0x0015_200F, 0x0025_200F]), Vec::new());
+        let mut core = core32();
+        core.set(10, 0x1234u32.into());
+        for _ in 0..3 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.pc, 12u32.into());
+        // Each operates on the 64-byte block holding the address
+        assert_eq!(memory.1, [(Clean, 0x1200), (Flush, 0x1200), (Invalidate, 0x1200)]);
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn test_menvcfg() {
+        let mut memory = Blocks(Memory::new(&[0x0005_200F, 0x0015_200F, 0x0025_200F]), Vec::new());
+        let mut core = core32().with_trap_strategy(HostReturn);
+        core.csr_mut().privilege = csr::Privilege::User;
+        // Below machine mode every operation is illegal until enabled
+        for pc in [0u32, 4, 8] {
+            core.pc = pc.into();
+            assert_eq!(core.execute(&mut memory), Some(system::Trap::IllegalInstruction));
+        }
+
+        // Enable CBO.CLEAN and CBO.FLUSH, with a CBIE of 0b01 having CBO.INVAL flush
+        core.set_csr(0x30A, 0b0101_0000u32.into());
+        core.pc = 0u32.into();
+        for _ in 0..3 {
+            assert_eq!(core.execute(&mut memory), None);
+        }
+        assert_eq!(memory.1, [(Clean, 0), (Flush, 0), (Flush, 0)]);
+
+        // The reserved CBIE of 0b10 leaves the field unchanged
+        core.set_csr(0x30A, 0b0010_0000u32.into());
+        assert_eq!(core.get_csr(0x30A).unwrap(), 0b0001_0000u32.into());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_menvcfg_core_dump() {
+        let memory = Memory::new(&[]);
+        let mut core = core32();
+        core.set_csr(0x30A, 0b0101_0000u32.into());
+        let dump = core.core_dump(&memory, None);
+
+        let mut restored = core32();
+        assert_eq!(restored.load_core_dump(&dump, &mut Memory::new(&[])), Ok(()));
+        assert_eq!(restored.get_csr(0x30A).unwrap(), 0b0101_0000u32.into());
+        assert!(core.diff(&restored).is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(feature = "ext-zicboz")]
+mod common;
+
+#[cfg(feature = "ext-zicboz")]
+mod ext_zicboz_tests {
+    use super::common::*;
+    use rysk_core::*;
+
+    #[test]
+    fn test_cbo_zero() {
+        // cbo.zero (a0)
+        let mut memory = Memory::new(&[0x0045_200F]);
+        memory.bytes[0x400..0x480].fill(0xFF);
+        let mut core = core32();
+        core.set(10, 0x43Cu32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 4u32.into());
+        // Only the 64-byte block holding the address is zeroed
+        assert!(memory.bytes[0x400..0x440].iter().all(|&byte| byte == 0));
+        assert!(memory.bytes[0x440..0x480].iter().all(|&byte| byte == 0xFF));
+    }
+
+    #[test]
+    fn test_cbo_zero_fault() {
+        // cbo.zero (a0)
+        let mut memory = ArrayRam::<0x100>::new(0);
+        memory.load(0, &0x0045_200Fu32.to_le_bytes());
+        let mut core = core32().with_trap_strategy(HostReturn);
+        // A block beyond memory raises a store access fault at the address in rs1
+        core.set(10, 0x105u32.into());
+        assert_eq!(core.execute(&mut memory), Some(system::Trap::StoreAccessFault(0x105)));
+
+        // Below machine mode CBO.ZERO is illegal until enabled by menvcfg
+        #[cfg(feature = "ext-csr")]
+        {
+            core.csr_mut().privilege = csr::Privilege::User;
+            core.pc = 0u32.into();
+            core.set(10, 0x40u32.into());
+            assert_eq!(core.execute(&mut memory), Some(system::Trap::IllegalInstruction));
+            core.set_csr(0x30A, 0b1000_0000u32.into());
+            core.pc = 0u32.into();
+            assert_eq!(core.execute(&mut memory), None);
+        }
+    }
+}
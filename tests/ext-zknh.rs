@@ -0,0 +1,87 @@
+#[cfg(feature = "ext-zknh")]
+mod common;
+
+#[cfg(feature = "ext-zknh")]
+mod ext_zknh_tests {
+    use super::common::*;
+    use rysk_core::*;
+
+    #[test]
+    fn test_sha256() {
+        // The message schedule and first round of hashing "abc"
+        assert_eq!(Register32::from(0x18u32).sha256sig1(), 0x000F_0000u32.into());
+        assert_eq!(Register32::from(0x6162_6380u32).sha256sig0(), 0x940E_90EFu32.into());
+        assert_eq!(Register32::from(0x6A09_E667u32).sha256sum0(), 0xCE20_B47Eu32.into());
+        assert_eq!(Register32::from(0x510E_527Fu32).sha256sum1(), 0x3587_272Bu32.into());
+        // The result is sign-extended on RV64
+        assert_eq!(Register64::from(0x6A09_E667u64).sha256sum0(), 0xFFFF_FFFF_CE20_B47Eu64.into());
+    }
+
+    #[test]
+    fn test_sha512() {
+        let x: Register64 = 0x6A09_E667_F3BC_C908u64.into();
+        assert_eq!(x.sha512sum0(), 0x08C4_DB56_AAC8_0C2Au64.into());
+        assert_eq!(x.sha512sum1(), 0x259A_6CC1_6433_36EFu64.into());
+        assert_eq!(x.sha512sig0(), 0x3DBA_E919_51CA_A1DFu64.into());
+        assert_eq!(x.sha512sig1(), 0xC8C6_19E7_3EE4_4510u64.into());
+    }
+
+    #[test]
+    fn test_sha512_rv32() {
+        // Each pair computes the low then the high word of a SHA-512 function of a2:a1 into a0 and a3:
+        // sha512sum0r, sha512sum1r, sha512sig0l and sha512sig0h, sha512sig1l and sha512sig1h
+        let pairs = [
+            (0x50C5_8533, 0x50B6_06B3, 0x08C4_DB56_AAC8_0C2Au64),
+            (0x52C5_8533, 0x52B6_06B3, 0x259A_6CC1_6433_36EF),
+            (0x54C5_8533, 0x5CB6_06B3, 0x3DBA_E919_51CA_A1DF),
+            (0x56C5_8533, 0x5EB6_06B3, 0xC8C6_19E7_3EE4_4510)
+        ];
+        for (low, high, expected) in pairs {
+            let mut memory = Memory::new(&[low, high]);
+            let mut core = core32();
+            core.set(11, 0xF3BC_C908u32.into());
+            core.set(12, 0x6A09_E667u32.into());
+            core.execute(&mut memory);
+            core.execute(&mut memory);
+            assert_eq!((core.get(10), core.get(13)), ((expected as u32).into(), ((expected >> 32) as u32).into()));
+        }
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn test_user_xlen32() {
+        // mret; sha512sum0r a0, a1, a2; sha512sum0 a0, a1
+        let mut memory = ArrayRam::<0x200>::new(0);
+        for (index, instruction) in [0x3020_0073u32, 0x50C5_8533, 0x1045_9513].iter().enumerate() {
+            memory.load(index * 4, &instruction.to_le_bytes());
+        }
+        let mut core = Core::<Register64>::new(0, 0);
+        // Return to user mode with a UXL of 32
+        core.csr_mut().write_mstatus((1u64 << csr::MSTATUS_UXL).into());
+        core.csr_mut().mepc = 4u64.into();
+        core.csr_mut().mtvec = 0x100u64.into();
+        core.set(11, 0xF3BC_C908u64.into());
+        core.set(12, 0x6A09_E667u64.into());
+        core.execute(&mut memory);
+        // The RV32 forms are legal under an effective XLEN of 32
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0xFFFF_FFFF_AAC8_0C2Au64.into());
+        assert_eq!(core.pc, 8u64.into());
+        // While the RV64 forms are not
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x100u64.into());
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u64.into());
+    }
+
+    #[test]
+    fn test_execute() {
+        // sha256sig0 a0, a1; sha256sum1 a0, a0
+        let mut memory = Memory::new(&[0x1025_9513, 0x1015_1513]);
+        let mut core = core32();
+        core.set(11, 0x6162_6380u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x940E_90EFu32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), Register32::from(0x940E_90EFu32).sha256sum1());
+    }
+}
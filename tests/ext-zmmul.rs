@@ -0,0 +1,30 @@
+#[cfg(feature = "ext-zmmul")]
+mod common;
+
+#[cfg(feature = "ext-zmmul")]
+mod ext_zmmul_tests {
+    use super::common::*;
+    use rysk_core::*;
+
+    #[test]
+    fn test_zmmul() {
+        // mul a0, a0, a1; div a0, a0, a1
+        let mut memory = Memory::new(&[0x02B5_0533, 0x02B5_4533]);
+        let mut core = core32().with_trap_strategy(HostReturn);
+        core.set(10, 6u32.into());
+        core.set(11, 7u32.into());
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.get(10), 42u32.into());
+
+        // Division is only provided by the full M extension
+        let divided = core.execute(&mut memory);
+        if cfg!(feature = "ext-m") {
+            assert_eq!(divided, None);
+            assert_eq!(core.get(10), 6u32.into());
+        } else {
+            assert_eq!(divided, Some(system::Trap::IllegalInstruction));
+        }
+        #[cfg(feature = "ext-csr")]
+        assert_eq!(core.get_csr(0x301).unwrap().word()[1] & 1 << 4, if cfg!(feature = "ext-m") { 1 << 4 } else { 0 });
+    }
+}
@@ -0,0 +1,37 @@
+/// Every extension that builds without `std`
+const FEATURES: &str = "ext-csr,ext-m,ext-a,ext-c,ext-zbkb,ext-zbkx,ext-zbs,ext-zbc,ext-zknh,ext-zawrs,ext-zicbom,ext-zicboz";
+
+/// Build the library for `target` without default features, with each set of features and any extra cargo arguments
+fn build(target: &str, feature_sets: &[String], extra: &[&str]) {
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let target_dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std");
+    for features in feature_sets {
+        let status = std::process::Command::new(&cargo)
+            .args(extra)
+            .args(["build", "--lib", "--no-default-features", "--features", features])
+            .args(["--target", target])
+            .arg("--target-dir").arg(&target_dir)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .status()
+            .expect("Unable to run cargo");
+        assert!(status.success(), "The crate does not build for {} without std with {}", target, features);
+    }
+}
+
+/// Build the crate without `std` for a bare-metal 32-bit target, both without an allocator and with `alloc`.
+/// Requires the target to be installed, such as with `rustup target add thumbv7em-none-eabi`, so must be run explicitly
+/// with `cargo test --test no_std -- --ignored`
+#[test]
+#[ignore]
+fn build_thumbv7em() {
+    build("thumbv7em-none-eabi", &[FEATURES.to_string(), format!("{},alloc,ext-v", FEATURES)], &[]);
+}
+
+/// Build the crate without `std` for a 16-bit target, where `RegisterSize` is compiled out.
+/// msp430-none-elf has no prebuilt standard library, so this requires a nightly toolchain with the `rust-src` component
+/// to build `core`, and must be run explicitly with `cargo +nightly test --test no_std -- --ignored build_msp430`
+#[test]
+#[ignore]
+fn build_msp430() {
+    build("msp430-none-elf", &[String::new(), FEATURES.to_string()], &["-Z", "build-std=core"]);
+}
@@ -0,0 +1,35 @@
+#[cfg(feature = "rv32imac")]
+mod common;
+
+#[cfg(feature = "rv32imac")]
+mod profile_tests {
+    use super::common::*;
+    use rysk_core::{ ArrayRam, Profile, Register32 };
+
+    #[test]
+    fn test_rv32imac() {
+        let mut memory = ArrayRam::<0x200>::new(0);
+        memory.load(0, &0x02C5_8533u32.to_le_bytes()); // mul a0, a1, a2
+        memory.load(4, &0x00C5_A6AFu32.to_le_bytes()); // amoadd.w a3, a2, (a1)
+        memory.load(8, &[0x05, 0x05]); // c.addi a0, 1
+        memory.load(0x100, &5u32.to_le_bytes());
+        let mut core = core32().with_profile(Profile::Rv32imac);
+        core.set(11, 0x100u32.into());
+        core.set(12, 3u32.into());
+
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x300u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get(13), 5u32.into());
+        assert_eq!(memory.bytes[0x100..0x104], 8u32.to_le_bytes());
+        core.execute(&mut memory);
+        assert_eq!(core.get(10), 0x301u32.into());
+        assert_eq!(core.pc, Register32::from(10u32));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_wrong_width() {
+        core32().with_profile(Profile::Rv64gc);
+    }
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::*;
+use rysk_core::{ ArrayRam, Core, Register, Register32, Register64 };
+
+#[test]
+fn shift_amount_masked_rv32() {
+    let one = Register32::from(1u32);
+    let high = Register32::from(0x8000_0000u32);
+    // Only the low 5 bits of the shift amount are used
+    assert_eq!(one.shl(33u32.into()), 2u32.into());
+    assert_eq!(high.shr(33u32.into()), 0x4000_0000u32.into());
+    assert_eq!(high.sha(33u32.into()), 0xC000_0000u32.into());
+    assert_eq!(one.shl(32u32.into()), one);
+
+    // sll x3, x1, x2
+    let mut memory = Memory::new(&[0x0020_91B3]);
+    let mut core = core32();
+    core.set(1, one);
+    core.set(2, 33u32.into());
+    core.execute(&mut memory);
+    assert_eq!(core.get(3), 2u32.into());
+}
+
+#[test]
+fn shift_amount_masked_rv64() {
+    let one = Register64(1u64.to_le_bytes());
+    let high = Register64(0x8000_0000_0000_0000u64.to_le_bytes());
+    let by = |amount: u64| Register64(amount.to_le_bytes());
+    // Only the low 6 bits of the shift amount are used
+    assert_eq!(one.shl(by(65)), Register64(2u64.to_le_bytes()));
+    assert_eq!(high.shr(by(65)), Register64(0x4000_0000_0000_0000u64.to_le_bytes()));
+    assert_eq!(high.sha(by(65)), Register64(0xC000_0000_0000_0000u64.to_le_bytes()));
+    assert_eq!(one.shl(by(64)), one);
+    // Shift amounts of 32 to 63 are not masked away as they would be for a word
+    assert_eq!(one.shl(by(33)), Register64((1u64 << 33).to_le_bytes()));
+
+    // sll x3, x1, x2
+    let mut memory = ArrayRam::<0x10>::new(0);
+    memory.load(0, &0x0020_91B3u32.to_le_bytes());
+    #[cfg(feature = "ext-csr")]
+    let mut core = Core::<Register64>::new(0, 0);
+    #[cfg(not(feature = "ext-csr"))]
+    let mut core = Core::<Register64>::new(0);
+    core.set(1, one);
+    core.set(2, by(65));
+    core.execute(&mut memory);
+    assert_eq!(core.get(3), Register64(2u64.to_le_bytes()));
+}
+
+#[test]
+fn integer_rotate() {
+    use rysk_core::register::Integer;
+    // Rotating by 0 or by the width is the identity
+    assert_eq!(Integer::rotl(0x8000_0001u32, 0), 0x8000_0001);
+    assert_eq!(Integer::rotr(0x8000_0001u32, 32), 0x8000_0001);
+    assert_eq!(Integer::rotl(0x8000_0000_0000_0001u64, 64), 0x8000_0000_0000_0001);
+    assert_eq!(Integer::rotr(0x8000_0000_0000_0001u64, 0), 0x8000_0000_0000_0001);
+    // Bits leaving one end enter the other
+    assert_eq!(Integer::rotl(0x8000_0001u32, 1), 0x0000_0003);
+    assert_eq!(Integer::rotr(0x8000_0001u32, 33), 0xC000_0000);
+    assert_eq!(Integer::rotr(1u64, 65), 0x8000_0000_0000_0000);
+    assert_eq!(Integer::rotl(-2i32, 1), -3);
+
+    assert_eq!(Register32::from(0x8000_0001u32).rotl(33u32.into()), 3u32.into());
+    assert_eq!(Register64(1u64.to_le_bytes()).rotr(Register64(65u64.to_le_bytes())), Register64((1u64 << 63).to_le_bytes()));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+mod rv128_tests {
+    use super::common::*;
+    use rysk_core::Register128;
+
+    fn reg(value: u128) -> Register128 {
+        Register128(value.to_le_bytes())
+    }
+
+    #[test]
+    fn test_shifts() {
+        let mut memory = program(&[
+            0x0640_9093, // slli x1, x1, 100
+            0x47F2_5193, // srai x3, x4, 127
+            0x01F3_129B, // slliw x5, x6, 31
+            0x00B0_D533, // srl x10, x1, x11
+        ]);
+        let mut core = core128();
+        core.set(1, reg(1));
+        core.set(4, reg(1 << 127));
+        core.set(6, reg(1));
+        core.set(11, reg(129));
+        for _ in 0..4 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.get(1), reg(1 << 100));
+        assert_eq!(core.get(3), reg(u128::MAX));
+        // Word results are sign extended to the full 128 bits
+        assert_eq!(core.get(5), reg(0xFFFF_FFFF_FFFF_FFFF_FFFF_FFFF_8000_0000));
+        // Register shifts only use the low 7 bits of the shift amount
+        assert_eq!(core.get(10), reg(1 << 99));
+    }
+
+    #[test]
+    fn test_quad_memory() {
+        let mut memory = program(&[
+            0x0010_4823, // sq x1, 16(x0)
+            0x0100_210F, // lq x2, 16(x0)
+            0x0180_7183, // ldu x3, 24(x0)
+            0x0180_3203, // ld x4, 24(x0)
+        ]);
+        let mut core = core128();
+        let value = 0x8000_0000_0000_0001_0000_0000_0000_0002;
+        core.set(1, reg(value));
+        for _ in 0..4 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(memory.bytes[16..32], value.to_le_bytes());
+        assert_eq!(core.get(2), reg(value));
+        assert_eq!(core.get(3), reg(0x8000_0000_0000_0001));
+        assert_eq!(core.get(4), reg(0xFFFF_FFFF_FFFF_FFFF_8000_0000_0000_0001));
+    }
+
+    #[cfg(feature = "ext-zmmul")]
+    #[test]
+    fn test_multiply() {
+        let mut memory = program(&[
+            0x0220_81B3, // mul x3, x1, x2
+            0x0220_B233, // mulhu x4, x1, x2
+            0x0220_92B3, // mulh x5, x1, x2
+        ]);
+        let mut core = core128();
+        core.set(1, reg(u128::MAX));
+        core.set(2, reg(u128::MAX));
+        for _ in 0..3 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.get(3), reg(1));
+        assert_eq!(core.get(4), reg(u128::MAX - 1));
+        // -1 * -1 has no high bits
+        assert_eq!(core.get(5), reg(0));
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn test_misa() {
+        let core = core128();
+        // MXL is in the top 2 bits of misa
+        assert_eq!(core.get_csr(0x301).unwrap().0[15] >> 6, 3);
+    }
+}
@@ -0,0 +1,180 @@
+mod common;
+
+mod rv64_tests {
+    use super::common::*;
+    use rysk_core::Register64;
+
+    fn reg(value: u64) -> Register64 {
+        Register64(value.to_le_bytes())
+    }
+
+    #[test]
+    fn test_shifts() {
+        let mut memory = program(&[
+            0x0210_9093, // slli x1, x1, 33
+            0x0200_D113, // srli x2, x1, 32
+            0x43F2_5193, // srai x3, x4, 63
+            0x01F3_129B, // slliw x5, x6, 31
+            0x4094_53BB, // sraw x7, x8, x9
+            0x00B0_D533, // srl x10, x1, x11
+        ]);
+        let mut core = core64();
+        core.set(1, reg(1));
+        core.set(4, reg(0x8000_0000_0000_0000));
+        core.set(6, reg(1));
+        core.set(8, reg(0x8000_0000));
+        core.set(9, reg(36));
+        core.set(11, reg(65));
+        for _ in 0..6 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.get(1), reg(1 << 33));
+        assert_eq!(core.get(2), reg(2));
+        assert_eq!(core.get(3), reg(u64::MAX));
+        // Word results are sign extended
+        assert_eq!(core.get(5), reg(0xFFFF_FFFF_8000_0000));
+        // Word shifts only use the low 5 bits of the shift amount
+        assert_eq!(core.get(7), reg(0xFFFF_FFFF_F800_0000));
+        // Register shifts only use the low 6 bits of the shift amount
+        assert_eq!(core.get(10), reg(1 << 32));
+    }
+
+    #[test]
+    fn test_word_hints() {
+        let hints = [
+            0x0010_801B, // addiw zero, ra, 1
+            0x0010_901B, // slliw zero, ra, 1
+            0x0010_D01B, // srliw zero, ra, 1
+            0x4010_D01B, // sraiw zero, ra, 1
+            0x0020_803B, // addw zero, ra, sp
+            0x4020_803B, // subw zero, ra, sp
+            0x0020_903B, // sllw zero, ra, sp
+            0x0020_D03B, // srlw zero, ra, sp
+            0x4020_D03B, // sraw zero, ra, sp
+        ];
+        let mut memory = program(&hints);
+        let mut core = core64();
+        core.set(1, reg(0x1234));
+        core.set(2, reg(7));
+        let registers = *core.registers();
+        for _ in hints.iter() {
+            core.execute(&mut memory);
+        }
+        // Each retires without writing a register or trapping
+        assert_eq!(*core.registers(), registers);
+        assert_eq!(core.pc, reg(hints.len() as u64 * 4));
+    }
+
+    #[test]
+    fn test_word_shifts() {
+        let mut memory = program(&[
+            0x0010_9093, // slli x1, x1, 1
+            0x0020_91BB, // sllw x3, x1, x2
+            0x0020_D23B, // srlw x4, x1, x2
+        ]);
+        let mut core = core64();
+        core.set(1, reg(0x1_4000_0000));
+        core.set(2, reg(1));
+        for _ in 0..3 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(core.get(1), reg(0x2_8000_0000));
+        // The upper word is discarded before shifting
+        assert_eq!(core.get(3), reg(0));
+        assert_eq!(core.get(4), reg(0x4000_0000));
+    }
+
+    #[test]
+    fn test_double_store() {
+        let mut memory = program(&[
+            0x1010_3023, // sd x1, 0x100(x0)
+            0x1000_3103, // ld x2, 0x100(x0)
+            0x1040_6183, // lwu x3, 0x104(x0)
+        ]);
+        let mut core = core64();
+        core.set(1, reg(0x8765_4321_0FED_CBA9));
+        for _ in 0..3 {
+            core.execute(&mut memory);
+        }
+        assert_eq!(memory.bytes[0x100..0x108], 0x8765_4321_0FED_CBA9u64.to_le_bytes());
+        assert_eq!(core.get(2), reg(0x8765_4321_0FED_CBA9));
+        assert_eq!(core.get(3), reg(0x8765_4321));
+        assert_eq!(core.pc, reg(12));
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn test_user_xlen32() {
+        use rysk_core::{ csr::{ self, Privilege }, register::RegisterWidth };
+
+        let mut instructions = vec![0x3020_0073]; // mret
+        instructions.resize(16, 0);
+        instructions.extend_from_slice(&[
+            // User mode at 0x40
+            0x0020_81B3, // add x3, x1, x2
+            0x0051_D233, // srl x4, x3, x5
+            0x0020_833B, // addw x6, x1, x2
+        ]);
+        let mut memory = program(&instructions);
+        let mut core = core64();
+        // Return to user mode with a UXL of 32
+        core.csr_mut().write_mstatus(reg(1 << csr::MSTATUS_UXL));
+        core.csr_mut().mepc = reg(0x40);
+        core.csr_mut().mtvec = reg(0x100);
+        core.set(1, reg(0x7FFF_FFFF));
+        core.set(2, reg(1));
+        core.set(5, reg(4));
+        core.set(7, reg(0x1_0000_0005));
+
+        core.execute(&mut memory);
+        assert_eq!(core.csr().privilege, Privilege::User);
+        assert_eq!(core.xlen(), RegisterWidth::Bits32);
+        core.execute(&mut memory);
+        // The result is sign-extended from 32 bits
+        assert_eq!(core.get(3), reg(0xFFFF_FFFF_8000_0000));
+        core.execute(&mut memory);
+        assert_eq!(core.get(4), reg(0x0800_0000));
+        // Instructions only present in RV64 are illegal
+        core.execute(&mut memory);
+        assert_eq!(core.pc, reg(0x100));
+        assert_eq!(core.get_csr(0x342).unwrap(), reg(2));
+        assert_eq!(core.csr().privilege, Privilege::Machine);
+        assert_eq!(core.xlen(), RegisterWidth::Bits64);
+        // Registers not written at the narrower width keep their upper bits
+        assert_eq!(core.get(7), reg(0x1_0000_0005));
+    }
+
+    #[cfg(all(feature = "ext-csr", feature = "alloc"))]
+    #[test]
+    fn test_icache_xlen() {
+        use rysk_core::csr;
+
+        let mut memory = program(&[
+            0x0050_91B3, // sll x3, x1, x5
+            0x3020_0073, // mret
+        ]);
+        let mut core = core64().with_icache(16);
+        core.csr_mut().write_mstatus(reg(1 << csr::MSTATUS_UXL));
+        core.set(1, reg(1));
+        core.set(5, reg(32));
+        core.execute(&mut memory);
+        assert_eq!(core.get(3), reg(0x1_0000_0000));
+        core.execute(&mut memory);
+        // The cached SLL is decoded again as SLLW under a UXL of 32, which only uses the low 5 bits of the shift amount
+        core.execute(&mut memory);
+        assert_eq!(core.get(3), reg(1));
+    }
+
+    #[cfg(feature = "ext-zbkb")]
+    #[test]
+    fn test_packw() {
+        // packw x3, x1, x2
+        let mut memory = program(&[0x0820_C1BB]);
+        let mut core = core64();
+        core.set(1, reg(0xAAAA_AAAA_5555_1234));
+        core.set(2, reg(0xBBBB_BBBB_5555_8765));
+        core.execute(&mut memory);
+        // The packed word is sign-extended
+        assert_eq!(core.get(3), reg(0xFFFF_FFFF_8765_1234));
+    }
+}
@@ -0,0 +1,49 @@
+mod common;
+
+#[cfg(feature = "serde")]
+mod serde_tests {
+    use super::common::*;
+    use rysk_core::*;
+
+    // addi x1, x0, 5; loop: addi x2, x2, 3; addi x1, x1, -1; bne x1, x0, loop
+    const PROGRAM: [u32; 4] = [0x0050_0093, 0x0031_0113, 0xFFF0_8093, 0xFE00_9CE3];
+
+    #[test]
+    fn snapshot_resumes() {
+        let mut memory = Memory::new(&PROGRAM);
+        let mut core = core32();
+        for _ in 0..6 {
+            core.execute(&mut memory);
+        }
+
+        let snapshot = serde_json::to_string(&core).unwrap();
+        let mut restored: Core<Register32> = serde_json::from_str(&snapshot).unwrap();
+        assert!(core.diff(&restored).is_empty());
+
+        let mut restored_memory = Memory::new(&PROGRAM);
+        for _ in 0..10 {
+            core.execute(&mut memory);
+            restored.execute(&mut restored_memory);
+        }
+        assert!(core.diff(&restored).is_empty());
+        assert_eq!(restored.get(2), 15u32.into());
+    }
+
+    #[cfg(feature = "ext-csr")]
+    #[test]
+    fn trap_strategy_not_restored() {
+        use rysk_core::system::Trap;
+        let mut memory = Memory::new(&[0]);
+        let core = core32().with_trap_strategy(HostReturn);
+        let snapshot = serde_json::to_string(&core).unwrap();
+
+        // The restored core has the default strategy, so the program handles its illegal instruction
+        let mut restored: Core<Register32> = serde_json::from_str(&snapshot).unwrap();
+        assert_eq!(restored.execute(&mut memory), None);
+        assert_eq!(restored.get_csr(0x342).unwrap(), 2u32.into());
+
+        // Until the strategy is set again
+        let mut restored = serde_json::from_str::<Core<Register32>>(&snapshot).unwrap().with_trap_strategy(HostReturn);
+        assert_eq!(restored.execute(&mut memory), Some(Trap::IllegalInstruction));
+    }
+}
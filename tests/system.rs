@@ -0,0 +1,976 @@
+mod common;
+
+use common::*;
+use rysk_core::{ ArrayRam, Core, HostReturn, MemoryFault, Mmu, Register32, Rom, RunResult, StepOutcome };
+
+#[test]
+fn register_introspection() {
+    let mut core = core32();
+    core.set(2, 0x8000u32.into());
+    core.set(10, 0xDEAD_BEEFu32.into());
+
+    assert_eq!(core.registers()[10], 0xDEAD_BEEFu32.into());
+    let named: Vec<_> = core.abi_registers().filter(|&(_, _, value)| value != Register32::default()).collect();
+    assert_eq!(named, vec![(2, "sp", 0x8000u32.into()), (10, "a0", 0xDEAD_BEEFu32.into())]);
+
+    let debug = format!("{:?}", core);
+    assert!(debug.starts_with("pc   00000000\n"));
+    assert!(debug.contains("a0   DEADBEEF  a1   00000000"));
+    assert_eq!(debug.lines().count(), 9);
+}
+
+#[test]
+fn hints_retire_as_nops() {
+    let hints = [
+        0x1234_5037, // lui zero, 0x12345
+        0x0000_1017, // auipc zero, 0x1
+        0x0050_0013, // addi zero, zero, 5
+        0x0FF0_F013, // andi zero, ra, 255
+        0x0010_E013, // ori zero, ra, 1
+        0xFFF0_C013, // xori zero, ra, -1
+        0x0010_A013, // slti zero, ra, 1
+        0x0010_B013, // sltiu zero, ra, 1
+        0x0030_9013, // slli zero, ra, 3
+        0x0030_D013, // srli zero, ra, 3
+        0x4030_D013, // srai zero, ra, 3
+        0x0020_8033, // add zero, ra, sp
+        0x0020_0033, // add zero, zero, sp, the non-temporal locality hint ntl.p1
+        0x4020_8033, // sub zero, ra, sp
+        0x0020_9033, // sll zero, ra, sp
+        0x0020_A033, // slt zero, ra, sp
+        0x0020_B033, // sltu zero, ra, sp
+        0x0020_C033, // xor zero, ra, sp
+        0x0020_D033, // srl zero, ra, sp
+        0x4020_D033, // sra zero, ra, sp
+        0x0020_E033, // or zero, ra, sp
+        0x0020_F033, // and zero, ra, sp
+        0x0010_000F, // fence 0, w
+        0x0FF0_808F, // fence with rs1 and rd of ra
+    ];
+    let mut memory = Memory::new(&hints);
+    let mut core = core32().with_trap_strategy(HostReturn);
+    core.set(1, 0x1234u32.into());
+    core.set(2, 7u32.into());
+    let registers = *core.registers();
+    for _ in hints.iter() {
+        assert_eq!(core.execute(&mut memory), None, "{:#010X}", hints[core.pc.0[0] as usize / 4]);
+        assert_eq!(core.last_outcome(), StepOutcome::Retired);
+    }
+    assert_eq!(*core.registers(), registers);
+    assert_eq!(core.pc, (hints.len() as u32 * 4).into());
+}
+
+#[test]
+fn embedded_register_file() {
+    // addi x0, x0, 5; add x20, x1, x2
+    let program = [0x0050_0013, 0x0020_8A33];
+
+    let mut memory = Memory::new(&program);
+    let mut core = core32e().with_trap_strategy(HostReturn);
+    core.set(1, 1u32.into());
+    core.set(2, 2u32.into());
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(0), 0u32.into());
+    // x20 is beyond the 16 registers of RV32E
+    assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::IllegalInstruction));
+    assert_eq!(core.pc, 4u32.into());
+    #[cfg(feature = "ext-csr")]
+    {
+        let misa = core.get_csr(0x301).unwrap().0;
+        assert_eq!((misa[0] & 1 << 4, misa[1] & 1), (1 << 4, 0));
+    }
+
+    let mut memory = Memory::new(&program);
+    let mut core = core32().with_trap_strategy(HostReturn);
+    core.set(1, 1u32.into());
+    core.set(2, 2u32.into());
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(0), 0u32.into());
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(20), 3u32.into());
+    #[cfg(feature = "ext-csr")]
+    {
+        let misa = core.get_csr(0x301).unwrap().0;
+        assert_eq!((misa[0] & 1 << 4, misa[1] & 1), (0, 1));
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn diff_single_register() {
+    let left = core32();
+    let mut right = core32();
+    assert!(left.diff(&right).is_empty());
+
+    right.set(5, 0x1234u32.into());
+    let diff = left.diff(&right);
+    assert_eq!(diff.registers, vec![(5, 0u32.into(), 0x1234u32.into())]);
+    assert_eq!(diff.pc, None);
+    #[cfg(feature = "ext-csr")]
+    assert!(diff.csrs.is_empty());
+}
+
+#[test]
+#[cfg(all(feature = "alloc", feature = "ext-csr"))]
+fn diff_csrs() {
+    let left = core32();
+    let mut right = core32();
+    right.set_csr(0x300, (1u32 << 3).into()); // mstatus.MIE
+    right.set_csr(0x310, (1u32 << 5).into()); // mstatush.MBE
+    right.set_csr(0xB83, 1u32.into()); // mhpmcounter3h
+    right.set_csr(0x324, 2u32.into()); // mhpmevent4
+    right.set_time(7);
+    let csrs: Vec<usize> = left.diff(&right).csrs.iter().map(|&(index, _, _)| index).collect();
+    assert_eq!(csrs, [0x300, 0x310, 0xB83, 0x324, 0xC01]);
+    #[cfg(feature = "ext-f")]
+    {
+        let mut right = core32();
+        right.set_csr(0x003, 1u32.into());
+        assert_eq!(left.diff(&right).csrs, [(0x003, 0u32.into(), 1u32.into())]);
+    }
+}
+
+#[cfg(feature = "ext-csr")]
+#[test]
+fn memory_latency() {
+    // lw x1, 0(x2); lw x1, 0(x3)
+    let mut memory = Memory::new(&[0x0001_2083, 0x0001_a083]);
+    memory.slow = 0x1000;
+    memory.latency = 10;
+    let mut core = core32();
+    core.set(2, 0x100u32.into());
+    core.set(3, 0x1000u32.into());
+
+    core.execute(&mut memory);
+    assert_eq!(core.get_csr(0xB00).unwrap(), 1u32.into());
+    core.execute(&mut memory);
+    assert_eq!(core.get_csr(0xB00).unwrap(), 12u32.into());
+    // Only instructions count towards minstret
+    assert_eq!(core.get_csr(0xB02).unwrap(), 2u32.into());
+}
+
+#[test]
+fn executable_range() {
+    // jal x0, 0x200
+    let mut memory = Memory::new(&[0x2000_006F]);
+    let mut core = core32();
+    core.set_executable_range(0..0x100);
+
+    #[cfg(not(feature = "ext-csr"))]
+    {
+        assert_eq!(core.execute(&mut memory), None);
+        assert_eq!(core.pc, 0x200u32.into());
+        assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::InstructionAccessFault(0x200)));
+        assert_eq!(core.pc, 0x200u32.into());
+    }
+    #[cfg(feature = "ext-csr")]
+    {
+        core.execute(&mut memory);
+        assert_eq!(core.pc, 0x200u32.into());
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0x342).unwrap(), 1u32.into());
+        assert_eq!(core.get_csr(0x343).unwrap(), 0x200u32.into());
+        assert_eq!(core.pc, 0u32.into());
+    }
+
+    // An instruction whose last byte is outside of the range, addi x1, x0, 1
+    memory.bytes[0xFE..0x102].copy_from_slice(&0x0010_0093u32.to_le_bytes());
+    let mut core = core32().with_trap_strategy(HostReturn);
+    core.set_executable_range(0..0x100);
+    core.pc = 0xFEu32.into();
+    assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::InstructionAccessFault(0xFE)));
+    assert_eq!(core.get(1), 0u32.into());
+    core.set_executable_range(0..0x102);
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(1), 1u32.into());
+}
+
+#[test]
+fn current_instruction() {
+    // addi x1, x0, 1; addi x1, x1, 1
+    let mut memory = Memory::new(&[0x0010_0093, 0x0010_8093]);
+    let mut core = core32();
+    assert_eq!(core.current_instruction(&memory), Ok([0x93, 0x00, 0x10, 0x00]));
+    assert_eq!(core.current_instruction(&memory), Ok([0x93, 0x00, 0x10, 0x00]));
+    assert_eq!(core.pc, 0u32.into());
+    assert_eq!(core.get(1), 0u32.into());
+
+    core.execute(&mut memory);
+    assert_eq!(core.current_instruction(&memory), Ok([0x93, 0x80, 0x10, 0x00]));
+}
+
+#[test]
+fn watchdog() {
+    // jal x0, 0
+    let mut memory = Memory::new(&[0x0000_006F]);
+    let mut core = core32();
+    core.set_watchdog(1000);
+    assert_eq!(core.run(&mut memory), RunResult::WatchdogExpired);
+    assert_eq!(core.pc, 0u32.into());
+    #[cfg(feature = "ext-csr")]
+    assert_eq!(core.get_csr(0xB02).unwrap(), 1000u32.into());
+
+    // The limit stays expired until the watchdog is set again
+    assert_eq!(core.run(&mut memory), RunResult::WatchdogExpired);
+    #[cfg(feature = "ext-csr")]
+    assert_eq!(core.get_csr(0xB02).unwrap(), 1000u32.into());
+}
+
+#[test]
+fn watchdog_resume() {
+    // addi x1, x0, 100; addi x2, x2, 3; addi x1, x1, -1; bne x1, x0, -8; ebreak
+    let program = [0x0640_0093, 0x0031_0113, 0xFFF0_8093, 0xFE00_9CE3, 0x0010_0073];
+    let mut memory = Memory::new(&program);
+    let mut whole = core32().with_trap_strategy(HostReturn);
+    assert_eq!(whole.run(&mut memory), RunResult::Trap(rysk_core::system::Trap::Breakpoint));
+
+    // Setting the watchdog again after it expires resumes at the next instruction
+    let mut memory = Memory::new(&program);
+    let mut chunked = core32().with_trap_strategy(HostReturn);
+    let result = loop {
+        chunked.set_watchdog(7);
+        match chunked.run(&mut memory) {
+            RunResult::WatchdogExpired => continue,
+            result => break result
+        }
+    };
+    assert_eq!(result, RunResult::Trap(rysk_core::system::Trap::Breakpoint));
+    assert_eq!(chunked.pc, whole.pc);
+    for register in 0..32 {
+        assert_eq!(chunked.get(register), whole.get(register));
+    }
+    assert_eq!(chunked.get(2), 300u32.into());
+    #[cfg(feature = "ext-csr")]
+    {
+        assert_eq!(chunked.get_csr(0xB00), whole.get_csr(0xB00));
+        assert_eq!(chunked.get_csr(0xB02), whole.get_csr(0xB02));
+        assert_eq!(chunked.get_csr(0xB02).unwrap(), 301u32.into());
+    }
+}
+
+#[cfg(feature = "ext-csr")]
+#[test]
+fn watchdog_counts_trapped() {
+    // ecall; jal x0, 0
+    let mut memory = Memory::new(&[0x0000_0073, 0x0000_006F]);
+    let mut core = core32();
+    core.csr_mut().mtvec = 4u32.into();
+    core.set_watchdog(10);
+    // The ECALL is handled by the program, so counts towards the limit though it does not retire
+    assert_eq!(core.run(&mut memory), RunResult::WatchdogExpired);
+    assert_eq!(core.get_csr(0xB00).unwrap(), 10u32.into());
+    assert_eq!(core.get_csr(0xB02).unwrap(), 9u32.into());
+
+    // A trap handler at an illegal instruction traps on itself forever
+    let mut memory = Memory::new(&[]);
+    let mut core = core32();
+    core.csr_mut().mtvec = 0u32.into();
+    core.set_watchdog(1000);
+    assert_eq!(core.run(&mut memory), RunResult::WatchdogExpired);
+    assert_eq!(core.pc, 0u32.into());
+    assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+    assert_eq!(core.get_csr(0xB02).unwrap(), 0u32.into());
+}
+
+#[test]
+#[cfg(feature = "test-util")]
+fn run_program_watchdog() {
+    // addi x1, x1, 1; jal x0, -4
+    let core = rysk_core::run_program::<Register32>(0x1000, &[0x0010_8093, 0xFFDF_F06F]);
+    assert_eq!(core.get(1), (rysk_core::RUN_PROGRAM_WATCHDOG as u32 / 2).into());
+    assert_eq!(core.pc, 0x1000u32.into());
+}
+
+#[test]
+fn last_outcome() {
+    // addi x1, x0, 1; illegal
+    let mut memory = Memory::new(&[0x0010_0093, 0x0000_0000]);
+    let mut core = core32();
+    assert_eq!(core.last_outcome(), StepOutcome::Retired);
+
+    core.execute(&mut memory);
+    assert_eq!(core.last_outcome(), StepOutcome::Retired);
+    core.execute(&mut memory);
+    assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+    #[cfg(feature = "ext-csr")]
+    assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+}
+
+#[test]
+fn array_ram() {
+    // addi x1, x0, 5; addi x1, x1, 7; ebreak
+    let program: Vec<u8> = [0x0050_0093u32, 0x0070_8093, 0x0010_0073].iter().flat_map(|instruction| instruction.to_le_bytes().to_vec()).collect();
+    let mut memory = ArrayRam::<64>::new(0x8000_0000);
+    memory.load(0x8000_0000, &program);
+    #[cfg(not(feature = "ext-csr"))]
+    let core = Core::<Register32>::new(0x8000_0000);
+    #[cfg(feature = "ext-csr")]
+    let core = Core::<Register32>::new(0x8000_0000, 0);
+    let mut core = core.with_trap_strategy(HostReturn);
+
+    core.set_watchdog(3);
+    assert_eq!(core.run(&mut memory), RunResult::Trap(rysk_core::system::Trap::Breakpoint));
+    assert_eq!(core.get(1), 12u32.into());
+
+    // Unmapped memory reads as zero
+    assert_eq!(Mmu::<Register32>::get(&memory, 0x8000_0040), 0);
+    assert_eq!(Mmu::<Register32>::get(&memory, 0), 0);
+}
+
+#[test]
+fn memory_regions() {
+    let mut memory = ArrayRam::<64>::new(0x1000);
+    memory.load(0x1010, &[1, 2, 3, 4, 5]);
+    let region = memory.region(0x1010..0x1018).unwrap();
+    for (offset, byte) in region.iter().enumerate() {
+        assert_eq!(*byte, Mmu::<Register32>::get(&memory, 0x1010 + offset as u32));
+    }
+    assert_eq!(region, &[1, 2, 3, 4, 5, 0, 0, 0]);
+    assert_eq!(memory.as_slice().len(), 64);
+    assert_eq!(memory.region(0x1000..0x1040), Some(memory.as_slice()));
+    // Ranges reaching outside of memory
+    assert_eq!(memory.region(0x0FFF..0x1004), None);
+    assert_eq!(memory.region(0x1030..0x1041), None);
+
+    let bytes = [1, 2, 3, 4];
+    let rom = Rom::new(0x100, &bytes);
+    assert_eq!(rom.region(0x102..0x104), Some(&bytes[2..]));
+    assert_eq!(rom.as_slice(), &bytes);
+}
+
+#[test]
+fn is_mapped() {
+    let memory = ArrayRam::<64>::new(0x1000);
+    assert!(!Mmu::<Register32>::is_mapped(&memory, 0x0FFF));
+    assert!(Mmu::<Register32>::is_mapped(&memory, 0x1000));
+    assert!(Mmu::<Register32>::is_mapped(&memory, 0x103F));
+    assert!(!Mmu::<Register32>::is_mapped(&memory, 0x1040));
+
+    // Memories that don't know better consider everything mapped
+    assert!(Mmu::<Register32>::is_mapped(&Memory::new(&[]), 0xFFFF_FFFF));
+}
+
+#[test]
+fn straddling_store() {
+    let mut memory = ArrayRam::<64>::new(0x1000);
+    memory.load(0x103C, &[1, 2, 3, 4]);
+    // A store reaching past the end of memory faults without writing the bytes that are mapped
+    assert_eq!(Mmu::<Register32>::set_half(&mut memory, 0x103F, [0xAA; 2]), Err(MemoryFault::AccessFault));
+    assert_eq!(Mmu::<Register32>::set_word(&mut memory, 0x103E, [0xAA; 4]), Err(MemoryFault::AccessFault));
+    assert_eq!(Mmu::<Register32>::set_double(&mut memory, 0x103C, [0xAA; 8]), Err(MemoryFault::AccessFault));
+    assert_eq!(memory.region(0x103C..0x1040), Some(&[1, 2, 3, 4][..]));
+    assert_eq!(Mmu::<Register32>::set_word(&mut memory, 0x103C, [0xAA; 4]), Ok(()));
+    assert_eq!(memory.region(0x103C..0x1040), Some(&[0xAA; 4][..]));
+}
+
+#[test]
+fn set_register_part() {
+    let mut core = core32();
+    core.set(5, 0x1234_5678u32.into());
+    core.set_register_byte(5, 0, 0xAB);
+    assert_eq!(core.get(5), 0x1234_56ABu32.into());
+    core.set_register_byte(5, 3, 0xCD);
+    assert_eq!(core.get(5), 0xCD34_56ABu32.into());
+    core.set_register_half(5, 1, [0x00, 0xFF]);
+    assert_eq!(core.get(5), 0xCDFF_00ABu32.into());
+    core.set_register_word(5, 0, [1, 2, 3, 4]);
+    assert_eq!(core.get(5), 0x0403_0201u32.into());
+
+    // x0 remains hardwired to zero
+    core.set_register_byte(0, 0, 0xFF);
+    assert_eq!(core.get(0), 0u32.into());
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn invariant() {
+    // addi sp, x0, 16; addi sp, sp, -8; addi sp, sp, -8; addi sp, sp, 8
+    let mut memory = Memory::new(&[0x0100_0113, 0xFF81_0113, 0xFF81_0113, 0x0081_0113]);
+    let mut core = core32();
+    core.set(2, 1u32.into());
+    core.set_invariant(|core| core.get(2) != 0u32.into());
+    core.set_watchdog(4);
+
+    assert_eq!(core.run(&mut memory), RunResult::InvariantViolated(8u32.into()));
+    assert_eq!(core.pc, 12u32.into());
+    assert_eq!(core.get(2), 0u32.into());
+}
+
+#[test]
+fn stack_limit() {
+    // lw t0, 0x80(zero); addi sp, sp, -8; sw ra, 0(sp); jal zero, -8
+    let mut memory = Memory::new(&[0x0800_2283, 0xFF81_0113, 0x0011_2023, 0xFF9F_F06F]);
+    let mut core = core32();
+    core.set(2, 0x110u32.into());
+    core.set_stack_limit(0x100);
+    core.set_watchdog(100);
+
+    // Loads below the limit are allowed until the stack pointer passes it, at which point the push overflows
+    assert_eq!(core.run(&mut memory), RunResult::StackOverflow(0xF8u32.into()));
+    assert_eq!(core.pc, 12u32.into());
+    assert_eq!(core.get(2), 0xF8u32.into());
+
+    core.clear_stack_limit();
+    assert_eq!(core.run(&mut memory), RunResult::WatchdogExpired);
+
+    // Loads and stores below the limit but also below the stack pointer are not to the stack, so never overflow it
+    // sw ra, 0x80(zero); lw t0, 0x84(zero); jal zero, -8
+    let mut memory = Memory::new(&[0x0810_2023, 0x0840_2283, 0xFF9F_F06F]);
+    let mut core = core32();
+    core.set(2, 0x110u32.into());
+    core.set_stack_limit(0x100);
+    core.set_watchdog(100);
+    assert_eq!(core.run(&mut memory), RunResult::WatchdogExpired);
+}
+
+#[test]
+fn rom() {
+    // addi x1, x0, 0x42; sb x1, 0(x0)
+    let program: Vec<u8> = [0x0420_0093u32, 0x0010_0023].iter().flat_map(|instruction| instruction.to_le_bytes().to_vec()).collect();
+    let mut rom = Rom::new(0, &program);
+    let mut core = core32();
+    core.execute(&mut rom);
+    assert_eq!(core.get(1), 0x42u32.into());
+    // The store raises an access fault and has no effect
+    #[cfg(not(feature = "ext-csr"))]
+    assert_eq!(core.execute(&mut rom), Some(rysk_core::system::Trap::StoreAccessFault(0)));
+    #[cfg(feature = "ext-csr")]
+    {
+        core.execute(&mut rom);
+        assert_eq!(core.get_csr(0x342).unwrap(), 7u32.into());
+        assert_eq!(core.get_csr(0x343).unwrap(), 0u32.into());
+    }
+    assert_eq!(rom.bytes[0], 0x93);
+}
+
+/// Memory with a reserved range that faults on access, and which does not emulate misaligned accesses
+struct Reserved(Memory);
+impl Mmu<Register32> for Reserved {
+    fn get(&self, address: u32) -> u8 {
+        self.0.get(address)
+    }
+    fn set(&mut self, address: u32, value: u8) {
+        self.0.set(address, value)
+    }
+    fn is_mapped(&self, address: u32) -> bool {
+        !(0x1000..0x1100).contains(&address)
+    }
+    fn emulates_misaligned(&self, _address: u32) -> bool {
+        false
+    }
+}
+
+#[test]
+fn load_faults() {
+    // lw x1, 4(x2)
+    let mut memory = Reserved(Memory::new(&[0x0041_2083]));
+    let mut core = core32();
+
+    core.set(2, 0x1000u32.into());
+    #[cfg(not(feature = "ext-csr"))]
+    assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::LoadAccessFault(0x1004)));
+    #[cfg(feature = "ext-csr")]
+    {
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0x342).unwrap(), 5u32.into());
+        assert_eq!(core.get_csr(0x343).unwrap(), 0x1004u32.into());
+    }
+    assert_eq!(core.pc, 0u32.into());
+    assert_eq!(core.get(1), 0u32.into());
+
+    // Misaligned takes priority over the access fault
+    core.set(2, 0x1001u32.into());
+    #[cfg(not(feature = "ext-csr"))]
+    assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::LoadMisaligned(0x1005)));
+    #[cfg(feature = "ext-csr")]
+    {
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0x342).unwrap(), 4u32.into());
+        assert_eq!(core.get_csr(0x343).unwrap(), 0x1005u32.into());
+    }
+
+    // Accesses outside of the reserved range succeed
+    memory.0.bytes[0x104] = 0x42;
+    core.set(2, 0x100u32.into());
+    #[cfg(not(feature = "ext-csr"))]
+    assert_eq!(core.execute(&mut memory), None);
+    #[cfg(feature = "ext-csr")]
+    core.execute(&mut memory);
+    assert_eq!(core.get(1), 0x42u32.into());
+}
+
+#[test]
+fn misaligned_priority() {
+    use rysk_core::system::Trap;
+    // lh x1, 1(x2); lw x1, 2(x2); sh x1, 3(x2); sw x1, 1(x2); lbu x1, 1(x2)
+    let mut memory = Reserved(Memory::new(&[0x0011_1083, 0x0021_2083, 0x0011_11A3, 0x0011_20A3, 0x0011_4083]));
+    let mut core = core32().with_trap_strategy(HostReturn);
+    core.set(2, 0x1000u32.into());
+
+    // Misaligned accesses to the unmapped range report the misalignment rather than the access fault
+    assert_eq!(core.execute(&mut memory), Some(Trap::LoadMisaligned(0x1001)));
+    core.pc = 4u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::LoadMisaligned(0x1002)));
+    core.pc = 8u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::StoreMisaligned(0x1003)));
+    core.pc = 12u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::StoreMisaligned(0x1001)));
+    // A byte access is never misaligned
+    core.pc = 16u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::LoadAccessFault(0x1001)));
+
+    // Misaligned accesses are emulated by the default memory
+    let mut memory = Memory::new(&[0x0021_2083]);
+    let mut core = core32().with_trap_strategy(HostReturn);
+    memory.bytes[0x102..0x106].copy_from_slice(&[1, 2, 3, 4]);
+    core.set(2, 0x100u32.into());
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(1), 0x0403_0201u32.into());
+}
+
+#[test]
+fn host_return_strategy() {
+    // Illegal instruction
+    let mut memory = Memory::new(&[0x0000_0000]);
+    let mut core = core32().with_trap_strategy(HostReturn);
+    assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::IllegalInstruction));
+    assert_eq!(core.last_trap(), Some(rysk_core::system::Trap::IllegalInstruction));
+    assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+    // The core is left at the instruction that trapped
+    assert_eq!(core.pc, 0u32.into());
+    assert_eq!(core.run(&mut memory), RunResult::Trap(rysk_core::system::Trap::IllegalInstruction));
+}
+
+#[test]
+fn trap_addresses() {
+    use rysk_core::system::Trap;
+    // lw x1, 4(x2); sw x1, 0(x2)
+    let mut memory = Reserved(Memory::new(&[0x0041_2083, 0x0011_2023]));
+    let mut core = core32().with_trap_strategy(HostReturn);
+
+    core.set(2, 0x1001u32.into());
+    assert_eq!(core.execute(&mut memory), Some(Trap::LoadMisaligned(0x1005)));
+    assert_eq!(format!("{:?}", core.last_trap().unwrap()), "Load Address is Misaligned at 0x1005");
+    core.pc = 4u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::StoreMisaligned(0x1001)));
+    core.set(2, 0x1000u32.into());
+    core.pc = 4u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::StoreAccessFault(0x1000)));
+
+    core.pc = 0x1000u32.into();
+    assert_eq!(core.execute(&mut memory), Some(Trap::InstructionAccessFault(0x1000)));
+    assert_eq!(format!("{:?}", core.last_trap().unwrap()), "Instruction Access Fault at 0x1000");
+}
+
+#[cfg(feature = "ext-csr")]
+#[test]
+fn csr_handled_strategy() {
+    // Illegal instruction
+    let mut memory = Memory::new(&[0x0000_0000]);
+    let mut core = Core::<Register32>::new(0, 0).with_trap_strategy(rysk_core::CsrHandled);
+    core.csr_mut().mtvec = 0x100u32.into();
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.last_trap(), None);
+    assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+    // The program's trap handler is entered instead
+    assert_eq!(core.pc, 0x100u32.into());
+    assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+    assert_eq!(core.get_csr(0x341).unwrap(), 0u32.into());
+}
+
+#[test]
+fn reserved_shamt() {
+    // slli x1, x1, 32
+    let mut memory = Memory::new(&[0x0200_9093]);
+    let mut core = core32();
+    #[cfg(not(feature = "ext-csr"))]
+    assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::IllegalInstruction));
+    #[cfg(feature = "ext-csr")]
+    {
+        core.execute(&mut memory);
+        assert_eq!(core.get_csr(0x342).unwrap(), 2u32.into());
+    }
+}
+
+#[test]
+fn misaligned_jump() {
+    // jal x1, 6
+    let mut memory = Memory::new(&[0x0060_00EF]);
+    let mut core = core32();
+    #[cfg(not(feature = "ext-csr"))]
+    {
+        #[cfg(not(feature = "ext-c"))]
+        assert_eq!(core.execute(&mut memory), Some(rysk_core::system::Trap::InstructionMisaligned(6)));
+        #[cfg(feature = "ext-c")]
+        assert_eq!(core.execute(&mut memory), None);
+    }
+    #[cfg(feature = "ext-csr")]
+    core.execute(&mut memory);
+
+    #[cfg(not(feature = "ext-c"))]
+    {
+        // The link register is left untouched by the trapping jump
+        assert_eq!(core.last_outcome(), StepOutcome::Trapped);
+        assert_eq!(core.get(1), 0u32.into());
+    }
+    #[cfg(feature = "ext-c")]
+    assert_eq!(core.pc, 6u32.into());
+}
+
+#[cfg(not(feature = "ext-csr"))]
+#[test]
+fn last_trap() {
+    // illegal; addi x1, x0, 1
+    let mut memory = Memory::new(&[0x0000_0000, 0x0010_0093]);
+    let mut core = core32();
+    assert_eq!(core.last_trap(), None);
+
+    core.execute(&mut memory);
+    assert_eq!(core.last_trap(), Some(rysk_core::system::Trap::IllegalInstruction));
+    // The trap is kept until the next instruction executes
+    assert_eq!(core.last_trap(), Some(rysk_core::system::Trap::IllegalInstruction));
+    core.pc = 4u32.into();
+    core.execute(&mut memory);
+    assert_eq!(core.last_trap(), None);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn icache_matches_uncached() {
+    // Sum the words at 0x100..0x140 into a0, storing each partial sum over the word, then call the environment
+    let program = [
+        0x1000_0293, // addi t0, zero, 0x100
+        0x1400_0313, // addi t1, zero, 0x140
+        0x0002_A383, // lw t2, 0(t0)
+        0x0075_0533, // add a0, a0, t2
+        0x00A2_A023, // sw a0, 0(t0)
+        0x0042_8293, // addi t0, t0, 4
+        0xFE62_98E3, // bne t0, t1, -16
+        0x0000_0073  // ecall
+    ];
+    let run = |mut core: Core<Register32>| {
+        let mut memory = Memory::new(&program);
+        for (index, byte) in memory.bytes[0x100..0x140].iter_mut().enumerate() {
+            *byte = index as u8
+        }
+        assert!(matches!(core.run(&mut memory), RunResult::Trap(_)));
+        (core, memory.bytes)
+    };
+    let (uncached, uncached_memory) = run(core32().with_trap_strategy(HostReturn));
+    // A cache smaller than the program also exercises eviction
+    for &entries in &[4, 64] {
+        let (cached, cached_memory) = run(core32().with_trap_strategy(HostReturn).with_icache(entries));
+        assert_eq!(cached.registers(), uncached.registers());
+        assert_eq!(cached.pc, uncached.pc);
+        assert_eq!(cached_memory, uncached_memory);
+        #[cfg(feature = "ext-csr")]
+        {
+            assert_eq!(cached.get_csr(0xB00), uncached.get_csr(0xB00));
+            assert_eq!(cached.get_csr(0xB02), uncached.get_csr(0xB02));
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn flush_icache() {
+    // addi a0, a0, 1; jal zero, -4
+    let mut memory = Memory::new(&[0x0015_0513, 0xFFDF_F06F]);
+    let mut core = core32().with_icache(16);
+    core.execute(&mut memory);
+    core.execute(&mut memory);
+
+    // The cached instruction is executed until the cache is flushed
+    memory.bytes[0..4].copy_from_slice(&0x0025_0513u32.to_le_bytes()); // addi a0, a0, 2
+    core.execute(&mut memory);
+    assert_eq!(core.get(10), 2u32.into());
+    core.execute(&mut memory);
+    core.flush_icache();
+    core.execute(&mut memory);
+    assert_eq!(core.get(10), 4u32.into());
+}
+
+/// Bubble sort the 64 words at 0x400 into ascending order, then call the environment
+const BUBBLE_SORT: [u32; 17] = [
+    0x4000_0413, // addi s0, zero, 0x400
+    0x03F0_0493, // addi s1, zero, 63
+    0x0000_0293, // addi t0, zero, 0
+    0x0000_0E13, // addi t3, zero, 0
+    0x0292_8663, // beq t0, s1, 44
+    0x0022_9313, // slli t1, t0, 2
+    0x0083_0333, // add t1, t1, s0
+    0x0003_2F03, // lw t5, 0(t1)
+    0x0043_2F83, // lw t6, 4(t1)
+    0x01EF_D863, // bge t6, t5, 16
+    0x01F3_2023, // sw t6, 0(t1)
+    0x01E3_2223, // sw t5, 4(t1)
+    0x0010_0E13, // addi t3, zero, 1
+    0x0012_8293, // addi t0, t0, 1
+    0xFD9F_F06F, // jal zero, -40
+    0xFC0E_16E3, // bne t3, zero, -52
+    0x0000_0073  // ecall
+];
+
+#[test]
+fn bubble_sort() {
+    let mut memory = Memory::new(&BUBBLE_SORT);
+    for index in 0..64 {
+        let value = (index as u32).wrapping_mul(0x9E37_79B9);
+        memory.bytes[0x400 + index * 4..0x404 + index * 4].copy_from_slice(&value.to_le_bytes());
+    }
+    let mut core = core32().with_trap_strategy(HostReturn);
+    assert_eq!(core.run(&mut memory), RunResult::Trap(rysk_core::system::Trap::SystemCall));
+
+    let sorted: Vec<_> = memory.bytes[0x400..0x500].chunks(4).map(|word| i32::from_le_bytes([word[0], word[1], word[2], word[3]])).collect();
+    assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+#[test]
+fn skip_trapped_instruction() {
+    use rysk_core::system::Trap;
+    // ecall; addi x1, x0, 1; ebreak
+    let mut memory = Memory::new(&[0x0000_0073, 0x0010_0093, 0x0010_0073]);
+    let mut core = core32().with_trap_strategy(HostReturn);
+
+    assert_eq!(core.execute(&mut memory), Some(Trap::SystemCall));
+    // The core is left at the ECALL until the host skips it
+    assert_eq!(core.pc, 0u32.into());
+    assert!(core.skip_trapped_instruction());
+    assert_eq!(core.pc, 4u32.into());
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(1), 1u32.into());
+
+    assert_eq!(core.execute(&mut memory), Some(Trap::Breakpoint));
+    assert!(core.skip_trapped_instruction());
+    assert_eq!(core.pc, 12u32.into());
+    // Only one skip per trap
+    assert!(!core.skip_trapped_instruction());
+    assert_eq!(core.pc, 12u32.into());
+}
+
+#[test]
+#[cfg(feature = "register-hook")]
+fn register_hook() {
+    // addi x10, x0, 1; addi x10, x0, 0x42; addi x11, x0, 3
+    let mut memory = Memory::new(&[0x0010_0513, 0x0420_0513, 0x0030_0593]);
+    let mut core = core32();
+    core.set_register_hook(|index, _old, new| index == 10 && new == 0x42u32.into());
+
+    // Stops after the instruction which writes the value, without executing the next
+    assert_eq!(core.run(&mut memory), RunResult::RegisterBreakpoint(4u32.into()));
+    assert_eq!(core.pc, 8u32.into());
+    assert_eq!(core.get(11), 0u32.into());
+
+    core.clear_register_hook();
+    core.set(10, 0x42u32.into());
+    core.execute(&mut memory);
+    assert_eq!(core.get(11), 3u32.into());
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn core_dump() {
+    use rysk_core::{ CoreDumpError, Register64 };
+    // addi x1, x0, 5; sw x1, 0x100(x0); addi x2, x1, 2
+    let mut memory = ArrayRam::<0x200>::new(0);
+    memory.load(0, &[0x93, 0x00, 0x50, 0x00, 0x23, 0x20, 0x10, 0x10, 0x13, 0x81, 0x20, 0x00]);
+    let mut core = core32();
+    for _ in 0..3 {
+        core.execute(&mut memory);
+    }
+    #[cfg(feature = "ext-csr")]
+    core.set_csr(0x340, 0x1234u32.into());
+    let dump = core.core_dump(&memory, Some(0x100..0x110));
+
+    let mut restored_memory = ArrayRam::<0x200>::new(0);
+    let mut restored = core32();
+    assert_eq!(restored.load_core_dump(&dump, &mut restored_memory), Ok(()));
+    assert!(core.diff(&restored).is_empty());
+    assert_eq!(restored_memory.bytes[0x100..0x110], memory.bytes[0x100..0x110]);
+    assert_eq!(restored_memory.bytes[0..12], [0; 12]);
+    // The number of bytes is a u64 so ranges of 4GiB and more are not truncated
+    assert_eq!(dump[dump.len() - 24..dump.len() - 16], 16u64.to_le_bytes());
+
+    // Truncated dumps and dumps from a different core leave the core unchanged
+    let mut fresh = core32();
+    assert_eq!(fresh.load_core_dump(&dump[..dump.len() - 1], &mut restored_memory), Err(CoreDumpError::Malformed));
+    assert_eq!(fresh.pc, 0u32.into());
+    #[cfg(feature = "ext-csr")]
+    let mut core64 = Core::<Register64>::new(0, 0);
+    #[cfg(not(feature = "ext-csr"))]
+    let mut core64 = Core::<Register64>::new(0);
+    assert_eq!(core64.load_core_dump(&dump, &mut ArrayRam::<0x200>::new(0)), Err(CoreDumpError::Mismatch));
+}
+
+#[test]
+#[cfg(feature = "ext-csr")]
+fn misa_register_size() {
+    use rysk_core::RegisterSize;
+    let misa = Core::<RegisterSize>::new(0, 0).get_csr(0x301).unwrap().0;
+    // MXL is in the top 2 bits, giving the host's width
+    #[cfg(target_pointer_width = "64")]
+    assert_eq!(misa[7] >> 6, 2);
+    #[cfg(target_pointer_width = "32")]
+    assert_eq!(misa[3] >> 6, 1);
+    // The base ISA is I
+    assert_eq!(misa[1] & 1, 1);
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn branch_hook() {
+    use std::{ cell::RefCell, rc::Rc };
+    // addi t0, zero, 0; addi t1, zero, 5; addi t0, t0, 1; bne t0, t1, -4; ecall
+    let mut memory = Memory::new(&[0x0000_0293, 0x0050_0313, 0x0012_8293, 0xFE62_9EE3, 0x0000_0073]);
+    let mut core = core32().with_trap_strategy(HostReturn);
+    let outcomes = Rc::new(RefCell::new(Vec::new()));
+    let recorded = Rc::clone(&outcomes);
+    core.set_branch_hook(move |pc, taken| recorded.borrow_mut().push((pc, taken)));
+
+    assert_eq!(core.run(&mut memory), RunResult::Trap(rysk_core::system::Trap::SystemCall));
+    let outcomes = outcomes.borrow();
+    assert!(outcomes.iter().all(|&(pc, _)| pc == 12u32.into()));
+    // Taken on all but the last of the 5 iterations
+    assert_eq!(outcomes.iter().filter(|&&(_, taken)| taken).count(), 4);
+    assert_eq!(outcomes.iter().filter(|&&(_, taken)| !taken).count(), 1);
+    assert_eq!(outcomes.last(), Some(&(12u32.into(), false)));
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn pause_hook() {
+    use std::{ cell::Cell, rc::Rc };
+    // pause; fence w, 0 with rd of t0, which is a normal fence; pause
+    let mut memory = Memory::new(&[0x0100_000F, 0x0100_028F, 0x0100_000F]);
+    let mut core = core32();
+    let pauses = Rc::new(Cell::new(0));
+    let counted = Rc::clone(&pauses);
+    core.set_pause_hook(move || counted.set(counted.get() + 1));
+
+    core.execute(&mut memory);
+    assert_eq!(core.last_outcome(), StepOutcome::Waiting);
+    core.execute(&mut memory);
+    assert_eq!(core.last_outcome(), StepOutcome::Retired);
+    assert_eq!(pauses.get(), 1);
+
+    // Without the hook the host thread yields instead
+    core.clear_pause_hook();
+    core.execute(&mut memory);
+    assert_eq!(core.last_outcome(), StepOutcome::Waiting);
+    assert_eq!(pauses.get(), 1);
+    assert_eq!(core.pc, 12u32.into());
+}
+
+/// A device register which may only be accessed as a whole word
+struct WordOnly(Memory, Vec<rysk_core::AccessInfo>);
+impl Mmu<Register32> for WordOnly {
+    fn get(&self, address: u32) -> u8 {
+        self.0.get(address)
+    }
+    fn set(&mut self, address: u32, value: u8) {
+        self.0.set(address, value)
+    }
+    fn load_bytes(&self, address: u32, bytes: &mut [u8], info: rysk_core::AccessInfo) -> Result<(), MemoryFault> {
+        if address >= 0x100 && info.width != 4 {
+            return Err(MemoryFault::AccessFault)
+        }
+        bytes.copy_from_slice(&self.get_word(address)?[..info.width as usize]);
+        Ok(())
+    }
+    fn store_bytes(&mut self, address: u32, bytes: &[u8], info: rysk_core::AccessInfo) -> Result<(), MemoryFault> {
+        self.1.push(info);
+        if address >= 0x100 && info.width != 4 {
+            return Err(MemoryFault::AccessFault)
+        }
+        self.set_word(address, [bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+#[test]
+fn access_info() {
+    use rysk_core::{ AccessInfo, system::Trap };
+    // sw x1, 0x100(x0); lw x2, 0x100(x0); sb x1, 0x100(x0); lbu x3, 0x100(x0)
+    let mut memory = WordOnly(Memory::new(&[0x1010_2023, 0x1000_2103, 0x1010_0023, 0x1000_4183]), Vec::new());
+    let mut core = core32().with_trap_strategy(HostReturn);
+    core.set(1, 0x1234_5678u32.into());
+
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.execute(&mut memory), None);
+    assert_eq!(core.get(2), 0x1234_5678u32.into());
+    // Sub-word accesses to the device fault
+    assert_eq!(core.execute(&mut memory), Some(Trap::StoreAccessFault(0x100)));
+    assert_eq!(memory.1, [AccessInfo::store(4), AccessInfo::store(1)]);
+    core.step();
+    assert_eq!(core.execute(&mut memory), Some(Trap::LoadAccessFault(0x100)));
+    assert_eq!(AccessInfo::load(1, false), AccessInfo { width: 1, is_write: false, is_signed: false });
+}
+
+/// Memory recording the ordering requested by each FENCE and counting each FENCE.I
+struct Fences(Memory, Vec<(u8, u8)>, usize);
+impl Mmu<Register32> for Fences {
+    fn get(&self, address: u32) -> u8 {
+        self.0.get(address)
+    }
+    fn set(&mut self, address: u32, value: u8) {
+        self.0.set(address, value)
+    }
+    fn fence(&mut self, pred: u8, succ: u8) {
+        self.1.push((pred, succ))
+    }
+    fn fence_i(&mut self) {
+        self.2 += 1
+    }
+}
+
+#[test]
+fn fence() {
+    // fence rw, w; fence.tso; a fence with reserved fm, rs1 and rd fields
+    let mut memory = Fences(Memory::new(&[0x0310_000F, 0x8330_000F, 0x5FF5_850F]), Vec::new(), 0);
+    let mut core = core32().with_trap_strategy(HostReturn);
+    for _ in 0..3 {
+        assert_eq!(core.execute(&mut memory), None);
+    }
+    assert_eq!(memory.1, [(0b0011, 0b0001), (0b0011, 0b0011), (0b1111, 0b1111)]);
+    assert_eq!(core.pc, 12u32.into());
+}
+
+#[test]
+fn ztso() {
+    // lw a0, 0x100(zero); sw a0, 0x104(zero)
+    let program = [0x1000_2503, 0x10A0_2223];
+    let mut memory = Fences(Memory::new(&program), Vec::new(), 0);
+    let mut core = core32();
+    core.execute(&mut memory);
+    core.execute(&mut memory);
+    assert!(memory.1.is_empty());
+    #[cfg(feature = "alloc")]
+    assert!(!core.isa_string().contains("ztso"));
+
+    // Loads are ordered after loads, and stores after both loads and stores
+    let mut memory = Fences(Memory::new(&program), Vec::new(), 0);
+    let mut core = core32().with_ztso(true);
+    core.execute(&mut memory);
+    core.execute(&mut memory);
+    assert_eq!(memory.1, [(0b0010, 0b0010), (0b0011, 0b0001)]);
+    #[cfg(feature = "alloc")]
+    {
+        assert!(core.isa_string().starts_with("rv32i"));
+        assert!(core.isa_string().ends_with("_ztso"));
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn fence_i() {
+    // addi a0, a0, 1; sw t0, 0(zero); fence.i; jal zero, -12
+    let mut memory = Fences(Memory::new(&[0x0015_0513, 0x0050_2023, 0x0000_100F, 0xFF5F_F06F]), Vec::new(), 0);
+    let mut core = core32().with_icache(16);
+    // addi a0, a0, 2
+    core.set(5, 0x0025_0513u32.into());
+    for _ in 0..5 {
+        core.execute(&mut memory);
+    }
+    // The modified instruction is fetched again after FENCE.I flushes the cache
+    assert_eq!(core.get(10), 3u32.into());
+    assert_eq!(memory.2, 1);
+}
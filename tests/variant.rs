@@ -38,4 +38,54 @@ fn variant_s() {
         source2: 0x1F,
         immediate: 0xFFFFFFFFu32.into()
     });
-}
\ No newline at end of file
+}
+
+#[test]
+fn variant_s_immediate() {
+    /// Encode `sw x2, offset(x1)`, splitting the offset between bits 7 to 11 and 25 to 31
+    fn sw(offset: i32) -> [u8; 4] {
+        let offset = offset as u32 & 0xFFF;
+        ((offset >> 5) << 25 | 2 << 20 | 1 << 15 | 0b010 << 12 | (offset & 0x1F) << 7 | 0b0100011).to_le_bytes()
+    }
+    for &offset in &[4, -4, 2047, -2048, 0x20, 0x1F] {
+        assert_eq!(variant::S::<Register32>::decode(sw(offset)), variant::S {
+            source1: 1,
+            source2: 2,
+            immediate: (offset as u32).into()
+        }, "offset {}", offset);
+        assert_eq!(variant::S::<Register64>::decode(sw(offset)).immediate, (offset as i64).into(), "offset {}", offset);
+    }
+}
+
+#[test]
+fn has_immediate() {
+    use variant::HasImmediate;
+    fn describe(variant: &dyn HasImmediate) -> String {
+        format!("imm={}", variant.immediate_bits())
+    }
+    // addi x1, x0, -5
+    let addi: variant::I<Register32> = Variant::decode(0xFFB0_0093u32.to_le_bytes());
+    // beq x0, x0, -8
+    let beq: variant::B<Register32> = Variant::decode(0xFE00_0CE3u32.to_le_bytes());
+    assert_eq!(describe(&addi), "imm=-5");
+    assert_eq!(describe(&beq), "imm=-8");
+}
+
+#[test]
+fn decode_instruction() {
+    use decode::Instruction::*;
+    let decode = |bits: u32| variant::decode_instruction::<Register32>(bits.to_le_bytes());
+    // add x3, x1, x2
+    assert_eq!(decode(0x0020_81B3), Ok(Add { rd: 3, rs1: 1, rs2: 2 }));
+    // lw x5, 8(x2)
+    assert_eq!(decode(0x0081_2283), Ok(Lw { rd: 5, rs1: 2, imm: 8u32.into() }));
+    // sw x5, -4(x2)
+    assert_eq!(decode(0xFE51_2E23), Ok(Sw { rs1: 2, rs2: 5, imm: 0xFFFF_FFFCu32.into() }));
+    // beq x0, x0, -8
+    assert_eq!(decode(0xFE00_0CE3), Ok(Beq { rs1: 0, rs2: 0, imm: 0xFFFF_FFF8u32.into() }));
+    // lui x1, 0x12345
+    assert_eq!(decode(0x1234_50B7), Ok(Lui { rd: 1, imm: 0x1234_5000u32.into() }));
+    assert_eq!(decode(0x0000_0073), Ok(Ecall));
+    // The all-zero encoding is defined to be illegal
+    assert_eq!(decode(0), Err(system::Trap::IllegalInstruction));
+}